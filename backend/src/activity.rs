@@ -0,0 +1,125 @@
+//! Activity log.
+//!
+//! Every task change appends an entry to a capped Redis stream. The log is
+//! read newest first and paged by stream id (`?after=<id>&limit=`), which
+//! stays cheap however long the stream gets. Without Redis nothing is
+//! recorded and the endpoint answers 503.
+
+use axum::{extract::Query, http::StatusCode, response::Json};
+use redis::{
+    streams::{StreamMaxlen, StreamRangeReply},
+    AsyncCommands,
+};
+use serde::Deserialize;
+use shared::{ActivityEvent, ActivityKind, ListResponse, Task};
+use tracing::Instrument;
+
+use crate::{error::ApiError, snooze, telemetry::redis_span, Redis, RedisPool};
+
+pub const STREAM_KEY: &str = "activity";
+const MAX_ENTRIES: usize = 10_000;
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 200;
+
+/// Appends an entry for `task`. Failures are logged rather than failing the
+/// change that triggered them.
+pub async fn record(redis: &Option<RedisPool>, kind: ActivityKind, task: &Task) {
+    let Some(pool) = redis else {
+        return;
+    };
+
+    let event = ActivityEvent {
+        id: String::new(),
+        task_id: task.id,
+        title: task.title.clone(),
+        kind,
+        at: snooze::now_secs(),
+    };
+    let Ok(json) = serde_json::to_string(&event) else {
+        return;
+    };
+
+    let result = async {
+        let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+        conn.xadd_maxlen::<_, _, _, _, ()>(STREAM_KEY, StreamMaxlen::Approx(MAX_ENTRIES), "*", &[("event", json)])
+            .instrument(redis_span("XADD"))
+            .await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!(task = %task.id, "failed to record activity: {}", e);
+    }
+}
+
+/// The kind of entry an update from `before` to `after` records.
+pub fn update_kind(before: &Task, after: &Task) -> ActivityKind {
+    match (before.completed, after.completed) {
+        (false, true) => ActivityKind::Completed,
+        (true, false) => ActivityKind::Reopened,
+        _ => ActivityKind::Updated,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityQuery {
+    /// Id of the last entry already seen; the page starts just before it.
+    after: Option<String>,
+    limit: Option<usize>,
+}
+
+fn valid_stream_id(id: &str) -> bool {
+    let (ms, seq) = id.split_once('-').unwrap_or((id, "0"));
+    !ms.is_empty() && !seq.is_empty() && ms.bytes().chain(seq.bytes()).all(|b| b.is_ascii_digit())
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_activity(
+    Redis(pool): Redis,
+    Query(query): Query<ActivityQuery>,
+) -> Result<Json<ListResponse<ActivityEvent>>, ApiError> {
+    if query.after.as_deref().is_some_and(|after| !valid_stream_id(after)) {
+        return Err(ApiError::Status(StatusCode::BAD_REQUEST));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    // The range end is inclusive, so ask for the cursor entry too and drop it.
+    // One extra entry beyond the page tells whether more remain.
+    let end = query.after.as_deref().unwrap_or("+");
+    let count = limit + 1 + usize::from(query.after.is_some());
+    let (reply, total): (StreamRangeReply, u64) = redis::pipe()
+        .xrevrange_count(STREAM_KEY, end, "-", count)
+        .xlen(STREAM_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    let mut events: Vec<ActivityEvent> = reply
+        .ids
+        .into_iter()
+        .filter(|entry| Some(&entry.id) != query.after.as_ref())
+        .filter_map(|entry| {
+            let json: String = entry.get("event")?;
+            let mut event: ActivityEvent = serde_json::from_str(&json).ok()?;
+            event.id = entry.id;
+            Some(event)
+        })
+        .collect();
+
+    let next_cursor = if events.len() > limit {
+        events.truncate(limit);
+        events.last().map(|event| event.id.clone())
+    } else {
+        None
+    };
+
+    Ok(Json(ListResponse {
+        data: events,
+        page: 1,
+        per_page: limit as u32,
+        total,
+        next_cursor,
+    }))
+}
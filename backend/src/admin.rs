@@ -0,0 +1,129 @@
+//! Admin access, and `GET /api/admin/overview`: one call gathering the
+//! numbers the Admin page leads with, so an operator can tell at a glance
+//! whether the app is healthy.
+//!
+//! Every `/api/admin/*` route, and anything else that changes the app for
+//! all users, takes [`AdminToken`]: `Authorization: Bearer <ADMIN_TOKEN>`.
+//! Without `ADMIN_TOKEN` set they answer 503.
+//!
+//! Redis figures are left out when Redis isn't configured or doesn't answer;
+//! the rest of the overview doesn't depend on it.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, StatusCode},
+    response::Json,
+};
+use shared::{AdminOverview, LogLevel, Priority, QueueDepth, RedisOverview, TaskTotals};
+use tracing::Instrument;
+
+use crate::{
+    activity, capture::constant_time_eq, client_errors, error::ApiError, live, logs, snooze, store::Store,
+    telemetry::redis_span, AppState, RedisPool,
+};
+
+/// Error log entries included in the overview.
+const RECENT_ERRORS: usize = 10;
+
+/// Extractor admitting requests that carry `ADMIN_TOKEN` as a bearer token.
+pub struct AdminToken;
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminToken {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let Some(expected) = &state.server.admin_token else {
+            return Err(ApiError::Unavailable("Admin is not configured (set ADMIN_TOKEN).".to_string()));
+        };
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        match token {
+            Some(token) if constant_time_eq(expected.as_bytes(), token.as_bytes()) => Ok(AdminToken),
+            _ => Err(ApiError::Status(StatusCode::UNAUTHORIZED)),
+        }
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_overview(
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+) -> Result<Json<AdminOverview>, ApiError> {
+    let tasks = store.list().await?;
+
+    let now = snooze::now_secs();
+    let totals = TaskTotals {
+        total: tasks.len() as u64,
+        completed: tasks.iter().filter(|t| t.completed).count() as u64,
+        pinned: tasks.iter().filter(|t| t.pinned).count() as u64,
+        snoozed: tasks.iter().filter(|t| t.is_snoozed(now)).count() as u64,
+        flagged: tasks.iter().filter(|t| t.flagged).count() as u64,
+        by_priority: Priority::ALL
+            .iter()
+            .map(|priority| (*priority, tasks.iter().filter(|t| t.priority == *priority).count() as u64))
+            .collect(),
+    };
+
+    let redis = match &redis {
+        Some(pool) => redis_overview(pool)
+            .await
+            .map_err(|e| tracing::warn!("admin overview: redis unavailable: {}", e))
+            .ok(),
+        None => None,
+    };
+
+    Ok(Json(AdminOverview {
+        store: store.name().to_string(),
+        tasks: totals,
+        redis,
+        live_clients: live.client_count() as u64,
+        recent_errors: logs::newest(LogLevel::Error).into_iter().take(RECENT_ERRORS).collect(),
+    }))
+}
+
+async fn redis_overview(pool: &RedisPool) -> redis::RedisResult<RedisOverview> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let (info, activity, client_errors): (String, u64, u64) = redis::pipe()
+        .cmd("INFO")
+        .arg("memory")
+        .cmd("XLEN")
+        .arg(activity::STREAM_KEY)
+        .llen(client_errors::REPORTS_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    let field = |name: &str| {
+        info.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(0)
+    };
+
+    let (connections, idle_connections) = pool.status();
+    Ok(RedisOverview {
+        used_memory: field("used_memory"),
+        peak_memory: field("used_memory_peak"),
+        connections: connections as u64,
+        idle_connections: idle_connections as u64,
+        queues: vec![
+            QueueDepth {
+                name: "activity".to_string(),
+                length: activity,
+            },
+            QueueDepth {
+                name: "client_errors".to_string(),
+                length: client_errors,
+            },
+        ],
+    })
+}
@@ -0,0 +1,333 @@
+//! Task analytics.
+//!
+//! With Redis, aggregates are kept up to date on every write: per-day created
+//! and completed counts, completions per hour of the day, the total time
+//! tasks took to complete, and per-tag totals, completions and the sum of
+//! creation times (from which the average age follows). Reads then only touch
+//! those hashes instead of scanning every task. Without Redis, tag stats fall
+//! back to a scan and per-day counts are unavailable.
+//!
+//! The aggregates are updated after the write they count has been saved, not
+//! with it, so they are only eventually consistent: if that update fails it
+//! is logged and the counts stay off by that write. To rebuild them, delete
+//! the `analytics:*` keys and restart the backend; [`backfill`] then seeds
+//! the tag aggregates and the per-day created counts from the stored tasks
+//! again. Completions from before the rebuild have no date to go by, so the
+//! per-day and per-hour completion counts start over.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use redis::AsyncCommands;
+use serde::Deserialize;
+use shared::{AnalyticsSummary, DailyCounts, HourCount, ListResponse, TagStats, Task};
+use std::collections::{BTreeMap, HashMap};
+use tracing::Instrument;
+
+use crate::{error::ApiError, pagination::Pagination, snooze, store::Store, telemetry::redis_span, Redis, RedisPool};
+
+const CREATED_PER_DAY_KEY: &str = "analytics:created";
+const COMPLETED_PER_DAY_KEY: &str = "analytics:completed";
+/// Completions by UTC hour of the day, `0` to `23`.
+const COMPLETED_PER_HOUR_KEY: &str = "analytics:completed:hours";
+/// Seconds from creation to completion, summed over completions of tasks
+/// with a `created_at` ...
+const COMPLETION_SECS_SUM_KEY: &str = "analytics:completion_secs:sum";
+/// ... and how many those are.
+const COMPLETION_SECS_COUNT_KEY: &str = "analytics:completion_secs:count";
+const TAG_TOTAL_KEY: &str = "analytics:tags:total";
+const TAG_COMPLETED_KEY: &str = "analytics:tags:completed";
+/// Sum of `created_at` over the tagged tasks that have one ...
+const TAG_CREATED_SUM_KEY: &str = "analytics:tags:created_sum";
+/// ... and how many those are.
+const TAG_DATED_KEY: &str = "analytics:tags:dated";
+/// Set once the aggregates have been seeded from the existing tasks.
+const BACKFILLED_KEY: &str = "analytics:backfilled";
+
+/// `YYYY-MM-DD` (UTC) for a unix timestamp.
+pub fn utc_date(secs: u64) -> String {
+    // Howard Hinnant's civil_from_days
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Adds (`sign` 1) or removes (`sign` -1) a task's share of the tag aggregates.
+fn add_tags(pipe: &mut redis::Pipeline, task: &Task, sign: i64) {
+    for tag in &task.tags {
+        pipe.hincr(TAG_TOTAL_KEY, tag, sign).ignore();
+        if task.completed {
+            pipe.hincr(TAG_COMPLETED_KEY, tag, sign).ignore();
+        }
+        if let Some(created_at) = task.created_at {
+            pipe.hincr(TAG_CREATED_SUM_KEY, tag, sign * created_at as i64).ignore();
+            pipe.hincr(TAG_DATED_KEY, tag, sign).ignore();
+        }
+    }
+}
+
+/// Updates the aggregates for a task going from `before` to `after` (`None`
+/// for a create or delete respectively), in one MULTI transaction. Failures
+/// are logged rather than failing the write that triggered them.
+pub async fn record(redis: &Option<RedisPool>, before: Option<&Task>, after: Option<&Task>) {
+    let Some(pool) = redis else {
+        return;
+    };
+
+    let now = snooze::now_secs();
+    let today = utc_date(now);
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    if before.is_none() && after.is_some() {
+        pipe.hincr(CREATED_PER_DAY_KEY, &today, 1).ignore();
+    }
+    if let Some(completed) = after.filter(|t| t.completed && !before.is_some_and(|t| t.completed)) {
+        pipe.hincr(COMPLETED_PER_DAY_KEY, &today, 1).ignore();
+        pipe.hincr(COMPLETED_PER_HOUR_KEY, now % 86_400 / 3_600, 1).ignore();
+        if let Some(created_at) = completed.created_at {
+            pipe.incr(COMPLETION_SECS_SUM_KEY, now.saturating_sub(created_at)).ignore();
+            pipe.incr(COMPLETION_SECS_COUNT_KEY, 1).ignore();
+        }
+    }
+    if let Some(before) = before {
+        add_tags(&mut pipe, before, -1);
+    }
+    if let Some(after) = after {
+        add_tags(&mut pipe, after, 1);
+    }
+    apply(pool, pipe).await;
+}
+
+/// Counts a task towards the tag aggregates only, for tasks restored from
+/// the trash or imported: their creation and completion were counted the
+/// first time round, or happened elsewhere.
+pub async fn count_tags(redis: &Option<RedisPool>, task: &Task) {
+    let Some(pool) = redis else {
+        return;
+    };
+
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    add_tags(&mut pipe, task, 1);
+    apply(pool, pipe).await;
+}
+
+async fn apply(pool: &RedisPool, pipe: redis::Pipeline) {
+    let result = async {
+        let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+        pipe.query_async::<_, ()>(&mut conn).instrument(redis_span("MULTI")).await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!("failed to update analytics aggregates, which are off until rebuilt: {}", e);
+    }
+}
+
+/// Seeds the aggregates from the stored tasks the first time the backend runs
+/// against a Redis that predates them. Completions of existing tasks have no
+/// date, so they only count towards the tag aggregates.
+pub async fn backfill(pool: RedisPool, store: Store) {
+    let result = async {
+        let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+        let first: bool = conn.set_nx(BACKFILLED_KEY, 1).instrument(redis_span("SETNX")).await?;
+        if !first {
+            return Ok(0);
+        }
+
+        let tasks = store.list().await.unwrap_or_default();
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for task in &tasks {
+            if let Some(created_at) = task.created_at {
+                pipe.hincr(CREATED_PER_DAY_KEY, utc_date(created_at), 1).ignore();
+            }
+            add_tags(&mut pipe, task, 1);
+        }
+        pipe.query_async::<_, ()>(&mut conn).instrument(redis_span("MULTI")).await?;
+        Ok::<_, redis::RedisError>(tasks.len())
+    }
+    .await;
+
+    match result {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("seeded analytics aggregates from {} tasks", count),
+        Err(e) => tracing::warn!("failed to seed analytics aggregates: {}", e),
+    }
+}
+
+#[derive(Default)]
+struct Tally {
+    total: u64,
+    completed: u64,
+    created_sum: u64,
+    dated: u64,
+}
+
+fn scan_tags(tasks: &[Task]) -> BTreeMap<String, Tally> {
+    let mut tallies: BTreeMap<String, Tally> = BTreeMap::new();
+    for task in tasks {
+        for tag in &task.tags {
+            let tally = tallies.entry(tag.clone()).or_default();
+            tally.total += 1;
+            if task.completed {
+                tally.completed += 1;
+            }
+            if let Some(created_at) = task.created_at {
+                tally.created_sum += created_at;
+                tally.dated += 1;
+            }
+        }
+    }
+    tallies
+}
+
+/// Signed, since a counter that drifted below zero must still parse.
+type TagCounts = HashMap<String, i64>;
+
+fn count(counts: &TagCounts, tag: &str) -> u64 {
+    counts.get(tag).copied().unwrap_or(0).max(0) as u64
+}
+
+async fn read_tags(pool: &RedisPool) -> Result<BTreeMap<String, Tally>, redis::RedisError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let (totals, completed, created_sums, dated): (TagCounts, TagCounts, TagCounts, TagCounts) = redis::pipe()
+        .hgetall(TAG_TOTAL_KEY)
+        .hgetall(TAG_COMPLETED_KEY)
+        .hgetall(TAG_CREATED_SUM_KEY)
+        .hgetall(TAG_DATED_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    Ok(totals
+        .keys()
+        .filter(|tag| count(&totals, tag) > 0)
+        .map(|tag| {
+            let tally = Tally {
+                total: count(&totals, tag),
+                completed: count(&completed, tag),
+                created_sum: count(&created_sums, tag),
+                dated: count(&dated, tag),
+            };
+            (tag.clone(), tally)
+        })
+        .collect())
+}
+
+/// Per-tag counts, completion rate and average age, busiest tags first.
+#[tracing::instrument(skip(store, redis))]
+pub async fn tag_stats(
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<TagStats>>, ApiError> {
+    let tallies = match &redis {
+        Some(pool) => read_tags(pool).await?,
+        None => scan_tags(&store.list().await?),
+    };
+    let now = snooze::now_secs();
+
+    let mut stats: Vec<TagStats> = tallies
+        .into_iter()
+        .map(|(tag, tally)| TagStats {
+            tag,
+            total: tally.total,
+            completed: tally.completed,
+            completion_rate: tally.completed as f64 / tally.total as f64,
+            average_age_secs: (tally.dated > 0).then(|| now.saturating_sub(tally.created_sum / tally.dated)),
+        })
+        .collect();
+    // Stable sort keeps ties in tag order
+    stats.sort_by_key(|s| std::cmp::Reverse(s.total));
+
+    Ok(Json(pagination.paginate(stats)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyQuery {
+    /// How many days back from today (UTC) to report, today included.
+    days: Option<u64>,
+}
+
+/// Tasks created and completed per UTC day, newest day first.
+#[tracing::instrument(skip_all)]
+pub async fn daily_counts(
+    Redis(pool): Redis,
+    Query(query): Query<DailyQuery>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<DailyCounts>>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let counts = read_daily(&mut conn, query.days).await?;
+
+    Ok(Json(pagination.paginate(counts)))
+}
+
+/// Per-day counts for the last `days` (default 30) UTC days, newest first.
+async fn read_daily(conn: &mut crate::redis_pool::Connection, days: Option<u64>) -> redis::RedisResult<Vec<DailyCounts>> {
+    let days = days.unwrap_or(30).clamp(1, 366);
+    let today = snooze::now_secs() / 86_400;
+    let dates: Vec<String> = (0..days).map(|offset| utc_date((today - offset) * 86_400)).collect();
+
+    let (created, completed): (Vec<Option<u64>>, Vec<Option<u64>>) = redis::pipe()
+        .cmd("HMGET")
+        .arg(CREATED_PER_DAY_KEY)
+        .arg(&dates)
+        .cmd("HMGET")
+        .arg(COMPLETED_PER_DAY_KEY)
+        .arg(&dates)
+        .query_async(conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    Ok(dates
+        .into_iter()
+        .zip(created.into_iter().zip(completed))
+        .map(|(date, (created, completed))| DailyCounts {
+            date,
+            created: created.unwrap_or(0),
+            completed: completed.unwrap_or(0),
+        })
+        .collect())
+}
+
+/// Completions per day and per hour of the day, and how long tasks take to
+/// get done.
+#[tracing::instrument(skip_all)]
+pub async fn summary(Redis(pool): Redis, Query(query): Query<DailyQuery>) -> Result<Json<AnalyticsSummary>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let daily = read_daily(&mut conn, query.days).await?;
+
+    let (per_hour, sum, count): (HashMap<u8, u64>, Option<u64>, Option<u64>) = redis::pipe()
+        .hgetall(COMPLETED_PER_HOUR_KEY)
+        .get(COMPLETION_SECS_SUM_KEY)
+        .get(COMPLETION_SECS_COUNT_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    let hours = (0..24)
+        .map(|hour| HourCount {
+            hour,
+            completed: per_hour.get(&hour).copied().unwrap_or(0),
+        })
+        .collect();
+    let average_completion_secs = match (sum, count) {
+        (Some(sum), Some(count)) if count > 0 => Some(sum / count),
+        _ => None,
+    };
+
+    Ok(Json(AnalyticsSummary {
+        daily,
+        hours,
+        average_completion_secs,
+    }))
+}
@@ -0,0 +1,84 @@
+//! "What's new" announcements written by admins and shown in the header.
+//! Publishing and deleting sit under `/api/admin/`, so they need
+//! `ADMIN_TOKEN`; reading them doesn't.
+//!
+//! Kept in the `announcements` Redis hash, keyed by id. Without Redis there
+//! is nowhere to keep them: the list is empty and publishing answers 503.
+//! Which announcements a user has read is tracked by the browser.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use redis::AsyncCommands;
+use shared::{Announcement, CreateAnnouncementRequest, ListResponse};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{error::ApiError, pagination::Pagination, snooze, telemetry::redis_span, Redis, RedisPool};
+
+const ANNOUNCEMENTS_KEY: &str = "announcements";
+const MAX_TITLE_LEN: usize = 200;
+const MAX_BODY_LEN: usize = 5000;
+
+/// Published announcements, newest first.
+#[tracing::instrument(skip_all)]
+pub async fn get_announcements(
+    State(redis): State<Option<RedisPool>>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<Announcement>>, ApiError> {
+    let Some(pool) = redis else {
+        return Ok(Json(pagination.paginate(Vec::new())));
+    };
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let stored: Vec<String> = conn.hvals(ANNOUNCEMENTS_KEY).instrument(redis_span("HVALS")).await?;
+    let mut announcements: Vec<Announcement> = stored.iter().filter_map(|json| serde_json::from_str(json).ok()).collect();
+    announcements.sort_by(|a, b| b.published_at.cmp(&a.published_at).then(b.id.cmp(&a.id)));
+
+    Ok(Json(pagination.paginate(announcements)))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn publish(
+    Redis(pool): Redis,
+    Json(payload): Json<CreateAnnouncementRequest>,
+) -> Result<Json<Announcement>, ApiError> {
+    let title = payload.title.trim();
+    let body = payload.body.trim();
+    if title.is_empty() || title.len() > MAX_TITLE_LEN || body.len() > MAX_BODY_LEN {
+        return Err(ApiError::Validation(format!(
+            "Announcements need a title of up to {} characters and a body of up to {}.",
+            MAX_TITLE_LEN, MAX_BODY_LEN
+        )));
+    }
+
+    let announcement = Announcement {
+        id: Uuid::new_v4(),
+        title: title.to_string(),
+        body: body.to_string(),
+        published_at: snooze::now_secs(),
+    };
+    let json = serde_json::to_string(&announcement)?;
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    conn.hset::<_, _, _, ()>(ANNOUNCEMENTS_KEY, announcement.id.to_string(), json)
+        .instrument(redis_span("HSET"))
+        .await?;
+
+    Ok(Json(announcement))
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn delete(Path(id): Path<Uuid>, Redis(pool): Redis) -> Result<StatusCode, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let removed: usize = conn
+        .hdel(ANNOUNCEMENTS_KEY, id.to_string())
+        .instrument(redis_span("HDEL"))
+        .await?;
+
+    if removed == 0 {
+        return Err(ApiError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
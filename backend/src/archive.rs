@@ -0,0 +1,114 @@
+//! The archive.
+//!
+//! With `ARCHIVE_AFTER_DAYS` set, a background job moves tasks completed more
+//! than that many days ago out of the task list and into the archive, with
+//! `archived_at` set; repeating tasks are left alone. To clients they're
+//! deleted, and they drop out of the analytics tag aggregates, as the scan
+//! without Redis only sees the task list. Archived tasks are kept until
+//! they're restored, which puts them back in the list as they were.
+
+use axum::{
+    extract::{Path, Query, State},
+    response::Json,
+};
+use shared::{ActivityKind, ListResponse, Task, TaskEvent};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{activity, analytics, config, error::ApiError, live, pagination::Pagination, snooze, status, store::Store, RedisPool};
+
+/// How often the job looks for tasks to archive.
+const ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The job's name on `GET /status`.
+const JOB: &str = "archive";
+
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+    /// How long after completion a task is archived.
+    after: Duration,
+}
+
+impl ArchiveConfig {
+    /// Reads `ARCHIVE_AFTER_DAYS`; `None` when it's unset or `0`.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let days = config::parse_var("ARCHIVE_AFTER_DAYS", str::parse::<u64>)?.unwrap_or(0);
+        Ok((days > 0).then(|| Self {
+            after: Duration::from_secs(days * 24 * 60 * 60),
+        }))
+    }
+}
+
+pub fn spawn_job(
+    store: Store,
+    redis: Option<RedisPool>,
+    live: live::Hub,
+    monitor: status::Monitor,
+    config: Option<ArchiveConfig>,
+) {
+    let Some(config) = config else {
+        return;
+    };
+    monitor.register(JOB, ARCHIVE_INTERVAL);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ARCHIVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            monitor.track(JOB, archive_completed(&store, &redis, &live, &config)).await;
+        }
+    });
+}
+
+/// Returns whether the tasks due could be archived.
+#[tracing::instrument(skip_all)]
+async fn archive_completed(store: &Store, redis: &Option<RedisPool>, live: &live::Hub, config: &ArchiveConfig) -> bool {
+    let now = snooze::now_secs();
+    let before = now.saturating_sub(config.after.as_secs());
+    match store.archive_completed_before(before, now).await {
+        Ok(tasks) => {
+            for task in &tasks {
+                activity::record(redis, ActivityKind::Archived, task).await;
+                analytics::record(redis, Some(task), None).await;
+                live.publish(TaskEvent::Deleted { id: task.id });
+            }
+            if !tasks.is_empty() {
+                tracing::info!(count = tasks.len(), "archived completed tasks");
+            }
+            true
+        }
+        Err(e) => {
+            tracing::warn!("archive job failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Archived tasks, most recently archived first.
+#[tracing::instrument(skip(store))]
+pub async fn get_archive(
+    State(store): State<Store>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<Task>>, ApiError> {
+    let tasks = store.list_archive().await?;
+    Ok(Json(pagination.paginate(tasks)))
+}
+
+/// Moves an archived task back into the task list.
+#[tracing::instrument(skip(store, redis, live))]
+pub async fn restore(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+) -> Result<Json<Task>, ApiError> {
+    let task = store
+        .unarchive(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    activity::record(&redis, ActivityKind::Restored, &task).await;
+    analytics::count_tags(&redis, &task).await;
+    live.publish(TaskEvent::Created { task: task.clone() });
+
+    Ok(Json(task))
+}
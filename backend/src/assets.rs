@@ -0,0 +1,68 @@
+//! Frontend assets compiled into the binary (`embed-frontend` feature).
+//!
+//! Files are served from memory with their guessed content type and a
+//! SHA-256 based ETag, so clients revalidate cheaply with `If-None-Match`.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use rust_embed::{EmbeddedFile, RustEmbed};
+
+use crate::AppState;
+
+#[derive(RustEmbed)]
+#[folder = "../frontend/dist/"]
+struct FrontendAssets;
+
+fn etag(file: &EmbeddedFile) -> String {
+    let hash: String = file
+        .metadata
+        .sha256_hash()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("\"{}\"", hash)
+}
+
+fn respond(file: EmbeddedFile, headers: &HeaderMap) -> Response {
+    let etag = etag(&file);
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|candidate| candidate.trim() == etag));
+
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        (
+            [(header::CONTENT_TYPE, file.metadata.mimetype().to_string())],
+            file.data.into_owned(),
+        )
+            .into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&etag) {
+        response_headers.insert(header::ETAG, value);
+    }
+    // Asset names aren't content-hashed, so always revalidate
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+
+    response
+}
+
+/// The page the shell streams; see [`crate::shell`].
+pub fn index_html() -> Option<String> {
+    FrontendAssets::get("index.html").map(|file| String::from_utf8_lossy(&file.data).into_owned())
+}
+
+pub async fn serve_embedded(state: State<AppState>, uri: Uri, headers: HeaderMap) -> Response {
+    let path = uri.path().trim_start_matches('/');
+    match FrontendAssets::get(path) {
+        Some(file) if path != "index.html" => respond(file, &headers),
+        _ => crate::shell::serve(state, uri, headers).await,
+    }
+}
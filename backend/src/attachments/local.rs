@@ -0,0 +1,59 @@
+use axum::{async_trait, body::Bytes};
+use std::{io, path::PathBuf, time::Duration};
+
+use super::AttachmentStorage;
+use crate::store::StoreError;
+
+/// Attachments stored as plain files under one directory.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    // Keys are built from UUIDs by the handlers, never from user input.
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+fn unavailable(e: io::Error) -> StoreError {
+    StoreError::Unavailable(e.to_string())
+}
+
+#[async_trait]
+impl AttachmentStorage for LocalStorage {
+    fn name(&self) -> &'static str {
+        "local disk"
+    }
+
+    async fn put(&self, key: &str, data: Bytes, _content_type: &str) -> Result<(), StoreError> {
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(unavailable)?;
+        }
+        tokio::fs::write(path, data).await.map_err(unavailable)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        match tokio::fs::read(self.path(key)).await {
+            Ok(data) => Ok(Some(data.into())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(unavailable(e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        match tokio::fs::remove_file(self.path(key)).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(unavailable(e)),
+            _ => Ok(()),
+        }
+    }
+
+    async fn presigned_url(&self, _key: &str, _expires_in: Duration) -> Result<Option<String>, StoreError> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,320 @@
+//! Task attachments.
+//!
+//! Metadata lives on the task itself; the bytes go to an [`AttachmentStorage`]
+//! backend picked by `ATTACHMENTS_STORAGE`. Files are kept on local disk
+//! (`ATTACHMENTS_DIR`, default `attachments`) unless `S3_BUCKET` is set, in
+//! which case any S3-compatible service is used and downloads are redirected
+//! to presigned URLs so large files don't stream through the backend. With
+//! `ATTACHMENTS_STORAGE=redis` they go to the Redis the tasks are in.
+//!
+//! Each upload is limited to `ATTACHMENTS_MAX_MB`; a bigger one is answered
+//! with 413. Uploads can be slow, so the attachment list is changed on the
+//! task as it is once the bytes are stored, not as it was when the request
+//! came in. Downloads served from here are always attachments with
+//! `nosniff`, and keep their uploaded type only if it's in [`SERVED_TYPES`].
+
+mod local;
+mod redis;
+mod s3;
+
+use axum::{
+    async_trait,
+    body::Bytes,
+    extract::{Multipart, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    Json,
+};
+use shared::Attachment;
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+pub use self::local::LocalStorage;
+pub use self::redis::RedisStorage;
+pub use self::s3::S3Storage;
+
+use crate::store::{Store, StoreError};
+use crate::RedisPool;
+
+use crate::error::ApiError;
+
+pub type Attachments = Arc<dyn AttachmentStorage>;
+
+/// Largest upload accepted by the attachment endpoint unless
+/// `ATTACHMENTS_MAX_MB` says otherwise.
+const DEFAULT_MAX_UPLOAD_MB: usize = 25;
+
+const PRESIGNED_URL_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Saves tried before giving up on a task that keeps changing.
+const MAX_ATTEMPTS: usize = 5;
+
+/// Content types a download is served with as uploaded. Anything else, HTML
+/// and SVG included, could run script on our origin if opened, so it goes
+/// out as `application/octet-stream`.
+const SERVED_TYPES: [&str; 8] = [
+    "application/pdf",
+    "application/zip",
+    "image/gif",
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+    "text/csv",
+    "text/plain",
+];
+
+#[async_trait]
+pub trait AttachmentStorage: Send + Sync {
+    /// Human-readable backend name, e.g. for logs.
+    fn name(&self) -> &'static str;
+
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<(), StoreError>;
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError>;
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError>;
+
+    /// A time-limited URL the client can download from directly, for backends
+    /// that support it. `None` means the backend serves the bytes itself.
+    async fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<Option<String>, StoreError>;
+}
+
+/// The backend `ATTACHMENTS_STORAGE` names: `disk`, `s3` or `redis`
+/// (default: `s3` when `S3_BUCKET` is set, otherwise `disk`). `redis` uses
+/// the tasks' pool, so it needs `REDIS_URL`.
+pub fn from_env(redis: Option<&RedisPool>) -> Result<Attachments, String> {
+    let bucket = crate::config::var("S3_BUCKET");
+    let storage = crate::config::var("ATTACHMENTS_STORAGE")
+        .unwrap_or_else(|| if bucket.is_some() { "s3" } else { "disk" }.to_string());
+    match storage.trim().to_ascii_lowercase().as_str() {
+        "disk" => {
+            let dir = crate::config::var("ATTACHMENTS_DIR").unwrap_or_else(|| "attachments".to_string());
+            Ok(Arc::new(LocalStorage::new(dir.into())))
+        }
+        "s3" => {
+            let bucket = bucket.ok_or("ATTACHMENTS_STORAGE=s3 needs S3_BUCKET")?;
+            Ok(Arc::new(S3Storage::from_env(&bucket)?))
+        }
+        "redis" => {
+            let pool = redis.ok_or("ATTACHMENTS_STORAGE=redis needs REDIS_URL")?;
+            Ok(Arc::new(RedisStorage::new(pool.clone())))
+        }
+        other => Err(format!("ATTACHMENTS_STORAGE must be disk, s3 or redis, not {:?}", other)),
+    }
+}
+
+/// The largest upload accepted, from `ATTACHMENTS_MAX_MB`.
+pub fn max_upload_bytes() -> Result<usize, String> {
+    let mb = crate::config::parse_var("ATTACHMENTS_MAX_MB", str::parse::<usize>)?.unwrap_or(DEFAULT_MAX_UPLOAD_MB);
+    if mb == 0 {
+        return Err("ATTACHMENTS_MAX_MB must be at least 1".to_string());
+    }
+    Ok(mb * 1024 * 1024)
+}
+
+fn object_key(task_id: Uuid, attachment_id: Uuid) -> String {
+    format!("tasks/{}/{}", task_id, attachment_id)
+}
+
+/// The uploaded type without parameters if it's in [`SERVED_TYPES`].
+fn served_type(content_type: &str) -> &'static str {
+    let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    SERVED_TYPES
+        .into_iter()
+        .find(|served| *served == essence)
+        .unwrap_or("application/octet-stream")
+}
+
+/// Always a download. `filename` is plain ASCII for old clients, with quotes,
+/// backslashes and control characters replaced so they can't end the
+/// parameter or the header; `filename*` (RFC 6266) has the name as uploaded.
+fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| if (c.is_ascii_graphic() || c == ' ') && c != '"' && c != '\\' { c } else { '_' })
+        .collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'_' => char::from(byte).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect();
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
+
+/// Makes `change` to the latest version of the task and saves it, trying
+/// again if another write gets in first. `change` returns whether it found
+/// anything to change; 404 if it didn't or the task is gone.
+async fn update_attachments(store: &Store, task_id: Uuid, change: impl Fn(&mut Vec<Attachment>) -> bool) -> Result<(), ApiError> {
+    for _ in 0..MAX_ATTEMPTS {
+        let mut task = store
+            .get(task_id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        if !change(&mut task.attachments) {
+            return Err(ApiError::NotFound);
+        }
+        let expected = task.version;
+        if store.save_if_version(&mut task, expected).await? {
+            return Ok(());
+        }
+    }
+    Err(ApiError::Status(StatusCode::CONFLICT))
+}
+
+/// Removes the stored bytes of every attachment on a deleted task. Failures are
+/// logged rather than returned since the task itself is already gone.
+pub async fn purge(storage: &Attachments, task: &shared::Task) {
+    for attachment in &task.attachments {
+        if let Err(e) = storage.delete(&object_key(task.id, attachment.id)).await {
+            tracing::warn!(task = %task.id, attachment = %attachment.id, "failed to delete attachment: {}", e);
+        }
+    }
+}
+
+#[tracing::instrument(skip(store, storage, multipart))]
+pub async fn upload_attachment(
+    Path(task_id): Path<Uuid>,
+    State(store): State<Store>,
+    State(storage): State<Attachments>,
+    mut multipart: Multipart,
+) -> Result<Json<Attachment>, ApiError> {
+    store
+        .get(task_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let field = loop {
+        match multipart.next_field().await.map_err(|e| e.status())? {
+            Some(field) if field.name() == Some("file") => break field,
+            Some(_) => continue,
+            None => return Err(ApiError::Status(StatusCode::BAD_REQUEST)),
+        }
+    };
+
+    let filename = field.file_name().unwrap_or("attachment").to_string();
+    let content_type = field
+        .content_type()
+        .unwrap_or("application/octet-stream")
+        .to_string();
+    // 413 past `ATTACHMENTS_MAX_MB`, 400 for a malformed body
+    let data = field.bytes().await.map_err(|e| e.status())?;
+
+    let attachment = Attachment {
+        id: Uuid::new_v4(),
+        filename,
+        content_type,
+        size: data.len() as u64,
+    };
+
+    let key = object_key(task_id, attachment.id);
+    storage
+        .put(&key, data, &attachment.content_type)
+        .await?;
+
+    let added = update_attachments(&store, task_id, |attachments| {
+        attachments.push(attachment.clone());
+        true
+    })
+    .await;
+    if let Err(e) = added {
+        // Deleted meanwhile, or couldn't be saved; don't keep the bytes
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!(task = %task_id, attachment = %attachment.id, "failed to delete attachment: {}", e);
+        }
+        return Err(e);
+    }
+
+    Ok(Json(attachment))
+}
+
+#[tracing::instrument(skip(store, storage))]
+pub async fn download_attachment(
+    Path((task_id, attachment_id)): Path<(Uuid, Uuid)>,
+    State(store): State<Store>,
+    State(storage): State<Attachments>,
+) -> Result<Response, ApiError> {
+    let task = store
+        .get(task_id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    let attachment = task
+        .attachments
+        .iter()
+        .find(|attachment| attachment.id == attachment_id)
+        .ok_or(ApiError::NotFound)?;
+
+    let key = object_key(task_id, attachment_id);
+    if let Some(url) = storage
+        .presigned_url(&key, PRESIGNED_URL_TTL)
+        .await?
+    {
+        return Ok(Redirect::temporary(&url).into_response());
+    }
+
+    let data = storage
+        .get(&key)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, served_type(&attachment.content_type).to_string()),
+            (header::CONTENT_DISPOSITION, content_disposition(&attachment.filename)),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+#[tracing::instrument(skip(store, storage))]
+pub async fn delete_attachment(
+    Path((task_id, attachment_id)): Path<(Uuid, Uuid)>,
+    State(store): State<Store>,
+    State(storage): State<Attachments>,
+) -> Result<StatusCode, ApiError> {
+    update_attachments(&store, task_id, |attachments| {
+        let before = attachments.len();
+        attachments.retain(|attachment| attachment.id != attachment_id);
+        attachments.len() < before
+    })
+    .await?;
+    storage
+        .delete(&object_key(task_id, attachment_id))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_listed_types_are_served_as_uploaded() {
+        assert_eq!(served_type("image/png"), "image/png");
+        assert_eq!(served_type("Text/Plain; charset=utf-8"), "text/plain");
+        assert_eq!(served_type("text/html"), "application/octet-stream");
+        assert_eq!(served_type("image/svg+xml"), "application/octet-stream");
+        assert_eq!(served_type(""), "application/octet-stream");
+    }
+
+    #[test]
+    fn filenames_cannot_break_out_of_the_header() {
+        assert_eq!(
+            content_disposition("notes.txt"),
+            "attachment; filename=\"notes.txt\"; filename*=UTF-8''notes.txt"
+        );
+        assert_eq!(
+            content_disposition("a\"b\r\nSet-Cookie: x.txt"),
+            "attachment; filename=\"a_b__Set-Cookie: x.txt\"; filename*=UTF-8''a%22b%0D%0ASet-Cookie%3A%20x.txt"
+        );
+        assert_eq!(
+            content_disposition("résumé.pdf"),
+            "attachment; filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"
+        );
+        assert!(axum::http::HeaderValue::from_str(&content_disposition("tab\there\u{7f}")).is_ok());
+    }
+}
@@ -0,0 +1,76 @@
+use axum::{async_trait, body::Bytes};
+use redis::AsyncCommands;
+use std::time::Duration;
+use tracing::Instrument;
+
+use super::AttachmentStorage;
+use crate::{store::StoreError, telemetry::redis_span, RedisPool};
+
+/// Prefix of the Redis keys holding attachment bytes.
+const KEY_PREFIX: &str = "attachment:";
+
+/// Attachments kept in Redis next to the tasks, for deployments without a
+/// writable disk or an S3 bucket. Everything counts against Redis memory, so
+/// `ATTACHMENTS_MAX_MB` is best kept small.
+pub struct RedisStorage {
+    pool: RedisPool,
+}
+
+impl RedisStorage {
+    pub fn new(pool: RedisPool) -> Self {
+        Self { pool }
+    }
+
+    async fn connection(&self) -> Result<crate::redis_pool::Connection, StoreError> {
+        self.pool
+            .get()
+            .instrument(redis_span("CONNECT"))
+            .await
+            .map_err(unavailable)
+    }
+}
+
+fn unavailable(e: redis::RedisError) -> StoreError {
+    StoreError::Unavailable(e.to_string())
+}
+
+fn redis_key(key: &str) -> String {
+    format!("{}{}", KEY_PREFIX, key)
+}
+
+#[async_trait]
+impl AttachmentStorage for RedisStorage {
+    fn name(&self) -> &'static str {
+        "Redis"
+    }
+
+    async fn put(&self, key: &str, data: Bytes, _content_type: &str) -> Result<(), StoreError> {
+        let mut conn = self.connection().await?;
+        conn.set::<_, _, ()>(redis_key(key), data.as_ref())
+            .instrument(redis_span("SET"))
+            .await
+            .map_err(unavailable)
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        let mut conn = self.connection().await?;
+        let data: Option<Vec<u8>> = conn
+            .get(redis_key(key))
+            .instrument(redis_span("GET"))
+            .await
+            .map_err(unavailable)?;
+        Ok(data.map(Bytes::from))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        let mut conn = self.connection().await?;
+        conn.del::<_, ()>(redis_key(key))
+            .instrument(redis_span("DEL"))
+            .await
+            .map_err(unavailable)
+    }
+
+    async fn presigned_url(&self, _key: &str, _expires_in: Duration) -> Result<Option<String>, StoreError> {
+        Ok(None)
+    }
+}
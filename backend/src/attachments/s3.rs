@@ -0,0 +1,83 @@
+use axum::{async_trait, body::Bytes, http::Method};
+use object_store::{
+    aws::{AmazonS3, AmazonS3Builder},
+    path::Path,
+    signer::Signer,
+    Attribute, Attributes, ObjectStore, PutOptions, PutPayload,
+};
+use std::time::Duration;
+
+use super::AttachmentStorage;
+use crate::store::StoreError;
+
+/// Attachments in an S3 bucket or any S3-compatible service (MinIO, R2, ...).
+pub struct S3Storage {
+    store: AmazonS3,
+}
+
+impl S3Storage {
+    /// Credentials and region come from the usual `AWS_*` variables;
+    /// `S3_ENDPOINT` points at a non-AWS service, which is then addressed
+    /// path-style.
+    pub fn from_env(bucket: &str) -> Result<Self, String> {
+        let mut builder = AmazonS3Builder::from_env().with_bucket_name(bucket);
+        if let Some(endpoint) = crate::config::var("S3_ENDPOINT") {
+            builder = builder
+                .with_allow_http(endpoint.starts_with("http://"))
+                .with_endpoint(endpoint);
+        }
+
+        let store = builder
+            .build()
+            .map_err(|e| format!("Invalid S3 attachment storage configuration: {}", e))?;
+
+        Ok(Self { store })
+    }
+}
+
+fn unavailable(e: object_store::Error) -> StoreError {
+    StoreError::Unavailable(e.to_string())
+}
+
+#[async_trait]
+impl AttachmentStorage for S3Storage {
+    fn name(&self) -> &'static str {
+        "S3"
+    }
+
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<(), StoreError> {
+        let mut attributes = Attributes::new();
+        attributes.insert(Attribute::ContentType, content_type.to_string().into());
+        let options = PutOptions {
+            attributes,
+            ..Default::default()
+        };
+
+        self.store
+            .put_opts(&Path::from(key), PutPayload::from_bytes(data), options)
+            .await
+            .map_err(unavailable)?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, StoreError> {
+        match self.store.get(&Path::from(key)).await {
+            Ok(result) => result.bytes().await.map(Some).map_err(unavailable),
+            Err(object_store::Error::NotFound { .. }) => Ok(None),
+            Err(e) => Err(unavailable(e)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StoreError> {
+        self.store.delete(&Path::from(key)).await.map_err(unavailable)
+    }
+
+    async fn presigned_url(&self, key: &str, expires_in: Duration) -> Result<Option<String>, StoreError> {
+        let url = self
+            .store
+            .signed_url(Method::GET, &Path::from(key), expires_in)
+            .await
+            .map_err(unavailable)?;
+        Ok(Some(url.to_string()))
+    }
+}
@@ -0,0 +1,439 @@
+//! Automation rules: triggers, conditions and actions run on tasks by a
+//! background job.
+//!
+//! Handlers tell the [`Engine`] when a task was created, completed or
+//! reopened, the snooze wake job when a snooze ran out, and the job itself
+//! checks hourly for tasks that just turned stale or whose due date passed. The job looks up the rules
+//! of the workspace the change came from, runs the actions of every enabled
+//! rule the task matches, saving the task once for all of them, and logs
+//! each run to the capped `automations:log` list.
+//!
+//! Rules live in Redis, globally in `automations:rules` and per workspace in
+//! the `automations:workspaces` hash, like the content filter's modes. Tasks
+//! found by the background jobs don't belong to a workspace, so only the
+//! global rules run for them. Without Redis there are no rules and no job.
+//! Rules can complete and retag tasks in bulk, so only admins (`ADMIN_TOKEN`)
+//! can read or replace them.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query, State},
+    http::request::Parts,
+    response::Json,
+};
+use redis::AsyncCommands;
+use serde_json::json;
+use shared::{
+    automation::{AutomationRule, AutomationSettings, RuleAction, RuleExecution, RuleTrigger, SetAutomationRulesRequest},
+    ListResponse, Task, TaskEvent,
+};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    activity, analytics, error::ApiError, flags::workspace_from_headers, live, pagination::Pagination, snooze, status,
+    store::Store, telemetry::redis_span, AppState, Redis, RedisPool,
+};
+
+const GLOBAL_KEY: &str = "automations:rules";
+const WORKSPACES_KEY: &str = "automations:workspaces";
+const LOG_KEY: &str = "automations:log";
+const MAX_LOG_ENTRIES: isize = 500;
+
+/// How often the job looks for tasks that turned stale or came due.
+const STALE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The job's name on `GET /status`.
+const JOB: &str = "automations";
+
+const DAY: u64 = 86_400;
+
+/// Something that happened to a task, for the job to run rules on.
+#[derive(Debug)]
+pub struct Fired {
+    trigger: RuleTrigger,
+    task_id: Uuid,
+    workspace: Option<String>,
+}
+
+/// Hands fired triggers to the job.
+#[derive(Clone)]
+pub struct Engine {
+    sender: mpsc::UnboundedSender<Fired>,
+}
+
+impl Engine {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<Fired>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+
+    pub fn fire(&self, trigger: RuleTrigger, task_id: Uuid, workspace: Option<String>) {
+        // Nobody's listening without Redis
+        let _ = self.sender.send(Fired {
+            trigger,
+            task_id,
+            workspace,
+        });
+    }
+}
+
+/// Extractor for firing triggers on behalf of the requesting workspace.
+pub struct Automations {
+    engine: Engine,
+    workspace: Option<String>,
+}
+
+impl Automations {
+    /// Fires what a save from `before` to `task` amounts to.
+    pub fn saved(&self, before: Option<&Task>, task: &Task) {
+        let trigger = match before {
+            None => RuleTrigger::Created,
+            Some(before) if task.completed && !before.completed => RuleTrigger::Completed,
+            Some(before) if !task.completed && before.completed => RuleTrigger::Reopened,
+            Some(_) => return,
+        };
+        self.engine.fire(trigger, task.id, self.workspace.clone());
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Automations {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        Ok(Automations {
+            engine: state.automations.clone(),
+            workspace: workspace_from_headers(&parts.headers),
+        })
+    }
+}
+
+fn parse(rules: Option<String>) -> Option<Vec<AutomationRule>> {
+    serde_json::from_str(&rules?).ok()
+}
+
+async fn resolve_rules(pool: &RedisPool, workspace: Option<&str>) -> Result<Vec<AutomationRule>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let (global, scoped): (Option<String>, Option<String>) = redis::pipe()
+        .get(GLOBAL_KEY)
+        .hget(WORKSPACES_KEY, workspace.unwrap_or_default())
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    Ok(parse(scoped.filter(|_| workspace.is_some()))
+        .or_else(|| parse(global))
+        .unwrap_or_default())
+}
+
+/// What the job needs to run rules.
+struct Runner {
+    store: Store,
+    pool: RedisPool,
+    live: live::Hub,
+    http: reqwest::Client,
+}
+
+pub fn spawn_job(
+    mut fired: mpsc::UnboundedReceiver<Fired>,
+    store: Store,
+    redis: Option<RedisPool>,
+    live: live::Hub,
+    monitor: status::Monitor,
+) {
+    let Some(pool) = redis else {
+        return;
+    };
+    let http = match reqwest::Client::builder().timeout(WEBHOOK_TIMEOUT).build() {
+        Ok(http) => http,
+        Err(e) => {
+            tracing::error!("automations job can't make webhook calls, not starting: {}", e);
+            return;
+        }
+    };
+    let runner = Runner { store, pool, live, http };
+
+    monitor.register(JOB, STALE_INTERVAL);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(STALE_INTERVAL);
+        // Tasks that turned stale or came due before the backend started
+        // already had their chance
+        let mut checked_until = snooze::now_secs();
+        loop {
+            tokio::select! {
+                Some(fired) = fired.recv() => {
+                    monitor.track(JOB, runner.run(fired)).await;
+                }
+                _ = interval.tick() => {
+                    let now = snooze::now_secs();
+                    monitor.track(JOB, runner.fire_scheduled(checked_until, now)).await;
+                    checked_until = now;
+                }
+            }
+        }
+    });
+}
+
+impl Runner {
+    /// Fires `Stale` for open tasks that reached a stale rule's age, and
+    /// `DuePassed` for open tasks whose due date came, after `since` and by
+    /// `now`. Returns whether every run went through.
+    #[tracing::instrument(skip(self))]
+    async fn fire_scheduled(&self, since: u64, now: u64) -> bool {
+        let rules = match resolve_rules(&self.pool, None).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::warn!("automations job could not read the rules: {:?}", e);
+                return false;
+            }
+        };
+        let mut ages: Vec<u32> = rules
+            .iter()
+            .filter_map(|rule| match rule.trigger {
+                RuleTrigger::Stale { days } if rule.enabled => Some(days),
+                _ => None,
+            })
+            .collect();
+        ages.sort_unstable();
+        ages.dedup();
+        let due = rules.iter().any(|rule| rule.enabled && rule.trigger == RuleTrigger::DuePassed);
+        if ages.is_empty() && !due {
+            return true;
+        }
+
+        let tasks = match self.store.list().await {
+            Ok(tasks) => tasks,
+            Err(e) => {
+                tracing::warn!("automations job could not list tasks: {}", e);
+                return false;
+            }
+        };
+        let mut ok = true;
+        let came = |at: u64| since < at && at <= now;
+        for task in tasks.iter().filter(|task| !task.completed) {
+            let mut triggers = Vec::new();
+            if let Some(created) = task.created_at {
+                for &days in &ages {
+                    if came(created + u64::from(days) * DAY) {
+                        triggers.push(RuleTrigger::Stale { days });
+                    }
+                }
+            }
+            if due && task.due_at.is_some_and(came) {
+                triggers.push(RuleTrigger::DuePassed);
+            }
+            for trigger in triggers {
+                let fired = Fired {
+                    trigger,
+                    task_id: task.id,
+                    workspace: None,
+                };
+                ok &= self.run(fired).await;
+            }
+        }
+        ok
+    }
+
+    /// Runs the matching rules on the task. Returns whether they all went
+    /// through.
+    #[tracing::instrument(skip(self))]
+    async fn run(&self, fired: Fired) -> bool {
+        let rules = match resolve_rules(&self.pool, fired.workspace.as_deref()).await {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::warn!("automations job could not read the rules: {:?}", e);
+                return false;
+            }
+        };
+        if !rules.iter().any(|rule| rule.enabled && rule.trigger == fired.trigger) {
+            return true;
+        }
+        let before = match self.store.get(fired.task_id).await {
+            Ok(Some(task)) => task,
+            // Deleted since
+            Ok(None) => return true,
+            Err(e) => {
+                tracing::warn!(task = %fired.task_id, "automations job could not load task: {}", e);
+                return false;
+            }
+        };
+        let matching: Vec<&AutomationRule> = rules.iter().filter(|rule| rule.matches(fired.trigger, &before)).collect();
+        if matching.is_empty() {
+            return true;
+        }
+
+        let mut task = before.clone();
+        let mut changed = false;
+        for action in matching.iter().flat_map(|rule| &rule.actions) {
+            changed |= action.apply(&mut task);
+        }
+        let saved = if changed { self.save(&before, &mut task).await } else { Ok(()) };
+
+        let mut ok = saved.is_ok();
+        let mut executions = Vec::new();
+        for rule in matching {
+            let mut error = saved.clone().err();
+            for action in &rule.actions {
+                if let Err(e) = self.side_effect(rule, fired.trigger, &task, action).await {
+                    error.get_or_insert(e);
+                }
+            }
+            match &error {
+                Some(e) => {
+                    tracing::warn!(task = %task.id, rule = %rule.name, "automation rule failed: {}", e);
+                    ok = false;
+                }
+                None => tracing::info!(task = %task.id, rule = %rule.name, "automation rule ran"),
+            }
+            executions.push(RuleExecution {
+                at: snooze::now_secs(),
+                rule_id: rule.id,
+                rule_name: rule.name.clone(),
+                trigger: fired.trigger,
+                task_id: task.id,
+                workspace: fired.workspace.clone(),
+                actions: rule.actions.iter().map(RuleAction::label).collect(),
+                error,
+            });
+        }
+        if let Err(e) = self.log(&executions).await {
+            tracing::warn!("failed to log automation runs: {:?}", e);
+        }
+        ok
+    }
+
+    async fn save(&self, before: &Task, task: &mut Task) -> Result<(), String> {
+        match self.store.save_if_version(task, before.version).await {
+            Ok(true) => {
+                let redis = Some(self.pool.clone());
+                activity::record(&redis, activity::update_kind(before, task), task).await;
+                analytics::record(&redis, Some(before), Some(task)).await;
+                self.live.publish(TaskEvent::Updated { task: task.clone() });
+                Ok(())
+            }
+            Ok(false) => Err("the task was changed meanwhile".to_string()),
+            Err(e) => Err(format!("failed to save the task: {}", e)),
+        }
+    }
+
+    /// Runs the actions that reach outside the task.
+    async fn side_effect(&self, rule: &AutomationRule, trigger: RuleTrigger, task: &Task, action: &RuleAction) -> Result<(), String> {
+        match action {
+            RuleAction::Notify { message } => {
+                self.live.publish(TaskEvent::Notice {
+                    task_id: task.id,
+                    message: message.clone(),
+                });
+                Ok(())
+            }
+            RuleAction::Webhook { url } => {
+                let body = json!({ "rule": rule.name, "trigger": trigger, "task": task });
+                let response = self
+                    .http
+                    .post(url)
+                    .json(&body)
+                    .send()
+                    .await
+                    .map_err(|e| format!("webhook {} failed: {}", url, e))?;
+                if !response.status().is_success() {
+                    return Err(format!("webhook {} answered {}", url, response.status()));
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    async fn log(&self, executions: &[RuleExecution]) -> Result<(), ApiError> {
+        let mut pipe = redis::pipe();
+        for execution in executions {
+            pipe.lpush(LOG_KEY, serde_json::to_string(execution)?).ignore();
+        }
+        pipe.ltrim(LOG_KEY, 0, MAX_LOG_ENTRIES - 1).ignore();
+
+        let mut conn = self.pool.get().instrument(redis_span("CONNECT")).await?;
+        pipe.query_async::<_, ()>(&mut conn).instrument(redis_span("PIPELINE")).await?;
+        Ok(())
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_settings(State(state): State<AppState>) -> Result<Json<AutomationSettings>, ApiError> {
+    let mut settings = AutomationSettings::default();
+    let Some(pool) = &state.redis else {
+        return Ok(Json(settings));
+    };
+
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let (global, workspaces): (Option<String>, Vec<(String, String)>) = redis::pipe()
+        .get(GLOBAL_KEY)
+        .hgetall(WORKSPACES_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    if let Some(rules) = parse(global) {
+        settings.rules = rules;
+    }
+    settings.workspaces = workspaces
+        .into_iter()
+        .filter_map(|(workspace, rules)| Some((workspace, parse(Some(rules))?)))
+        .collect();
+    Ok(Json(settings))
+}
+
+/// Replaces the rules of a workspace, or the global ones.
+#[tracing::instrument(skip(state, pool))]
+pub async fn set_rules(
+    State(state): State<AppState>,
+    Redis(pool): Redis,
+    Json(mut payload): Json<SetAutomationRulesRequest>,
+) -> Result<Json<AutomationSettings>, ApiError> {
+    for rule in &mut payload.rules {
+        rule.validate().map_err(ApiError::Validation)?;
+    }
+    let rules = serde_json::to_string(&payload.rules)?;
+
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    match payload.workspace.as_deref().map(str::trim).filter(|w| !w.is_empty()) {
+        Some(workspace) => conn
+            .hset::<_, _, _, ()>(WORKSPACES_KEY, workspace, rules)
+            .instrument(redis_span("HSET"))
+            .await?,
+        None => conn
+            .set::<_, _, ()>(GLOBAL_KEY, rules)
+            .instrument(redis_span("SET"))
+            .await?,
+    }
+
+    get_settings(State(state)).await
+}
+
+/// Latest runs first.
+#[tracing::instrument(skip_all)]
+pub async fn get_log(
+    Redis(pool): Redis,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<RuleExecution>>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let start = pagination.offset() as isize;
+    let stop = start + pagination.per_page() as isize - 1;
+    let (entries, total): (Vec<String>, u64) = redis::pipe()
+        .lrange(LOG_KEY, start, stop)
+        .llen(LOG_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    let entries = entries
+        .iter()
+        .filter_map(|json| serde_json::from_str(json).ok())
+        .collect();
+    Ok(Json(pagination.respond(entries, total)))
+}
@@ -0,0 +1,118 @@
+//! `POST /api/tasks/bulk`: complete, delete and retag many tasks at once.
+//!
+//! Operations run in order against the tasks as they were when the request
+//! arrived, and the result is written in one transaction, so either every
+//! operation takes effect or none does. Deleted tasks go to the trash. If
+//! any of the tasks is changed by someone else before the write, nothing is
+//! written and the request gets 409.
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use shared::{ActivityKind, BulkOperation, BulkRequest, BulkResponse, Task, TaskEvent};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    activity, analytics,
+    automations::Automations,
+    error::ApiError,
+    live, snooze,
+    store::{Store, Versions},
+    RedisPool,
+};
+
+/// Operations accepted in one request.
+pub const MAX_OPERATIONS: usize = 500;
+
+#[tracing::instrument(skip_all, fields(operations = payload.operations.len()))]
+pub async fn apply(
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    automations: Automations,
+    Json(payload): Json<BulkRequest>,
+) -> Result<Json<BulkResponse>, ApiError> {
+    if payload.operations.len() > MAX_OPERATIONS {
+        return Err(ApiError::Status(StatusCode::PAYLOAD_TOO_LARGE));
+    }
+
+    let mut before: HashMap<Uuid, Task> = HashMap::new();
+    for id in payload.operations.iter().map(BulkOperation::id) {
+        if before.contains_key(&id) {
+            continue;
+        }
+        let task = store
+            .get(id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        before.insert(id, task);
+    }
+
+    // Changed tasks in the order they were first touched
+    let mut order: Vec<Uuid> = Vec::new();
+    let mut after: HashMap<Uuid, Task> = HashMap::new();
+    let mut deleted: Vec<Uuid> = Vec::new();
+    for operation in payload.operations {
+        let id = operation.id();
+        if deleted.contains(&id) {
+            // Nothing left to operate on
+            return Err(ApiError::Validation(format!("Task {} was deleted earlier in the same request.", id)));
+        }
+        let task = after.entry(id).or_insert_with(|| {
+            order.push(id);
+            before[&id].clone()
+        });
+
+        match operation {
+            BulkOperation::Complete { .. } => task.completed = true,
+            BulkOperation::Delete { .. } => {
+                after.remove(&id);
+                order.retain(|changed| *changed != id);
+                deleted.push(id);
+            }
+            BulkOperation::Retag { add, remove, .. } => {
+                let remove = shared::normalize_tags(remove);
+                let tags = task.tags.iter().cloned().chain(add);
+                task.tags = shared::normalize_tags(tags).into_iter().filter(|tag| !remove.contains(tag)).collect();
+            }
+        }
+    }
+
+    let mut updated: Vec<Task> = order
+        .iter()
+        .filter_map(|id| after.remove(id))
+        .filter(|task| {
+            let original = &before[&task.id];
+            task.completed != original.completed || task.tags != original.tags
+        })
+        .collect();
+    let now = snooze::now_secs();
+    for task in &mut updated {
+        task.stamp_completion(before[&task.id].completed, now);
+    }
+    let trashed: Vec<Task> = deleted
+        .iter()
+        .map(|id| Task {
+            deleted_at: Some(now),
+            ..before[id].clone()
+        })
+        .collect();
+    let expected: Versions = updated.iter().chain(&trashed).map(|task| (task.id, before[&task.id].version)).collect();
+    if !store.apply_batch(&mut updated, &trashed, &expected).await? {
+        return Err(ApiError::Status(StatusCode::CONFLICT));
+    }
+
+    for task in &updated {
+        let original = &before[&task.id];
+        activity::record(&redis, activity::update_kind(original, task), task).await;
+        analytics::record(&redis, Some(original), Some(task)).await;
+        live.publish(TaskEvent::Updated { task: task.clone() });
+        automations.saved(Some(original), task);
+    }
+    for task in &trashed {
+        activity::record(&redis, ActivityKind::Deleted, task).await;
+        analytics::record(&redis, Some(task), None).await;
+        live.publish(TaskEvent::Deleted { id: task.id });
+    }
+
+    Ok(Json(BulkResponse { updated, deleted }))
+}
@@ -0,0 +1,177 @@
+//! `POST /api/capture`: pages sent from the companion browser extension.
+//!
+//! The extension posts the page it's on (title, URL and any selected text)
+//! with `Authorization: Bearer <token>`, where the token is one of
+//! `CAPTURE_TOKENS`, and the page becomes a task tagged [`TAG`]. Without any
+//! tokens configured the endpoint answers 503.
+//!
+//! The route sits outside the app-wide CORS layer built from
+//! [`CorsConfig`](crate::cors::CorsConfig): it is the one endpoint taking
+//! credentials from outside the app, so rather than the origins in
+//! `CORS_ORIGINS` it only admits browser extension origins, or exactly those
+//! listed in `CAPTURE_ORIGINS`.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Router,
+};
+use shared::{validation, ActivityKind, CaptureRequest, Task, TaskEvent};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{activity, analytics, automations::Automations, content_filter::Moderation, error::ApiError, live, snooze, store::Store, AppState, RedisPool};
+
+/// Tag given to every captured task, so they can be filtered as a reading list.
+pub const TAG: &str = "captured";
+
+/// Origin prefixes of requests made by browser extensions.
+const EXTENSION_SCHEMES: &[&str] = &["chrome-extension://", "moz-extension://", "safari-web-extension://"];
+
+#[derive(Debug, Clone, Default)]
+pub struct CaptureConfig {
+    tokens: Vec<String>,
+    origins: Vec<HeaderValue>,
+}
+
+impl CaptureConfig {
+    /// Reads `CAPTURE_TOKENS` and `CAPTURE_ORIGINS`, both comma-separated.
+    pub fn from_env() -> Result<Self, String> {
+        let list = |name: &str| -> Vec<String> {
+            crate::config::var(name)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+
+        let origins = list("CAPTURE_ORIGINS")
+            .into_iter()
+            .map(|origin| HeaderValue::from_str(&origin).map_err(|_| format!("Invalid origin in CAPTURE_ORIGINS: {}", origin)))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            tokens: list("CAPTURE_TOKENS"),
+            origins,
+        })
+    }
+
+    pub fn enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    fn accepts(&self, token: &str) -> bool {
+        self.tokens.iter().any(|known| constant_time_eq(known.as_bytes(), token.as_bytes()))
+    }
+
+    fn cors(&self) -> CorsLayer {
+        let origins = if self.origins.is_empty() {
+            AllowOrigin::predicate(|origin: &HeaderValue, _: &Parts| {
+                origin
+                    .to_str()
+                    .is_ok_and(|origin| EXTENSION_SCHEMES.iter().any(|scheme| origin.starts_with(scheme)))
+            })
+        } else {
+            AllowOrigin::list(self.origins.clone())
+        };
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([Method::POST])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+    }
+}
+
+/// Compares without stopping at the first difference, so response times
+/// don't reveal how much of a guessed token was right.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Extractor admitting requests that carry one of `CAPTURE_TOKENS` as a
+/// bearer token.
+pub struct CaptureToken;
+
+#[async_trait]
+impl FromRequestParts<AppState> for CaptureToken {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        if !state.capture.enabled() {
+            return Err(ApiError::Unavailable("Capture is not configured (set CAPTURE_TOKENS).".to_string()));
+        }
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        match token {
+            Some(token) if state.capture.accepts(token) => Ok(CaptureToken),
+            _ => Err(ApiError::Status(StatusCode::UNAUTHORIZED)),
+        }
+    }
+}
+
+/// The capture route with its own CORS policy; merge it in after the
+/// app-wide CORS layer so that layer doesn't answer its preflights.
+pub fn routes(config: &CaptureConfig) -> Router<AppState> {
+    Router::new().route("/api/capture", post(capture)).layer(config.cors())
+}
+
+/// Titled after the page, with the link first in the description and the
+/// selection quoted below it. Long page titles are cut to fit rather than
+/// refused.
+fn task_from(request: CaptureRequest) -> Task {
+    let url = request.url.trim();
+    let title = match request.title.trim() {
+        "" => url,
+        title => title,
+    };
+
+    let mut description = url.to_string();
+    let selection = request.selection.trim();
+    if !selection.is_empty() {
+        description.push_str("\n\n");
+        let quoted: Vec<String> = selection.lines().map(|line| format!("> {}", line.trim_end())).collect();
+        description.push_str(&quoted.join("\n"));
+    }
+
+    let title: String = title.chars().take(validation::MAX_TITLE_CHARS).collect();
+    let mut task = Task::new(title, description);
+    task.tags = vec![TAG.to_string()];
+    task
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn capture(
+    _token: CaptureToken,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    moderation: Moderation,
+    automations: Automations,
+    Json(payload): Json<CaptureRequest>,
+) -> Result<Response, ApiError> {
+    let url = payload.url.trim();
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(ApiError::Validation("Only http and https pages can be captured.".to_string()));
+    }
+
+    let mut task = task_from(payload);
+    task.created_at = Some(snooze::now_secs());
+    validation::check_task(None, &mut task).map_err(ApiError::Fields)?;
+    moderation.review(None, &mut task)?;
+
+    store.save(&mut task).await?;
+    activity::record(&redis, ActivityKind::Created, &task).await;
+    analytics::record(&redis, None, Some(&task)).await;
+    live.publish(TaskEvent::Created { task: task.clone() });
+    automations.saved(None, &task);
+
+    Ok(Json(task).into_response())
+}
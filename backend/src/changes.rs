@@ -0,0 +1,36 @@
+//! Delta sync: which tasks changed since a client last looked.
+//!
+//! Every store save and delete bumps a global change number. A client notes
+//! the number before loading its task list and later asks for what happened
+//! after it, so refreshing after the tab was hidden or offline only
+//! transfers the difference.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use shared::TaskChanges;
+
+use crate::{error::ApiError, store::Store};
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// Change number from the client's last sync. Without it only the
+    /// current number is returned, as a starting point.
+    since: Option<u64>,
+}
+
+#[tracing::instrument(skip(store))]
+pub async fn get_changes(
+    State(store): State<Store>,
+    Query(query): Query<ChangesQuery>,
+) -> Result<Json<TaskChanges>, ApiError> {
+    let changes = match query.since {
+        Some(since) => store.changes_since(since).await,
+        // Nothing can be newer than the newest change
+        None => store.changes_since(u64::MAX).await,
+    };
+
+    changes.map(Json).map_err(ApiError::from)
+}
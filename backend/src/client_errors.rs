@@ -0,0 +1,86 @@
+//! Error reports sent by the frontend.
+//!
+//! Reports are logged and kept in a capped Redis list so the admin page can
+//! show recent user-facing failures.
+
+use axum::{extract::Query, http::StatusCode, response::Json};
+use shared::{ClientErrorReport, ListResponse};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::Instrument;
+
+use crate::{error::ApiError, pagination::Pagination, telemetry::redis_span, Redis};
+
+pub const REPORTS_KEY: &str = "client_errors";
+const MAX_REPORTS: isize = 500;
+const MAX_MSG_LOG: usize = 50;
+const MAX_FIELD_LEN: usize = 2000;
+
+fn truncate(value: &mut String) {
+    if value.len() > MAX_FIELD_LEN {
+        let mut end = MAX_FIELD_LEN;
+        while !value.is_char_boundary(end) {
+            end -= 1;
+        }
+        value.truncate(end);
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn report_client_error(
+    Redis(pool): Redis,
+    Json(mut report): Json<ClientErrorReport>,
+) -> Result<StatusCode, ApiError> {
+    // Reports come from untrusted clients, so bound what gets stored.
+    truncate(&mut report.message);
+    truncate(&mut report.url);
+    truncate(&mut report.user_agent);
+    let skip = report.msg_log.len().saturating_sub(MAX_MSG_LOG);
+    report.msg_log.drain(..skip);
+    report.msg_log.iter_mut().for_each(truncate);
+    report.received_at = SystemTime::now().duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs());
+
+    tracing::warn!(
+        kind = ?report.kind,
+        app_version = %report.app_version,
+        url = %report.url,
+        "client error: {}",
+        report.message
+    );
+
+    let report_json = serde_json::to_string(&report)?;
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    redis::pipe()
+        .lpush(REPORTS_KEY, report_json)
+        .ignore()
+        .ltrim(REPORTS_KEY, 0, MAX_REPORTS - 1)
+        .ignore()
+        .query_async::<_, ()>(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_client_errors(
+    Redis(pool): Redis,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<ClientErrorReport>>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let start = pagination.offset() as isize;
+    let stop = start + pagination.per_page() as isize - 1;
+    let (reports, total): (Vec<String>, u64) = redis::pipe()
+        .lrange(REPORTS_KEY, start, stop)
+        .llen(REPORTS_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    let reports = reports
+        .iter()
+        .filter_map(|json| serde_json::from_str(json).ok())
+        .collect();
+    Ok(Json(pagination.respond(reports, total)))
+}
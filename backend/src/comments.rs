@@ -0,0 +1,108 @@
+//! Comments on tasks.
+//!
+//! Each task's comments are kept oldest first in the Redis list
+//! `comments:<task id>` and are deleted with the task when it's purged from
+//! the trash. Without Redis the endpoints answer 503.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use redis::AsyncCommands;
+use shared::{Comment, CreateCommentRequest, ListResponse};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use crate::{
+    content_filter::Moderation, error::ApiError, pagination::Pagination, snooze, store::Store, telemetry::redis_span,
+    Redis, RedisPool,
+};
+
+fn key(task_id: Uuid) -> String {
+    format!("comments:{}", task_id)
+}
+
+/// A task's comments, oldest first.
+#[tracing::instrument(skip(store, pool))]
+pub async fn list_comments(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    Redis(pool): Redis,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<Comment>>, ApiError> {
+    store.get(id).await?.ok_or(ApiError::NotFound)?;
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let start = pagination.offset() as isize;
+    let stop = start + pagination.per_page() as isize - 1;
+    let (comments, total): (Vec<String>, u64) = redis::pipe()
+        .lrange(key(id), start, stop)
+        .llen(key(id))
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    let comments = comments
+        .iter()
+        .filter_map(|json| serde_json::from_str(json).ok())
+        .collect();
+    Ok(Json(pagination.respond(comments, total)))
+}
+
+#[tracing::instrument(skip(store, pool, moderation, request))]
+pub async fn add_comment(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    Redis(pool): Redis,
+    moderation: Moderation,
+    Json(request): Json<CreateCommentRequest>,
+) -> Result<(StatusCode, Json<Comment>), ApiError> {
+    let body = request.body.trim();
+    if body.is_empty() {
+        return Err(ApiError::Validation("Write something before posting the comment.".to_string()));
+    }
+    if body.chars().count() > Comment::MAX_CHARS {
+        return Err(ApiError::Validation(format!(
+            "Comments can be at most {} characters long.",
+            Comment::MAX_CHARS
+        )));
+    }
+    store.get(id).await?.ok_or(ApiError::NotFound)?;
+
+    let comment = Comment {
+        id: Uuid::new_v4(),
+        task_id: id,
+        body: body.to_string(),
+        created_at: snooze::now_secs(),
+    };
+    moderation.review_comment(&comment)?;
+
+    let json = serde_json::to_string(&comment)?;
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    conn.rpush::<_, _, ()>(key(id), json)
+        .instrument(redis_span("RPUSH"))
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// Deletes a task's comments. Failures are logged rather than failing the
+/// purge that triggered them.
+pub async fn forget(redis: &Option<RedisPool>, task_id: Uuid) {
+    let Some(pool) = redis else {
+        return;
+    };
+
+    let result = async {
+        let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+        conn.del::<_, ()>(key(task_id))
+            .instrument(redis_span("DEL"))
+            .await
+    }
+    .await;
+
+    if let Err(e) = result {
+        tracing::warn!(task = %task_id, "failed to delete comments: {}", e);
+    }
+}
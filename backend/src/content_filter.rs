@@ -0,0 +1,312 @@
+//! Content filtering for public deployments.
+//!
+//! A [`ContentFilter`] looks at the text of every task created or edited,
+//! and of every comment.
+//! What happens when it objects depends on the [`FilterMode`]: `off` skips
+//! it, `flag` saves the task marked for review, `reject` refuses it with 422.
+//!
+//! The filter is a no-op unless `CONTENT_FILTER_WORDS` (comma-separated) or
+//! `CONTENT_FILTER_WORDLIST` (a file, one word per line) is set; the mode
+//! defaults to `CONTENT_FILTER_MODE`, or `reject` when words are configured.
+//! Admins can override the mode globally in `content_filter:mode` and per
+//! workspace in the `content_filter:workspaces` hash, like feature flags.
+//!
+//! The workspace is whatever `X-Workspace-Id` a client sends, so it can't
+//! decide how strictly that client is moderated: every request gets the
+//! strictest mode set anywhere. A workspace override can tighten the filter
+//! but never loosen it.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::request::Parts,
+    response::{IntoResponse, Json, Response},
+};
+use redis::AsyncCommands;
+use shared::{Comment, ContentFilterSettings, FilterMode, SetFilterModeRequest, Task};
+use std::{collections::HashSet, sync::Arc};
+use tracing::Instrument;
+
+use crate::{error::ApiError, telemetry::redis_span, AppState, Redis, RedisPool};
+
+const GLOBAL_KEY: &str = "content_filter:mode";
+const WORKSPACES_KEY: &str = "content_filter:workspaces";
+
+pub trait ContentFilter: Send + Sync {
+    /// Human-readable description, e.g. for the Admin page.
+    fn describe(&self) -> String;
+
+    /// What the filter objects to in `text`, if anything.
+    fn check(&self, text: &str) -> Option<String>;
+}
+
+/// Lets everything through.
+pub struct NoFilter;
+
+impl ContentFilter for NoFilter {
+    fn describe(&self) -> String {
+        "none".to_string()
+    }
+
+    fn check(&self, _text: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Objects to any of a list of words. Whole words only, ignoring case, so
+/// innocent words that contain a listed one aren't caught.
+pub struct WordList {
+    words: HashSet<String>,
+}
+
+impl WordList {
+    pub fn new(words: impl IntoIterator<Item = String>) -> Self {
+        let words = words
+            .into_iter()
+            .map(|word| word.trim().to_lowercase())
+            .filter(|word| !word.is_empty())
+            .collect();
+        Self { words }
+    }
+}
+
+impl ContentFilter for WordList {
+    fn describe(&self) -> String {
+        format!("word list ({} words)", self.words.len())
+    }
+
+    fn check(&self, text: &str) -> Option<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase)
+            .find(|word| self.words.contains(word))
+    }
+}
+
+pub struct FilterConfig {
+    filter: Arc<dyn ContentFilter>,
+    default_mode: FilterMode,
+}
+
+impl FilterConfig {
+    pub fn from_env() -> Result<Self, String> {
+        let mut words: Vec<String> = crate::config::var("CONTENT_FILTER_WORDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::to_string)
+            .collect();
+        if let Some(path) = crate::config::var("CONTENT_FILTER_WORDLIST") {
+            let list = std::fs::read_to_string(&path).map_err(|e| format!("CONTENT_FILTER_WORDLIST {}: {}", path, e))?;
+            words.extend(list.lines().map(str::to_string));
+        }
+
+        let list = WordList::new(words);
+        let (filter, fallback): (Arc<dyn ContentFilter>, FilterMode) = if list.words.is_empty() {
+            (Arc::new(NoFilter), FilterMode::Off)
+        } else {
+            (Arc::new(list), FilterMode::Reject)
+        };
+
+        let default_mode = match crate::config::var("CONTENT_FILTER_MODE") {
+            Some(mode) => FilterMode::from_name(mode.trim())
+                .ok_or_else(|| format!("CONTENT_FILTER_MODE must be off, flag or reject, not {}", mode))?,
+            None => fallback,
+        };
+
+        Ok(Self { filter, default_mode })
+    }
+}
+
+/// Refusal of a task under `reject`.
+pub struct Rejected;
+
+impl Rejected {
+    pub const MESSAGE: &'static str = "This task contains words that aren't allowed here. Please reword it and try again.";
+}
+
+impl IntoResponse for Rejected {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
+    }
+}
+
+/// Extractor yielding the filter and the mode requests are moderated under.
+pub struct Moderation {
+    filter: Arc<dyn ContentFilter>,
+    mode: FilterMode,
+}
+
+impl Moderation {
+    #[cfg(test)]
+    pub(crate) fn new(filter: Arc<dyn ContentFilter>, mode: FilterMode) -> Self {
+        Self { filter, mode }
+    }
+
+    /// Checks the task's text before it is saved, updating
+    /// [`Task::flagged`] under `flag`. Edits that leave the text as it was in
+    /// `before` pass untouched, so an old task can still be completed.
+    pub fn review(&self, before: Option<&Task>, task: &mut Task) -> Result<(), Rejected> {
+        let unchanged = before.is_some_and(|before| {
+            before.title == task.title && before.description == task.description && before.tags == task.tags
+        });
+        if self.mode == FilterMode::Off || unchanged {
+            return Ok(());
+        }
+
+        let found = [task.title.as_str(), task.description.as_str()]
+            .into_iter()
+            .chain(task.tags.iter().map(String::as_str))
+            .find_map(|text| self.filter.check(text));
+        match (&found, self.mode) {
+            (Some(word), FilterMode::Reject) => {
+                tracing::info!(task = %task.id, word = %word, "content filter rejected task");
+                return Err(Rejected);
+            }
+            (Some(word), _) => tracing::warn!(task = %task.id, word = %word, "content filter flagged task"),
+            (None, _) => {}
+        }
+        task.flagged = found.is_some();
+        Ok(())
+    }
+
+    /// Checks a comment before it is saved. Comments can't be flagged, so
+    /// under `flag` an objection is only logged.
+    pub fn review_comment(&self, comment: &Comment) -> Result<(), ApiError> {
+        if self.mode == FilterMode::Off {
+            return Ok(());
+        }
+        match (self.filter.check(&comment.body), self.mode) {
+            (Some(word), FilterMode::Reject) => {
+                tracing::info!(task = %comment.task_id, word = %word, "content filter rejected comment");
+                Err(ApiError::Validation(
+                    "This comment contains words that aren't allowed here. Please reword it and try again.".to_string(),
+                ))
+            }
+            (Some(word), _) => {
+                tracing::warn!(task = %comment.task_id, word = %word, "content filter flagged comment");
+                Ok(())
+            }
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+/// The strictest of the global mode and every workspace's.
+async fn resolve_mode(config: &FilterConfig, pool: Option<&RedisPool>) -> Result<FilterMode, ApiError> {
+    let Some(pool) = pool else {
+        return Ok(config.default_mode);
+    };
+
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let (global, workspaces): (Option<String>, Vec<String>) = redis::pipe()
+        .get(GLOBAL_KEY)
+        .hvals(WORKSPACES_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    let global = global.as_deref().and_then(FilterMode::from_name).unwrap_or(config.default_mode);
+    Ok(workspaces
+        .iter()
+        .filter_map(|mode| FilterMode::from_name(mode))
+        .fold(global, FilterMode::max))
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Moderation {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let mode = resolve_mode(&state.content_filter, state.redis.as_ref()).await?;
+        Ok(Moderation {
+            filter: state.content_filter.filter.clone(),
+            mode,
+        })
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_settings(State(state): State<AppState>) -> Result<Json<ContentFilterSettings>, ApiError> {
+    let config = &state.content_filter;
+    let mut settings = ContentFilterSettings {
+        filter: config.filter.describe(),
+        mode: config.default_mode,
+        workspaces: Default::default(),
+    };
+    let Some(pool) = &state.redis else {
+        return Ok(Json(settings));
+    };
+
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+    let (global, workspaces): (Option<String>, Vec<(String, String)>) = redis::pipe()
+        .get(GLOBAL_KEY)
+        .hgetall(WORKSPACES_KEY)
+        .query_async(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    if let Some(mode) = global.as_deref().and_then(FilterMode::from_name) {
+        settings.mode = mode;
+    }
+    settings.workspaces = workspaces
+        .into_iter()
+        .filter_map(|(workspace, mode)| Some((workspace, FilterMode::from_name(&mode)?)))
+        .collect();
+    Ok(Json(settings))
+}
+
+#[tracing::instrument(skip(state, pool))]
+pub async fn set_mode(
+    State(state): State<AppState>,
+    Redis(pool): Redis,
+    Json(payload): Json<SetFilterModeRequest>,
+) -> Result<Json<ContentFilterSettings>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let mode = payload.mode.name();
+    match payload.workspace.as_deref().map(str::trim).filter(|w| !w.is_empty()) {
+        Some(workspace) => conn
+            .hset::<_, _, _, ()>(WORKSPACES_KEY, workspace, mode)
+            .instrument(redis_span("HSET"))
+            .await?,
+        None => conn
+            .set::<_, _, ()>(GLOBAL_KEY, mode)
+            .instrument(redis_span("SET"))
+            .await?,
+    }
+
+    get_settings(State(state)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(words: &[&str]) -> WordList {
+        WordList::new(words.iter().map(|word| word.to_string()))
+    }
+
+    #[test]
+    fn listed_words_are_found_whole_and_in_any_case() {
+        let filter = list(&["spam", " Scam "]);
+        assert_eq!(filter.check("Buy SPAM today"), Some("spam".to_string()));
+        assert_eq!(filter.check("scam!"), Some("scam".to_string()));
+        assert_eq!(filter.check("no-spam-here"), Some("spam".to_string()));
+        assert_eq!(filter.check("spammer spamspam"), None);
+        assert_eq!(filter.check(""), None);
+    }
+
+    #[test]
+    fn words_are_split_on_anything_but_letters_and_digits() {
+        let filter = list(&["café", "42"]);
+        assert_eq!(filter.check("Meet at the CAFÉ"), Some("café".to_string()));
+        assert_eq!(filter.check("answer:42."), Some("42".to_string()));
+        assert_eq!(filter.check("cafés 420"), None);
+    }
+
+    #[test]
+    fn blank_words_are_dropped() {
+        let filter = list(&["", "  ", "spam"]);
+        assert_eq!(filter.words.len(), 1);
+        assert_eq!(filter.check("two  spaces"), None);
+    }
+}
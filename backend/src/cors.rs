@@ -0,0 +1,155 @@
+//! Which browser origins may call the API, with which methods and headers.
+//!
+//! The frontend is served from the same origin as the API, so CORS only
+//! comes into play for other sites and tools calling it from a browser.
+//! Without `CORS_ORIGINS` only `localhost`, `127.0.0.1` and `[::1]` origins
+//! are let in, on any port, which covers a frontend dev server and local
+//! tools; a deployment lists the origins it trusts instead, or `*` for any.
+//! Credentials are never allowed cross-origin. Preflight answers are cached
+//! by browsers for `CORS_MAX_AGE_SECS`.
+//!
+//! `POST /api/capture` has its own rules for browser extensions; see
+//! [`crate::capture`].
+
+use axum::http::{header, request::Parts, HeaderName, HeaderValue, Method};
+use std::{str::FromStr, time::Duration};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{config, experiments::CLIENT_HEADER, flags::WORKSPACE_HEADER, telemetry::TRACE_ID_HEADER};
+
+const DEFAULT_METHODS: [Method; 5] = [Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE];
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+#[derive(Debug, Clone)]
+pub enum Origins {
+    /// Origins on this machine, the default.
+    Local,
+    Any,
+    List(Vec<HeaderValue>),
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    pub origins: Origins,
+    pub methods: Vec<Method>,
+    pub headers: Vec<HeaderName>,
+    pub max_age: Duration,
+}
+
+impl CorsConfig {
+    /// Reads `CORS_ORIGINS`, `CORS_METHODS` and `CORS_HEADERS`, all
+    /// comma-separated, and `CORS_MAX_AGE_SECS`.
+    pub fn from_env() -> Result<Self, String> {
+        let origins = match config::parse_var("CORS_ORIGINS", |value| list(value, parse_origin))? {
+            None => Origins::Local,
+            Some(origins) if origins.is_empty() => Origins::Local,
+            Some(origins) if origins.iter().any(|origin| origin == "*") => Origins::Any,
+            Some(origins) => Origins::List(origins),
+        };
+        let methods = config::parse_var("CORS_METHODS", |value| {
+            list(value, |method| Method::from_str(&method.to_ascii_uppercase()).map_err(|e| e.to_string()))
+        })?
+        .unwrap_or_else(|| DEFAULT_METHODS.to_vec());
+        let headers = config::parse_var("CORS_HEADERS", |value| {
+            list(value, |name| HeaderName::from_str(name).map_err(|e| e.to_string()))
+        })?
+        .unwrap_or_else(default_headers);
+        let max_age = config::parse_var("CORS_MAX_AGE_SECS", u64::from_str)?.unwrap_or(3_600);
+
+        Ok(Self {
+            origins,
+            methods,
+            headers,
+            max_age: Duration::from_secs(max_age),
+        })
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let origins = match &self.origins {
+            Origins::Local => AllowOrigin::predicate(|origin: &HeaderValue, _: &Parts| is_local(origin)),
+            Origins::Any => AllowOrigin::any(),
+            Origins::List(origins) => AllowOrigin::list(origins.clone()),
+        };
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(self.methods.clone())
+            .allow_headers(self.headers.clone())
+            .expose_headers([header::ETAG, header::RETRY_AFTER, TRACE_ID_HEADER])
+            .max_age(self.max_age)
+    }
+
+    /// The allowed origins, for the admin config page.
+    pub fn describe_origins(&self) -> String {
+        match &self.origins {
+            Origins::Local => "localhost only".to_string(),
+            Origins::Any => "any".to_string(),
+            Origins::List(origins) => {
+                let origins: Vec<&str> = origins.iter().filter_map(|origin| origin.to_str().ok()).collect();
+                origins.join(", ")
+            }
+        }
+    }
+}
+
+/// The headers the frontend sends.
+fn default_headers() -> Vec<HeaderName> {
+    vec![
+        header::CONTENT_TYPE,
+        header::IF_MATCH,
+        header::IF_NONE_MATCH,
+        HeaderName::from_static(CLIENT_HEADER),
+        HeaderName::from_static(WORKSPACE_HEADER),
+    ]
+}
+
+fn list<T>(value: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(parse).collect()
+}
+
+/// `*`, or a scheme and host with an optional port.
+pub fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let valid = origin == "*"
+        || origin.split_once("://").is_some_and(|(scheme, host)| {
+            matches!(scheme, "http" | "https") && !host.is_empty() && !host.contains('/')
+        });
+    if !valid {
+        return Err(format!("{:?} is not an origin like https://tasks.example.com", origin));
+    }
+    HeaderValue::from_str(origin).map_err(|e| e.to_string())
+}
+
+fn is_local(origin: &HeaderValue) -> bool {
+    let Some((_, host)) = origin.to_str().ok().and_then(|origin| origin.split_once("://")) else {
+        return false;
+    };
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => host,
+    };
+    LOCAL_HOSTS.contains(&host)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn origins_are_scheme_and_host() {
+        assert!(parse_origin("https://tasks.example.com").is_ok());
+        assert!(parse_origin("http://localhost:8080").is_ok());
+        assert!(parse_origin("*").is_ok());
+        assert!(parse_origin("tasks.example.com").is_err());
+        assert!(parse_origin("https://tasks.example.com/app").is_err());
+    }
+
+    #[test]
+    fn only_this_machine_is_local() {
+        let local = |origin: &str| is_local(&HeaderValue::from_str(origin).unwrap());
+        assert!(local("http://localhost:8080"));
+        assert!(local("http://127.0.0.1"));
+        assert!(local("http://[::1]:3000"));
+        assert!(!local("https://localhost.example.com"));
+        assert!(!local("https://tasks.example.com"));
+        assert!(!local("null"));
+    }
+}
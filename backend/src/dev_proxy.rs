@@ -0,0 +1,101 @@
+//! Development mode: proxy non-API requests to a frontend dev server.
+//!
+//! When `DEV_PROXY` is set (e.g. `http://127.0.0.1:8080` for `trunk serve`),
+//! every path the router doesn't handle is forwarded there instead of being
+//! served from `frontend/dist`, so frontend changes hot-reload while still
+//! talking to the real API. WebSocket upgrades are tunnelled too, which is
+//! what the dev server's live-reload script uses.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, uri::PathAndQuery, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use hyper_util::{
+    client::legacy::{connect::HttpConnector, Client},
+    rt::{TokioExecutor, TokioIo},
+};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct DevProxy {
+    client: Client<HttpConnector, Body>,
+    upstream: Uri,
+}
+
+impl DevProxy {
+    pub fn from_env() -> Option<Arc<Self>> {
+        let upstream = crate::config::var("DEV_PROXY")?;
+        let upstream: Uri = match upstream.parse() {
+            Ok(uri) => uri,
+            Err(e) => {
+                tracing::warn!("ignoring invalid DEV_PROXY {:?}: {}", upstream, e);
+                return None;
+            }
+        };
+
+        Some(Arc::new(Self {
+            client: Client::builder(TokioExecutor::new()).build_http(),
+            upstream,
+        }))
+    }
+
+    pub fn upstream(&self) -> &Uri {
+        &self.upstream
+    }
+
+    fn target(&self, uri: &Uri) -> Result<Uri, StatusCode> {
+        let mut parts = self.upstream.clone().into_parts();
+        parts.path_and_query = Some(
+            uri.path_and_query()
+                .cloned()
+                .unwrap_or_else(|| PathAndQuery::from_static("/")),
+        );
+        Uri::from_parts(parts).map_err(|_| StatusCode::BAD_GATEWAY)
+    }
+}
+
+fn is_upgrade(request: &Request) -> bool {
+    request
+        .headers()
+        .get(header::CONNECTION)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.to_ascii_lowercase().contains("upgrade"))
+}
+
+pub async fn proxy(State(proxy): State<Arc<DevProxy>>, mut request: Request) -> Response {
+    let target = match proxy.target(request.uri()) {
+        Ok(target) => target,
+        Err(status) => return status.into_response(),
+    };
+
+    let upgrade = is_upgrade(&request);
+    let client_upgrade = upgrade.then(|| hyper::upgrade::on(&mut request));
+
+    let (mut parts, body) = request.into_parts();
+    parts.uri = target;
+    parts.headers.remove(header::HOST);
+
+    let mut response = match proxy.client.request(Request::from_parts(parts, body)).await {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!("dev proxy request to {} failed: {}", proxy.upstream, e);
+            return (StatusCode::BAD_GATEWAY, "Dev server unavailable").into_response();
+        }
+    };
+
+    if let Some(client_upgrade) = client_upgrade {
+        if response.status() == StatusCode::SWITCHING_PROTOCOLS {
+            let upstream_upgrade = hyper::upgrade::on(&mut response);
+            tokio::spawn(async move {
+                let (Ok(client), Ok(upstream)) = (client_upgrade.await, upstream_upgrade.await) else {
+                    return;
+                };
+                let _ = tokio::io::copy_bidirectional(&mut TokioIo::new(client), &mut TokioIo::new(upstream)).await;
+            });
+        }
+    }
+
+    response.map(Body::new)
+}
@@ -0,0 +1,73 @@
+//! `POST /api/tasks/:id/edits`: stamped edits merged without conflicts.
+//!
+//! Experimental, behind the `crdt_sync` flag. A device sends the edits it
+//! made, possibly while offline, each with the time and replica it was made
+//! on; [`shared::crdt`] merges them so every device converges on the same
+//! task whatever order the edits arrive in. That also means a save that
+//! races another write can just merge the edits into the newer task and try
+//! again.
+
+use axum::{
+    extract::{Path, State},
+    response::{Json, Response},
+};
+use shared::{crdt::TaskEdit, flags as flag_names, validation, TaskEvent};
+use uuid::Uuid;
+
+use crate::{
+    activity, analytics, automations::Automations, content_filter::Moderation, error::ApiError, etag, flags::Flags, live, snooze, store::Store, RedisPool,
+};
+
+/// Saves tried before giving up on a task that keeps changing.
+const MAX_ATTEMPTS: usize = 5;
+
+#[tracing::instrument(skip(store, redis, live, flags, moderation, automations, edits))]
+#[allow(clippy::too_many_arguments)]
+pub async fn apply_edits(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    Flags(flags): Flags,
+    moderation: Moderation,
+    automations: Automations,
+    Json(edits): Json<Vec<TaskEdit>>,
+) -> Result<Response, ApiError> {
+    if !flags.is_enabled(flag_names::CRDT_SYNC) {
+        return Err(ApiError::NotFound);
+    }
+
+    let mut attempt = 1;
+    let (before, task) = loop {
+        let before = store
+            .get(id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+
+        let mut task = before.clone();
+        for edit in edits.iter().cloned() {
+            task.apply_edit(edit);
+        }
+        task.stamp_completion(before.completed, snooze::now_secs());
+        validation::check_task(Some(&before), &mut task).map_err(ApiError::Fields)?;
+        moderation.review(Some(&before), &mut task)?;
+
+        if store.save_if_version(&mut task, before.version).await? {
+            break (before, task);
+        }
+        if attempt == MAX_ATTEMPTS {
+            let latest = store
+                .get(id)
+                .await?
+                .ok_or(ApiError::NotFound)?;
+            return Ok(etag::conflict(latest));
+        }
+        attempt += 1;
+    };
+    activity::record(&redis, activity::update_kind(&before, &task), &task).await;
+    analytics::record(&redis, Some(&before), Some(&task)).await;
+    live.publish(TaskEvent::Updated { task: task.clone() });
+    automations.saved(Some(&before), &task);
+
+    Ok(etag::respond(task))
+}
@@ -0,0 +1,133 @@
+//! Errors returned by handlers.
+//!
+//! Handlers return [`ApiError`] and use `?` on storage, Redis and JSON
+//! results; the conversions pick the status. Every error is answered with a
+//! JSON [`ErrorBody`] whose message the frontend shows as is. Details of
+//! internal errors stay in the log, under an error id the body carries.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use shared::{validation::FieldError, ErrorBody};
+use uuid::Uuid;
+
+use crate::{content_filter::Rejected, store::StoreError};
+
+const STORAGE_UNAVAILABLE: &str = "Storage is unavailable right now. Please try again in a moment.";
+
+#[derive(Debug)]
+pub enum ApiError {
+    /// 503: a service the request needs can't be reached, or isn't
+    /// configured. The message is shown to the user.
+    Unavailable(String),
+    /// 500: a stored record couldn't be decoded, or something else that
+    /// isn't the client's fault. The message is only logged.
+    Internal(String),
+    /// 422: the request was understood but can't be carried out. The
+    /// message is shown to the user.
+    Validation(String),
+    /// 422: fields of a task that are out of bounds, each with a message
+    /// to show under its input.
+    Fields(Vec<FieldError>),
+    /// 404.
+    NotFound,
+    /// Any other status, e.g. 400 or 409, with its standard reason.
+    Status(StatusCode),
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Validation(_) | ApiError::Fields(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Status(status) => *status,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body = match self {
+            ApiError::Unavailable(message) => body("unavailable", message, None),
+            ApiError::Internal(detail) => {
+                let error_id = Uuid::new_v4().simple().to_string();
+                tracing::error!(error_id = %error_id, "internal error: {}", detail);
+                body("internal", "Something went wrong on the server.".to_string(), Some(error_id))
+            }
+            ApiError::Validation(message) => body("validation", message, None),
+            ApiError::Fields(fields) => {
+                let message = fields.iter().map(|f| f.message.as_str()).collect::<Vec<_>>().join(" ");
+                ErrorBody {
+                    fields,
+                    ..body("validation", message, None)
+                }
+            }
+            ApiError::NotFound => body("not_found", "Not found.".to_string(), None),
+            ApiError::Status(status) => {
+                let reason = status.canonical_reason().unwrap_or("Error");
+                body(&reason.to_lowercase().replace([' ', '-'], "_"), format!("{}.", reason), None)
+            }
+        };
+        (status, Json(body)).into_response()
+    }
+}
+
+fn body(error: &str, message: String, error_id: Option<String>) -> ErrorBody {
+    ErrorBody {
+        error: error.to_string(),
+        message,
+        error_id,
+        fields: Vec::new(),
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(e: StoreError) -> Self {
+        match e {
+            StoreError::Unavailable(detail) => {
+                tracing::warn!("storage unavailable: {}", detail);
+                ApiError::Unavailable(STORAGE_UNAVAILABLE.to_string())
+            }
+            StoreError::Corrupt(detail) => ApiError::Internal(format!("corrupt record: {}", detail)),
+        }
+    }
+}
+
+impl From<redis::RedisError> for ApiError {
+    /// Lost or refused connections and timeouts (including waiting on the
+    /// pool) mean Redis is unavailable; anything else, like a reply that
+    /// doesn't decode, is internal.
+    fn from(e: redis::RedisError) -> Self {
+        if e.is_io_error() || e.is_connection_refusal() || e.is_connection_dropped() || e.is_timeout() {
+            tracing::warn!("Redis unavailable: {}", e);
+            ApiError::Unavailable(STORAGE_UNAVAILABLE.to_string())
+        } else {
+            ApiError::Internal(format!("Redis: {}", e))
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        ApiError::Internal(format!("JSON: {}", e))
+    }
+}
+
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => ApiError::NotFound,
+            status => ApiError::Status(status),
+        }
+    }
+}
+
+impl From<Rejected> for ApiError {
+    fn from(_: Rejected) -> Self {
+        ApiError::Validation(Rejected::MESSAGE.to_string())
+    }
+}
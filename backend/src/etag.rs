@@ -0,0 +1,111 @@
+//! Per-task ETags for conditional requests.
+//!
+//! The tag is the task's `version`, which goes up with every save and only
+//! then. `GET` honours `If-None-Match`; `PUT`, `PATCH` and `DELETE` honour
+//! `If-Match` and answer 412 when the client's copy is stale. They also
+//! answer 409 with the current task when the request names an older
+//! `version` or the task changed while the write was being made.
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use shared::Task;
+
+use crate::error::ApiError;
+
+pub fn of(task: &Task) -> String {
+    format!("\"v{}\"", task.version)
+}
+
+/// How a header's tags are compared with ours (RFC 9110, section 8.8.3.2).
+#[derive(Clone, Copy)]
+enum Comparison {
+    /// Weak tags never match; for `If-Match`.
+    Strong,
+    /// `W/` is ignored; for `If-None-Match`.
+    Weak,
+}
+
+fn matches(headers: &HeaderMap, name: header::HeaderName, etag: &str, comparison: Comparison) -> Option<bool> {
+    let value = headers.get(name)?.to_str().ok()?;
+    Some(value.split(',').map(str::trim).any(|candidate| {
+        candidate == "*"
+            || match comparison {
+                Comparison::Strong => candidate == etag,
+                Comparison::Weak => candidate.trim_start_matches("W/") == etag,
+            }
+    }))
+}
+
+/// Rejects a write whose `If-Match` doesn't name the task's current ETag.
+pub fn check_if_match(headers: &HeaderMap, task: &Task) -> Result<(), ApiError> {
+    match matches(headers, header::IF_MATCH, &of(task), Comparison::Strong) {
+        Some(false) => Err(ApiError::Status(StatusCode::PRECONDITION_FAILED)),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a `GET` can be answered with 304.
+pub fn not_modified(headers: &HeaderMap, task: &Task) -> bool {
+    matches(headers, header::IF_NONE_MATCH, &of(task), Comparison::Weak).unwrap_or(false)
+}
+
+/// 409 with the task as it is now, for a write made against an older
+/// version, so the client can show what changed.
+pub fn conflict(task: Task) -> Response {
+    let mut response = respond(task);
+    *response.status_mut() = StatusCode::CONFLICT;
+    response
+}
+
+/// The task as JSON with its `ETag` header.
+pub fn respond(task: Task) -> Response {
+    let mut response = Json(&task).into_response();
+    if let Ok(value) = HeaderValue::from_str(&of(&task)) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with(name: header::HeaderName, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn task(version: u64) -> Task {
+        let mut task = Task::new("Buy milk".to_string(), String::new());
+        task.version = version;
+        task
+    }
+
+    #[test]
+    fn the_tag_follows_the_version_only() {
+        let mut edited = task(3);
+        assert_eq!(of(&edited), "\"v3\"");
+        edited.title = "Buy oat milk".to_string();
+        assert_eq!(of(&edited), "\"v3\"");
+    }
+
+    #[test]
+    fn if_match_compares_strongly() {
+        assert!(check_if_match(&HeaderMap::new(), &task(2)).is_ok());
+        assert!(check_if_match(&with(header::IF_MATCH, "\"v1\", \"v2\""), &task(2)).is_ok());
+        assert!(check_if_match(&with(header::IF_MATCH, "*"), &task(2)).is_ok());
+        assert!(check_if_match(&with(header::IF_MATCH, "W/\"v2\""), &task(2)).is_err());
+        assert!(check_if_match(&with(header::IF_MATCH, "\"v1\""), &task(2)).is_err());
+    }
+
+    #[test]
+    fn if_none_match_compares_weakly() {
+        assert!(not_modified(&with(header::IF_NONE_MATCH, "W/\"v2\""), &task(2)));
+        assert!(not_modified(&with(header::IF_NONE_MATCH, "\"v2\""), &task(2)));
+        assert!(!not_modified(&with(header::IF_NONE_MATCH, "\"v1\""), &task(2)));
+        assert!(!not_modified(&HeaderMap::new(), &task(2)));
+    }
+}
@@ -0,0 +1,185 @@
+//! A/B experiments built on top of the flag system.
+//!
+//! Experiments are configured through the `EXPERIMENTS` environment variable
+//! (`create_form=classic|compact;other=a|b`); the first variant is the control.
+//! Subjects (the `X-Client-Id` user, falling back to the workspace) are bucketed
+//! deterministically, so the same user always sees the same variant.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query, State},
+    http::{request::Parts, StatusCode},
+    response::Json,
+};
+use redis::AsyncCommands;
+use shared::{ExperimentAssignments, ExperimentResults, ExposureRequest, ListResponse, VariantResults};
+use std::{collections::BTreeMap, convert::Infallible};
+use tracing::Instrument;
+
+use crate::{error::ApiError, flags::workspace_from_headers, pagination::Pagination, telemetry::redis_span, AppState, Redis};
+
+pub const CLIENT_HEADER: &str = "x-client-id";
+
+#[derive(Debug, Clone)]
+pub struct ExperimentConfig {
+    experiments: BTreeMap<String, Vec<String>>,
+}
+
+impl ExperimentConfig {
+    pub fn from_env() -> Self {
+        let spec = crate::config::var("EXPERIMENTS").unwrap_or_default();
+        Self::parse(&spec)
+    }
+
+    fn parse(spec: &str) -> Self {
+        let experiments = spec
+            .split(';')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(name, variants)| {
+                let variants: Vec<String> = variants
+                    .split('|')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                (name.trim().to_string(), variants)
+            })
+            .filter(|(name, variants)| !name.is_empty() && !variants.is_empty())
+            .collect();
+
+        Self { experiments }
+    }
+
+    fn variants(&self, experiment: &str) -> Option<&[String]> {
+        self.experiments.get(experiment).map(Vec::as_slice)
+    }
+
+    pub fn assign(&self, subject: &str) -> ExperimentAssignments {
+        ExperimentAssignments(
+            self.experiments
+                .iter()
+                .map(|(name, variants)| {
+                    let index = bucket(name, subject, variants.len());
+                    (name.clone(), variants[index].clone())
+                })
+                .collect(),
+        )
+    }
+}
+
+/// FNV-1a over `experiment:subject`; stable across builds, unlike `DefaultHasher`.
+fn bucket(experiment: &str, subject: &str, buckets: usize) -> usize {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in experiment.bytes().chain([b':']).chain(subject.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    (hash % buckets as u64) as usize
+}
+
+fn exposures_key(experiment: &str) -> String {
+    format!("experiments:{}:exposures", experiment)
+}
+
+fn subjects_key(experiment: &str, variant: &str) -> String {
+    format!("experiments:{}:subjects:{}", experiment, variant)
+}
+
+/// The unit an experiment is randomized over for the current request.
+pub struct Subject(pub String);
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for Subject {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let client = parts
+            .headers
+            .get(CLIENT_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string);
+
+        Ok(Subject(
+            client
+                .or_else(|| workspace_from_headers(&parts.headers))
+                .unwrap_or_else(|| "anonymous".to_string()),
+        ))
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_assignments(
+    State(state): State<AppState>,
+    Subject(subject): Subject,
+) -> Json<ExperimentAssignments> {
+    Json(state.experiments.assign(&subject))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn log_exposure(
+    State(state): State<AppState>,
+    Redis(pool): Redis,
+    Subject(subject): Subject,
+    Json(payload): Json<ExposureRequest>,
+) -> Result<StatusCode, ApiError> {
+    // Only accept exposures the server would actually have assigned.
+    let assigned = state.experiments.assign(&subject);
+    if state.experiments.variants(&payload.experiment).is_none()
+        || assigned.variant(&payload.experiment) != Some(payload.variant.as_str())
+    {
+        return Err(ApiError::Status(StatusCode::BAD_REQUEST));
+    }
+
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    redis::pipe()
+        .hincr(exposures_key(&payload.experiment), &payload.variant, 1)
+        .ignore()
+        .sadd(subjects_key(&payload.experiment, &payload.variant), &subject)
+        .ignore()
+        .query_async::<_, ()>(&mut conn)
+        .instrument(redis_span("PIPELINE"))
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_results(
+    State(state): State<AppState>,
+    Redis(pool): Redis,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<ExperimentResults>>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let experiments = &state.experiments.experiments;
+    let mut results = Vec::new();
+    for (experiment, variants) in experiments.iter().skip(pagination.offset()).take(pagination.per_page() as usize) {
+        let exposures: BTreeMap<String, u64> = conn
+            .hgetall(exposures_key(experiment))
+            .instrument(redis_span("HGETALL"))
+            .await?;
+
+        let mut variant_results = Vec::new();
+        for variant in variants {
+            let unique_subjects: u64 = conn
+                .scard(subjects_key(experiment, variant))
+                .instrument(redis_span("SCARD"))
+                .await?;
+            variant_results.push(VariantResults {
+                variant: variant.clone(),
+                exposures: exposures.get(variant).copied().unwrap_or(0),
+                unique_subjects,
+            });
+        }
+
+        results.push(ExperimentResults {
+            experiment: experiment.clone(),
+            variants: variant_results,
+        });
+    }
+
+    Ok(Json(pagination.respond(results, experiments.len() as u64)))
+}
@@ -0,0 +1,148 @@
+//! Feature flags.
+//!
+//! Defaults come from the `FEATURE_FLAGS` environment variable
+//! (`boards=on,analytics_v2=off`); they can be overridden globally through the
+//! `flags` Redis hash and per workspace through `flags:workspace:<id>`.
+//! Flags are evaluated for every request so toggles take effect immediately.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    http::{request::Parts, HeaderMap},
+    response::Json,
+};
+use redis::AsyncCommands;
+use shared::{flags, FeatureFlags, SetFlagRequest};
+use std::collections::{BTreeMap, HashMap};
+use tracing::Instrument;
+
+use crate::{admin::AdminToken, error::ApiError, telemetry::redis_span, AppState, Redis, RedisPool};
+
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+
+const GLOBAL_KEY: &str = "flags";
+
+#[derive(Debug, Clone)]
+pub struct FlagConfig {
+    defaults: BTreeMap<String, bool>,
+}
+
+impl FlagConfig {
+    pub fn from_env() -> Self {
+        let spec = crate::config::var("FEATURE_FLAGS").unwrap_or_default();
+        Self::parse(&spec)
+    }
+
+    fn parse(spec: &str) -> Self {
+        // Every known flag is listed so the frontend sees the full set, even when off.
+        let mut defaults: BTreeMap<String, bool> = [flags::BOARDS, flags::ANALYTICS_V2, flags::CRDT_SYNC]
+            .iter()
+            .map(|name| (name.to_string(), false))
+            .collect();
+
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (name, value) = match entry.split_once('=') {
+                Some((name, value)) => (name.trim(), parse_bool(value).unwrap_or(false)),
+                None => (entry, true),
+            };
+            defaults.insert(name.to_string(), value);
+        }
+
+        Self { defaults }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "on" | "yes" => Some(true),
+        "0" | "false" | "off" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+fn workspace_key(workspace: &str) -> String {
+    format!("flags:workspace:{}", workspace)
+}
+
+pub fn workspace_from_headers(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(WORKSPACE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Without Redis there is nowhere to keep overrides, so the defaults apply.
+pub async fn evaluate(
+    config: &FlagConfig,
+    pool: Option<&RedisPool>,
+    workspace: Option<&str>,
+) -> Result<FeatureFlags, ApiError> {
+    let mut resolved = config.defaults.clone();
+    let Some(pool) = pool else {
+        return Ok(FeatureFlags(resolved));
+    };
+
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let global: HashMap<String, String> = conn.hgetall(GLOBAL_KEY).instrument(redis_span("HGETALL")).await?;
+    apply_overrides(&mut resolved, global);
+
+    if let Some(workspace) = workspace {
+        let scoped: HashMap<String, String> = conn
+            .hgetall(workspace_key(workspace))
+            .instrument(redis_span("HGETALL"))
+            .await?;
+        apply_overrides(&mut resolved, scoped);
+    }
+
+    Ok(FeatureFlags(resolved))
+}
+
+fn apply_overrides(resolved: &mut BTreeMap<String, bool>, overrides: HashMap<String, String>) {
+    for (name, value) in overrides {
+        if let Some(enabled) = parse_bool(&value) {
+            resolved.insert(name, enabled);
+        }
+    }
+}
+
+/// Extractor yielding the flags evaluated for the requesting workspace.
+pub struct Flags(pub FeatureFlags);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Flags {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let workspace = workspace_from_headers(&parts.headers);
+        evaluate(&state.flags, state.redis.as_ref(), workspace.as_deref()).await.map(Flags)
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_flags(Flags(flags): Flags) -> Json<FeatureFlags> {
+    Json(flags)
+}
+
+/// Overrides a flag globally or for one workspace; admins only.
+#[tracing::instrument(skip(_admin, state, pool, payload))]
+pub async fn set_flag(
+    _admin: AdminToken,
+    Path(name): Path<String>,
+    State(state): State<AppState>,
+    Redis(pool): Redis,
+    Json(payload): Json<SetFlagRequest>,
+) -> Result<Json<FeatureFlags>, ApiError> {
+    let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+
+    let key = match payload.workspace.as_deref() {
+        Some(workspace) => workspace_key(workspace),
+        None => GLOBAL_KEY.to_string(),
+    };
+    let value = if payload.enabled { "on" } else { "off" };
+    conn.hset::<_, _, _, ()>(&key, &name, value).instrument(redis_span("HSET")).await?;
+
+    evaluate(&state.flags, Some(&pool), payload.workspace.as_deref()).await.map(Json)
+}
@@ -0,0 +1,518 @@
+#[cfg(feature = "embed-frontend")]
+mod assets;
+mod activity;
+mod admin;
+mod analytics;
+mod announcements;
+mod archive;
+mod attachments;
+mod automations;
+mod bulk;
+mod capture;
+mod changes;
+mod client_errors;
+mod comments;
+pub mod config;
+mod content_filter;
+pub mod cors;
+mod dev_proxy;
+mod edits;
+mod error;
+mod etag;
+mod experiments;
+mod flags;
+mod live;
+mod locations;
+mod logs;
+mod openapi;
+mod patch;
+mod pagination;
+mod plans;
+mod pwa;
+mod qr;
+mod rate_limit;
+mod recent;
+mod recurrence;
+pub mod redis_pool;
+mod release_notes;
+mod self_check;
+pub mod seed;
+mod seo;
+pub mod server;
+mod shell;
+mod snooze;
+mod status;
+pub mod store;
+pub mod telemetry;
+mod throttle;
+mod transfer;
+mod trash;
+mod version;
+
+use axum::{
+    async_trait,
+    extract::{DefaultBodyLimit, FromRef, FromRequestParts, Path, Query, State},
+    handler::Handler,
+    http::{request::Parts, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post, put},
+    Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use pagination::Pagination;
+use shared::{validation, ActivityKind, CreateTaskRequest, ListResponse, Task, TaskEvent, UpdateTaskRequest};
+use attachments::Attachments;
+use automations::Automations;
+use content_filter::Moderation;
+use error::ApiError;
+use experiments::Subject;
+use rate_limit::ClientIp;
+use std::sync::Arc;
+use store::{Store, Versions};
+use tower::ServiceExt;
+use tower_http::trace::TraceLayer;
+use uuid::Uuid;
+
+use redis_pool::RedisPool;
+
+#[derive(Clone)]
+pub struct AppState {
+    /// Only set when `REDIS_URL` is configured; see [`Redis`].
+    pub redis: Option<RedisPool>,
+    pub store: Store,
+    pub attachments: Attachments,
+    pub live: live::Hub,
+    pub throttle: throttle::Throttle,
+    pub flags: Arc<flags::FlagConfig>,
+    pub content_filter: Arc<content_filter::FilterConfig>,
+    pub experiments: Arc<experiments::ExperimentConfig>,
+    pub server: Arc<config::Config>,
+    pub capture: Arc<capture::CaptureConfig>,
+    pub status: status::Monitor,
+    pub automations: automations::Engine,
+}
+
+impl FromRef<AppState> for Store {
+    fn from_ref(state: &AppState) -> Self {
+        state.store.clone()
+    }
+}
+
+impl FromRef<AppState> for Attachments {
+    fn from_ref(state: &AppState) -> Self {
+        state.attachments.clone()
+    }
+}
+
+impl FromRef<AppState> for throttle::Throttle {
+    fn from_ref(state: &AppState) -> Self {
+        state.throttle.clone()
+    }
+}
+
+impl FromRef<AppState> for status::Monitor {
+    fn from_ref(state: &AppState) -> Self {
+        state.status.clone()
+    }
+}
+
+impl FromRef<AppState> for live::Hub {
+    fn from_ref(state: &AppState) -> Self {
+        state.live.clone()
+    }
+}
+
+/// For handlers that use Redis when it's there but work without it.
+impl FromRef<AppState> for Option<RedisPool> {
+    fn from_ref(state: &AppState) -> Self {
+        state.redis.clone()
+    }
+}
+
+/// Extractor for features that only exist with a Redis backend (flag
+/// overrides, experiment results, client error reports). Without `REDIS_URL`
+/// those endpoints answer 503.
+pub struct Redis(pub RedisPool);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Redis {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        state
+            .redis
+            .clone()
+            .map(Redis)
+            .ok_or_else(|| ApiError::Unavailable("This feature requires Redis (set REDIS_URL).".to_string()))
+    }
+}
+
+#[cfg(not(feature = "embed-frontend"))]
+fn serve_frontend(router: Router<AppState>, state: &AppState) -> Router<AppState> {
+    // Static files first, then the streamed page (see `shell`) for `/` and
+    // any other SPA route
+    let files = tower_http::services::ServeDir::new(&state.server.static_dir)
+        .append_index_html_on_directories(false)
+        .fallback(shell::serve.with_state(state.clone()));
+    router.fallback_service(files)
+}
+
+#[cfg(feature = "embed-frontend")]
+fn serve_frontend(router: Router<AppState>, _state: &AppState) -> Router<AppState> {
+    router.fallback(assets::serve_embedded)
+}
+
+impl AppState {
+    /// The state around `store` and `config`, with the features configured
+    /// from the environment, and the receiving end of the automation engine
+    /// for [`spawn_jobs`].
+    pub fn from_env(
+        config: config::Config,
+        redis: Option<RedisPool>,
+        store: Store,
+    ) -> (Self, tokio::sync::mpsc::UnboundedReceiver<automations::Fired>) {
+        let attachments = attachments::from_env(redis.as_ref()).expect("Invalid attachment storage configuration");
+        let content_filter = content_filter::FilterConfig::from_env().expect("Invalid content filter configuration");
+        let capture = capture::CaptureConfig::from_env().expect("Invalid capture configuration");
+        tracing::info!("attachments: {}", attachments.name());
+
+        let (engine, fired) = automations::Engine::new();
+        let state = AppState {
+            redis,
+            store,
+            attachments,
+            live: live::Hub::new(config.runtime.live_buffer),
+            throttle: throttle::Throttle::new(config.runtime.throttle_clients),
+            flags: Arc::new(flags::FlagConfig::from_env()),
+            content_filter: Arc::new(content_filter),
+            experiments: Arc::new(experiments::ExperimentConfig::from_env()),
+            server: Arc::new(config),
+            capture: Arc::new(capture),
+            status: status::Monitor::new(),
+            automations: engine,
+        };
+        (state, fired)
+    }
+}
+
+/// Redis when `REDIS_URL` is set, otherwise a single SQLite file next to the
+/// binary.
+pub async fn open_store(config: &config::Config) -> (Option<RedisPool>, Store) {
+    match &config.storage {
+        config::Storage::Redis(redis_url) => {
+            tracing::info!("storage: Redis at {}", redis_url);
+            let pool = RedisPool::new(redis_url, &config.redis_pool).expect("Invalid Redis configuration");
+            let store = store::RedisStore::new(pool.clone());
+            match store.index_existing().await {
+                Ok(0) => {}
+                Ok(count) => tracing::info!("indexed {} existing tasks", count),
+                Err(e) => tracing::warn!("failed to index existing tasks: {}", e),
+            }
+            (Some(pool), Arc::new(store))
+        }
+        config::Storage::Sqlite(path) => {
+            tracing::info!("storage: SQLite at {}", path.display());
+            (None, Arc::new(store::SqliteStore::open(path).expect("Failed to open SQLite database")))
+        }
+    }
+}
+
+/// Starts the background jobs: waking snoozed tasks, repeating, purging the
+/// trash, archiving, automations and the self-check.
+pub fn spawn_jobs(state: &AppState, fired: tokio::sync::mpsc::UnboundedReceiver<automations::Fired>) {
+    let self_check = self_check::SelfCheckConfig::from_env().expect("Invalid self-check configuration");
+    let archive = archive::ArchiveConfig::from_env().expect("Invalid archive configuration");
+    let AppState { store, redis, live, attachments, status: monitor, automations: engine, .. } = state;
+    snooze::spawn_wake_job(store.clone(), engine.clone(), live.clone(), monitor.clone());
+    recurrence::spawn_job(store.clone(), redis.clone(), live.clone(), monitor.clone());
+    trash::spawn_purge_job(store.clone(), redis.clone(), attachments.clone(), monitor.clone());
+    archive::spawn_job(store.clone(), redis.clone(), live.clone(), monitor.clone(), archive);
+    automations::spawn_job(fired, store.clone(), redis.clone(), live.clone(), monitor.clone());
+    self_check::spawn_job(store.clone(), monitor.clone(), self_check);
+    if let Some(pool) = redis.clone() {
+        tokio::spawn(analytics::backfill(pool, store.clone()));
+    }
+}
+
+/// The app on `redis` and `store`, configured from the environment, without
+/// the background jobs. What the integration tests run against.
+pub fn build_app(redis: Option<RedisPool>, store: Store) -> Router {
+    let config = config::Config::from_env().expect("Invalid server configuration");
+    router(AppState::from_env(config, redis, store).0)
+}
+
+/// Every route, with the frontend (or the dev proxy) as the fallback and
+/// the middleware around them.
+pub fn router(state: AppState) -> Router {
+    let rate_limiter = rate_limit::RateLimiter::new(state.server.rate_limit.clone(), state.redis.clone());
+    if rate_limiter.unenforced() {
+        tracing::warn!("rate limiting is configured but needs Redis (REDIS_URL); requests to /api/* are not limited");
+    }
+    tracing::info!("rate limit: {}", rate_limiter.describe());
+    let max_upload_bytes = state.server.max_upload_bytes;
+
+    let api = Router::new()
+        .route("/api/tasks", get(get_tasks).post(create_task))
+        .route("/api/tasks/bulk", post(bulk::apply))
+        .route("/api/tasks/changes", get(changes::get_changes))
+        .route("/api/tasks/export", get(transfer::export))
+        .route(
+            "/api/tasks/import",
+            post(transfer::import).layer(DefaultBodyLimit::max(transfer::MAX_IMPORT_BYTES)),
+        )
+        .route("/api/tasks/nearby", get(locations::nearby))
+        .route("/api/tasks/recent", get(recent::get_recent))
+        .route("/api/tasks/:id", get(get_task).put(update_task).patch(patch::patch_task).delete(delete_task))
+        .route(
+            "/api/tasks/:id/attachments",
+            post(attachments::upload_attachment).layer(DefaultBodyLimit::max(max_upload_bytes)),
+        )
+        .route(
+            "/api/tasks/:id/attachments/:attachment_id",
+            get(attachments::download_attachment).delete(attachments::delete_attachment),
+        )
+        .route("/api/tasks/:id/comments", get(comments::list_comments).post(comments::add_comment))
+        .route("/api/tasks/:id/edits", post(edits::apply_edits))
+        .route("/api/tasks/:id/qr", get(qr::task_qr))
+        .route("/api/tasks/:id/restore", post(trash::restore))
+        .route("/api/trash", get(trash::get_trash))
+        .route("/api/trash/:id", delete(trash::purge))
+        .route("/api/archive", get(archive::get_archive))
+        .route("/api/archive/:id/restore", post(archive::restore))
+        .route("/api/ws", get(live::connect))
+        .route("/api/events", get(live::events))
+        .route("/api/activity", get(activity::get_activity))
+        .route("/api/analytics", get(analytics::summary))
+        .route("/api/analytics/tags", get(analytics::tag_stats))
+        .route("/api/analytics/daily", get(analytics::daily_counts))
+        .route("/api/release-notes", get(release_notes::get_release_notes))
+        .route("/api/plans/:date", get(plans::get_plan).put(plans::save_plan))
+        .route("/api/plans/:date/rollover", post(plans::roll_over))
+        .route("/api/flags", get(flags::get_flags))
+        .route("/api/flags/:name", put(flags::set_flag))
+        .route("/api/experiments", get(experiments::get_assignments))
+        .route("/api/experiments/exposures", post(experiments::log_exposure))
+        .route("/api/version", get(version::get_version))
+        .route("/api/openapi.json", get(openapi::get_document))
+        .route("/api/docs", get(openapi::get_docs))
+        .route("/api/announcements", get(announcements::get_announcements))
+        .route("/api/client-errors", post(client_errors::report_client_error))
+        .route("/status", get(status::get_status))
+        .route("/robots.txt", get(seo::robots))
+        .route("/sitemap.xml", get(seo::sitemap))
+        .route("/sw.js", get(pwa::service_worker));
+
+    let admin = Router::new()
+        .route("/api/admin/experiments", get(experiments::get_results))
+        .route("/api/admin/announcements", post(announcements::publish))
+        .route("/api/admin/announcements/:id", delete(announcements::delete))
+        .route("/api/admin/overview", get(admin::get_overview))
+        .route("/api/admin/config", get(config::get_config))
+        .route("/api/admin/content-filter", get(content_filter::get_settings).put(content_filter::set_mode))
+        .route("/api/admin/automations", get(automations::get_settings).put(automations::set_rules))
+        .route("/api/admin/automations/log", get(automations::get_log))
+        .route("/api/admin/logs", get(logs::get_logs))
+        .route("/api/admin/client-errors", get(client_errors::get_client_errors))
+        .route_layer(axum::middleware::from_extractor_with_state::<admin::AdminToken, _>(state.clone()));
+    let api = api.merge(admin);
+
+    let app = match dev_proxy::DevProxy::from_env() {
+        Some(proxy) => {
+            tracing::info!("dev mode: proxying frontend requests to {}", proxy.upstream());
+            api.fallback_service(dev_proxy::proxy.with_state(proxy))
+        }
+        None => serve_frontend(api, &state),
+    };
+
+    let base_path = state.server.base_path.clone();
+    let app = app
+        .layer(state.server.cors.layer())
+        .merge(capture::routes(&state.capture))
+        .layer(axum::middleware::from_fn_with_state(rate_limiter, rate_limit::limit))
+        .with_state(state);
+    let app = match base_path.as_str() {
+        "" => app,
+        base => under_base_path(base, app),
+    };
+
+    app
+        .layer(axum::middleware::from_fn(telemetry::echo_trace_id))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(telemetry::request_span)
+                .on_request(())
+                .on_response(telemetry::record_response)
+                // Handlers log their own errors
+                .on_failure(()),
+        )
+}
+
+/// `app` moved under `base`, like `/todo`; its handlers see paths without it.
+fn under_base_path(base: &str, app: Router) -> Router {
+    // `nest` leaves out `/todo/` itself, the Tasks page
+    let root = app.clone();
+    let index = move |request: axum::extract::Request| async move {
+        let (mut parts, body) = request.into_parts();
+        let path = match parts.uri.query() {
+            Some(query) => format!("/?{}", query),
+            None => "/".to_string(),
+        };
+        parts.uri = path.parse().expect("a path is a valid URI");
+        match root.oneshot(axum::extract::Request::from_parts(parts, body)).await {
+            Ok(response) => response,
+            Err(never) => match never {},
+        }
+    };
+    Router::new().nest(base, app).route(&format!("{}/", base), axum::routing::any(index))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListTasksQuery {
+    /// Also return tasks that are currently snoozed.
+    #[serde(default)]
+    include_snoozed: bool,
+}
+
+#[tracing::instrument(skip(store))]
+async fn get_tasks(
+    State(store): State<Store>,
+    Query(query): Query<ListTasksQuery>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<Task>>, ApiError> {
+    let awake_at = (!query.include_snoozed).then(snooze::now_secs);
+    let (tasks, total) = store
+        .list_page(pagination.offset(), pagination.per_page() as usize, awake_at)
+        .await?;
+
+    Ok(Json(pagination.respond(tasks, total)))
+}
+
+#[tracing::instrument(skip(store))]
+async fn get_task(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    match store.get(id).await? {
+        Some(task) if etag::not_modified(&headers, &task) => Ok(StatusCode::NOT_MODIFIED.into_response()),
+        Some(task) => Ok(etag::respond(task)),
+        None => Err(ApiError::NotFound),
+    }
+}
+
+#[tracing::instrument(skip_all)]
+#[allow(clippy::too_many_arguments)]
+async fn create_task(
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    State(throttle): State<throttle::Throttle>,
+    Subject(client): Subject,
+    ClientIp(ip): ClientIp,
+    moderation: Moderation,
+    automations: Automations,
+    Json(payload): Json<CreateTaskRequest>,
+) -> Result<Response, ApiError> {
+    if let Err(limited) = throttle.check(&client, ip.as_deref(), &payload.title) {
+        tracing::info!(client = %client, ip = ?ip, "task creation throttled");
+        return Ok(limited.into_response());
+    }
+
+    let mut task = Task::new(payload.title, payload.description)
+        .with_tags(payload.tags)
+        .with_priority(payload.priority)
+        .created_at(snooze::now_secs());
+    validation::check_task(None, &mut task).map_err(ApiError::Fields)?;
+    moderation.review(None, &mut task)?;
+
+    store.save(&mut task).await?;
+    activity::record(&redis, ActivityKind::Created, &task).await;
+    analytics::record(&redis, None, Some(&task)).await;
+    live.publish(TaskEvent::Created { task: task.clone() });
+    automations.saved(None, &task);
+
+    Ok(Json(task).into_response())
+}
+
+#[tracing::instrument(skip(store, redis, live, moderation, automations, headers, payload))]
+#[allow(clippy::too_many_arguments)]
+async fn update_task(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    moderation: Moderation,
+    automations: Automations,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateTaskRequest>,
+) -> Result<Response, ApiError> {
+    let mut task = store
+        .get(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    etag::check_if_match(&headers, &task)?;
+    if payload.version.is_some_and(|version| version != task.version) {
+        return Ok(etag::conflict(task));
+    }
+    let before = task.clone();
+
+    if let Some(Some(location)) = &payload.location {
+        if !locations::valid(location) {
+            return Err(ApiError::Validation(locations::INVALID.to_string()));
+        }
+    }
+    if let Some(Some(recurrence)) = &payload.recurrence {
+        recurrence.validate().map_err(ApiError::Validation)?;
+    }
+    payload.apply(&mut task);
+    task.stamp_completion(before.completed, snooze::now_secs());
+    validation::check_task(Some(&before), &mut task).map_err(ApiError::Fields)?;
+    moderation.review(Some(&before), &mut task)?;
+
+    // Someone else may have saved since we read the task
+    if !store.save_if_version(&mut task, before.version).await? {
+        let latest = store
+            .get(id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        return Ok(etag::conflict(latest));
+    }
+    activity::record(&redis, activity::update_kind(&before, &task), &task).await;
+    analytics::record(&redis, Some(&before), Some(&task)).await;
+    live.publish(TaskEvent::Updated { task: task.clone() });
+    automations.saved(Some(&before), &task);
+
+    Ok(etag::respond(task))
+}
+
+/// Moves the task to the trash; see [`trash`].
+#[tracing::instrument(skip(store, redis, live, headers))]
+async fn delete_task(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let mut task = store
+        .get(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    etag::check_if_match(&headers, &task)?;
+    task.deleted_at = Some(snooze::now_secs());
+
+    // Trashing an older copy would lose the edit made since we read it
+    let expected = Versions::from([(id, task.version)]);
+    if !store.apply_batch(&mut [], std::slice::from_ref(&task), &expected).await? {
+        let latest = store
+            .get(id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        return Ok(etag::conflict(latest));
+    }
+    activity::record(&redis, ActivityKind::Deleted, &task).await;
+    analytics::record(&redis, Some(&task), None).await;
+    live.publish(TaskEvent::Deleted { id });
+    Ok(Json(json!({"message": "Task moved to the trash"})).into_response())
+}
@@ -0,0 +1,226 @@
+//! Live task updates pushed to browsers over WebSocket (`GET /api/ws`), or
+//! as Server-Sent Events (`GET /api/events`) where WebSockets are blocked.
+//!
+//! Handlers publish a [`TaskEvent`] after every create, update and delete;
+//! each connected client gets them as JSON, in text frames or `data:` lines,
+//! so open tabs stay in step without polling. Events only reach clients of
+//! the same process, and a client that falls too far behind is disconnected
+//! so it reconnects and catches up through `GET /api/tasks/changes`.
+//!
+//! The channel only pushes, so the handshake and framing (RFC 6455) are done
+//! here over hyper's upgrade instead of pulling in a full WebSocket stack.
+//! Client frames are read only to answer pings and closes.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use base64::Engine;
+use futures_util::stream;
+use hyper_util::rt::TokioIo;
+use shared::TaskEvent;
+use std::{convert::Infallible, io};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::{broadcast, mpsc},
+};
+
+/// Largest client frame accepted; clients have nothing big to say.
+const MAX_FRAME_LEN: u64 = 64 * 1024;
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OP_TEXT: u8 = 0x1;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+#[derive(Clone)]
+pub struct Hub {
+    sender: broadcast::Sender<TaskEvent>,
+}
+
+impl Hub {
+    /// A hub whose clients may fall `buffered` events behind before they're
+    /// dropped.
+    pub fn new(buffered: usize) -> Self {
+        Self {
+            sender: broadcast::channel(buffered).0,
+        }
+    }
+
+    /// Sends `event` to every connected client; a no-op when none are.
+    pub fn publish(&self, event: TaskEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    /// Clients currently connected, over either transport.
+    pub fn client_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn connect(State(hub): State<Hub>, mut request: Request) -> Response {
+    let Some(accept) = accept_key(request.headers()) else {
+        return (StatusCode::UPGRADE_REQUIRED, "Expected a WebSocket upgrade").into_response();
+    };
+
+    let upgrade = hyper::upgrade::on(&mut request);
+    let events = hub.sender.subscribe();
+    tokio::spawn(async move {
+        match upgrade.await {
+            Ok(upgraded) => serve(TokioIo::new(upgraded), events).await,
+            Err(e) => tracing::warn!("websocket upgrade failed: {}", e),
+        }
+    });
+
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+    let headers = response.headers_mut();
+    headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+    headers.insert(header::CONNECTION, HeaderValue::from_static("upgrade"));
+    if let Ok(accept) = HeaderValue::from_str(&accept) {
+        headers.insert(header::SEC_WEBSOCKET_ACCEPT, accept);
+    }
+    response
+}
+
+/// The same events as the socket, for clients that can't open one. The
+/// stream ends when the client falls behind.
+#[tracing::instrument(skip_all)]
+pub async fn events(State(hub): State<Hub>) -> impl IntoResponse {
+    let events = stream::unfold(hub.sender.subscribe(), |mut events| async move {
+        loop {
+            match events.recv().await {
+                Ok(event) => match Event::default().json_data(&event) {
+                    Ok(event) => return Some((Ok::<_, Infallible>(event), events)),
+                    Err(_) => continue,
+                },
+                Err(_) => return None,
+            }
+        }
+    });
+    // Proxies buffering the response would hold events back
+    (
+        [(header::CACHE_CONTROL, "no-cache"), (header::HeaderName::from_static("x-accel-buffering"), "no")],
+        Sse::new(events).keep_alive(KeepAlive::default()),
+    )
+}
+
+/// `Sec-WebSocket-Accept` for a valid upgrade request, `None` otherwise.
+fn accept_key(headers: &HeaderMap) -> Option<String> {
+    let has_token = |name: header::HeaderName, token: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.split(',').any(|part| part.trim().eq_ignore_ascii_case(token)))
+    };
+    if !has_token(header::UPGRADE, "websocket") || !has_token(header::CONNECTION, "upgrade") {
+        return None;
+    }
+    if headers.get(header::SEC_WEBSOCKET_VERSION).map(HeaderValue::as_bytes) != Some(b"13") {
+        return None;
+    }
+
+    let key = headers.get(header::SEC_WEBSOCKET_KEY)?.to_str().ok()?;
+    let digest = sha1_smol::Sha1::from(format!("{}{}", key.trim(), ACCEPT_GUID)).digest().bytes();
+    Some(base64::engine::general_purpose::STANDARD.encode(digest))
+}
+
+async fn serve<S>(stream: S, mut events: broadcast::Receiver<TaskEvent>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, mut writer) = tokio::io::split(stream);
+
+    // Reads run on their own task so a frame is never cut off half-read
+    // when an event needs sending
+    let (replies, mut to_send) = mpsc::channel::<(u8, Vec<u8>)>(8);
+    tokio::spawn(async move {
+        while let Ok((opcode, payload)) = read_frame(&mut reader).await {
+            let reply = match opcode {
+                OP_PING => OP_PONG,
+                OP_CLOSE => OP_CLOSE,
+                _ => continue,
+            };
+            if replies.send((reply, payload)).await.is_err() || reply == OP_CLOSE {
+                break;
+            }
+        }
+    });
+
+    loop {
+        let (opcode, payload) = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => match serde_json::to_vec(&event) {
+                    Ok(json) => (OP_TEXT, json),
+                    Err(_) => continue,
+                },
+                // Behind or shutting down: close and let the client catch up
+                Err(_) => (OP_CLOSE, Vec::new()),
+            },
+            reply = to_send.recv() => reply.unwrap_or((OP_CLOSE, Vec::new())),
+        };
+
+        if write_frame(&mut writer, opcode, &payload).await.is_err() || opcode == OP_CLOSE {
+            break;
+        }
+    }
+}
+
+/// Reads one client frame, unmasking its payload.
+async fn read_frame<R: AsyncRead + Unpin>(reader: &mut R) -> io::Result<(u8, Vec<u8>)> {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head).await?;
+    let opcode = head[0] & 0x0f;
+    let masked = head[1] & 0x80 != 0;
+
+    let len = match head[1] & 0x7f {
+        126 => reader.read_u16().await? as u64,
+        127 => reader.read_u64().await?,
+        len => len as u64,
+    };
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "websocket frame too large"));
+    }
+
+    let mut mask = [0u8; 4];
+    if masked {
+        reader.read_exact(&mut mask).await?;
+    }
+    let mut payload = vec![0u8; len as usize];
+    reader.read_exact(&mut payload).await?;
+    if masked {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+
+    Ok((opcode, payload))
+}
+
+/// Writes one unfragmented, unmasked frame as servers must.
+async fn write_frame<W: AsyncWrite + Unpin>(writer: &mut W, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+
+    writer.write_all(&frame).await?;
+    writer.flush().await
+}
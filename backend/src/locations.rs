@@ -0,0 +1,55 @@
+//! Location-tagged tasks and the "near me" query.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use shared::{ListResponse, Location, Task};
+
+use crate::{error::ApiError, pagination::Pagination, snooze, store::Store};
+
+/// Why [`valid`] turned a location down, for the user.
+pub const INVALID: &str = "Locations need a latitude from -90 to 90, a longitude from -180 to 180 and a positive radius.";
+
+/// Coordinates must be on the globe and the radius positive and finite.
+pub fn valid(location: &Location) -> bool {
+    (-90.0..=90.0).contains(&location.lat)
+        && (-180.0..=180.0).contains(&location.lng)
+        && location.radius_m.is_finite()
+        && location.radius_m > 0.0
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NearbyQuery {
+    lat: f64,
+    lng: f64,
+}
+
+/// Open, awake tasks whose location radius covers the given point, nearest first.
+#[tracing::instrument(skip(store))]
+pub async fn nearby(
+    State(store): State<Store>,
+    Query(query): Query<NearbyQuery>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<Task>>, ApiError> {
+    if !(-90.0..=90.0).contains(&query.lat) || !(-180.0..=180.0).contains(&query.lng) {
+        return Err(ApiError::Status(StatusCode::BAD_REQUEST));
+    }
+
+    let now = snooze::now_secs();
+    let tasks = store.list().await?;
+    let mut nearby: Vec<(f64, Task)> = tasks
+        .into_iter()
+        .filter(|task| !task.completed && !task.is_snoozed(now))
+        .filter_map(|task| {
+            let location = task.location.as_ref()?;
+            let distance = location.distance_m(query.lat, query.lng);
+            (distance <= location.radius_m).then_some((distance, task))
+        })
+        .collect();
+    nearby.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    Ok(Json(pagination.paginate(nearby.into_iter().map(|(_, task)| task).collect())))
+}
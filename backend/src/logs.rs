@@ -0,0 +1,167 @@
+//! Recent log entries kept in memory for `GET /api/admin/logs`, so operators
+//! can see what the server has been saying without shelling into it.
+//!
+//! [`CaptureLayer`] copies every event that passes the `RUST_LOG` filter into
+//! a ring buffer of the last `LOG_BUFFER_ENTRIES` (see [`set_capacity`]);
+//! `0` turns capture off. Capture is rate limited:
+//! past [`MAX_PER_SECOND`] entries a second the rest are counted instead of
+//! stored, so a burst of noise can't push out what led up to it.
+
+use axum::{extract::Query, response::Json};
+use serde::Deserialize;
+use shared::{ListResponse, LogEntry, LogLevel};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{field::Field, Event, Level, Subscriber};
+use tracing_subscriber::{layer::Context, Layer};
+
+use crate::pagination::Pagination;
+
+const MAX_PER_SECOND: u32 = 100;
+
+struct Buffer {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+    next_id: u64,
+    /// Unix second the rate limit is currently counting.
+    second: u64,
+    in_second: u32,
+    dropped: u64,
+}
+
+static BUFFER: Mutex<Buffer> = Mutex::new(Buffer {
+    entries: VecDeque::new(),
+    capacity: 500,
+    next_id: 1,
+    second: 0,
+    in_second: 0,
+    dropped: 0,
+});
+
+impl Buffer {
+    fn push(&mut self, at: u64, level: LogLevel, target: String, message: String) {
+        let second = at / 1000;
+        if second != self.second {
+            self.second = second;
+            self.in_second = 0;
+            if self.dropped > 0 {
+                let note = format!("{} log entries dropped (over {} per second)", self.dropped, MAX_PER_SECOND);
+                self.dropped = 0;
+                self.store(at, LogLevel::Warn, module_path!().to_string(), note);
+            }
+        }
+
+        if self.in_second >= MAX_PER_SECOND {
+            self.dropped += 1;
+            return;
+        }
+        self.in_second += 1;
+        self.store(at, level, target, message);
+    }
+
+    fn store(&mut self, at: u64, level: LogLevel, target: String, message: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            id: self.next_id,
+            at,
+            level,
+            target,
+            message,
+        });
+        self.next_id += 1;
+    }
+}
+
+/// Sets how many entries are kept, dropping the oldest past it.
+pub fn set_capacity(capacity: usize) {
+    if let Ok(mut buffer) = BUFFER.lock() {
+        buffer.capacity = capacity;
+        let excess = buffer.entries.len().saturating_sub(capacity);
+        buffer.entries.drain(..excess);
+    }
+}
+
+/// `tracing` layer feeding the buffer.
+pub struct CaptureLayer;
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let level = match *event.metadata().level() {
+            Level::TRACE => LogLevel::Trace,
+            Level::DEBUG => LogLevel::Debug,
+            Level::INFO => LogLevel::Info,
+            Level::WARN => LogLevel::Warn,
+            Level::ERROR => LogLevel::Error,
+        };
+        let at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+
+        if let Ok(mut buffer) = BUFFER.lock() {
+            buffer.push(at, level, event.metadata().target().to_string(), visitor.finish());
+        }
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        match (self.message.is_empty(), self.fields.is_empty()) {
+            (_, true) => self.message,
+            (true, false) => self.fields,
+            (false, false) => format!("{} {}", self.message, self.fields),
+        }
+    }
+}
+
+impl tracing::field::Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push(' ');
+            }
+            let _ = write!(self.fields, "{}={:?}", field.name(), value);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogsQuery {
+    /// Least severe level to include; defaults to `info`.
+    #[serde(default)]
+    level: Option<String>,
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_logs(
+    Query(query): Query<LogsQuery>,
+    Query(pagination): Query<Pagination>,
+) -> Json<ListResponse<LogEntry>> {
+    let min_level = query.level.as_deref().and_then(LogLevel::from_name).unwrap_or_default();
+    Json(pagination.paginate(newest(min_level)))
+}
+
+/// Buffered entries at `min_level` or above, newest first.
+pub fn newest(min_level: LogLevel) -> Vec<LogEntry> {
+    match BUFFER.lock() {
+        Ok(buffer) => buffer.entries.iter().rev().filter(|entry| entry.level >= min_level).cloned().collect(),
+        Err(_) => Vec::new(),
+    }
+}
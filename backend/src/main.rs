@@ -1,161 +1,73 @@
-use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::{Html, Json},
-    routing::{get, post, put, delete},
-    Router,
-};
-use redis::{AsyncCommands, Client};
-use serde_json::json;
-use shared::{CreateTaskRequest, Task, UpdateTaskRequest};
-use std::sync::Arc;
-use tower_http::{cors::CorsLayer, services::ServeDir};
-use uuid::Uuid;
+use backend::{config, seed, server, telemetry, AppState};
 
-type RedisPool = Arc<Client>;
+const USAGE: &str = "Usage: backend [--print-config]
 
-async fn serve_index() -> Html<String> {
-    let html = std::fs::read_to_string("frontend/dist/index.html")
-        .unwrap_or_else(|_| r#"
-<!DOCTYPE html>
-<html>
-<head>
-    <meta charset="utf-8">
-    <title>Task Manager</title>
-    <link href="./styles.css" rel="stylesheet">
-</head>
-<body class="bg-gray-100 font-sans">
-    <script type="module">
-        import init from './frontend.js';
-        init();
-    </script>
-</body>
-</html>
-"#.to_string());
-    
-    Html(html)
-}
-
-#[tokio::main]
-async fn main() {
-    let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
-    
-    let client = Client::open(redis_url).expect("Failed to connect to Redis");
-    let pool = Arc::new(client);
+Settings come from the environment or CONFIG_FILE; see DEPLOYMENT.md.
 
-    let app = Router::new()
-        .route("/api/tasks", get(get_tasks).post(create_task))
-        .route("/api/tasks/:id", get(get_task).put(update_task).delete(delete_task))
-        // Serve static files first
-        .nest_service("/", ServeDir::new("frontend/dist"))
-        // Fallback route for SPA - serves index.html for any unmatched routes
-        .fallback(serve_index)
-        .layer(CorsLayer::permissive())
-        .with_state(pool);
+  --print-config  Print the settings the server would run with and exit";
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
-    println!("Server running on http://localhost:3000");
-    println!("Redis URL: {}", std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()));
-    axum::serve(listener, app).await.unwrap();
-}
-
-async fn get_tasks(State(pool): State<RedisPool>) -> Result<Json<Vec<Task>>, StatusCode> {
-    let mut conn = pool.get_async_connection().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    let keys: Vec<String> = conn.keys("task:*").await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let mut tasks = Vec::new();
-    
-    for key in keys {
-        let task_json: String = conn.get(&key).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-        if let Ok(task) = serde_json::from_str::<Task>(&task_json) {
-            tasks.push(task);
+fn main() {
+    let mut print_config = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--print-config" => print_config = true,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => {
+                eprintln!("Unknown argument {:?}\n\n{}", arg, USAGE);
+                std::process::exit(2);
+            }
         }
     }
-    
-    // Sort by ID to ensure consistent ordering across requests
-    tasks.sort_by(|a, b| a.id.cmp(&b.id));
-    
-    Ok(Json(tasks))
-}
 
-async fn get_task(
-    Path(id): Path<Uuid>,
-    State(pool): State<RedisPool>,
-) -> Result<Json<Task>, StatusCode> {
-    let mut conn = pool.get_async_connection().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    let key = format!("task:{}", id);
-    let task_json: Option<String> = conn.get(&key).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    match task_json {
-        Some(json) => {
-            let task: Task = serde_json::from_str(&json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            Ok(Json(task))
+    // Nothing is logging yet, so a bad setting is reported on stderr
+    let config = match config::load_file().and_then(|()| config::Config::from_env()) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if print_config {
+        let entries = config.entries();
+        let width = entries.iter().map(|entry| entry.name.len()).max().unwrap_or(0);
+        for entry in entries {
+            println!("{:width$}  {}", entry.name, entry.value, width = width);
         }
-        None => Err(StatusCode::NOT_FOUND),
+        return;
     }
-}
 
-async fn create_task(
-    State(pool): State<RedisPool>,
-    Json(payload): Json<CreateTaskRequest>,
-) -> Result<Json<Task>, StatusCode> {
-    let task = Task::new(payload.title, payload.description);
-    let task_json = serde_json::to_string(&task).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    let mut conn = pool.get_async_connection().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let key = format!("task:{}", task.id);
-    
-    conn.set(&key, &task_json).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    Ok(Json(task))
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(config.runtime.worker_threads)
+        .max_blocking_threads(config.runtime.max_blocking_threads)
+        .enable_all()
+        .build()
+        .expect("Failed to start the async runtime");
+    runtime.block_on(run(config));
 }
 
-async fn update_task(
-    Path(id): Path<Uuid>,
-    State(pool): State<RedisPool>,
-    Json(payload): Json<UpdateTaskRequest>,
-) -> Result<Json<Task>, StatusCode> {
-    let mut conn = pool.get_async_connection().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let key = format!("task:{}", id);
-    
-    let task_json: Option<String> = conn.get(&key).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    match task_json {
-        Some(json) => {
-            let mut task: Task = serde_json::from_str(&json).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            
-            if let Some(title) = payload.title {
-                task.title = title;
-            }
-            if let Some(description) = payload.description {
-                task.description = description;
-            }
-            if let Some(completed) = payload.completed {
-                task.completed = completed;
-            }
-            
-            let updated_json = serde_json::to_string(&task).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            conn.set(&key, &updated_json).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            
-            Ok(Json(task))
-        }
-        None => Err(StatusCode::NOT_FOUND),
-    }
-}
+async fn run(config: config::Config) {
+    let tracer_provider = telemetry::init(&config);
 
-async fn delete_task(
-    Path(id): Path<Uuid>,
-    State(pool): State<RedisPool>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    let mut conn = pool.get_async_connection().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let key = format!("task:{}", id);
-    
-    let deleted: usize = conn.del(&key).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    
-    if deleted > 0 {
-        Ok(Json(json!({"message": "Task deleted successfully"})))
-    } else {
-        Err(StatusCode::NOT_FOUND)
+    let (redis, store) = backend::open_store(&config).await;
+    if let Some(seed) = &config.seed {
+        seed::run(&store, seed).await;
     }
-}
\ No newline at end of file
+
+    let (state, fired) = AppState::from_env(config.clone(), redis, store);
+    backend::spawn_jobs(&state, fired);
+    let app = backend::router(state);
+
+    let build = shared::build_info!();
+    tracing::info!(
+        version = %build.version,
+        git_hash = %build.git_hash,
+        worker_threads = config.runtime.worker_threads,
+        "starting"
+    );
+    server::serve(&config, app).await.unwrap();
+
+    let _ = tracer_provider.shutdown();
+}
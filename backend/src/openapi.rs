@@ -0,0 +1,284 @@
+//! OpenAPI 3 description of the HTTP API, served at `GET /api/openapi.json`
+//! and browsable with Swagger UI at `GET /api/docs`.
+//!
+//! Built from a table kept next to the router rather than derived from the
+//! handlers, so adding a route means adding a line to [`OPERATIONS`] too.
+//! The schemas of request and response bodies are generated from the
+//! `shared` types with utoipa, so they can't drift from what's sent.
+//! Operations carry an example body where they take JSON; the Settings page
+//! playground pre-fills its request editor with it.
+
+use axum::response::{Html, Json};
+use serde_json::{json, Map, Value};
+use shared::{
+    crdt::TaskEdit, BulkRequest, BulkResponse, Comment, CreateCommentRequest, CreateTaskRequest, ErrorBody, ListResponse, Task,
+    TaskChanges, UpdateTaskRequest,
+};
+use utoipa::{OpenApi, PartialSchema, ToSchema};
+
+/// Every type named by [`OPERATIONS`]; utoipa adds the ones they contain.
+#[derive(OpenApi)]
+#[openapi(components(schemas(
+    Task,
+    CreateTaskRequest,
+    UpdateTaskRequest,
+    TaskEdit,
+    BulkRequest,
+    BulkResponse,
+    TaskChanges,
+    Comment,
+    CreateCommentRequest,
+    ErrorBody
+)))]
+struct Schemas;
+
+/// A reference to `T`'s schema under `components`.
+fn schema<T: ToSchema>() -> Value {
+    json!({"$ref": format!("#/components/schemas/{}", T::name())})
+}
+
+/// A page of `T`s in the [`ListResponse`] envelope.
+fn page<T: ToSchema>() -> Value {
+    serde_json::to_value(ListResponse::<T>::schema()).unwrap_or(Value::Null)
+}
+
+fn list<T: ToSchema>() -> Value {
+    json!({"type": "array", "items": schema::<T>()})
+}
+
+/// Where a request body is expected, and what a sensible one looks like.
+enum Body {
+    None,
+    /// An example, and the body's schema when it's one of the `shared` types.
+    Json(&'static str, Option<fn() -> Value>),
+    Multipart,
+}
+
+struct Operation {
+    method: &'static str,
+    /// Axum-style path; `:name` segments become path parameters.
+    path: &'static str,
+    summary: &'static str,
+    query: &'static [&'static str],
+    body: Body,
+    /// Schema of a successful response's JSON body.
+    returns: Option<fn() -> Value>,
+}
+
+const PAGED: &[&str] = &["page", "per_page"];
+
+const fn op(method: &'static str, path: &'static str, summary: &'static str) -> Operation {
+    Operation {
+        method,
+        path,
+        summary,
+        query: &[],
+        body: Body::None,
+        returns: None,
+    }
+}
+
+impl Operation {
+    const fn query(mut self, query: &'static [&'static str]) -> Self {
+        self.query = query;
+        self
+    }
+
+    const fn json(mut self, example: &'static str) -> Self {
+        self.body = Body::Json(example, None);
+        self
+    }
+
+    /// Like [`json`](Self::json), with the schema the body follows.
+    const fn json_as(mut self, example: &'static str, schema: fn() -> Value) -> Self {
+        self.body = Body::Json(example, Some(schema));
+        self
+    }
+
+    const fn returns(mut self, schema: fn() -> Value) -> Self {
+        self.returns = Some(schema);
+        self
+    }
+
+    const fn multipart(mut self) -> Self {
+        self.body = Body::Multipart;
+        self
+    }
+}
+
+const OPERATIONS: &[Operation] = &[
+    op("get", "/api/tasks", "List tasks").query(&["include_snoozed", "page", "per_page"]).returns(page::<Task>),
+    op("post", "/api/tasks", "Create a task")
+        .json_as(r#"{"title": "Buy milk", "description": "", "priority": "medium", "tags": []}"#, schema::<CreateTaskRequest>)
+        .returns(schema::<Task>),
+    op("post", "/api/tasks/bulk", "Complete, delete and retag tasks in one transaction")
+        .json_as(r#"{"operations": [{"op": "complete", "id": "00000000-0000-0000-0000-000000000000"}]}"#, schema::<BulkRequest>)
+        .returns(schema::<BulkResponse>),
+    op("get", "/api/tasks/changes", "Tasks changed since a change number").query(&["since"]).returns(schema::<TaskChanges>),
+    op("get", "/api/tasks/export", "Download every task as JSON or CSV").query(&["format"]),
+    op("post", "/api/tasks/import", "Import tasks from a JSON or CSV export, skipping taken ids")
+        .query(&["format", "dry_run"])
+        .multipart(),
+    op("get", "/api/tasks/nearby", "Open tasks around a point, nearest first").query(&["lat", "lng", "page", "per_page"]).returns(page::<Task>),
+    op("get", "/api/tasks/recent", "Tasks created, completed or deleted since a time, most recent first")
+        .query(&["by", "since", "page", "per_page"])
+        .returns(page::<Task>),
+    op("get", "/api/tasks/:id", "Get a task").returns(schema::<Task>),
+    op("put", "/api/tasks/:id", "Update a task")
+        .json_as(r#"{"title": "Buy oat milk", "version": 1}"#, schema::<UpdateTaskRequest>)
+        .returns(schema::<Task>),
+    op("patch", "/api/tasks/:id", "Update a task with a JSON Merge Patch")
+        .json_as(r#"{"completed": true}"#, schema::<UpdateTaskRequest>)
+        .returns(schema::<Task>),
+    op("delete", "/api/tasks/:id", "Move a task to the trash"),
+    op("post", "/api/tasks/:id/restore", "Restore a task from the trash").returns(schema::<Task>),
+    op("get", "/api/tasks/:id/comments", "A task's comments, oldest first").query(PAGED).returns(page::<Comment>),
+    op("post", "/api/tasks/:id/comments", "Comment on a task")
+        .json_as(r#"{"body": "Done, see the attached receipt."}"#, schema::<CreateCommentRequest>)
+        .returns(schema::<Comment>),
+    op("post", "/api/tasks/:id/edits", "Merge stamped field edits")
+        .json_as(r#"[{"stamp": {"at": 0, "replica": "playground"}, "title": "Buy oat milk"}]"#, list::<TaskEdit>)
+        .returns(schema::<Task>),
+    op("get", "/api/tasks/:id/qr", "QR code (SVG) for the task's share link"),
+    op("post", "/api/tasks/:id/attachments", "Upload an attachment").multipart(),
+    op("get", "/api/tasks/:id/attachments/:attachment_id", "Download an attachment"),
+    op("delete", "/api/tasks/:id/attachments/:attachment_id", "Remove an attachment"),
+    op("post", "/api/capture", "Save a page from the browser extension as a task (bearer token)")
+        .json(r#"{"title": "An article", "url": "https://example.com/article", "selection": ""}"#),
+    op("get", "/api/trash", "Deleted tasks, most recently deleted first").query(PAGED).returns(page::<Task>),
+    op("delete", "/api/trash/:id", "Delete a trashed task for good"),
+    op("get", "/api/archive", "Archived tasks, most recently archived first").query(PAGED).returns(page::<Task>),
+    op("post", "/api/archive/:id/restore", "Move an archived task back into the task list").returns(schema::<Task>),
+    op("get", "/api/activity", "Task activity log, newest first").query(&["after", "limit"]),
+    op("get", "/api/analytics", "Completions per day and hour, and average time to complete").query(&["days"]),
+    op("get", "/api/analytics/tags", "Per-tag task statistics").query(PAGED),
+    op("get", "/api/analytics/daily", "Tasks created and completed per day").query(&["days", "page", "per_page"]),
+    op("get", "/api/release-notes", "Tasks completed between two dates, grouped by tag, as Markdown").query(&["from", "to"]),
+    op("get", "/api/plans/:date", "Get the \"My day\" plan for a date"),
+    op("put", "/api/plans/:date", "Replace a day's plan").json(r#"{"task_ids": []}"#),
+    op("post", "/api/plans/:date/rollover", "Move unfinished planned tasks to another date")
+        .json(r#"{"to": "2024-01-02"}"#),
+    op("get", "/api/flags", "Feature flags"),
+    op("put", "/api/flags/:name", "Toggle a feature flag").json(r#"{"enabled": true, "workspace": null}"#),
+    op("get", "/api/experiments", "Experiment variants for this client"),
+    op("post", "/api/experiments/exposures", "Record an experiment exposure")
+        .json(r#"{"experiment": "create_form", "variant": "compact"}"#),
+    op("get", "/api/version", "Backend build information"),
+    op("get", "/status", "Uptime, storage latency and background job health; 503 when storage is down"),
+    op("get", "/api/announcements", "\"What's new\" announcements").query(PAGED),
+    op("post", "/api/client-errors", "Report a frontend error").json(
+        r#"{"kind": "api", "message": "Sent from the API playground", "msg_log": [], "app_version": "", "url": "", "user_agent": ""}"#,
+    ),
+    op("get", "/api/admin/overview", "Admin overview"),
+    op("get", "/api/admin/experiments", "Experiment results").query(PAGED),
+    op("post", "/api/admin/announcements", "Publish an announcement")
+        .json(r#"{"title": "New feature", "body": "What it does"}"#),
+    op("delete", "/api/admin/announcements/:id", "Withdraw an announcement"),
+    op("get", "/api/admin/config", "Effective server settings").query(PAGED),
+    op("get", "/api/admin/content-filter", "Content filter settings"),
+    op("put", "/api/admin/content-filter", "Set the content filter mode").json(r#"{"mode": "flag", "workspace": null}"#),
+    op("get", "/api/admin/automations", "Automation rules"),
+    op("put", "/api/admin/automations", "Replace the automation rules").json(
+        r#"{"rules": [{"id": "00000000-0000-0000-0000-000000000000", "name": "Flag stale work", "trigger": {"kind": "stale", "days": 7}, "conditions": [{"kind": "has_tag", "tag": "work"}], "actions": [{"kind": "add_tag", "tag": "stale"}]}], "workspace": null}"#,
+    ),
+    op("get", "/api/admin/automations/log", "Latest automation rule runs").query(PAGED),
+    op("get", "/api/admin/logs", "Recent backend log entries").query(&["level", "page", "per_page"]),
+    op("get", "/api/admin/client-errors", "Recent client error reports").query(PAGED),
+];
+
+/// `/api/tasks/:id` as OpenAPI writes it, `/api/tasks/{id}`, with the
+/// parameter names.
+fn template(path: &str) -> (String, Vec<&str>) {
+    let mut params = Vec::new();
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) => {
+                params.push(name);
+                format!("{{{}}}", name)
+            }
+            None => segment.to_string(),
+        })
+        .collect();
+    (segments.join("/"), params)
+}
+
+fn operation(op: &Operation, path_params: &[&str]) -> Value {
+    let parameters: Vec<Value> = path_params
+        .iter()
+        .map(|name| json!({"name": name, "in": "path", "required": true, "schema": {"type": "string"}}))
+        .chain(op.query.iter().map(|name| json!({"name": name, "in": "query", "schema": {"type": "string"}})))
+        .collect();
+
+    let mut operation = json!({
+        "summary": op.summary,
+        "parameters": parameters,
+        "responses": {"default": {"description": "See the README for response shapes"}},
+    });
+    if let Some(returns) = op.returns {
+        operation["responses"] = json!({
+            "2XX": {"description": "Success", "content": {"application/json": {"schema": returns()}}},
+            "default": {"description": "Error", "content": {"application/json": {"schema": schema::<ErrorBody>()}}},
+        });
+    }
+    match op.body {
+        Body::None => {}
+        Body::Json(example, body_schema) => {
+            let example: Value = serde_json::from_str(example).unwrap_or(Value::Null);
+            let mut content = json!({"example": example});
+            if let Some(body_schema) = body_schema {
+                content["schema"] = body_schema();
+            }
+            operation["requestBody"] = json!({"content": {"application/json": content}});
+        }
+        Body::Multipart => {
+            operation["requestBody"] = json!({"content": {"multipart/form-data": {}}});
+        }
+    }
+    operation
+}
+
+pub fn document() -> Value {
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let (path, params) = template(op.path);
+        let item = paths.entry(path).or_insert_with(|| Value::Object(Map::new()));
+        item[op.method] = operation(op, &params);
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Full-Stack Rust Demo API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": serde_json::to_value(Schemas::openapi().components).unwrap_or(Value::Null),
+    })
+}
+
+#[tracing::instrument]
+pub async fn get_document() -> Json<Value> {
+    Json(document())
+}
+
+/// Swagger UI for the document, loaded from a CDN so the backend doesn't
+/// have to ship its assets.
+pub async fn get_docs() -> Html<&'static str> {
+    Html(
+        r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>API docs</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>
+"##,
+    )
+}
@@ -0,0 +1,90 @@
+//! Page-number pagination shared by the list endpoints.
+
+use serde::Deserialize;
+use shared::ListResponse;
+
+const DEFAULT_PER_PAGE: u32 = 50;
+const MAX_PER_PAGE: u32 = 200;
+
+/// `?page=&per_page=`, with `page` starting at 1. Extract it with its own
+/// `Query` next to any endpoint-specific query struct.
+#[derive(Debug, Default, Deserialize)]
+pub struct Pagination {
+    page: Option<u32>,
+    per_page: Option<u32>,
+}
+
+impl Pagination {
+    /// The first page of `per_page` items, for lists sent outside the list
+    /// endpoints.
+    pub fn first(per_page: u32) -> Self {
+        Self {
+            page: Some(1),
+            per_page: Some(per_page),
+        }
+    }
+
+    pub fn page(&self) -> u32 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    pub fn per_page(&self) -> u32 {
+        self.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)
+    }
+
+    pub fn offset(&self) -> usize {
+        (self.page() as usize - 1) * self.per_page() as usize
+    }
+
+    /// Wraps one already-fetched page of a list of `total` items.
+    pub fn respond<T>(&self, data: Vec<T>, total: u64) -> ListResponse<T> {
+        ListResponse {
+            data,
+            page: self.page(),
+            per_page: self.per_page(),
+            total,
+            next_cursor: None,
+        }
+    }
+
+    /// Cuts the requested page out of a fully loaded list.
+    pub fn paginate<T>(&self, items: Vec<T>) -> ListResponse<T> {
+        let total = items.len() as u64;
+        let data = items.into_iter().skip(self.offset()).take(self.per_page() as usize).collect();
+        self.respond(data, total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn pagination() -> impl Strategy<Value = Pagination> {
+        (prop::option::of(0..20u32), prop::option::of(0..300u32)).prop_map(|(page, per_page)| Pagination { page, per_page })
+    }
+
+    proptest! {
+        #[test]
+        fn a_page_holds_at_most_per_page_items(items in prop::collection::vec(any::<u8>(), 0..500), pagination in pagination()) {
+            let response = pagination.paginate(items.clone());
+            prop_assert!((1..=MAX_PER_PAGE).contains(&response.per_page));
+            prop_assert!(response.page >= 1);
+            prop_assert!(response.data.len() <= response.per_page as usize);
+            prop_assert_eq!(response.total, items.len() as u64);
+        }
+
+        #[test]
+        fn the_pages_together_are_the_whole_list(items in prop::collection::vec(any::<u8>(), 0..500), per_page in 1..MAX_PER_PAGE) {
+            let mut seen = Vec::new();
+            for page in 1.. {
+                let response = Pagination { page: Some(page), per_page: Some(per_page) }.paginate(items.clone());
+                if response.data.is_empty() {
+                    break;
+                }
+                seen.extend(response.data);
+            }
+            prop_assert_eq!(seen, items);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! `PATCH /api/tasks/:id` with JSON Merge Patch (RFC 7396).
+//!
+//! The patch is merged into the task's JSON form, so a missing key leaves a
+//! field untouched and `null` clears it. Server-owned fields are restored
+//! afterwards, and a patch that leaves the task invalid is rejected with 422.
+
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::{Json, Response},
+};
+use serde_json::Value;
+use shared::{validation, Task, TaskEvent};
+use uuid::Uuid;
+
+use crate::{
+    activity, analytics, automations::Automations, content_filter::Moderation, error::ApiError, etag, live, locations, snooze,
+    store::Store, RedisPool,
+};
+
+/// Applies `patch` to `target` as described in RFC 7396 section 2.
+pub fn merge(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(target) = target else {
+        unreachable!()
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[tracing::instrument(skip(store, redis, live, moderation, automations, headers, patch))]
+#[allow(clippy::too_many_arguments)]
+pub async fn patch_task(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    moderation: Moderation,
+    automations: Automations,
+    headers: HeaderMap,
+    Json(patch): Json<Value>,
+) -> Result<Response, ApiError> {
+    let original = store
+        .get(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    etag::check_if_match(&headers, &original)?;
+    // A `version` in the patch is the one the client edited, not a new value
+    if patch.get("version").and_then(Value::as_u64).is_some_and(|version| version != original.version) {
+        return Ok(etag::conflict(original));
+    }
+
+    let mut document = serde_json::to_value(&original)?;
+    merge(&mut document, &patch);
+    let mut task: Task = serde_json::from_value(document)
+        .map_err(|e| ApiError::Validation(format!("The patch doesn't leave a valid task: {}", e)))?;
+
+    // Managed through their own endpoints or set by the server
+    task.id = original.id;
+    task.attachments = original.attachments.clone();
+    task.created_at = original.created_at;
+    task.clock = original.clock.clone();
+    task.flagged = original.flagged;
+    task.version = original.version;
+    task.deleted_at = original.deleted_at;
+    task.completed_at = original.completed_at;
+    task.stamp_completion(original.completed, snooze::now_secs());
+
+    task.tags = shared::normalize_tags(task.tags);
+    if task.location.as_ref().is_some_and(|l| !locations::valid(l)) {
+        return Err(ApiError::Validation(locations::INVALID.to_string()));
+    }
+    if let Some(rule) = &task.recurrence {
+        rule.validate().map_err(ApiError::Validation)?;
+    }
+    validation::check_task(Some(&original), &mut task).map_err(ApiError::Fields)?;
+    moderation.review(Some(&original), &mut task)?;
+
+    if !store.save_if_version(&mut task, original.version).await? {
+        let latest = store
+            .get(id)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        return Ok(etag::conflict(latest));
+    }
+    activity::record(&redis, activity::update_kind(&original, &task), &task).await;
+    analytics::record(&redis, Some(&original), Some(&task)).await;
+    live.publish(TaskEvent::Updated { task: task.clone() });
+    automations.saved(Some(&original), &task);
+
+    Ok(etag::respond(task))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use shared::{fixtures::Fixtures, Priority, UpdateTaskRequest};
+
+    fn task() -> impl Strategy<Value = Task> {
+        (any::<u64>(), 0..4_102_444_800u64).prop_map(|(seed, now)| Fixtures::new(seed).task(now))
+    }
+
+    fn json() -> impl Strategy<Value = Value> {
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::from),
+            any::<i64>().prop_map(Value::from),
+            "[a-z]{0,6}".prop_map(Value::from),
+        ];
+        leaf.prop_recursive(3, 24, 4, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 0..4).prop_map(Value::from),
+                prop::collection::btree_map("[a-c]", inner, 0..4).prop_map(|map| Value::Object(map.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// Updates `PUT` and `PATCH` both accept, with tags already normalized
+    /// since only `PUT` normalizes them.
+    fn request() -> impl Strategy<Value = UpdateTaskRequest> {
+        (
+            prop::option::of("[a-zA-Z ]{1,20}"),
+            prop::option::of(any::<bool>()),
+            prop::option::of(prop::sample::select(Priority::ALL.to_vec())),
+            prop::option::of(prop::collection::vec("[a-z]{1,6}", 0..3).prop_map(shared::normalize_tags)),
+            prop::option::of(prop::option::of(0..4_102_444_800u64)),
+        )
+            .prop_map(|(title, completed, priority, tags, snoozed_until)| UpdateTaskRequest {
+                title,
+                completed,
+                priority,
+                tags,
+                snoozed_until,
+                ..Default::default()
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn merging_a_patch_twice_is_merging_it_once(mut target in json(), patch in json()) {
+            merge(&mut target, &patch);
+            let once = target.clone();
+            merge(&mut target, &patch);
+            prop_assert_eq!(target, once);
+        }
+
+        #[test]
+        fn an_empty_patch_leaves_an_object_alone(target in json()) {
+            let mut merged = target.clone();
+            merge(&mut merged, &Value::Object(Default::default()));
+            if target.is_object() {
+                prop_assert_eq!(merged, target);
+            } else {
+                prop_assert_eq!(merged, Value::Object(Default::default()));
+            }
+        }
+
+        #[test]
+        fn an_update_as_a_merge_patch_changes_what_put_would(task in task(), request in request()) {
+            let mut document = serde_json::to_value(&task).unwrap();
+            merge(&mut document, &serde_json::to_value(&request).unwrap());
+            let patched: Task = serde_json::from_value(document).unwrap();
+
+            let mut put = task.clone();
+            request.apply(&mut put);
+            prop_assert_eq!(patched, put);
+        }
+    }
+}
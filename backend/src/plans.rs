@@ -0,0 +1,95 @@
+//! "My day" plans: the tasks a user picked for a given date.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use shared::{DayPlan, RollOverRequest, SavePlanRequest};
+use std::collections::HashSet;
+
+use crate::{error::ApiError, store::Store};
+
+/// Dates come from the client's local calendar, so only the shape is checked.
+pub fn valid_date(date: &str) -> bool {
+    let bytes = date.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+async fn load(store: &Store, date: &str) -> Result<DayPlan, ApiError> {
+    let plan = store.get_plan(date).await?;
+    Ok(plan.unwrap_or_else(|| DayPlan {
+        date: date.to_string(),
+        task_ids: Vec::new(),
+    }))
+}
+
+#[tracing::instrument(skip(store))]
+pub async fn get_plan(Path(date): Path<String>, State(store): State<Store>) -> Result<Json<DayPlan>, ApiError> {
+    if !valid_date(&date) {
+        return Err(ApiError::Status(StatusCode::BAD_REQUEST));
+    }
+
+    load(&store, &date).await.map(Json)
+}
+
+#[tracing::instrument(skip(store, payload))]
+pub async fn save_plan(
+    Path(date): Path<String>,
+    State(store): State<Store>,
+    Json(payload): Json<SavePlanRequest>,
+) -> Result<Json<DayPlan>, ApiError> {
+    if !valid_date(&date) {
+        return Err(ApiError::Status(StatusCode::BAD_REQUEST));
+    }
+
+    let mut seen = HashSet::new();
+    let plan = DayPlan {
+        date,
+        task_ids: payload.task_ids.into_iter().filter(|id| seen.insert(*id)).collect(),
+    };
+    store.save_plan(&plan).await?;
+
+    Ok(Json(plan))
+}
+
+/// Moves the unfinished tasks of `date` onto the end of the `to` plan and
+/// returns that plan.
+#[tracing::instrument(skip(store))]
+pub async fn roll_over(
+    Path(date): Path<String>,
+    State(store): State<Store>,
+    Json(payload): Json<RollOverRequest>,
+) -> Result<Json<DayPlan>, ApiError> {
+    if !valid_date(&date) || !valid_date(&payload.to) || payload.to == date {
+        return Err(ApiError::Status(StatusCode::BAD_REQUEST));
+    }
+
+    let mut from = load(&store, &date).await?;
+    let mut to = load(&store, &payload.to).await?;
+
+    let mut unfinished = Vec::new();
+    for id in &from.task_ids {
+        let task = store.get(*id).await?;
+        // Deleted tasks are dropped from both plans
+        if task.is_some_and(|task| !task.completed) {
+            unfinished.push(*id);
+        }
+    }
+
+    for id in &unfinished {
+        if !to.task_ids.contains(id) {
+            to.task_ids.push(*id);
+        }
+    }
+    from.task_ids.retain(|id| !unfinished.contains(id));
+
+    store.save_plan(&to).await?;
+    store.save_plan(&from).await?;
+
+    Ok(Json(to))
+}
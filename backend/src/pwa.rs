@@ -0,0 +1,26 @@
+//! The service worker that makes the app installable and usable offline.
+//!
+//! `GET /sw.js` serves `sw.js` with this build's git hash and build time in
+//! its cache name, so a deploy replaces the cached shell and bundle instead
+//! of leaving clients on the old ones. The page registers it relative to
+//! `<base>`, so its scope is `BASE_PATH`. While it runs, the shell and
+//! bundle load from the cache, and pages and API reads fall back to the last
+//! answer cached when the network is down; writes go through the app's
+//! outbox as before.
+
+use axum::{http::header, response::IntoResponse};
+
+const SERVICE_WORKER: &str = include_str!("sw.js");
+
+#[tracing::instrument]
+pub async fn service_worker() -> impl IntoResponse {
+    let build = format!("{}-{}", shared::GIT_HASH, shared::BUILD_TIME);
+    (
+        [
+            (header::CONTENT_TYPE, "text/javascript; charset=utf-8"),
+            // Browsers check for a new worker on navigation; always let them see it
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        SERVICE_WORKER.replace("{{BUILD}}", &build),
+    )
+}
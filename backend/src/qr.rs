@@ -0,0 +1,51 @@
+//! `GET /api/tasks/:id/qr`: a QR code for the task's share link, for
+//! opening it on a phone.
+//!
+//! The link points at `PUBLIC_URL` when set, otherwise at the host the
+//! request came in on and `BASE_PATH`.
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use qrcode::{render::svg, QrCode};
+use uuid::Uuid;
+
+use crate::{error::ApiError, seo, AppState};
+
+fn share_link(base_path: &str, headers: &HeaderMap, id: Uuid) -> Option<String> {
+    Some(format!("{}/?task={}", seo::app_url(base_path, headers)?, id))
+}
+
+#[tracing::instrument(skip(state, headers))]
+pub async fn task_qr(
+    Path(id): Path<Uuid>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    state
+        .store
+        .get(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let link = share_link(&state.server.base_path, &headers, id).ok_or(StatusCode::BAD_REQUEST)?;
+    let code = QrCode::new(link.as_bytes()).map_err(|e| ApiError::Internal(format!("QR code: {}", e)))?;
+    let image = code
+        .render()
+        .min_dimensions(256, 256)
+        .dark_color(svg::Color("#000000"))
+        .light_color(svg::Color("#ffffff"))
+        .build();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "image/svg+xml"),
+            // The link only changes with PUBLIC_URL
+            (header::CACHE_CONTROL, "private, max-age=3600"),
+        ],
+        image,
+    )
+        .into_response())
+}
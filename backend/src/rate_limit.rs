@@ -0,0 +1,229 @@
+//! Per-IP request limits on `/api/*`.
+//!
+//! Each client IP gets a token bucket holding `RATE_LIMIT_REQUESTS` tokens
+//! that refills at that many per `RATE_LIMIT_WINDOW_SECS`, kept in Redis by
+//! [`BUCKET_SCRIPT`] so every replica shares it. A request takes a token;
+//! with none left it answers 429 with a `Retry-After` for when the next one
+//! comes, so no window ever lets through more than the limit plus what
+//! refilled. Without Redis, or when Redis fails, requests aren't limited;
+//! the former is warned about at startup.
+//!
+//! The IP is the connection's peer address, or with
+//! `RATE_LIMIT_TRUST_FORWARDED` the last `X-Forwarded-For` entry, which is
+//! the one added by a reverse proxy in front of the backend.
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{request::Parts, Extensions, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::Instrument;
+
+use crate::{config, telemetry::redis_span, throttle::Limited, AppState, RedisPool};
+
+/// Refills the bucket for the time since it was last used and takes a token
+/// if there is one. Returns 0 when the request may go ahead, or else the
+/// milliseconds until a token is back. `ARGV` holds the capacity, the refill
+/// window and the time, both in milliseconds. A bucket left alone for a
+/// window is full again, so it expires then.
+const BUCKET_SCRIPT: &str = r"
+local capacity = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'at')
+local tokens = tonumber(bucket[1]) or capacity
+local at = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - at) * capacity / window)
+local wait = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+else
+    wait = math.ceil((1 - tokens) * window / capacity)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'at', now)
+redis.call('PEXPIRE', KEYS[1], window)
+return wait
+";
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub requests: u64,
+    pub window: Duration,
+    pub trust_forwarded: bool,
+}
+
+impl RateLimitConfig {
+    /// Reads `RATE_LIMIT_REQUESTS`, `RATE_LIMIT_WINDOW_SECS` and
+    /// `RATE_LIMIT_TRUST_FORWARDED`. `None` when `RATE_LIMIT_REQUESTS=0`
+    /// turns limiting off.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let requests = config::parse_var("RATE_LIMIT_REQUESTS", str::parse::<u64>)?.unwrap_or(600);
+        let window = config::parse_var("RATE_LIMIT_WINDOW_SECS", str::parse::<u64>)?.unwrap_or(60);
+        if window == 0 {
+            return Err("RATE_LIMIT_WINDOW_SECS must be at least 1".to_string());
+        }
+        let trust_forwarded = config::parse_var("RATE_LIMIT_TRUST_FORWARDED", config::parse_switch)?.unwrap_or(false);
+        Ok((requests > 0).then(|| Self {
+            requests,
+            window: Duration::from_secs(window),
+            trust_forwarded,
+        }))
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    config: Option<Arc<RateLimitConfig>>,
+    redis: Option<RedisPool>,
+}
+
+impl RateLimiter {
+    pub fn new(config: Option<RateLimitConfig>, redis: Option<RedisPool>) -> Self {
+        Self {
+            config: config.map(Arc::new),
+            redis,
+        }
+    }
+
+    /// What to log at startup.
+    pub fn describe(&self) -> String {
+        match (&self.config, &self.redis) {
+            (None, _) => "off".to_string(),
+            (Some(_), None) => "off (requires Redis)".to_string(),
+            (Some(config), Some(_)) => {
+                format!("bursts of {} requests, refilled over {}s, per IP", config.requests, config.window.as_secs())
+            }
+        }
+    }
+
+    /// Whether limits are configured but there's no Redis to keep them in.
+    pub fn unenforced(&self) -> bool {
+        self.config.is_some() && self.redis.is_none()
+    }
+
+    /// Takes a token from `ip`'s bucket; `Err` with the time until the next
+    /// one once it's empty.
+    async fn take(config: &RateLimitConfig, pool: &RedisPool, ip: &str) -> Result<Result<(), Duration>, redis::RedisError> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+
+        let mut conn = pool.get().instrument(redis_span("CONNECT")).await?;
+        let wait: u64 = redis::Script::new(BUCKET_SCRIPT)
+            .key(format!("rate_limit:{}", ip))
+            .arg(config.requests)
+            .arg(config.window.as_millis() as u64)
+            .arg(now)
+            .invoke_async(&mut conn)
+            .instrument(redis_span("EVALSHA"))
+            .await?;
+
+        if wait > 0 {
+            Ok(Err(Duration::from_secs(wait.div_ceil(1000))))
+        } else {
+            Ok(Ok(()))
+        }
+    }
+}
+
+fn client_ip(trust_forwarded: bool, headers: &HeaderMap, extensions: &Extensions) -> Option<String> {
+    if trust_forwarded {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty());
+        if let Some(ip) = forwarded {
+            return Some(ip.to_string());
+        }
+    }
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(peer)| peer.ip().to_string())
+}
+
+/// Extractor for the client's IP, worked out as for rate limiting; `None`
+/// over a Unix socket.
+pub struct ClientIp(pub Option<String>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for ClientIp {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let trust_forwarded = state.server.rate_limit.as_ref().is_some_and(|limit| limit.trust_forwarded);
+        Ok(ClientIp(client_ip(trust_forwarded, &parts.headers, &parts.extensions)))
+    }
+}
+
+pub async fn limit(State(limiter): State<RateLimiter>, request: Request, next: Next) -> Response {
+    let (Some(config), Some(pool)) = (&limiter.config, &limiter.redis) else {
+        return next.run(request).await;
+    };
+    if !request.uri().path().starts_with("/api/") {
+        return next.run(request).await;
+    }
+    let Some(ip) = client_ip(config.trust_forwarded, request.headers(), request.extensions()) else {
+        return next.run(request).await;
+    };
+
+    match RateLimiter::take(config, pool, &ip).await {
+        Ok(Ok(())) => next.run(request).await,
+        Ok(Err(retry_after)) => {
+            tracing::info!(ip = %ip, "request rate limited");
+            Limited::new("Too many requests. Please wait a moment and try again.", retry_after).into_response()
+        }
+        Err(e) => {
+            tracing::warn!(ip = %ip, "rate limit check failed, letting the request through: {}", e);
+            next.run(request).await
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    fn request(forwarded: Option<&str>) -> Request {
+        let mut builder = Request::builder().uri("/api/tasks");
+        if let Some(forwarded) = forwarded {
+            builder = builder.header("x-forwarded-for", forwarded);
+        }
+        let mut request = builder.body(Body::empty()).unwrap();
+        request.extensions_mut().insert(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 50_000))));
+        request
+    }
+
+    #[test]
+    fn the_last_forwarded_entry_is_the_client_when_trusted() {
+        let ip = |forwarded| {
+            let request = request(forwarded);
+            client_ip(true, request.headers(), request.extensions())
+        };
+        assert_eq!(ip(Some("203.0.113.7")).as_deref(), Some("203.0.113.7"));
+        // Earlier entries come from the client and can be made up
+        assert_eq!(ip(Some("198.51.100.1, 203.0.113.7")).as_deref(), Some("203.0.113.7"));
+        assert_eq!(ip(Some("198.51.100.1,  ")).as_deref(), Some("10.0.0.1"));
+        assert_eq!(ip(None).as_deref(), Some("10.0.0.1"));
+    }
+
+    #[test]
+    fn forwarded_headers_are_ignored_unless_trusted() {
+        let request = request(Some("203.0.113.7"));
+        assert_eq!(client_ip(false, request.headers(), request.extensions()).as_deref(), Some("10.0.0.1"));
+    }
+
+    #[test]
+    fn without_a_peer_address_there_is_no_client() {
+        let request = Request::builder().uri("/api/tasks").body(Body::empty()).unwrap();
+        assert_eq!(client_ip(false, request.headers(), request.extensions()), None);
+    }
+}
@@ -0,0 +1,38 @@
+//! `GET /api/tasks/recent`: tasks created, completed or deleted since a
+//! given time, for the quick filters above the task list.
+//!
+//! The client picks `since`, so "today" and "this week" follow its own
+//! timezone. Lookups go through the stores' timestamp indexes; `deleted`
+//! lists the trash.
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+};
+use serde::Deserialize;
+use shared::{ListResponse, Task};
+
+use crate::{
+    error::ApiError,
+    pagination::Pagination,
+    store::{Store, Timestamp},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct RecentQuery {
+    /// `created`, `completed` or `deleted`.
+    by: Timestamp,
+    /// Unix seconds.
+    since: u64,
+}
+
+/// Most recent first.
+#[tracing::instrument(skip(store))]
+pub async fn get_recent(
+    State(store): State<Store>,
+    Query(query): Query<RecentQuery>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<Task>>, ApiError> {
+    let tasks = store.list_since(query.by, query.since).await?;
+    Ok(Json(pagination.paginate(tasks)))
+}
@@ -0,0 +1,105 @@
+//! Recurring tasks.
+//!
+//! Completing a task with a [`RecurrenceRule`] doesn't touch the rule. A
+//! background job picks up completed recurring tasks, saves the next
+//! occurrence as a new task snoozed until it's due, and moves the rule onto
+//! it, so each completion is repeated once. A task edited, deleted or
+//! archived since the job listed it is left alone until the next run.
+
+use shared::{recurrence::RecurrenceRule, ActivityKind, Task, TaskEvent};
+use std::time::Duration;
+
+use crate::{activity, analytics, live, snooze, status, store::{Store, Versions}, RedisPool};
+
+/// How often the job looks for completed recurring tasks.
+const INTERVAL: Duration = Duration::from_secs(60);
+
+/// The job's name on `GET /status`.
+const JOB: &str = "recurrence";
+
+pub fn spawn_job(store: Store, redis: Option<RedisPool>, live: live::Hub, monitor: status::Monitor) {
+    monitor.register(JOB, INTERVAL);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INTERVAL);
+        loop {
+            interval.tick().await;
+            monitor.track(JOB, repeat_completed(&store, &redis, &live)).await;
+        }
+    });
+}
+
+/// A fresh, open copy of `task`, snoozed until `rule` next fires after `now`.
+/// Its `created_at` is the due time, which the occurrence after it repeats
+/// from.
+fn next_occurrence(task: &Task, rule: &RecurrenceRule, now: u64) -> Option<Task> {
+    let due = rule.next_after(task.created_at.unwrap_or(now), now)?;
+
+    let mut next = Task::new(task.title.clone(), task.description.clone());
+    next.pinned = task.pinned;
+    next.priority = task.priority;
+    next.tags = task.tags.clone();
+    next.location = task.location.clone();
+    next.recurrence = Some(rule.clone());
+    next.created_at = Some(due);
+    next.snoozed_until = Some(due);
+    Some(next)
+}
+
+/// Returns whether every completed recurring task was repeated.
+#[tracing::instrument(skip_all)]
+async fn repeat_completed(store: &Store, redis: &Option<RedisPool>, live: &live::Hub) -> bool {
+    let tasks = match store.list().await {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            tracing::warn!("recurrence job could not list tasks: {}", e);
+            return false;
+        }
+    };
+
+    let now = snooze::now_secs();
+    let mut ok = true;
+    for mut done in tasks.into_iter().filter(|task| task.completed) {
+        let Some(rule) = done.recurrence.take() else {
+            continue;
+        };
+        let read = done.version;
+
+        let Some(next) = next_occurrence(&done, &rule, now) else {
+            // Only a cron rule that can't match again gets here; stop repeating
+            tracing::warn!(task = %done.id, rule = %rule.label(), "recurrence rule has no next occurrence");
+            match store.save_if_version(&mut done, read).await {
+                Ok(true) => live.publish(TaskEvent::Updated { task: done }),
+                Ok(false) => tracing::info!(task = %done.id, "task changed before its recurrence was cleared"),
+                Err(e) => {
+                    tracing::warn!(task = %done.id, "failed to clear recurrence: {}", e);
+                    ok = false;
+                }
+            }
+            continue;
+        };
+
+        // Both or neither, so an occurrence is never lost or made twice
+        let expected = Versions::from([(done.id, read)]);
+        let mut batch = [done, next];
+        match store.apply_batch(&mut batch, &[], &expected).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::info!(task = %batch[0].id, "task changed before it was repeated");
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!(task = %batch[0].id, "failed to repeat task: {}", e);
+                ok = false;
+                continue;
+            }
+        }
+        let [done, next] = batch;
+        tracing::info!(task = %done.id, next = %next.id, "repeated completed task");
+
+        activity::record(redis, ActivityKind::Created, &next).await;
+        analytics::record(redis, None, Some(&next)).await;
+        live.publish(TaskEvent::Updated { task: done });
+        live.publish(TaskEvent::Created { task: next });
+    }
+    ok
+}
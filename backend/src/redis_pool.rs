@@ -0,0 +1,93 @@
+//! Pooled Redis connections.
+//!
+//! Connections are opened on demand up to `REDIS_POOL_SIZE` and reused
+//! across requests instead of dialing Redis for every one. A connection is
+//! pinged before it is handed out again, so one Redis dropped (say, on a
+//! restart) is replaced rather than failing the request that got it. It is
+//! also unwatched then: a borrower that failed or was cancelled between
+//! `WATCH` and `EXEC` would otherwise leave keys watched, and the next
+//! borrower's unrelated transaction could abort on them.
+
+use axum::async_trait;
+use deadpool::managed::{self, Metrics, RecycleError, RecycleResult};
+use redis::{aio::ConnectionLike, Client, Cmd, ErrorKind, Pipeline, RedisError, RedisFuture, RedisResult, Value};
+use std::time::Duration;
+
+use crate::config::RedisPoolTuning;
+
+/// A freshly opened connection gets this long to answer its ping on reuse.
+const RECYCLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct Manager {
+    client: Client,
+}
+
+#[async_trait]
+impl managed::Manager for Manager {
+    type Type = redis::aio::Connection;
+    type Error = RedisError;
+
+    async fn create(&self) -> Result<Self::Type, Self::Error> {
+        self.client.get_async_connection().await
+    }
+
+    async fn recycle(&self, conn: &mut Self::Type, _metrics: &Metrics) -> RecycleResult<Self::Error> {
+        let ((), pong): ((), String) = redis::pipe().cmd("UNWATCH").cmd("PING").query_async(conn).await?;
+        if pong != "PONG" {
+            return Err(RecycleError::StaticMessage("unexpected PING reply"));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct RedisPool {
+    pool: managed::Pool<Manager>,
+}
+
+impl RedisPool {
+    pub fn new(url: &str, tuning: &RedisPoolTuning) -> Result<Self, String> {
+        let client = Client::open(url).map_err(|e| format!("invalid REDIS_URL: {}", e))?;
+        let pool = managed::Pool::builder(Manager { client })
+            .max_size(tuning.max_size)
+            .wait_timeout(Some(tuning.wait_timeout))
+            .create_timeout(Some(tuning.connect_timeout))
+            .recycle_timeout(Some(RECYCLE_TIMEOUT))
+            .runtime(deadpool::Runtime::Tokio1)
+            .build()
+            .map_err(|e| format!("failed to build Redis pool: {}", e))?;
+        Ok(Self { pool })
+    }
+
+    /// A connection from the pool, opening one if none is idle. Pool
+    /// timeouts come back as I/O errors like any other lost connection.
+    pub async fn get(&self) -> RedisResult<Connection> {
+        self.pool.get().await.map(Connection).map_err(|e| match e {
+            managed::PoolError::Backend(e) => e,
+            e => RedisError::from((ErrorKind::IoError, "Redis pool", e.to_string())),
+        })
+    }
+
+    /// Connections open and how many of them are idle.
+    pub fn status(&self) -> (usize, usize) {
+        let status = self.pool.status();
+        (status.size, status.available)
+    }
+}
+
+/// A pooled connection, returned to the pool when dropped.
+pub struct Connection(managed::Object<Manager>);
+
+impl ConnectionLike for Connection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.0.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(&'a mut self, cmd: &'a Pipeline, offset: usize, count: usize) -> RedisFuture<'a, Vec<Value>> {
+        self.0.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.get_db()
+    }
+}
@@ -0,0 +1,89 @@
+//! `GET /api/release-notes`: tasks completed in a date range, as Markdown.
+//!
+//! Tasks are grouped under their first tag, in alphabetical order, with
+//! untagged ones last. Only tasks with a `completed_at` count, so tasks
+//! completed before it was recorded are left out.
+
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use shared::Task;
+use std::collections::BTreeMap;
+
+use crate::{analytics::utc_date, error::ApiError, plans::valid_date, snooze, store::Store};
+
+/// Days covered when no `from` is given, counting `to`.
+const DEFAULT_DAYS: u64 = 14;
+
+/// Heading for tasks without tags.
+const UNTAGGED: &str = "Other";
+
+#[derive(Debug, Deserialize)]
+pub struct RangeQuery {
+    /// First day, `YYYY-MM-DD` (UTC); defaults to two weeks before `to`.
+    from: Option<String>,
+    /// Last day, included; defaults to today.
+    to: Option<String>,
+}
+
+#[tracing::instrument(skip(store))]
+pub async fn get_release_notes(State(store): State<Store>, Query(query): Query<RangeQuery>) -> Result<Response, ApiError> {
+    let now = snooze::now_secs();
+    let to = query.to.unwrap_or_else(|| utc_date(now));
+    let from = query.from.unwrap_or_else(|| utc_date(now.saturating_sub((DEFAULT_DAYS - 1) * 86_400)));
+    if !valid_date(&from) || !valid_date(&to) {
+        return Err(ApiError::Validation("Dates are YYYY-MM-DD.".to_string()));
+    }
+    if from > to {
+        return Err(ApiError::Validation("The range ends before it starts.".to_string()));
+    }
+
+    // ISO dates compare as strings
+    let completed: Vec<Task> = store
+        .list()
+        .await?
+        .into_iter()
+        .filter(|task| {
+            task.completed
+                && task.completed_at.map(utc_date).is_some_and(|date| from <= date && date <= to)
+        })
+        .collect();
+
+    let disposition = format!("attachment; filename=\"release-notes-{}-to-{}.md\"", from, to);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/markdown; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        render(&from, &to, completed),
+    )
+        .into_response())
+}
+
+fn render(from: &str, to: &str, tasks: Vec<Task>) -> String {
+    let mut groups: BTreeMap<Option<String>, Vec<Task>> = BTreeMap::new();
+    for task in tasks {
+        groups.entry(task.tags.first().cloned()).or_default().push(task);
+    }
+
+    let mut notes = format!("# Release notes: {} to {}\n", from, to);
+    if groups.is_empty() {
+        notes.push_str("\nNothing was completed in this period.\n");
+        return notes;
+    }
+
+    // `None` sorts first, but untagged tasks go at the end
+    let untagged = groups.remove(&None);
+    let groups = groups.into_iter().chain(untagged.map(|tasks| (None, tasks)));
+    for (tag, mut tasks) in groups {
+        tasks.sort_by_key(|task| task.completed_at);
+        notes.push_str(&format!("\n## {}\n\n", tag.as_deref().unwrap_or(UNTAGGED)));
+        for task in tasks {
+            notes.push_str(&format!("- {}\n", task.title.replace('\n', " ")));
+        }
+    }
+    notes
+}
@@ -0,0 +1,46 @@
+//! Made-up tasks for an empty store, to try the app out with a realistic
+//! list.
+//!
+//! With `SEED_TASKS=<count>` the backend saves that many tasks from
+//! [`shared::fixtures`] at startup, but only when the store has no tasks, so
+//! restarting with the variable still set doesn't add more. `SEED` picks
+//! the generator's seed, for the same tasks on every fresh store.
+
+use shared::fixtures::Fixtures;
+
+use crate::{
+    config, snooze,
+    store::{Store, Versions},
+};
+
+#[derive(Debug, Clone)]
+pub struct SeedConfig {
+    pub count: usize,
+    pub seed: u64,
+}
+
+impl SeedConfig {
+    /// Reads `SEED_TASKS` and `SEED`. `None` when `SEED_TASKS` is unset or `0`.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let count = config::parse_var("SEED_TASKS", str::parse::<usize>)?.unwrap_or(0);
+        let seed = config::parse_var("SEED", str::parse::<u64>)?.unwrap_or_else(snooze::now_secs);
+        Ok((count > 0).then_some(Self { count, seed }))
+    }
+}
+
+pub async fn run(store: &Store, config: &SeedConfig) {
+    match store.list().await {
+        Ok(tasks) if tasks.is_empty() => {}
+        Ok(_) => return,
+        Err(e) => {
+            tracing::warn!("failed to check for existing tasks before seeding: {}", e);
+            return;
+        }
+    }
+
+    let mut tasks = Fixtures::new(config.seed).tasks(config.count, snooze::now_secs());
+    match store.apply_batch(&mut tasks, &[], &Versions::new()).await {
+        Ok(_) => tracing::info!(seed = config.seed, "seeded {} tasks", tasks.len()),
+        Err(e) => tracing::warn!("failed to seed tasks: {}", e),
+    }
+}
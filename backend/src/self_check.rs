@@ -0,0 +1,118 @@
+//! The self-check job: a canary task taken through a whole lifecycle.
+//!
+//! Every `SELF_CHECK_INTERVAL_SECS` (default 5 minutes, `0` turns it off) the
+//! job creates a task, reads it back, completes it, trashes it and purges it,
+//! straight through the store. How long that took and how many checks failed
+//! in a row show on `GET /status` as the `self_check` job. After
+//! `SELF_CHECK_ALERT_AFTER` failures in a row (default 3) it logs an error,
+//! which lands in the admin dashboard's recent errors, and it logs again once
+//! a check passes.
+//!
+//! Live clients aren't told about the canary, but it does show in the change
+//! feed, as a task saved and then deleted.
+
+use shared::Task;
+use std::time::Duration;
+
+use crate::{config, snooze, status, store::Store};
+
+/// The job's name on `GET /status`.
+const JOB: &str = "self_check";
+
+/// Title of the canary task, to tell it apart if a failed check leaves it
+/// behind.
+const CANARY_TITLE: &str = "Self-check canary";
+
+#[derive(Debug, Clone)]
+pub struct SelfCheckConfig {
+    /// `None` when the job is off.
+    interval: Option<Duration>,
+    alert_after: u32,
+}
+
+impl SelfCheckConfig {
+    /// Reads `SELF_CHECK_INTERVAL_SECS` and `SELF_CHECK_ALERT_AFTER`.
+    pub fn from_env() -> Result<Self, String> {
+        let interval = config::parse_var("SELF_CHECK_INTERVAL_SECS", str::parse::<u64>)?.unwrap_or(300);
+        let alert_after = config::parse_var("SELF_CHECK_ALERT_AFTER", str::parse::<u32>)?.unwrap_or(3);
+        if alert_after == 0 {
+            return Err("SELF_CHECK_ALERT_AFTER must be at least 1".to_string());
+        }
+        Ok(Self {
+            interval: (interval > 0).then(|| Duration::from_secs(interval)),
+            alert_after,
+        })
+    }
+}
+
+pub fn spawn_job(store: Store, monitor: status::Monitor, config: SelfCheckConfig) {
+    let Some(period) = config.interval else {
+        return;
+    };
+    monitor.register(JOB, period);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        let mut failures: u32 = 0;
+        loop {
+            interval.tick().await;
+            if monitor.track(JOB, run_check(&store)).await {
+                if failures >= config.alert_after {
+                    tracing::info!(failures, "self-check passing again");
+                }
+                failures = 0;
+            } else {
+                failures += 1;
+                if failures == config.alert_after {
+                    tracing::error!(failures, "self-check failing repeatedly");
+                }
+            }
+        }
+    });
+}
+
+/// Returns whether the canary made it through.
+#[tracing::instrument(skip_all)]
+async fn run_check(store: &Store) -> bool {
+    let mut canary = Task::new(CANARY_TITLE.to_string(), String::new());
+    canary.created_at = Some(snooze::now_secs());
+    let result = lifecycle(store, &mut canary).await;
+    if let Err(e) = &result {
+        tracing::warn!("self-check failed: {}", e);
+        // Don't leave the canary behind, wherever it got to
+        canary.deleted_at = Some(snooze::now_secs());
+        let _ = store.trash(&canary).await;
+        let _ = store.purge(canary.id).await;
+    }
+    result.is_ok()
+}
+
+async fn lifecycle(store: &Store, canary: &mut Task) -> Result<(), String> {
+    store.save(canary).await.map_err(|e| format!("create: {}", e))?;
+
+    let stored = store
+        .get(canary.id)
+        .await
+        .map_err(|e| format!("read: {}", e))?
+        .ok_or("read: the canary wasn't there")?;
+    if stored.title != canary.title || stored.version != canary.version {
+        return Err("read: the canary came back different".to_string());
+    }
+
+    let expected = canary.version;
+    canary.completed = true;
+    canary.completed_at = Some(snooze::now_secs());
+    if !store.save_if_version(canary, expected).await.map_err(|e| format!("update: {}", e))? {
+        return Err("update: the canary's version moved".to_string());
+    }
+
+    canary.deleted_at = Some(snooze::now_secs());
+    if !store.trash(canary).await.map_err(|e| format!("delete: {}", e))? {
+        return Err("delete: the canary wasn't there to trash".to_string());
+    }
+    store
+        .purge(canary.id)
+        .await
+        .map_err(|e| format!("purge: {}", e))?
+        .ok_or("purge: the canary wasn't in the trash")?;
+    Ok(())
+}
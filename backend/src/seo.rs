@@ -0,0 +1,125 @@
+//! What crawlers and link previews see: `/robots.txt`, `/sitemap.xml`, and
+//! the title and Open Graph/Twitter tags the page shell puts in each page's
+//! `<head>`.
+//!
+//! Each route gets its own title and description. A task's share link
+//! (`/?task=<id>`, as in its QR code) is previewed with the task's own title
+//! and description instead. Absolute links use `PUBLIC_URL` when set,
+//! otherwise the host the request came in on and `BASE_PATH`.
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap},
+    response::IntoResponse,
+};
+use shared::Task;
+
+use crate::{shell::escape, AppState};
+
+/// The name previews show the app under.
+const SITE_NAME: &str = "Task Manager";
+
+/// Longest description put in a preview, in characters.
+const MAX_DESCRIPTION: usize = 200;
+
+struct Page {
+    path: &'static str,
+    title: &'static str,
+    description: &'static str,
+    /// Whether the page is listed in the sitemap.
+    listed: bool,
+}
+
+const PAGES: &[Page] = &[
+    Page { path: "/", title: "Tasks", description: "Your tasks, with tags, priorities, due dates and snoozing.", listed: true },
+    Page { path: "/my-day", title: "My Day", description: "The tasks planned for today.", listed: true },
+    Page { path: "/activity", title: "Activity", description: "Everything that happened to your tasks, most recent first.", listed: true },
+    Page { path: "/dashboard", title: "Dashboard", description: "Completion rates, tag statistics and charts of your tasks.", listed: true },
+    Page { path: "/trash", title: "Trash", description: "Deleted tasks, kept for a while before they're removed for good.", listed: false },
+    Page { path: "/archive", title: "Archive", description: "Tasks completed long ago, out of the way.", listed: false },
+    Page { path: "/settings", title: "Settings", description: "Theme, import and export.", listed: false },
+    Page { path: "/admin", title: "Admin", description: "Server status, configuration and logs.", listed: false },
+];
+
+/// Paths crawlers are asked to stay out of.
+const DISALLOWED: [&str; 3] = ["/api/", "/admin", "/settings"];
+
+/// The address the app is reached at, `BASE_PATH` included, without a
+/// trailing slash.
+pub fn app_url(base_path: &str, headers: &HeaderMap) -> Option<String> {
+    if let Some(url) = crate::config::var("PUBLIC_URL") {
+        return Some(url.trim_end_matches('/').to_string());
+    }
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let scheme = headers
+        .get("x-forwarded-proto")
+        .and_then(|proto| proto.to_str().ok())
+        .unwrap_or("http");
+    Some(format!("{}://{}{}", scheme, host, base_path))
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn robots(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    let base_path = &state.server.base_path;
+    let mut text = String::from("User-agent: *\n");
+    for path in DISALLOWED {
+        text.push_str(&format!("Disallow: {}{}\n", base_path, path));
+    }
+    if let Some(url) = app_url(base_path, &headers) {
+        text.push_str(&format!("\nSitemap: {}/sitemap.xml\n", url));
+    }
+    ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], text)
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn sitemap(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    let url = app_url(&state.server.base_path, &headers).unwrap_or_else(|| state.server.base_path.clone());
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for page in PAGES.iter().filter(|page| page.listed) {
+        xml.push_str(&format!("  <url><loc>{}{}</loc></url>\n", escape(&url), page.path));
+    }
+    xml.push_str("</urlset>\n");
+    ([(header::CONTENT_TYPE, "application/xml; charset=utf-8")], xml)
+}
+
+/// The `<title>` and preview tags for `path`, or for `task` when the page is
+/// its share link. `url` is the page's absolute address, if known.
+pub fn head_tags(path: &str, url: Option<&str>, task: Option<&Task>) -> String {
+    let (title, description) = match (task, PAGES.iter().find(|page| page.path == path)) {
+        (Some(task), _) => (task.title.clone(), summary(&task.description)),
+        (None, Some(page)) => (page.title.to_string(), page.description.to_string()),
+        (None, None) => (SITE_NAME.to_string(), PAGES[0].description.to_string()),
+    };
+    let title = if title == SITE_NAME { title } else { format!("{} · {}", title, SITE_NAME) };
+    let (title, description) = (escape(&title), escape(&description));
+
+    let mut tags = format!("<title>{}</title>\n", title);
+    let mut meta = |attribute: &str, name: &str, content: &str| {
+        tags.push_str(&format!("    <meta {}=\"{}\" content=\"{}\">\n", attribute, name, content));
+    };
+    meta("name", "description", &description);
+    meta("property", "og:site_name", SITE_NAME);
+    meta("property", "og:type", if task.is_some() { "article" } else { "website" });
+    meta("property", "og:title", &title);
+    meta("property", "og:description", &description);
+    if let Some(url) = url {
+        meta("property", "og:url", &escape(url));
+    }
+    meta("name", "twitter:card", "summary");
+    meta("name", "twitter:title", &title);
+    meta("name", "twitter:description", &description);
+    tags
+}
+
+/// `description` cut to [`MAX_DESCRIPTION`] characters on a word boundary.
+fn summary(description: &str) -> String {
+    let description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+    if description.chars().count() <= MAX_DESCRIPTION {
+        return description;
+    }
+    let cut: String = description.chars().take(MAX_DESCRIPTION).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(start, _)| start);
+    format!("{}…", cut)
+}
@@ -0,0 +1,186 @@
+//! Listeners: plain TCP, TLS terminated in-process (rustls), or a Unix
+//! domain socket for deployments behind a local proxy. All of them share one
+//! hyper connection builder configured from [`ServerTuning`].
+
+use axum::{extract::ConnectInfo, Router};
+use hyper_util::{
+    rt::{TokioExecutor, TokioIo, TokioTimer},
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use std::{fs::File, io, io::BufReader, net::SocketAddr, path::Path, sync::Arc};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+use tokio_rustls::TlsAcceptor;
+use tower::ServiceExt;
+
+use crate::config::{Config, Listen, ServerTuning, TlsConfig};
+
+#[derive(Clone)]
+struct Connections {
+    builder: Arc<Builder<TokioExecutor>>,
+    app: Router,
+    limit: Option<Arc<Semaphore>>,
+}
+
+impl Connections {
+    fn new(tuning: &ServerTuning, app: Router) -> Self {
+        let mut builder = Builder::new(TokioExecutor::new());
+        builder
+            .http1()
+            .timer(TokioTimer::new())
+            .keep_alive(tuning.http1_keep_alive)
+            .header_read_timeout(tuning.http1_header_read_timeout);
+        builder
+            .http2()
+            .timer(TokioTimer::new())
+            .max_concurrent_streams(tuning.http2_max_concurrent_streams)
+            .keep_alive_interval(tuning.http2_keep_alive_interval)
+            .keep_alive_timeout(tuning.http2_keep_alive_timeout);
+        if !tuning.http2 {
+            builder = builder.http1_only();
+        }
+
+        Self {
+            builder: Arc::new(builder),
+            app,
+            limit: tuning.max_connections.map(|max| Arc::new(Semaphore::new(max))),
+        }
+    }
+
+    /// Waits for a free connection slot when `MAX_CONNECTIONS` is set.
+    async fn reserve(&self) -> Option<OwnedSemaphorePermit> {
+        match &self.limit {
+            Some(limit) => limit.clone().acquire_owned().await.ok(),
+            None => None,
+        }
+    }
+
+    /// Serves one connection. `peer` is handed to handlers as
+    /// `ConnectInfo`; Unix sockets have none.
+    async fn serve<S>(&self, stream: S, peer: Option<SocketAddr>)
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let app = self.app.clone().map_request(move |mut request: hyper::Request<hyper::body::Incoming>| {
+            if let Some(peer) = peer {
+                request.extensions_mut().insert(ConnectInfo(peer));
+            }
+            request
+        });
+        let service = TowerToHyperService::new(app);
+        if let Err(e) = self
+            .builder
+            .serve_connection_with_upgrades(TokioIo::new(stream), service)
+            .await
+        {
+            tracing::warn!("connection error: {}", e);
+        }
+    }
+}
+
+pub async fn serve(config: &Config, app: Router) -> io::Result<()> {
+    let connections = Connections::new(&config.tuning, app);
+
+    match &config.listen {
+        Listen::Tcp(addr) => serve_tcp(*addr, connections).await,
+        Listen::Tls(addr, tls) => serve_tls(*addr, tls, connections).await,
+        Listen::Unix(path) => serve_unix(path, connections).await,
+    }
+}
+
+async fn serve_tcp(addr: SocketAddr, connections: Connections) -> io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("server running on http://{}", addr);
+
+    loop {
+        let permit = connections.reserve().await;
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let connections = connections.clone();
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    connections.serve(stream, Some(peer)).await;
+                });
+            }
+            Err(e) => tracing::warn!("failed to accept connection: {}", e),
+        }
+    }
+}
+
+async fn serve_tls(addr: SocketAddr, tls: &TlsConfig, connections: Connections) -> io::Result<()> {
+    let acceptor = TlsAcceptor::from(load_tls_config(tls, connections.builder.is_http2_available())?);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    tracing::info!("server running on https://{}", addr);
+
+    loop {
+        let permit = connections.reserve().await;
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let acceptor = acceptor.clone();
+                let connections = connections.clone();
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    match acceptor.accept(stream).await {
+                        Ok(stream) => connections.serve(stream, Some(peer)).await,
+                        Err(e) => tracing::warn!("TLS handshake failed: {}", e),
+                    }
+                });
+            }
+            Err(e) => tracing::warn!("failed to accept connection: {}", e),
+        }
+    }
+}
+
+async fn serve_unix(path: &Path, connections: Connections) -> io::Result<()> {
+    // A socket file left behind by a previous run would make bind fail
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)?;
+    tracing::info!("server running on unix:{}", path.display());
+
+    loop {
+        let permit = connections.reserve().await;
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let connections = connections.clone();
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    connections.serve(stream, None).await;
+                });
+            }
+            Err(e) => tracing::warn!("failed to accept connection: {}", e),
+        }
+    }
+}
+
+fn load_tls_config(tls: &TlsConfig, http2: bool) -> io::Result<Arc<rustls::ServerConfig>> {
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    };
+
+    let certs = rustls_pemfile::certs(&mut open(&tls.cert_path)?).collect::<Result<Vec<_>, _>>()?;
+    let key = rustls_pemfile::private_key(&mut open(&tls.key_path)?)?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no private key found in {}", tls.key_path.display()),
+        )
+    })?;
+
+    let mut config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    config.alpn_protocols = if http2 {
+        vec![b"h2".to_vec(), b"http/1.1".to_vec()]
+    } else {
+        vec![b"http/1.1".to_vec()]
+    };
+
+    Ok(Arc::new(config))
+}
@@ -0,0 +1,212 @@
+//! The app's HTML page, streamed in two parts.
+//!
+//! Everything up to `</body>` goes out at once with a skeleton of the task
+//! list after it, so the browser paints and starts fetching the styles and
+//! the app while the store is still being asked. When the first page of
+//! tasks is in, it follows as plain cards in place of the skeleton, and as
+//! JSON the app starts from instead of fetching the same page again. The
+//! app's script is a module, so it only runs once the whole page is in.
+//!
+//! Only the Tasks page (`/`) waits for tasks; other routes get the skeleton
+//! and the end of the page straight away.
+//!
+//! The `<head>` gets the route's title and link preview tags (see
+//! [`crate::seo`]); for a task's share link that means reading the task
+//! before the first part goes out.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, HeaderName, StatusCode, Uri},
+    response::{Html, IntoResponse, Response},
+};
+use futures_util::stream::{self, StreamExt};
+use shared::{InitialTasks, Task, TASK_PAGE_SIZE};
+use std::convert::Infallible;
+use uuid::Uuid;
+
+use crate::{pagination::Pagination, seo, snooze, store::Store, AppState};
+
+/// What's served when there's no built frontend to read `index.html` from.
+const FALLBACK_INDEX: &str = r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>Task Manager</title>
+    <link href="./styles.css" rel="stylesheet">
+</head>
+<body class="bg-gray-100 font-sans">
+    <script type="module">
+        import init from './frontend.js';
+        init();
+    </script>
+</body>
+</html>
+"#;
+
+/// Opens the shell; the app removes it when it starts.
+const SKELETON: &str = r#"<div id="shell" class="max-w-6xl mx-auto px-6 py-8">
+<div id="shell-skeleton" class="space-y-4" aria-busy="true" aria-label="Loading tasks">
+<div class="h-24 rounded-xl bg-ctp-surface0 animate-pulse"></div>
+<div class="h-24 rounded-xl bg-ctp-surface0 animate-pulse"></div>
+<div class="h-24 rounded-xl bg-ctp-surface0 animate-pulse"></div>
+<div class="h-24 rounded-xl bg-ctp-surface0 animate-pulse"></div>
+</div>
+"#;
+
+pub async fn serve(State(state): State<AppState>, uri: Uri, headers: HeaderMap) -> Response {
+    // Missing files with an extension are real 404s; anything else is a SPA route
+    let name = uri.path().rsplit('/').next().unwrap_or_default();
+    if name.contains('.') && name != "index.html" {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let page = with_head_tags(index_html(&state), &state, &uri, &headers).await;
+    let Some(end) = page.rfind("</body>") else {
+        return Html(page).into_response();
+    };
+    let head = format!("{}{}", &page[..end], SKELETON);
+    let tail = page[end..].to_string();
+
+    let rest = async move {
+        let initial = match uri.path() {
+            "/" => first_page(&state.store).await,
+            _ => None,
+        };
+        let mut html = initial.as_ref().map(render).unwrap_or_default();
+        html.push_str("</div>\n");
+        if let Some(json) = initial.and_then(|initial| serde_json::to_string(&initial).ok()) {
+            // `</script>` in a title would end the element early
+            html.push_str(&format!(
+                "<script type=\"application/json\" id=\"initial-tasks\">{}</script>\n",
+                json.replace('<', "\\u003c")
+            ));
+        }
+        html.push_str(&tail);
+        html
+    };
+    let body = stream::once(async move { head })
+        .chain(stream::once(rest))
+        .map(|html| Ok::<_, Infallible>(Bytes::from(html)));
+
+    // Proxies buffering the response would hold the skeleton back
+    (
+        [
+            (header::CONTENT_TYPE, "text/html; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-cache"),
+            (HeaderName::from_static("x-accel-buffering"), "no"),
+        ],
+        Body::from_stream(body),
+    )
+        .into_response()
+}
+
+#[cfg(not(feature = "embed-frontend"))]
+fn index_html(state: &AppState) -> String {
+    std::fs::read_to_string(state.server.static_dir.join("index.html")).unwrap_or_else(|_| FALLBACK_INDEX.to_string())
+}
+
+#[cfg(feature = "embed-frontend")]
+fn index_html(_state: &AppState) -> String {
+    crate::assets::index_html().unwrap_or_else(|| FALLBACK_INDEX.to_string())
+}
+
+/// `page` with its `<title>` replaced by the route's title and preview tags,
+/// after a `<base>` that resolves the page's relative links under
+/// `BASE_PATH`.
+async fn with_head_tags(page: String, state: &AppState, uri: &Uri, headers: &HeaderMap) -> String {
+    let shared = match uri.path() {
+        "/" => shared_task(uri),
+        _ => None,
+    };
+    let task = match shared {
+        Some(id) => state.store.get(id).await.ok().flatten(),
+        None => None,
+    };
+    let base_path = &state.server.base_path;
+    let url = seo::app_url(base_path, headers).map(|app| format!("{}{}", app, uri));
+    let tags = format!(
+        "<base href=\"{}/\">\n    {}",
+        escape(base_path),
+        seo::head_tags(uri.path(), url.as_deref(), task.as_ref())
+    );
+
+    let title = page.find("<title>").zip(page.find("</title>")).filter(|(start, end)| start < end);
+    match (title, page.find("</head>")) {
+        (Some((start, end)), _) => format!("{}{}{}", &page[..start], tags.trim_end(), &page[end + "</title>".len()..]),
+        (None, Some(end)) => format!("{}    {}{}", &page[..end], tags, &page[end..]),
+        (None, None) => page,
+    }
+}
+
+/// The task a share link (`/?task=<id>`) points at.
+fn shared_task(uri: &Uri) -> Option<Uuid> {
+    uri.query()?.split('&').find_map(|pair| pair.strip_prefix("task="))?.parse().ok()
+}
+
+/// The page the app would load first, as `GET /api/tasks` gives it.
+async fn first_page(store: &Store) -> Option<InitialTasks> {
+    // Taken before the list, as the app does, so nothing saved meanwhile is missed
+    let version = store.changes_since(u64::MAX).await.ok().map(|changes| changes.version);
+    let first = Pagination::first(TASK_PAGE_SIZE);
+    let (tasks, total) = match store.list_page(first.offset(), first.per_page() as usize, Some(snooze::now_secs())).await {
+        Ok(page) => page,
+        Err(e) => {
+            tracing::warn!("failed to list tasks for the page shell: {}", e);
+            return None;
+        }
+    };
+    Some(InitialTasks {
+        list: first.respond(tasks, total),
+        version,
+    })
+}
+
+/// The tasks as plain cards, pinned first and completed last, in place of
+/// the skeleton.
+fn render(initial: &InitialTasks) -> String {
+    let removal = "<script>document.getElementById(\"shell-skeleton\").remove()</script>\n";
+    if initial.list.data.is_empty() {
+        return format!("<p class=\"text-ctp-subtext0\">No tasks yet.</p>\n{}", removal);
+    }
+    let mut tasks: Vec<&Task> = initial.list.data.iter().collect();
+    tasks.sort_by_key(|task| (!task.pinned, task.completed));
+
+    let mut html = String::from("<ul class=\"space-y-4\">\n");
+    for task in tasks {
+        let (card, title) = if task.completed {
+            ("border-ctp-green bg-ctp-green/10", "line-through text-ctp-subtext0")
+        } else {
+            ("border-ctp-surface1", "text-ctp-text")
+        };
+        html.push_str(&format!(
+            "<li class=\"border rounded-xl p-6 bg-ctp-surface0 shadow-sm {}\"><h3 class=\"text-lg font-semibold {}\">{}</h3>",
+            card,
+            title,
+            escape(&task.title)
+        ));
+        if !task.description.is_empty() {
+            html.push_str(&format!("<p class=\"mt-2 text-ctp-subtext1\">{}</p>", escape(&task.description)));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+    html.push_str(removal);
+    html
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,73 @@
+//! Snoozed tasks.
+//!
+//! Snoozed tasks are left out of `GET /api/tasks` until their
+//! `snoozed_until` time passes. A background job then clears the field so the
+//! wake-up is recorded on the task itself and logged as an event, tells live
+//! clients, and fires the automation rules' `SnoozeEnded` trigger. A task
+//! changed since the job listed it is picked up again on the next run.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use shared::{automation::RuleTrigger, TaskEvent};
+
+use crate::{automations, live, status, store::Store};
+
+/// How often the wake job looks for tasks whose snooze has run out.
+const WAKE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The wake job's name on `GET /status`.
+const JOB: &str = "snooze_wake";
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+pub fn spawn_wake_job(store: Store, automations: automations::Engine, live: live::Hub, monitor: status::Monitor) {
+    monitor.register(JOB, WAKE_INTERVAL);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(WAKE_INTERVAL);
+        loop {
+            interval.tick().await;
+            monitor.track(JOB, wake_due_tasks(&store, &automations, &live)).await;
+        }
+    });
+}
+
+/// Returns whether every due task was woken.
+#[tracing::instrument(skip_all)]
+async fn wake_due_tasks(store: &Store, automations: &automations::Engine, live: &live::Hub) -> bool {
+    let tasks = match store.list().await {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            tracing::warn!("snooze wake job could not list tasks: {}", e);
+            return false;
+        }
+    };
+
+    let now = now_secs();
+    let mut ok = true;
+    for mut task in tasks {
+        if task.snoozed_until.is_none() || task.is_snoozed(now) {
+            continue;
+        }
+
+        let read = task.version;
+        task.snoozed_until = None;
+        match store.save_if_version(&mut task, read).await {
+            Ok(true) => {
+                tracing::info!(task = %task.id, "snoozed task woke up");
+                automations.fire(RuleTrigger::SnoozeEnded, task.id, None);
+                live.publish(TaskEvent::Updated { task });
+            }
+            Ok(false) => tracing::info!(task = %task.id, "snoozed task changed before it could be woken"),
+            Err(e) => {
+                tracing::warn!(task = %task.id, "failed to wake snoozed task: {}", e);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
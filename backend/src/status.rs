@@ -0,0 +1,153 @@
+//! `GET /status`: whether the backend is up, for uptime monitors and the
+//! status badge on the Settings page.
+//!
+//! Reports uptime, the build, how long a storage ping takes and how the
+//! background jobs' latest runs went. Answers 503 when storage doesn't
+//! answer; failing or overdue jobs only make it `degraded`.
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use shared::{Health, JobHealth, JobStatus, ServiceStatus, StorageStatus};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{snooze, store::Store};
+
+/// A ping slower than this counts as a failure.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Start time and the latest runs of the background jobs, which report each
+/// run here.
+#[derive(Clone)]
+pub struct Monitor {
+    started_at: u64,
+    jobs: Arc<Mutex<BTreeMap<&'static str, Job>>>,
+}
+
+struct Job {
+    interval: Duration,
+    last_run: Option<u64>,
+    last_success: Option<u64>,
+    last_duration: Option<Duration>,
+    consecutive_failures: u32,
+}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self {
+            started_at: snooze::now_secs(),
+            jobs: Arc::default(),
+        }
+    }
+
+    /// Adds a job that runs every `interval`; it shows as pending until its
+    /// first run finishes.
+    pub fn register(&self, name: &'static str, interval: Duration) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.insert(
+                name,
+                Job {
+                    interval,
+                    last_run: None,
+                    last_success: None,
+                    last_duration: None,
+                    consecutive_failures: 0,
+                },
+            );
+        }
+    }
+
+    /// Runs one pass of job `name` and records how long it took and whether
+    /// it went through without errors, which it returns.
+    pub async fn track(&self, name: &'static str, run: impl Future<Output = bool>) -> bool {
+        let started = Instant::now();
+        let ok = run.await;
+        let Ok(mut jobs) = self.jobs.lock() else {
+            return ok;
+        };
+        if let Some(job) = jobs.get_mut(name) {
+            let now = snooze::now_secs();
+            job.last_run = Some(now);
+            job.last_duration = Some(started.elapsed());
+            if ok {
+                job.last_success = Some(now);
+                job.consecutive_failures = 0;
+            } else {
+                job.consecutive_failures += 1;
+            }
+        }
+        ok
+    }
+
+    fn jobs(&self, now: u64) -> Vec<JobStatus> {
+        let Ok(jobs) = self.jobs.lock() else {
+            return Vec::new();
+        };
+        jobs.iter()
+            .map(|(name, job)| {
+                // A job that never ran is late from when the backend started
+                let overdue = now.saturating_sub(job.last_run.unwrap_or(self.started_at)) > 2 * job.interval.as_secs();
+                let health = match job.last_run {
+                    _ if overdue => JobHealth::Stalled,
+                    None => JobHealth::Pending,
+                    Some(run) if job.last_success == Some(run) => JobHealth::Ok,
+                    Some(_) => JobHealth::Failing,
+                };
+                JobStatus {
+                    name: name.to_string(),
+                    health,
+                    last_run: job.last_run,
+                    last_success: job.last_success,
+                    duration_ms: job.last_duration.map(|d| d.as_secs_f64() * 1000.0),
+                    consecutive_failures: job.consecutive_failures,
+                }
+            })
+            .collect()
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn get_status(State(store): State<Store>, State(monitor): State<Monitor>) -> Response {
+    let started = Instant::now();
+    let latency_ms = match tokio::time::timeout(PING_TIMEOUT, store.ping()).await {
+        Ok(Ok(())) => Some(started.elapsed().as_secs_f64() * 1000.0),
+        Ok(Err(e)) => {
+            tracing::warn!("status: storage ping failed: {}", e);
+            None
+        }
+        Err(_) => {
+            tracing::warn!("status: storage ping timed out");
+            None
+        }
+    };
+
+    let now = snooze::now_secs();
+    let jobs = monitor.jobs(now);
+    let health = if latency_ms.is_none() {
+        Health::Down
+    } else if jobs.iter().any(|job| matches!(job.health, JobHealth::Failing | JobHealth::Stalled)) {
+        Health::Degraded
+    } else {
+        Health::Ok
+    };
+    let status = ServiceStatus {
+        health,
+        build: shared::build_info!(),
+        uptime_secs: now.saturating_sub(monitor.started_at),
+        storage: StorageStatus {
+            backend: store.name().to_string(),
+            latency_ms,
+        },
+        jobs,
+    };
+
+    let code = if health == Health::Down { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    (code, [(header::CACHE_CONTROL, "no-store")], Json(status)).into_response()
+}
@@ -0,0 +1,139 @@
+//! Task persistence.
+//!
+//! Redis is used when `REDIS_URL` is set; otherwise tasks live in a single
+//! SQLite file (`SQLITE_PATH`, default `tasks.db`) so the app can run with no
+//! external services.
+
+mod redis;
+mod sqlite;
+
+use axum::async_trait;
+use serde::Deserialize;
+use shared::{DayPlan, Task, TaskChanges};
+use std::{collections::HashMap, fmt, sync::Arc};
+use uuid::Uuid;
+
+pub use self::redis::RedisStore;
+pub use self::sqlite::SqliteStore;
+
+pub type Store = Arc<dyn TaskStore>;
+
+/// The version each task was read at, for [`TaskStore::apply_batch`].
+pub type Versions = HashMap<Uuid, u64>;
+
+/// How long deleted tasks stay in the trash before they're purged.
+pub const TRASH_TTL_SECS: u64 = shared::TRASH_TTL_DAYS * 24 * 60 * 60;
+
+/// A task timestamp the stores keep an index on.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Timestamp {
+    /// `created_at`, over the task list.
+    Created,
+    /// `completed_at`, over the task list.
+    Completed,
+    /// `deleted_at`, over the trash.
+    Deleted,
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    /// The backing service could not be reached or rejected the command.
+    Unavailable(String),
+    /// A stored record could not be decoded.
+    Corrupt(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Unavailable(e) => write!(f, "storage unavailable: {}", e),
+            StoreError::Corrupt(e) => write!(f, "corrupt record: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+#[async_trait]
+pub trait TaskStore: Send + Sync {
+    /// Human-readable backend name, e.g. for the dashboard.
+    fn name(&self) -> &'static str;
+
+    /// The cheapest round trip to the backing service, to check it answers.
+    async fn ping(&self) -> Result<(), StoreError>;
+
+    /// All tasks, ordered by id so repeated requests are stable.
+    async fn list(&self) -> Result<Vec<Task>, StoreError>;
+
+    /// `limit` tasks from `offset` in the task list ordered by `created_at`,
+    /// then id, and how many tasks the whole list has. With `awake_at`, tasks
+    /// still snoozed at that time are left out of both.
+    async fn list_page(&self, offset: usize, limit: usize, awake_at: Option<u64>) -> Result<(Vec<Task>, u64), StoreError>;
+
+    async fn get(&self, id: Uuid) -> Result<Option<Task>, StoreError>;
+
+    /// Inserts or replaces a task, bumping its `version`.
+    ///
+    /// Saves and deletes bump a global change number, which
+    /// [`changes_since`](Self::changes_since) reports against.
+    async fn save(&self, task: &mut Task) -> Result<(), StoreError>;
+
+    /// Like [`save`](Self::save), but only if the stored task is still at
+    /// version `expected`, checked and written atomically. Returns whether
+    /// the task was saved.
+    async fn save_if_version(&self, task: &mut Task, expected: u64) -> Result<bool, StoreError>;
+
+    /// Saves `tasks` and moves `trashed` to the trash as one unit: either
+    /// every change is made or none is. Tasks in `expected`, from either
+    /// list, must still be stored at the version given there, checked
+    /// atomically with the writes like [`save_if_version`](Self::save_if_version);
+    /// the others are written regardless. Returns whether the batch was
+    /// applied.
+    async fn apply_batch(&self, tasks: &mut [Task], trashed: &[Task], expected: &Versions) -> Result<bool, StoreError>;
+
+    /// Moves a task, with `deleted_at` set, out of the task list and into
+    /// the trash; to the change feed this counts as a delete. Returns whether
+    /// the task was there to move.
+    async fn trash(&self, task: &Task) -> Result<bool, StoreError>;
+
+    /// Trashed tasks, most recently deleted first.
+    async fn list_trash(&self) -> Result<Vec<Task>, StoreError>;
+
+    /// Tasks whose `timestamp` is at or after `since` (unix seconds), most
+    /// recent first, looked up through the index rather than a full scan.
+    async fn list_since(&self, timestamp: Timestamp, since: u64) -> Result<Vec<Task>, StoreError>;
+
+    /// Moves a task back out of the trash and saves it, returning it as
+    /// saved.
+    async fn restore(&self, id: Uuid) -> Result<Option<Task>, StoreError>;
+
+    /// Deletes a trashed task for good, returning it so its attachments can
+    /// be removed too.
+    async fn purge(&self, id: Uuid) -> Result<Option<Task>, StoreError>;
+
+    /// Deletes every task trashed before `before` (unix seconds) for good.
+    async fn purge_trashed_before(&self, before: u64) -> Result<Vec<Task>, StoreError>;
+
+    /// Moves every task completed before `before` (unix seconds) out of the
+    /// task list and into the archive, with `archived_at` set to `now`; to
+    /// the change feed each counts as a delete. Tasks that still repeat are
+    /// left for the recurrence job. Returns the tasks as archived.
+    async fn archive_completed_before(&self, before: u64, now: u64) -> Result<Vec<Task>, StoreError>;
+
+    /// Archived tasks, most recently archived first.
+    async fn list_archive(&self) -> Result<Vec<Task>, StoreError>;
+
+    /// Moves a task back out of the archive and saves it, returning it as
+    /// saved.
+    async fn unarchive(&self, id: Uuid) -> Result<Option<Task>, StoreError>;
+
+    /// Ids of tasks saved or deleted after change number `since`, with the
+    /// current change number. Each task is listed once, by its latest change.
+    async fn changes_since(&self, since: u64) -> Result<TaskChanges, StoreError>;
+
+    /// The "My day" plan for a `YYYY-MM-DD` date, if one was saved.
+    async fn get_plan(&self, date: &str) -> Result<Option<DayPlan>, StoreError>;
+
+    async fn save_plan(&self, plan: &DayPlan) -> Result<(), StoreError>;
+}
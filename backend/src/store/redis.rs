@@ -0,0 +1,676 @@
+use axum::async_trait;
+use redis::AsyncCommands;
+use shared::{DayPlan, Task, TaskChanges};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use super::{StoreError, TaskStore, Timestamp, Versions, TRASH_TTL_SECS};
+use crate::{redis_pool, snooze, telemetry::redis_span, RedisPool};
+
+pub struct RedisStore {
+    pool: RedisPool,
+}
+
+impl RedisStore {
+    pub fn new(pool: RedisPool) -> Self {
+        Self { pool }
+    }
+
+    /// A pooled connection. The pool unwatches it before handing it out
+    /// again, so the transactions below can return, or fail, between
+    /// `WATCH` and `EXEC` without leaving keys watched.
+    async fn connection(&self) -> Result<redis_pool::Connection, StoreError> {
+        self.pool
+            .get()
+            .instrument(redis_span("CONNECT"))
+            .await
+            .map_err(unavailable)
+    }
+
+    /// Adds tasks saved before the indexes existed, the first time the
+    /// backend runs against such a Redis. Runs before serving so listings are
+    /// complete from the first request.
+    pub async fn index_existing(&self) -> Result<usize, StoreError> {
+        let mut conn = self.connection().await?;
+        let markers: usize = conn
+            .exists(&[INDEXED_KEY, COMPLETED_INDEXED_KEY, SNOOZED_INDEXED_KEY])
+            .instrument(redis_span("EXISTS"))
+            .await
+            .map_err(unavailable)?;
+        if markers == 3 {
+            return Ok(0);
+        }
+
+        // SCAN rather than KEYS so this doesn't block Redis either
+        let mut keys: Vec<String> = Vec::new();
+        {
+            let mut iter = conn.scan_match::<_, String>("task:*").instrument(redis_span("SCAN")).await.map_err(unavailable)?;
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for chunk in keys.chunks(LIST_CHUNK) {
+            let values: Vec<Option<String>> = conn.mget(chunk).instrument(redis_span("MGET")).await.map_err(unavailable)?;
+            for task in values.iter().flatten().filter_map(|json| serde_json::from_str::<Task>(json).ok()) {
+                pipe.zadd(INDEX_KEY, task.id.to_string(), index_score(&task)).ignore();
+                if let Some(completed_at) = task.completed_at {
+                    pipe.zadd(COMPLETED_INDEX_KEY, task.id.to_string(), completed_at).ignore();
+                }
+                if let Some(snoozed_until) = task.snoozed_until {
+                    pipe.zadd(SNOOZED_INDEX_KEY, task.id.to_string(), snoozed_until).ignore();
+                }
+            }
+        }
+        pipe.set(INDEXED_KEY, 1).ignore();
+        pipe.set(COMPLETED_INDEXED_KEY, 1).ignore();
+        pipe.set(SNOOZED_INDEXED_KEY, 1).ignore();
+        pipe.query_async::<_, ()>(&mut conn).instrument(redis_span("MULTI")).await.map_err(unavailable)?;
+
+        Ok(keys.len())
+    }
+}
+
+fn unavailable(e: redis::RedisError) -> StoreError {
+    StoreError::Unavailable(e.to_string())
+}
+
+fn task_key(id: Uuid) -> String {
+    format!("task:{}", id)
+}
+
+fn trash_key(id: Uuid) -> String {
+    format!("trash:{}", id)
+}
+
+fn archive_key(id: Uuid) -> String {
+    format!("archive:{}", id)
+}
+
+fn plan_key(date: &str) -> String {
+    format!("plan:{}", date)
+}
+
+/// Every task id, scored by `created_at`, so listing never has to walk the
+/// keyspace.
+const INDEX_KEY: &str = "tasks:index";
+/// Set once tasks saved before the index existed have been added to it.
+const INDEXED_KEY: &str = "tasks:indexed";
+/// Ids of completed tasks, scored by `completed_at`.
+const COMPLETED_INDEX_KEY: &str = "tasks:completed";
+/// Like [`INDEXED_KEY`], for [`COMPLETED_INDEX_KEY`].
+const COMPLETED_INDEXED_KEY: &str = "tasks:completed:indexed";
+/// Ids of snoozed tasks, scored by `snoozed_until`, so a page of the tasks
+/// that are awake can be cut from [`INDEX_KEY`] without loading the rest.
+const SNOOZED_INDEX_KEY: &str = "tasks:snoozed";
+/// Like [`INDEXED_KEY`], for [`SNOOZED_INDEX_KEY`].
+const SNOOZED_INDEXED_KEY: &str = "tasks:snoozed:indexed";
+/// Tasks fetched per `MGET`.
+const LIST_CHUNK: usize = 500;
+
+/// Global change number, bumped by every save and delete.
+const VERSION_KEY: &str = "changes:version";
+/// Task ids scored by the change number of their latest save, or delete.
+const SAVED_KEY: &str = "changes:saved";
+const DELETED_KEY: &str = "changes:deleted";
+
+/// Writes the task and records the change in one step, so a sync never sees
+/// a change number without its task.
+const SAVE_SCRIPT: &str = r"
+redis.call('SET', KEYS[1], ARGV[1])
+local version = redis.call('INCR', KEYS[2])
+redis.call('ZADD', KEYS[3], version, ARGV[2])
+redis.call('ZREM', KEYS[4], ARGV[2])
+redis.call('ZADD', KEYS[5], ARGV[3], ARGV[2])
+if ARGV[4] == '' then
+    redis.call('ZREM', KEYS[6], ARGV[2])
+else
+    redis.call('ZADD', KEYS[6], ARGV[4], ARGV[2])
+end
+if ARGV[5] == '' then
+    redis.call('ZREM', KEYS[7], ARGV[2])
+else
+    redis.call('ZADD', KEYS[7], ARGV[5], ARGV[2])
+end
+";
+
+/// Trashed task ids, scored by `deleted_at`.
+const TRASH_INDEX_KEY: &str = "trash:index";
+/// Trashed tasks are purged by the sweep in `trash.rs`, which also removes
+/// their attachments; the key expiry only catches what a stopped backend
+/// never got round to.
+const TRASH_EXPIRY_SECS: u64 = TRASH_TTL_SECS + 24 * 60 * 60;
+
+/// Moves the task to `trash:<id>`, recording the delete, if it's still there.
+const TRASH_SCRIPT: &str = r"
+if redis.call('DEL', KEYS[1]) == 0 then
+    return 0
+end
+local version = redis.call('INCR', KEYS[2])
+redis.call('ZADD', KEYS[4], version, ARGV[1])
+redis.call('ZREM', KEYS[3], ARGV[1])
+redis.call('ZREM', KEYS[5], ARGV[1])
+redis.call('SET', KEYS[6], ARGV[2], 'EX', ARGV[4])
+redis.call('ZADD', KEYS[7], ARGV[3], ARGV[1])
+redis.call('ZREM', KEYS[8], ARGV[1])
+redis.call('ZREM', KEYS[9], ARGV[1])
+return 1
+";
+
+/// Archived task ids, scored by `archived_at`. Archived tasks are kept until
+/// they're restored.
+const ARCHIVE_INDEX_KEY: &str = "archive:index";
+
+/// Moves the task to `archive:<id>`, recording the delete, if it's still as
+/// it was read; a task reopened or edited meanwhile stays where it is.
+const ARCHIVE_SCRIPT: &str = r"
+if redis.call('GET', KEYS[1]) ~= ARGV[2] then
+    return 0
+end
+redis.call('DEL', KEYS[1])
+local version = redis.call('INCR', KEYS[2])
+redis.call('ZADD', KEYS[4], version, ARGV[1])
+redis.call('ZREM', KEYS[3], ARGV[1])
+redis.call('ZREM', KEYS[5], ARGV[1])
+redis.call('ZREM', KEYS[8], ARGV[1])
+redis.call('ZREM', KEYS[9], ARGV[1])
+redis.call('SET', KEYS[6], ARGV[3])
+redis.call('ZADD', KEYS[7], ARGV[4], ARGV[1])
+return 1
+";
+
+/// Tasks from before `created_at` was recorded sort first.
+fn index_score(task: &Task) -> u64 {
+    task.created_at.unwrap_or(0)
+}
+
+/// `completed_at` for [`SAVE_SCRIPT`], empty for open tasks.
+fn completed_score(task: &Task) -> String {
+    task.completed_at.map(|at| at.to_string()).unwrap_or_default()
+}
+
+/// `snoozed_until` for [`SAVE_SCRIPT`], empty for tasks never snoozed.
+fn snoozed_score(task: &Task) -> String {
+    task.snoozed_until.map(|until| until.to_string()).unwrap_or_default()
+}
+
+/// Adds [`SAVE_SCRIPT`] for `task` to a transaction.
+fn queue_save(pipe: &mut redis::Pipeline, task: &Task, task_json: &str) {
+    pipe.cmd("EVAL")
+        .arg(SAVE_SCRIPT)
+        .arg(7)
+        .arg(task_key(task.id))
+        .arg(VERSION_KEY)
+        .arg(SAVED_KEY)
+        .arg(DELETED_KEY)
+        .arg(INDEX_KEY)
+        .arg(COMPLETED_INDEX_KEY)
+        .arg(SNOOZED_INDEX_KEY)
+        .arg(task_json)
+        .arg(task.id.to_string())
+        .arg(index_score(task))
+        .arg(completed_score(task))
+        .arg(snoozed_score(task))
+        .ignore();
+}
+
+/// [`TRASH_SCRIPT`] for `task`.
+fn trash_command(task: &Task) -> Result<redis::Cmd, StoreError> {
+    let task_json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+    let mut cmd = redis::cmd("EVAL");
+    cmd.arg(TRASH_SCRIPT)
+        .arg(9)
+        .arg(task_key(task.id))
+        .arg(VERSION_KEY)
+        .arg(SAVED_KEY)
+        .arg(DELETED_KEY)
+        .arg(INDEX_KEY)
+        .arg(trash_key(task.id))
+        .arg(TRASH_INDEX_KEY)
+        .arg(COMPLETED_INDEX_KEY)
+        .arg(SNOOZED_INDEX_KEY)
+        .arg(task.id.to_string())
+        .arg(task_json)
+        .arg(task.deleted_at.unwrap_or(0))
+        .arg(TRASH_EXPIRY_SECS);
+    Ok(cmd)
+}
+
+fn decode_all(values: &[Option<String>]) -> Vec<Task> {
+    values.iter().flatten().filter_map(|json| serde_json::from_str(json).ok()).collect()
+}
+
+fn parse_ids(ids: Vec<String>) -> Vec<Uuid> {
+    ids.iter().filter_map(|id| id.parse().ok()).collect()
+}
+
+#[async_trait]
+impl TaskStore for RedisStore {
+    fn name(&self) -> &'static str {
+        "Redis"
+    }
+
+    async fn ping(&self) -> Result<(), StoreError> {
+        let mut conn = self.connection().await?;
+        redis::cmd("PING")
+            .query_async::<_, ()>(&mut conn)
+            .instrument(redis_span("PING"))
+            .await
+            .map_err(unavailable)
+    }
+
+    async fn list(&self) -> Result<Vec<Task>, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let ids: Vec<String> = conn.zrange(INDEX_KEY, 0, -1).instrument(redis_span("ZRANGE")).await.map_err(unavailable)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for chunk in ids.chunks(LIST_CHUNK) {
+            let keys: Vec<String> = chunk.iter().map(|id| format!("task:{}", id)).collect();
+            pipe.mget(keys);
+        }
+        let chunks: Vec<Vec<Option<String>>> =
+            pipe.query_async(&mut conn).instrument(redis_span("PIPELINE")).await.map_err(unavailable)?;
+
+        let mut tasks: Vec<Task> = chunks
+            .iter()
+            .flatten()
+            .flatten()
+            .filter_map(|json| serde_json::from_str(json).ok())
+            .collect();
+
+        // Sort by ID to ensure consistent ordering across requests
+        tasks.sort_by_key(|task| task.id);
+
+        Ok(tasks)
+    }
+
+    async fn list_page(&self, offset: usize, limit: usize, awake_at: Option<u64>) -> Result<(Vec<Task>, u64), StoreError> {
+        let mut conn = self.connection().await?;
+
+        let snoozed: Vec<String> = match awake_at {
+            Some(at) => conn
+                .zrangebyscore(SNOOZED_INDEX_KEY, format!("({}", at), "+inf")
+                .instrument(redis_span("ZRANGEBYSCORE"))
+                .await
+                .map_err(unavailable)?,
+            None => Vec::new(),
+        };
+        let mut ranks: Vec<usize> = Vec::new();
+        if !snoozed.is_empty() {
+            let mut pipe = redis::pipe();
+            for id in &snoozed {
+                pipe.zrank(INDEX_KEY, id);
+            }
+            let found: Vec<Option<usize>> = pipe.query_async(&mut conn).instrument(redis_span("PIPELINE")).await.map_err(unavailable)?;
+            ranks = found.into_iter().flatten().collect();
+            ranks.sort_unstable();
+        }
+        let total: u64 = conn.zcard(INDEX_KEY).instrument(redis_span("ZCARD")).await.map_err(unavailable)?;
+
+        // Snoozed tasks are few, so step over their places in the index
+        // rather than loading everything to drop them
+        let mut start = offset;
+        for rank in &ranks {
+            if *rank <= start {
+                start += 1;
+            }
+        }
+        let stop = start + limit + ranks.len();
+        let ids: Vec<String> = conn
+            .zrange(INDEX_KEY, start as isize, stop as isize - 1)
+            .instrument(redis_span("ZRANGE"))
+            .await
+            .map_err(unavailable)?;
+        let keys: Vec<String> = ids.iter().filter(|id| !snoozed.contains(id)).take(limit).map(|id| format!("task:{}", id)).collect();
+        let tasks = if keys.is_empty() {
+            Vec::new()
+        } else {
+            let values: Vec<Option<String>> = conn.mget(keys).instrument(redis_span("MGET")).await.map_err(unavailable)?;
+            decode_all(&values)
+        };
+
+        Ok((tasks, total.saturating_sub(ranks.len() as u64)))
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let task_json: Option<String> = conn.get(task_key(id)).instrument(redis_span("GET")).await.map_err(unavailable)?;
+
+        task_json
+            .map(|json| serde_json::from_str(&json).map_err(|e| StoreError::Corrupt(e.to_string())))
+            .transpose()
+    }
+
+    async fn save(&self, task: &mut Task) -> Result<(), StoreError> {
+        task.version += 1;
+        task.updated_at = Some(snooze::now_secs());
+        let task_json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+        let mut conn = self.connection().await?;
+
+        redis::Script::new(SAVE_SCRIPT)
+            .key(task_key(task.id))
+            .key(VERSION_KEY)
+            .key(SAVED_KEY)
+            .key(DELETED_KEY)
+            .key(INDEX_KEY)
+            .key(COMPLETED_INDEX_KEY)
+            .key(SNOOZED_INDEX_KEY)
+            .arg(&task_json)
+            .arg(task.id.to_string())
+            .arg(index_score(task))
+            .arg(completed_score(task))
+            .arg(snoozed_score(task))
+            .invoke_async::<_, ()>(&mut conn)
+            .instrument(redis_span("EVALSHA"))
+            .await
+            .map_err(unavailable)
+    }
+
+    async fn save_if_version(&self, task: &mut Task, expected: u64) -> Result<bool, StoreError> {
+        task.version = expected + 1;
+        task.updated_at = Some(snooze::now_secs());
+        let task_json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+        let key = task_key(task.id);
+        let mut conn = self.connection().await?;
+
+        // EXEC does nothing if the task is written between WATCH and it
+        redis::cmd("WATCH").arg(&key).query_async::<_, ()>(&mut conn).instrument(redis_span("WATCH")).await.map_err(unavailable)?;
+        let stored: Option<String> = conn.get(&key).instrument(redis_span("GET")).await.map_err(unavailable)?;
+        let current = stored.as_deref().map(serde_json::from_str::<Task>).transpose().map_err(|e| StoreError::Corrupt(e.to_string()));
+        if !matches!(current, Ok(Some(ref task)) if task.version == expected) {
+            return current.map(|_| false);
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        queue_save(&mut pipe, task, &task_json);
+        let saved: Option<()> = pipe.query_async(&mut conn).instrument(redis_span("MULTI")).await.map_err(unavailable)?;
+        Ok(saved.is_some())
+    }
+
+    async fn apply_batch(&self, tasks: &mut [Task], trashed: &[Task], expected: &Versions) -> Result<bool, StoreError> {
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for task in tasks.iter_mut() {
+            task.version = expected.get(&task.id).map_or(task.version, |version| *version) + 1;
+            task.updated_at = Some(snooze::now_secs());
+            let task_json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+            queue_save(&mut pipe, task, &task_json);
+        }
+        for task in trashed {
+            pipe.add_command(trash_command(task)?).ignore();
+        }
+
+        let mut conn = self.connection().await?;
+        if !expected.is_empty() {
+            // EXEC does nothing if any of them is written between WATCH and it
+            let (ids, keys): (Vec<Uuid>, Vec<String>) = expected.keys().map(|id| (*id, task_key(*id))).unzip();
+            redis::cmd("WATCH").arg(&keys).query_async::<_, ()>(&mut conn).instrument(redis_span("WATCH")).await.map_err(unavailable)?;
+            let stored: Vec<Option<String>> = conn.mget(&keys).instrument(redis_span("MGET")).await.map_err(unavailable)?;
+            let current = stored
+                .iter()
+                .map(|json| json.as_deref().map(serde_json::from_str::<Task>).transpose())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| StoreError::Corrupt(e.to_string()));
+            let unchanged = current.as_ref().is_ok_and(|current| {
+                ids.iter().zip(current).all(|(id, task)| task.as_ref().map(|task| task.version) == expected.get(id).copied())
+            });
+            if !unchanged {
+                return current.map(|_| false);
+            }
+        }
+
+        let applied: Option<()> = pipe.query_async(&mut conn).instrument(redis_span("MULTI")).await.map_err(unavailable)?;
+        Ok(applied.is_some())
+    }
+
+    async fn trash(&self, task: &Task) -> Result<bool, StoreError> {
+        let command = trash_command(task)?;
+        let mut conn = self.connection().await?;
+
+        let moved: usize = command.query_async(&mut conn).instrument(redis_span("EVAL")).await.map_err(unavailable)?;
+        Ok(moved > 0)
+    }
+
+    async fn list_trash(&self) -> Result<Vec<Task>, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let ids: Vec<String> = conn.zrevrange(TRASH_INDEX_KEY, 0, -1).instrument(redis_span("ZREVRANGE")).await.map_err(unavailable)?;
+        let mut tasks = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(LIST_CHUNK) {
+            let keys: Vec<String> = chunk.iter().map(|id| format!("trash:{}", id)).collect();
+            let values: Vec<Option<String>> = conn.mget(keys).instrument(redis_span("MGET")).await.map_err(unavailable)?;
+            tasks.extend(decode_all(&values));
+        }
+        Ok(tasks)
+    }
+
+    async fn list_since(&self, timestamp: Timestamp, since: u64) -> Result<Vec<Task>, StoreError> {
+        let (index, prefix) = match timestamp {
+            Timestamp::Created => (INDEX_KEY, "task"),
+            Timestamp::Completed => (COMPLETED_INDEX_KEY, "task"),
+            Timestamp::Deleted => (TRASH_INDEX_KEY, "trash"),
+        };
+        let mut conn = self.connection().await?;
+
+        let ids: Vec<String> = conn
+            .zrevrangebyscore(index, "+inf", since)
+            .instrument(redis_span("ZREVRANGEBYSCORE"))
+            .await
+            .map_err(unavailable)?;
+        let mut tasks = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(LIST_CHUNK) {
+            let keys: Vec<String> = chunk.iter().map(|id| format!("{}:{}", prefix, id)).collect();
+            let values: Vec<Option<String>> = conn.mget(keys).instrument(redis_span("MGET")).await.map_err(unavailable)?;
+            tasks.extend(decode_all(&values));
+        }
+        Ok(tasks)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        let key = trash_key(id);
+        let mut conn = self.connection().await?;
+
+        // A concurrent restore or purge makes EXEC do nothing
+        redis::cmd("WATCH").arg(&key).query_async::<_, ()>(&mut conn).instrument(redis_span("WATCH")).await.map_err(unavailable)?;
+        let stored: Option<String> = conn.get(&key).instrument(redis_span("GET")).await.map_err(unavailable)?;
+        let task = stored.as_deref().map(serde_json::from_str::<Task>).transpose().map_err(|e| StoreError::Corrupt(e.to_string()));
+        let Ok(Some(mut task)) = task else {
+            return task;
+        };
+
+        task.deleted_at = None;
+        task.version += 1;
+        task.updated_at = Some(snooze::now_secs());
+        let task_json = serde_json::to_string(&task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        pipe.del(&key).ignore();
+        pipe.zrem(TRASH_INDEX_KEY, id.to_string()).ignore();
+        queue_save(&mut pipe, &task, &task_json);
+        let restored: Option<()> = pipe.query_async(&mut conn).instrument(redis_span("MULTI")).await.map_err(unavailable)?;
+        Ok(restored.map(|()| task))
+    }
+
+    async fn purge(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        let key = trash_key(id);
+        let mut conn = self.connection().await?;
+
+        let (stored, ()): (Option<String>, ()) = redis::pipe()
+            .atomic()
+            .get(&key)
+            .del(&key)
+            .ignore()
+            .zrem(TRASH_INDEX_KEY, id.to_string())
+            .ignore()
+            .query_async(&mut conn)
+            .instrument(redis_span("MULTI"))
+            .await
+            .map_err(unavailable)?;
+        stored
+            .map(|json| serde_json::from_str(&json).map_err(|e| StoreError::Corrupt(e.to_string())))
+            .transpose()
+    }
+
+    async fn purge_trashed_before(&self, before: u64) -> Result<Vec<Task>, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let ids: Vec<String> = conn
+            .zrangebyscore(TRASH_INDEX_KEY, "-inf", format!("({}", before))
+            .instrument(redis_span("ZRANGEBYSCORE"))
+            .await
+            .map_err(unavailable)?;
+        let mut purged = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(LIST_CHUNK) {
+            let keys: Vec<String> = chunk.iter().map(|id| format!("trash:{}", id)).collect();
+            let (values, ()): (Vec<Option<String>>, ()) = redis::pipe()
+                .atomic()
+                .mget(&keys)
+                .del(&keys)
+                .ignore()
+                .zrem(TRASH_INDEX_KEY, chunk)
+                .ignore()
+                .query_async(&mut conn)
+                .instrument(redis_span("MULTI"))
+                .await
+                .map_err(unavailable)?;
+            purged.extend(decode_all(&values));
+        }
+        Ok(purged)
+    }
+
+    async fn archive_completed_before(&self, before: u64, now: u64) -> Result<Vec<Task>, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let ids: Vec<String> = conn
+            .zrangebyscore(COMPLETED_INDEX_KEY, "-inf", format!("({}", before))
+            .instrument(redis_span("ZRANGEBYSCORE"))
+            .await
+            .map_err(unavailable)?;
+        let mut archived = Vec::new();
+        for chunk in ids.chunks(LIST_CHUNK) {
+            let keys: Vec<String> = chunk.iter().map(|id| format!("task:{}", id)).collect();
+            let values: Vec<Option<String>> = conn.mget(keys).instrument(redis_span("MGET")).await.map_err(unavailable)?;
+            for stored in values.into_iter().flatten() {
+                let Ok(mut task) = serde_json::from_str::<Task>(&stored) else {
+                    continue;
+                };
+                if task.recurrence.is_some() {
+                    continue;
+                }
+                task.archived_at = Some(now);
+                let task_json = serde_json::to_string(&task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+                let moved: usize = redis::cmd("EVAL")
+                    .arg(ARCHIVE_SCRIPT)
+                    .arg(9)
+                    .arg(task_key(task.id))
+                    .arg(VERSION_KEY)
+                    .arg(SAVED_KEY)
+                    .arg(DELETED_KEY)
+                    .arg(INDEX_KEY)
+                    .arg(archive_key(task.id))
+                    .arg(ARCHIVE_INDEX_KEY)
+                    .arg(COMPLETED_INDEX_KEY)
+                    .arg(SNOOZED_INDEX_KEY)
+                    .arg(task.id.to_string())
+                    .arg(&stored)
+                    .arg(task_json)
+                    .arg(now)
+                    .query_async(&mut conn)
+                    .instrument(redis_span("EVAL"))
+                    .await
+                    .map_err(unavailable)?;
+                if moved > 0 {
+                    archived.push(task);
+                }
+            }
+        }
+        Ok(archived)
+    }
+
+    async fn list_archive(&self) -> Result<Vec<Task>, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let ids: Vec<String> = conn.zrevrange(ARCHIVE_INDEX_KEY, 0, -1).instrument(redis_span("ZREVRANGE")).await.map_err(unavailable)?;
+        let mut tasks = Vec::with_capacity(ids.len());
+        for chunk in ids.chunks(LIST_CHUNK) {
+            let keys: Vec<String> = chunk.iter().map(|id| format!("archive:{}", id)).collect();
+            let values: Vec<Option<String>> = conn.mget(keys).instrument(redis_span("MGET")).await.map_err(unavailable)?;
+            tasks.extend(decode_all(&values));
+        }
+        Ok(tasks)
+    }
+
+    async fn unarchive(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        let key = archive_key(id);
+        let mut conn = self.connection().await?;
+
+        // A concurrent restore makes EXEC do nothing
+        redis::cmd("WATCH").arg(&key).query_async::<_, ()>(&mut conn).instrument(redis_span("WATCH")).await.map_err(unavailable)?;
+        let stored: Option<String> = conn.get(&key).instrument(redis_span("GET")).await.map_err(unavailable)?;
+        let task = stored.as_deref().map(serde_json::from_str::<Task>).transpose().map_err(|e| StoreError::Corrupt(e.to_string()));
+        let Ok(Some(mut task)) = task else {
+            return task;
+        };
+
+        task.archived_at = None;
+        task.version += 1;
+        task.updated_at = Some(snooze::now_secs());
+        let task_json = serde_json::to_string(&task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        pipe.del(&key).ignore();
+        pipe.zrem(ARCHIVE_INDEX_KEY, id.to_string()).ignore();
+        queue_save(&mut pipe, &task, &task_json);
+        let restored: Option<()> = pipe.query_async(&mut conn).instrument(redis_span("MULTI")).await.map_err(unavailable)?;
+        Ok(restored.map(|()| task))
+    }
+
+    async fn changes_since(&self, since: u64) -> Result<TaskChanges, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let after = format!("({}", since);
+        let (version, changed, deleted): (Option<u64>, Vec<String>, Vec<String>) = redis::pipe()
+            .atomic()
+            .get(VERSION_KEY)
+            .zrangebyscore(SAVED_KEY, &after, "+inf")
+            .zrangebyscore(DELETED_KEY, &after, "+inf")
+            .query_async(&mut conn)
+            .instrument(redis_span("MULTI"))
+            .await
+            .map_err(unavailable)?;
+
+        Ok(TaskChanges {
+            version: version.unwrap_or(0),
+            changed: parse_ids(changed),
+            deleted: parse_ids(deleted),
+        })
+    }
+
+    async fn get_plan(&self, date: &str) -> Result<Option<DayPlan>, StoreError> {
+        let mut conn = self.connection().await?;
+
+        let plan_json: Option<String> = conn.get(plan_key(date)).instrument(redis_span("GET")).await.map_err(unavailable)?;
+
+        plan_json
+            .map(|json| serde_json::from_str(&json).map_err(|e| StoreError::Corrupt(e.to_string())))
+            .transpose()
+    }
+
+    async fn save_plan(&self, plan: &DayPlan) -> Result<(), StoreError> {
+        let plan_json = serde_json::to_string(plan).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+        let mut conn = self.connection().await?;
+
+        conn.set::<_, _, ()>(plan_key(&plan.date), &plan_json)
+            .instrument(redis_span("SET"))
+            .await
+            .map_err(unavailable)
+    }
+}
@@ -0,0 +1,519 @@
+use axum::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use shared::{DayPlan, Task, TaskChanges};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+use super::{StoreError, TaskStore, Timestamp, Versions};
+use crate::{snooze, telemetry::sqlite_span};
+
+/// Tasks kept as JSON documents in a single SQLite file.
+///
+/// rusqlite is blocking, so every call runs on tokio's blocking pool; a single
+/// connection behind a mutex is plenty for a self-hosted instance.
+pub struct SqliteStore {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<Self, StoreError> {
+        let conn = Connection::open(path).map_err(unavailable)?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS tasks (
+                 id   TEXT PRIMARY KEY,
+                 data TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS tasks_created_at ON tasks (json_extract(data, '$.created_at'));
+             CREATE INDEX IF NOT EXISTS tasks_completed_at ON tasks (json_extract(data, '$.completed_at'));
+             CREATE TABLE IF NOT EXISTS changes (
+                 task_id TEXT PRIMARY KEY,
+                 version INTEGER NOT NULL,
+                 deleted INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS changes_version ON changes (version);
+             CREATE TABLE IF NOT EXISTS trash (
+                 id         TEXT PRIMARY KEY,
+                 data       TEXT NOT NULL,
+                 deleted_at INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS trash_deleted_at ON trash (deleted_at);
+             CREATE TABLE IF NOT EXISTS archive (
+                 id          TEXT PRIMARY KEY,
+                 data        TEXT NOT NULL,
+                 archived_at INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS archive_archived_at ON archive (archived_at);
+             CREATE TABLE IF NOT EXISTS plans (
+                 date TEXT PRIMARY KEY,
+                 data TEXT NOT NULL
+             );",
+        )
+        .map_err(unavailable)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    async fn with_conn<T, F>(&self, operation: &'static str, f: F) -> Result<T, StoreError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, StoreError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| StoreError::Unavailable("connection poisoned".to_string()))?;
+            f(&conn)
+        })
+        .instrument(sqlite_span(operation))
+        .await
+        .map_err(|e| StoreError::Unavailable(e.to_string()))?
+    }
+}
+
+fn unavailable(e: rusqlite::Error) -> StoreError {
+    StoreError::Unavailable(e.to_string())
+}
+
+/// Records that `id` was saved or deleted under the next change number.
+fn record_change(conn: &Connection, id: &str, deleted: bool) -> Result<(), StoreError> {
+    conn.execute(
+        "INSERT INTO changes (task_id, version, deleted)
+         VALUES (?1, (SELECT COALESCE(MAX(version), 0) + 1 FROM changes), ?2)
+         ON CONFLICT(task_id) DO UPDATE SET version = excluded.version, deleted = excluded.deleted",
+        params![id, deleted],
+    )
+    .map_err(unavailable)?;
+    Ok(())
+}
+
+/// The version of task `id` in the task list, `None` when it isn't there.
+fn stored_version(conn: &Connection, id: &str) -> Result<Option<u64>, StoreError> {
+    let stored: Option<String> = conn
+        .query_row("SELECT data FROM tasks WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(unavailable)?;
+    Ok(stored.as_deref().map(decode).transpose()?.map(|task| task.version))
+}
+
+fn upsert(conn: &Connection, id: &str, json: &str) -> Result<(), StoreError> {
+    conn.execute(
+        "INSERT INTO tasks (id, data) VALUES (?1, ?2)
+         ON CONFLICT(id) DO UPDATE SET data = excluded.data",
+        params![id, json],
+    )
+    .map_err(unavailable)?;
+    record_change(conn, id, false)
+}
+
+/// Moves a task row into `trash`, recording the delete. Returns whether the
+/// task was in `tasks`.
+fn move_to_trash(conn: &Connection, id: &str, json: &str, deleted_at: u64) -> Result<bool, StoreError> {
+    if conn.execute("DELETE FROM tasks WHERE id = ?1", params![id]).map_err(unavailable)? == 0 {
+        return Ok(false);
+    }
+    record_change(conn, id, true)?;
+    conn.execute(
+        "INSERT INTO trash (id, data, deleted_at) VALUES (?1, ?2, ?3)
+         ON CONFLICT(id) DO UPDATE SET data = excluded.data, deleted_at = excluded.deleted_at",
+        params![id, json, deleted_at.min(i64::MAX as u64) as i64],
+    )
+    .map_err(unavailable)?;
+    Ok(true)
+}
+
+/// Removes a row from `trash`, returning the task it held.
+fn take_from_trash(conn: &Connection, id: &str) -> Result<Option<Task>, StoreError> {
+    let json: Option<String> = conn
+        .query_row("SELECT data FROM trash WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(unavailable)?;
+    conn.execute("DELETE FROM trash WHERE id = ?1", params![id]).map_err(unavailable)?;
+    json.as_deref().map(decode).transpose()
+}
+
+/// Removes a row from `archive`, returning the task it held.
+fn take_from_archive(conn: &Connection, id: &str) -> Result<Option<Task>, StoreError> {
+    let json: Option<String> = conn
+        .query_row("SELECT data FROM archive WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()
+        .map_err(unavailable)?;
+    conn.execute("DELETE FROM archive WHERE id = ?1", params![id]).map_err(unavailable)?;
+    json.as_deref().map(decode).transpose()
+}
+
+fn trash_row(task: &Task) -> Result<(String, String, u64), StoreError> {
+    let json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+    Ok((task.id.to_string(), json, task.deleted_at.unwrap_or(0)))
+}
+
+fn decode(json: &str) -> Result<Task, StoreError> {
+    serde_json::from_str(json).map_err(|e| StoreError::Corrupt(e.to_string()))
+}
+
+#[async_trait]
+impl TaskStore for SqliteStore {
+    fn name(&self) -> &'static str {
+        "SQLite"
+    }
+
+    async fn ping(&self) -> Result<(), StoreError> {
+        self.with_conn("SELECT", |conn| {
+            conn.query_row("SELECT 1", [], |_| Ok(())).map_err(unavailable)
+        })
+        .await
+    }
+
+    async fn list(&self) -> Result<Vec<Task>, StoreError> {
+        self.with_conn("SELECT", |conn| {
+            let mut stmt = conn.prepare("SELECT data FROM tasks ORDER BY id").map_err(unavailable)?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(unavailable)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(unavailable)?;
+
+            // Skip undecodable rows like the Redis store does
+            Ok(rows.iter().filter_map(|json| decode(json).ok()).collect())
+        })
+        .await
+    }
+
+    async fn list_page(&self, offset: usize, limit: usize, awake_at: Option<u64>) -> Result<(Vec<Task>, u64), StoreError> {
+        // A NULL `?1` keeps snoozed tasks in
+        const AWAKE: &str = "?1 IS NULL OR json_extract(data, '$.snoozed_until') IS NULL OR json_extract(data, '$.snoozed_until') <= ?1";
+        let awake_at = awake_at.map(|at| at.min(i64::MAX as u64) as i64);
+        let (limit, offset) = (limit.min(i64::MAX as usize) as i64, offset.min(i64::MAX as usize) as i64);
+
+        self.with_conn("SELECT", move |conn| {
+            let total: i64 = conn
+                .query_row(&format!("SELECT COUNT(*) FROM tasks WHERE {}", AWAKE), params![awake_at], |row| row.get(0))
+                .map_err(unavailable)?;
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT data FROM tasks WHERE {} ORDER BY json_extract(data, '$.created_at'), id LIMIT ?2 OFFSET ?3",
+                    AWAKE
+                ))
+                .map_err(unavailable)?;
+            let rows = stmt
+                .query_map(params![awake_at, limit, offset], |row| row.get::<_, String>(0))
+                .map_err(unavailable)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(unavailable)?;
+
+            Ok((rows.iter().filter_map(|json| decode(json).ok()).collect(), total as u64))
+        })
+        .await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        self.with_conn("SELECT", move |conn| {
+            let json: Option<String> = conn
+                .query_row("SELECT data FROM tasks WHERE id = ?1", params![id.to_string()], |row| row.get(0))
+                .optional()
+                .map_err(unavailable)?;
+            json.as_deref().map(decode).transpose()
+        })
+        .await
+    }
+
+    async fn save(&self, task: &mut Task) -> Result<(), StoreError> {
+        task.version += 1;
+        task.updated_at = Some(snooze::now_secs());
+        let id = task.id.to_string();
+        let json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+
+        self.with_conn("UPSERT", move |conn| {
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            upsert(&tx, &id, &json)?;
+            tx.commit().map_err(unavailable)
+        })
+        .await
+    }
+
+    async fn save_if_version(&self, task: &mut Task, expected: u64) -> Result<bool, StoreError> {
+        task.version = expected + 1;
+        task.updated_at = Some(snooze::now_secs());
+        let id = task.id.to_string();
+        let json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+
+        self.with_conn("UPSERT", move |conn| {
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            if stored_version(&tx, &id)? != Some(expected) {
+                return Ok(false);
+            }
+            upsert(&tx, &id, &json)?;
+            tx.commit().map_err(unavailable)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn apply_batch(&self, tasks: &mut [Task], trashed: &[Task], expected: &Versions) -> Result<bool, StoreError> {
+        let mut rows = Vec::with_capacity(tasks.len());
+        for task in tasks.iter_mut() {
+            task.version = expected.get(&task.id).map_or(task.version, |version| *version) + 1;
+            task.updated_at = Some(snooze::now_secs());
+            let json = serde_json::to_string(task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+            rows.push((task.id.to_string(), json));
+        }
+        let trashed = trashed.iter().map(trash_row).collect::<Result<Vec<_>, _>>()?;
+        let expected: Vec<(String, u64)> = expected.iter().map(|(id, version)| (id.to_string(), *version)).collect();
+
+        self.with_conn("BATCH", move |conn| {
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            for (id, version) in &expected {
+                if stored_version(&tx, id)? != Some(*version) {
+                    return Ok(false);
+                }
+            }
+            for (id, json) in &rows {
+                upsert(&tx, id, json)?;
+            }
+            for (id, json, deleted_at) in &trashed {
+                move_to_trash(&tx, id, json, *deleted_at)?;
+            }
+            tx.commit().map_err(unavailable)?;
+            Ok(true)
+        })
+        .await
+    }
+
+    async fn trash(&self, task: &Task) -> Result<bool, StoreError> {
+        let (id, json, deleted_at) = trash_row(task)?;
+
+        self.with_conn("DELETE", move |conn| {
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            let moved = move_to_trash(&tx, &id, &json, deleted_at)?;
+            tx.commit().map_err(unavailable)?;
+            Ok(moved)
+        })
+        .await
+    }
+
+    async fn list_trash(&self) -> Result<Vec<Task>, StoreError> {
+        self.with_conn("SELECT", |conn| {
+            let mut stmt = conn.prepare("SELECT data FROM trash ORDER BY deleted_at DESC").map_err(unavailable)?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(unavailable)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(unavailable)?;
+
+            Ok(rows.iter().filter_map(|json| decode(json).ok()).collect())
+        })
+        .await
+    }
+
+    async fn list_since(&self, timestamp: Timestamp, since: u64) -> Result<Vec<Task>, StoreError> {
+        // Written exactly as indexed, so the indexes are used
+        let query = match timestamp {
+            Timestamp::Created => {
+                "SELECT data FROM tasks WHERE json_extract(data, '$.created_at') >= ?1
+                 ORDER BY json_extract(data, '$.created_at') DESC"
+            }
+            Timestamp::Completed => {
+                "SELECT data FROM tasks WHERE json_extract(data, '$.completed_at') >= ?1
+                 ORDER BY json_extract(data, '$.completed_at') DESC"
+            }
+            Timestamp::Deleted => "SELECT data FROM trash WHERE deleted_at >= ?1 ORDER BY deleted_at DESC",
+        };
+        self.with_conn("SELECT", move |conn| {
+            let mut stmt = conn.prepare(query).map_err(unavailable)?;
+            let rows = stmt
+                .query_map(params![since.min(i64::MAX as u64) as i64], |row| row.get::<_, String>(0))
+                .map_err(unavailable)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(unavailable)?;
+
+            Ok(rows.iter().filter_map(|json| decode(json).ok()).collect())
+        })
+        .await
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        self.with_conn("RESTORE", move |conn| {
+            let id = id.to_string();
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            let Some(mut task) = take_from_trash(&tx, &id)? else {
+                return Ok(None);
+            };
+            task.deleted_at = None;
+            task.version += 1;
+            task.updated_at = Some(snooze::now_secs());
+            let json = serde_json::to_string(&task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+            upsert(&tx, &id, &json)?;
+            tx.commit().map_err(unavailable)?;
+            Ok(Some(task))
+        })
+        .await
+    }
+
+    async fn purge(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        self.with_conn("DELETE", move |conn| {
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            let task = take_from_trash(&tx, &id.to_string())?;
+            tx.commit().map_err(unavailable)?;
+            Ok(task)
+        })
+        .await
+    }
+
+    async fn purge_trashed_before(&self, before: u64) -> Result<Vec<Task>, StoreError> {
+        let before = before.min(i64::MAX as u64) as i64;
+        self.with_conn("DELETE", move |conn| {
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            let rows = {
+                let mut stmt = tx.prepare("SELECT data FROM trash WHERE deleted_at < ?1").map_err(unavailable)?;
+                let rows = stmt
+                    .query_map(params![before], |row| row.get::<_, String>(0))
+                    .map_err(unavailable)?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(unavailable)?;
+                rows
+            };
+            tx.execute("DELETE FROM trash WHERE deleted_at < ?1", params![before]).map_err(unavailable)?;
+            tx.commit().map_err(unavailable)?;
+            Ok(rows.iter().filter_map(|json| decode(json).ok()).collect())
+        })
+        .await
+    }
+
+    async fn archive_completed_before(&self, before: u64, now: u64) -> Result<Vec<Task>, StoreError> {
+        let before = before.min(i64::MAX as u64) as i64;
+        self.with_conn("ARCHIVE", move |conn| {
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            let rows = {
+                let mut stmt = tx
+                    .prepare("SELECT data FROM tasks WHERE json_extract(data, '$.completed_at') < ?1")
+                    .map_err(unavailable)?;
+                let rows = stmt
+                    .query_map(params![before], |row| row.get::<_, String>(0))
+                    .map_err(unavailable)?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(unavailable)?;
+                rows
+            };
+            let mut archived = Vec::new();
+            for mut task in rows.iter().filter_map(|json| decode(json).ok()) {
+                if task.recurrence.is_some() {
+                    continue;
+                }
+                task.archived_at = Some(now);
+                let id = task.id.to_string();
+                let json = serde_json::to_string(&task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+                tx.execute("DELETE FROM tasks WHERE id = ?1", params![id]).map_err(unavailable)?;
+                record_change(&tx, &id, true)?;
+                tx.execute(
+                    "INSERT INTO archive (id, data, archived_at) VALUES (?1, ?2, ?3)
+                     ON CONFLICT(id) DO UPDATE SET data = excluded.data, archived_at = excluded.archived_at",
+                    params![id, json, now.min(i64::MAX as u64) as i64],
+                )
+                .map_err(unavailable)?;
+                archived.push(task);
+            }
+            tx.commit().map_err(unavailable)?;
+            Ok(archived)
+        })
+        .await
+    }
+
+    async fn list_archive(&self) -> Result<Vec<Task>, StoreError> {
+        self.with_conn("SELECT", |conn| {
+            let mut stmt = conn.prepare("SELECT data FROM archive ORDER BY archived_at DESC").map_err(unavailable)?;
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .map_err(unavailable)?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(unavailable)?;
+
+            Ok(rows.iter().filter_map(|json| decode(json).ok()).collect())
+        })
+        .await
+    }
+
+    async fn unarchive(&self, id: Uuid) -> Result<Option<Task>, StoreError> {
+        self.with_conn("RESTORE", move |conn| {
+            let id = id.to_string();
+            let tx = conn.unchecked_transaction().map_err(unavailable)?;
+            let Some(mut task) = take_from_archive(&tx, &id)? else {
+                return Ok(None);
+            };
+            task.archived_at = None;
+            task.version += 1;
+            task.updated_at = Some(snooze::now_secs());
+            let json = serde_json::to_string(&task).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+            upsert(&tx, &id, &json)?;
+            tx.commit().map_err(unavailable)?;
+            Ok(Some(task))
+        })
+        .await
+    }
+
+    async fn changes_since(&self, since: u64) -> Result<TaskChanges, StoreError> {
+        // SQLite integers are signed
+        let since = since.min(i64::MAX as u64) as i64;
+        self.with_conn("SELECT", move |conn| {
+            let version: u64 = conn
+                .query_row("SELECT COALESCE(MAX(version), 0) FROM changes", [], |row| row.get(0))
+                .map_err(unavailable)?;
+
+            let mut changes = TaskChanges {
+                version,
+                changed: Vec::new(),
+                deleted: Vec::new(),
+            };
+            let mut stmt = conn
+                .prepare("SELECT task_id, deleted FROM changes WHERE version > ?1 ORDER BY version")
+                .map_err(unavailable)?;
+            let rows = stmt
+                .query_map(params![since], |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)))
+                .map_err(unavailable)?;
+            for row in rows {
+                let (id, deleted) = row.map_err(unavailable)?;
+                let Ok(id) = id.parse() else { continue };
+                if deleted {
+                    changes.deleted.push(id);
+                } else {
+                    changes.changed.push(id);
+                }
+            }
+            Ok(changes)
+        })
+        .await
+    }
+
+    async fn get_plan(&self, date: &str) -> Result<Option<DayPlan>, StoreError> {
+        let date = date.to_string();
+        self.with_conn("SELECT", move |conn| {
+            let json: Option<String> = conn
+                .query_row("SELECT data FROM plans WHERE date = ?1", params![date], |row| row.get(0))
+                .optional()
+                .map_err(unavailable)?;
+            json.map(|json| serde_json::from_str(&json).map_err(|e| StoreError::Corrupt(e.to_string())))
+                .transpose()
+        })
+        .await
+    }
+
+    async fn save_plan(&self, plan: &DayPlan) -> Result<(), StoreError> {
+        let date = plan.date.clone();
+        let json = serde_json::to_string(plan).map_err(|e| StoreError::Corrupt(e.to_string()))?;
+
+        self.with_conn("UPSERT", move |conn| {
+            conn.execute(
+                "INSERT INTO plans (date, data) VALUES (?1, ?2)
+                 ON CONFLICT(date) DO UPDATE SET data = excluded.data",
+                params![date, json],
+            )
+            .map_err(unavailable)?;
+            Ok(())
+        })
+        .await
+    }
+}
@@ -0,0 +1,77 @@
+// The service worker, served as `sw.js` under BASE_PATH by `pwa.rs`, which
+// fills in the build so each deploy gets a fresh cache.
+const CACHE = "tasks-{{BUILD}}";
+
+// The app shell and bundle, relative to the worker like everything else
+const SHELL = ["./", "frontend.js", "frontend_bg.wasm", "styles.css", "manifest.webmanifest", "icon.svg"];
+
+// Live updates are streams; they can't be cached and reconnect on their own
+const UNCACHED = ["api/events", "api/ws"];
+
+const scope = new URL("./", self.location);
+
+self.addEventListener("install", (event) => {
+    event.waitUntil(caches.open(CACHE).then((cache) => cache.addAll(SHELL)).then(() => self.skipWaiting()));
+});
+
+self.addEventListener("activate", (event) => {
+    event.waitUntil(
+        caches.keys()
+            .then((keys) => Promise.all(keys.filter((key) => key.startsWith("tasks-") && key !== CACHE).map((key) => caches.delete(key))))
+            .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener("fetch", (event) => {
+    const request = event.request;
+    const url = new URL(request.url);
+    if (request.method !== "GET" || url.origin !== scope.origin || !url.pathname.startsWith(scope.pathname)) {
+        return;
+    }
+    const path = url.pathname.slice(scope.pathname.length);
+
+    if (request.mode === "navigate") {
+        // Any route is the same app, so offline every page is the cached shell
+        event.respondWith(networkFirst(request, new URL("./", scope).href));
+    } else if (path.startsWith("api/")) {
+        if (!UNCACHED.includes(path)) {
+            event.respondWith(networkFirst(request));
+        }
+    } else if (SHELL.includes(path)) {
+        event.respondWith(staleWhileRevalidate(request));
+    }
+});
+
+// The network's answer, kept for later; the last one kept when offline.
+// Only pages and JSON are kept, not attachments or images.
+async function networkFirst(request, fallback) {
+    const cache = await caches.open(CACHE);
+    try {
+        const response = await fetch(request);
+        const type = response.headers.get("content-type") || "";
+        if (response.ok && (request.mode === "navigate" || type.startsWith("application/json"))) {
+            await cache.put(request, response.clone());
+        }
+        return response;
+    } catch (e) {
+        const cached = (await cache.match(request)) || (fallback && (await cache.match(fallback)));
+        return cached || Response.error();
+    }
+}
+
+// The cached file right away, refreshed in the background for next time.
+async function staleWhileRevalidate(request) {
+    const cache = await caches.open(CACHE);
+    const cached = await cache.match(request);
+    const fresh = fetch(request).then((response) => {
+        if (response.ok) {
+            cache.put(request, response.clone());
+        }
+        return response;
+    });
+    if (cached) {
+        fresh.catch(() => {});
+        return cached;
+    }
+    return fresh;
+}
@@ -0,0 +1,276 @@
+//! Logging and request tracing with OpenTelemetry.
+//!
+//! Spans are always recorded so every response can carry an `X-Trace-Id`
+//! header; they are only exported when `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+//! (the exporter reads the rest of the standard `OTEL_*` variables itself).
+//!
+//! Logs go to stdout, filtered by `RUST_LOG` (default `info`), as text or,
+//! with `LOG_FORMAT=json`, one JSON object per line carrying the trace id.
+//! Each request is logged at `debug` when it finishes and every Redis or
+//! SQLite call at `debug` under the `storage` target, or at `warn` when it
+//! takes longer than [`SLOW_STORAGE_CALL`].
+
+use axum::{
+    extract::Request,
+    http::{self, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use opentelemetry::{
+    trace::{TraceContextExt, TraceId, TracerProvider as _},
+    KeyValue,
+};
+use opentelemetry_sdk::{runtime, trace::TracerProvider, Resource};
+use serde_json::{Map, Value};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Span, Subscriber,
+};
+use tracing_opentelemetry::{OpenTelemetrySpanExt, OtelData};
+use tracing_subscriber::{
+    fmt::{
+        format::Writer,
+        time::{FormatTime, SystemTime},
+        FmtContext, FormatEvent, FormatFields,
+    },
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+pub const TRACE_ID_HEADER: HeaderName = HeaderName::from_static("x-trace-id");
+
+/// Redis or SQLite calls slower than this are logged as warnings.
+pub const SLOW_STORAGE_CALL: Duration = Duration::from_millis(250);
+
+/// Installs the global subscriber, recording events that pass `RUST_LOG`
+/// and keeping `LOG_BUFFER_ENTRIES` of them for the admin log view. Keep the
+/// returned provider alive and call `shutdown` on exit so buffered spans are
+/// flushed.
+pub fn init(config: &crate::config::Config) -> TracerProvider {
+    let mut builder = TracerProvider::builder()
+        .with_resource(Resource::new([KeyValue::new("service.name", "backend")]));
+
+    let mut exporter_error = None;
+    if std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some() {
+        match opentelemetry_otlp::SpanExporter::builder().with_tonic().build() {
+            Ok(exporter) => builder = builder.with_batch_exporter(exporter, runtime::Tokio),
+            Err(e) => exporter_error = Some(e),
+        }
+    }
+
+    let provider = builder.build();
+    let tracer = provider.tracer("backend");
+
+    crate::logs::set_capacity(config.runtime.log_buffer);
+    let format = crate::config::parse_var("LOG_FORMAT", LogFormat::parse);
+    let json = matches!(format, Ok(Some(LogFormat::Json)));
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::new(&config.log_filter))
+        .with((!json).then(tracing_subscriber::fmt::layer))
+        .with(json.then(|| tracing_subscriber::fmt::layer().event_format(JsonFormat)))
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .with(StorageTiming)
+        .with(crate::logs::CaptureLayer)
+        .init();
+
+    if let Err(e) = format {
+        tracing::warn!("{}, logging as text", e);
+    }
+    if let Some(e) = exporter_error {
+        tracing::warn!("failed to create OTLP exporter, spans will not be exported: {}", e);
+    }
+
+    provider
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected text or json".to_string()),
+        }
+    }
+}
+
+/// Span for a single Redis command, nested under the current handler span.
+pub fn redis_span(command: &'static str) -> Span {
+    tracing::info_span!("redis", db.system = "redis", db.operation = command)
+}
+
+/// Span for a statement run against the embedded SQLite store.
+pub fn sqlite_span(operation: &'static str) -> Span {
+    tracing::info_span!("sqlite", db.system = "sqlite", db.operation = operation)
+}
+
+/// Root span for a request, made by the `TraceLayer` in `main`.
+pub fn request_span<B>(request: &http::Request<B>) -> Span {
+    tracing::info_span!(
+        "request",
+        http.method = %request.method(),
+        http.target = %request.uri().path(),
+        http.status_code = tracing::field::Empty,
+    )
+}
+
+/// Records the status on the request span and logs how long it took.
+pub fn record_response<B>(response: &http::Response<B>, latency: Duration, span: &Span) {
+    let status = response.status().as_u16();
+    span.record("http.status_code", status);
+    tracing::debug!(status, latency_ms = latency.as_secs_f64() * 1000.0, "finished request");
+}
+
+/// Echoes the request's trace id back to the client. Runs inside the
+/// request span.
+pub async fn echo_trace_id(request: Request, next: Next) -> Response {
+    let mut response = next.run(request).await;
+
+    let trace_id = Span::current().context().span().span_context().trace_id();
+    if let Ok(value) = HeaderValue::from_str(&trace_id.to_string()) {
+        response.headers_mut().insert(TRACE_ID_HEADER, value);
+    }
+
+    response
+}
+
+/// When a Redis or SQLite span was opened, and what for.
+struct StorageCall {
+    started: Instant,
+    operation: String,
+}
+
+/// Logs how long each storage span was open.
+struct StorageTiming;
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for StorageTiming {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if !matches!(attrs.metadata().name(), "redis" | "sqlite") {
+            return;
+        }
+        let mut operation = OperationVisitor::default();
+        attrs.record(&mut operation);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(StorageCall {
+                started: Instant::now(),
+                operation: operation.0,
+            });
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(call) = span.extensions_mut().remove::<StorageCall>() else {
+            return;
+        };
+        let elapsed = call.started.elapsed();
+        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+        if elapsed >= SLOW_STORAGE_CALL {
+            tracing::warn!(target: "storage", system = span.name(), operation = %call.operation, elapsed_ms, "slow storage call");
+        } else {
+            tracing::debug!(target: "storage", system = span.name(), operation = %call.operation, elapsed_ms, "storage call");
+        }
+    }
+}
+
+#[derive(Default)]
+struct OperationVisitor(String);
+
+impl Visit for OperationVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "db.operation" {
+            self.0 = value.to_string();
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "db.operation" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+/// One JSON object per event: `timestamp`, `level`, `target`, the names of
+/// the spans it happened in, the request's `trace_id` and the event's
+/// `fields`, `message` included.
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let mut timestamp = String::new();
+        SystemTime.format_time(&mut Writer::new(&mut timestamp))?;
+
+        let mut line = Map::new();
+        line.insert("timestamp".to_string(), Value::String(timestamp));
+        line.insert("level".to_string(), Value::String(event.metadata().level().to_string()));
+        line.insert("target".to_string(), Value::String(event.metadata().target().to_string()));
+
+        if let Some(scope) = ctx.event_scope() {
+            let mut spans = Vec::new();
+            let mut trace_id = None;
+            for span in scope.from_root() {
+                if trace_id.is_none() {
+                    trace_id = span.extensions().get::<OtelData>().and_then(|data| data.builder.trace_id);
+                }
+                spans.push(Value::String(span.name().to_string()));
+            }
+            line.insert("spans".to_string(), Value::Array(spans));
+            if let Some(trace_id) = trace_id.filter(|id| *id != TraceId::INVALID) {
+                line.insert("trace_id".to_string(), Value::String(trace_id.to_string()));
+            }
+        }
+
+        let mut fields = JsonVisitor::default();
+        event.record(&mut fields);
+        line.insert("fields".to_string(), Value::Object(fields.0));
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{:?}", value).into());
+    }
+}
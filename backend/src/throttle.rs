@@ -0,0 +1,201 @@
+//! Soft limits on task creation, so one misbehaving client can't flood a
+//! shared demo deployment.
+//!
+//! Each client (the `X-Client-Id` subject, as for experiments) may create
+//! [`BURST_LIMIT`] tasks per [`BURST_WINDOW`], and the same title only
+//! [`REPEAT_LIMIT`] times per [`REPEAT_WINDOW`]. The header is up to the
+//! caller, so the same limits also apply to the client's IP, worked out as
+//! for rate limiting: a new client id each request doesn't get round them.
+//! Past that, creation answers 429 with a `Retry-After` and an error message
+//! meant to be shown to the user.
+//! Counts are kept in memory per process and forgotten on restart.
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use shared::ErrorBody;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+const BURST_LIMIT: usize = 10;
+const BURST_WINDOW: Duration = Duration::from_secs(10);
+const REPEAT_LIMIT: usize = 3;
+const REPEAT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Recent creations by one client, oldest first.
+#[derive(Default)]
+struct History {
+    created: VecDeque<(Instant, String)>,
+}
+
+impl History {
+    fn prune(&mut self, now: Instant) {
+        while self.created.front().is_some_and(|(at, _)| now.duration_since(*at) > REPEAT_WINDOW) {
+            self.created.pop_front();
+        }
+    }
+
+    /// Whether one more creation of `title` (already normalized) is allowed.
+    fn allows(&self, title: &str, now: Instant) -> Result<(), Limited> {
+        let in_burst: Vec<Instant> = self
+            .created
+            .iter()
+            .map(|(at, _)| *at)
+            .filter(|at| now.duration_since(*at) <= BURST_WINDOW)
+            .collect();
+        if in_burst.len() >= BURST_LIMIT {
+            return Err(Limited {
+                message: "You're adding tasks very quickly. Take a breather and try again in a few seconds.",
+                retry_after: BURST_WINDOW.saturating_sub(now.duration_since(in_burst[0])),
+            });
+        }
+
+        let repeats: Vec<Instant> = self.created.iter().filter(|(_, t)| t == title).map(|(at, _)| *at).collect();
+        if repeats.len() >= REPEAT_LIMIT {
+            return Err(Limited {
+                message: "You've just added this task several times. Try again in a minute if you really need another.",
+                retry_after: REPEAT_WINDOW.saturating_sub(now.duration_since(repeats[0])),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Throttle {
+    clients: Arc<Mutex<HashMap<String, History>>>,
+    /// Past this many clients, idle ones are forgotten on the next check.
+    max_clients: usize,
+}
+
+/// Why a creation was turned away.
+#[derive(Debug)]
+pub struct Limited {
+    message: &'static str,
+    retry_after: Duration,
+}
+
+impl Limited {
+    pub fn new(message: &'static str, retry_after: Duration) -> Self {
+        Self { message, retry_after }
+    }
+}
+
+impl IntoResponse for Limited {
+    fn into_response(self) -> Response {
+        let retry_after = self.retry_after.as_secs().max(1).to_string();
+        let body = ErrorBody {
+            error: "too_many_requests".to_string(),
+            message: self.message.to_string(),
+            error_id: None,
+            fields: Vec::new(),
+        };
+        (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after)], Json(body)).into_response()
+    }
+}
+
+impl Throttle {
+    pub fn new(max_clients: usize) -> Self {
+        Self {
+            clients: Arc::default(),
+            max_clients,
+        }
+    }
+
+    /// Records a creation by `client` from `ip`, unless either trips a limit.
+    pub fn check(&self, client: &str, ip: Option<&str>, title: &str) -> Result<(), Limited> {
+        self.check_at(client, ip, title, Instant::now())
+    }
+
+    fn check_at(&self, client: &str, ip: Option<&str>, title: &str, now: Instant) -> Result<(), Limited> {
+        let Ok(mut clients) = self.clients.lock() else {
+            return Ok(());
+        };
+        if clients.len() >= self.max_clients {
+            clients.retain(|_, history| {
+                history.prune(now);
+                !history.created.is_empty()
+            });
+        }
+
+        // Prefixed so a client id can't pass for an IP
+        let keys: Vec<String> = std::iter::once(format!("client:{}", client))
+            .chain(ip.map(|ip| format!("ip:{}", ip)))
+            .collect();
+        let title = title.trim().to_lowercase();
+        for key in &keys {
+            let history = clients.entry(key.clone()).or_default();
+            history.prune(now);
+            history.allows(&title, now)?;
+        }
+        for key in keys {
+            clients.entry(key).or_default().created.push_back((now, title.clone()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn a_burst_is_limited_until_its_window_passes() {
+        let throttle = Throttle::new(100);
+        let start = Instant::now();
+        for n in 0..BURST_LIMIT {
+            assert!(throttle.check_at("client", None, &format!("Task {}", n), start).is_ok());
+        }
+
+        let limited = throttle.check_at("client", None, "One more", start + SECOND).unwrap_err();
+        assert_eq!(limited.retry_after, BURST_WINDOW - SECOND);
+        assert!(throttle.check_at("someone else", None, "One more", start + SECOND).is_ok());
+        assert!(throttle.check_at("client", None, "One more", start + BURST_WINDOW + SECOND).is_ok());
+    }
+
+    #[test]
+    fn a_repeated_title_is_limited_for_the_repeat_window() {
+        let throttle = Throttle::new(100);
+        let start = Instant::now();
+        for n in 0..REPEAT_LIMIT {
+            // Spaced out so the burst limit doesn't get in the way
+            assert!(throttle.check_at("client", None, "Buy milk", start + BURST_WINDOW * n as u32).is_ok());
+        }
+
+        let later = start + BURST_WINDOW * REPEAT_LIMIT as u32;
+        // Titles differing only in case and surrounding space are the same
+        let limited = throttle.check_at("client", None, "  buy MILK ", later).unwrap_err();
+        assert_eq!(limited.retry_after, REPEAT_WINDOW - (later - start));
+        assert!(throttle.check_at("client", None, "Buy bread", later).is_ok());
+        assert!(throttle.check_at("client", None, "Buy milk", start + REPEAT_WINDOW + SECOND).is_ok());
+    }
+
+    #[test]
+    fn idle_clients_are_forgotten_past_the_client_limit() {
+        let throttle = Throttle::new(2);
+        let start = Instant::now();
+        throttle.check_at("a", None, "Task", start).unwrap();
+        throttle.check_at("b", None, "Task", start).unwrap();
+        throttle.check_at("c", None, "Task", start + REPEAT_WINDOW + SECOND).unwrap();
+        assert_eq!(throttle.clients.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn a_new_client_id_from_the_same_ip_is_still_limited() {
+        let throttle = Throttle::new(100);
+        let start = Instant::now();
+        for n in 0..BURST_LIMIT {
+            let client = format!("client {}", n);
+            assert!(throttle.check_at(&client, Some("203.0.113.7"), &format!("Task {}", n), start).is_ok());
+        }
+
+        assert!(throttle.check_at("yet another", Some("203.0.113.7"), "One more", start).is_err());
+        assert!(throttle.check_at("yet another", Some("198.51.100.1"), "One more", start).is_ok());
+    }
+}
@@ -0,0 +1,428 @@
+//! Exporting and importing tasks.
+//!
+//! `GET /api/tasks/export?format=json|csv` streams every task as a download.
+//! JSON holds the tasks exactly as the API returns them. CSV has one row per
+//! task with the columns in [`CSV_COLUMNS`], for spreadsheets; it leaves out
+//! locations and recurrence.
+//!
+//! `POST /api/tasks/import` takes either format back as a multipart `file`.
+//! Tasks whose id is already taken, in the list, the trash or the archive, are
+//! skipped, so importing a file twice is harmless, and tasks that don't validate are reported and skipped; the rest
+//! are saved in one batch. Attachment bytes aren't exported, so imported
+//! tasks come without attachments. With `?dry_run=true` nothing is saved and
+//! the summary lists what would happen to each task, for a preview.
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Multipart, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use shared::{validation, ActivityKind, ImportError, ImportRow, ImportStatus, ImportSummary, Priority, Task, TaskEvent};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::{activity, analytics, content_filter::Moderation, error::ApiError, live, snooze, store::{Store, Versions}, RedisPool};
+
+/// Largest file accepted by the import endpoint.
+pub const MAX_IMPORT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Header of a CSV export, in the order of [`CsvRow`]'s fields.
+const CSV_COLUMNS: [&str; 10] = [
+    "id",
+    "title",
+    "description",
+    "completed",
+    "pinned",
+    "priority",
+    "tags",
+    "created_at",
+    "snoozed_until",
+    "due_at",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Csv,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FormatQuery {
+    format: Option<Format>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    format: Option<Format>,
+    /// Only check the file and report on it.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// One task in a CSV file. Tags are comma-separated, and every column but
+/// `title` may be left out or empty; a task without an `id` gets a new one.
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    #[serde(default)]
+    id: Option<Uuid>,
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    completed: Option<bool>,
+    #[serde(default)]
+    pinned: Option<bool>,
+    #[serde(default)]
+    priority: Option<Priority>,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    created_at: Option<u64>,
+    #[serde(default)]
+    snoozed_until: Option<u64>,
+    #[serde(default)]
+    due_at: Option<u64>,
+}
+
+impl From<&Task> for CsvRow {
+    fn from(task: &Task) -> Self {
+        Self {
+            id: Some(task.id),
+            title: task.title.clone(),
+            description: task.description.clone(),
+            completed: Some(task.completed),
+            pinned: Some(task.pinned),
+            priority: Some(task.priority),
+            tags: task.tags.join(", "),
+            created_at: task.created_at,
+            snoozed_until: task.snoozed_until,
+            due_at: task.due_at,
+        }
+    }
+}
+
+impl CsvRow {
+    fn into_task(self) -> Task {
+        let mut task = Task::new(self.title, self.description);
+        if let Some(id) = self.id {
+            task.id = id;
+        }
+        task.completed = self.completed.unwrap_or_default();
+        task.pinned = self.pinned.unwrap_or_default();
+        task.priority = self.priority.unwrap_or_default();
+        task.tags = self.tags.split(',').map(str::to_string).collect();
+        task.created_at = self.created_at;
+        task.snoozed_until = self.snoozed_until;
+        task.due_at = self.due_at;
+        task
+    }
+}
+
+#[tracing::instrument(skip(store))]
+pub async fn export(State(store): State<Store>, Query(query): Query<FormatQuery>) -> Result<Response, ApiError> {
+    let tasks = store.list().await?;
+    let format = query.format.unwrap_or_default();
+
+    // Serialized a task at a time as the client reads
+    let body = match format {
+        Format::Json => Body::from_stream(json_chunks(tasks)),
+        Format::Csv => Body::from_stream(csv_chunks(tasks)),
+    };
+    let disposition = format!("attachment; filename=\"tasks.{}\"", format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn json_chunks(tasks: Vec<Task>) -> impl Stream<Item = Result<String, serde_json::Error>> {
+    let items = tasks.into_iter().enumerate().map(|(i, task)| {
+        let separator = if i == 0 { "" } else { "," };
+        serde_json::to_string(&task).map(|json| format!("{}{}", separator, json))
+    });
+    stream::iter(std::iter::once(Ok("[".to_string())).chain(items).chain(std::iter::once(Ok("]".to_string()))))
+}
+
+fn csv_chunks(tasks: Vec<Task>) -> impl Stream<Item = Result<Vec<u8>, csv::Error>> {
+    let rows = std::iter::once(None).chain(tasks.into_iter().map(Some)).map(|task| {
+        let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+        match task {
+            None => writer.write_record(CSV_COLUMNS)?,
+            Some(task) => writer.serialize(CsvRow::from(&task))?,
+        }
+        writer.into_inner().map_err(|e| e.into_error().into())
+    });
+    stream::iter(rows)
+}
+
+/// The format is taken from `?format=`, then from the file's extension.
+#[tracing::instrument(skip_all)]
+pub async fn import(
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+    moderation: Moderation,
+    Query(query): Query<ImportQuery>,
+    mut multipart: Multipart,
+) -> Result<Response, ApiError> {
+    let field = loop {
+        match multipart.next_field().await.map_err(|_| StatusCode::BAD_REQUEST)? {
+            Some(field) if field.name() == Some("file") => break field,
+            Some(_) => continue,
+            None => return Err(ApiError::Status(StatusCode::BAD_REQUEST)),
+        }
+    };
+    let from_extension = field
+        .file_name()
+        .is_some_and(|name| name.to_lowercase().ends_with(".csv"))
+        .then_some(Format::Csv);
+    let format = query.format.or(from_extension).unwrap_or_default();
+    let data = field.bytes().await.map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+
+    let records = match parse(format, &data) {
+        Ok(records) => records,
+        Err(message) => return Err(ApiError::Validation(message)),
+    };
+
+    let mut taken: HashSet<Uuid> = HashSet::new();
+    for task in store.list().await? {
+        taken.insert(task.id);
+    }
+    for task in store.list_trash().await? {
+        taken.insert(task.id);
+    }
+    for task in store.list_archive().await? {
+        taken.insert(task.id);
+    }
+
+    let now = snooze::now_secs();
+    let mut summary = ImportSummary::default();
+    let mut tasks: Vec<Task> = Vec::new();
+    for (index, record) in records.into_iter().enumerate() {
+        let title = record.as_ref().ok().map(|task| task.title.trim().to_string());
+        let (status, message) = match record.and_then(|task| prepare(task, &moderation, now)) {
+            Ok(task) if !taken.insert(task.id) => {
+                summary.duplicates += 1;
+                (ImportStatus::Duplicate, None)
+            }
+            Ok(task) => {
+                tasks.push(task);
+                (ImportStatus::Ok, None)
+            }
+            Err(message) => {
+                summary.errors.push(ImportError {
+                    record: index + 1,
+                    message: message.clone(),
+                });
+                (ImportStatus::Invalid, Some(message))
+            }
+        };
+        if query.dry_run {
+            summary.rows.push(ImportRow {
+                record: index + 1,
+                title,
+                status,
+                message,
+            });
+        }
+    }
+
+    if query.dry_run {
+        summary.imported = tasks.len();
+        summary.dry_run = true;
+        return Ok(Json(summary).into_response());
+    }
+
+    store.apply_batch(&mut tasks, &[], &Versions::new()).await?;
+    summary.imported = tasks.len();
+    tracing::info!(imported = summary.imported, duplicates = summary.duplicates, errors = summary.errors.len(), "imported tasks");
+
+    for task in tasks {
+        activity::record(&redis, ActivityKind::Created, &task).await;
+        analytics::count_tags(&redis, &task).await;
+        live.publish(TaskEvent::Created { task });
+    }
+
+    Ok(Json(summary).into_response())
+}
+
+/// Each task in the file, or why it couldn't be read. Fails as a whole only
+/// when the file isn't in `format` at all.
+fn parse(format: Format, data: &Bytes) -> Result<Vec<Result<Task, String>>, String> {
+    match format {
+        Format::Json => {
+            let values: Vec<serde_json::Value> =
+                serde_json::from_slice(data).map_err(|e| format!("Expected a JSON array of tasks: {}", e))?;
+            Ok(values
+                .into_iter()
+                .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+                .collect())
+        }
+        Format::Csv => {
+            let mut reader = csv::Reader::from_reader(data.as_ref());
+            let headers = reader.headers().map_err(|e| format!("Invalid CSV: {}", e))?;
+            if !headers.iter().any(|column| column == "title") {
+                return Err("The CSV file needs a title column".to_string());
+            }
+            Ok(reader
+                .deserialize::<CsvRow>()
+                .map(|row| row.map(CsvRow::into_task).map_err(|e| csv_error(&e)))
+                .collect())
+        }
+    }
+}
+
+/// The error without the position, which [`ImportError::record`] gives.
+fn csv_error(error: &csv::Error) -> String {
+    match error.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+        _ => error.to_string(),
+    }
+}
+
+/// Validates an imported task and resets what the server owns.
+fn prepare(mut task: Task, moderation: &Moderation, now: u64) -> Result<Task, String> {
+    validation::check_task(None, &mut task)
+        .map_err(|errors| errors.into_iter().map(|e| e.message).collect::<Vec<_>>().join(" "))?;
+    if let Some(rule) = &task.recurrence {
+        rule.validate()?;
+    }
+
+    task.tags = shared::normalize_tags(&task.tags);
+    task.created_at = task.created_at.or(Some(now));
+    task.attachments.clear();
+    task.deleted_at = None;
+    if !task.completed {
+        task.completed_at = None;
+    }
+    task.flagged = false;
+    task.version = 0;
+    task.clock = Default::default();
+    moderation
+        .review(None, &mut task)
+        .map_err(|_| "Contains words that aren't allowed here".to_string())?;
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_filter::{NoFilter, WordList};
+    use shared::{Attachment, FilterMode};
+    use std::sync::Arc;
+
+    fn parse_csv(text: &str) -> Result<Vec<Result<Task, String>>, String> {
+        parse(Format::Csv, &Bytes::from(text.to_string()))
+    }
+
+    fn allow_all() -> Moderation {
+        Moderation::new(Arc::new(NoFilter), FilterMode::Off)
+    }
+
+    #[test]
+    fn a_csv_file_without_a_title_column_is_refused() {
+        let error = parse_csv("description,priority\nMilk,high\n").unwrap_err();
+        assert!(error.contains("title column"), "{}", error);
+    }
+
+    #[test]
+    fn a_bad_csv_row_fails_alone() {
+        let records = parse_csv("title,priority,tags\nBuy milk,high,\"home, errands\"\nCall mum,urgentish,\n,,\n").unwrap();
+        assert_eq!(records.len(), 3);
+
+        let milk = records[0].as_ref().unwrap();
+        assert_eq!(milk.title, "Buy milk");
+        assert_eq!(milk.priority, Priority::High);
+        assert_eq!(milk.tags, vec!["home", " errands"]);
+        assert!(records[1].as_ref().unwrap_err().contains("urgentish"));
+        // An empty title reads fine here and is refused by prepare
+        assert_eq!(records[2].as_ref().unwrap().title, "");
+    }
+
+    #[test]
+    fn json_must_be_an_array_but_elements_fail_alone() {
+        let error = parse(Format::Json, &Bytes::from_static(b"{\"title\":\"Buy milk\"}")).unwrap_err();
+        assert!(error.starts_with("Expected a JSON array of tasks"), "{}", error);
+
+        let task = Task::new("Buy milk".to_string(), String::new());
+        let data = format!("[{}, {{\"title\": 7}}]", serde_json::to_string(&task).unwrap());
+        let records = parse(Format::Json, &Bytes::from(data)).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].as_ref().unwrap().id, task.id);
+        assert!(records[1].is_err());
+    }
+
+    #[test]
+    fn prepared_tasks_lose_what_the_server_owns() {
+        let mut task = Task::new("  Buy milk ".to_string(), String::new());
+        task.tags = vec![" #Home".to_string(), "home".to_string()];
+        task.completed_at = Some(5);
+        task.deleted_at = Some(6);
+        task.flagged = true;
+        task.version = 9;
+        task.attachments.push(Attachment {
+            id: Uuid::new_v4(),
+            filename: "receipt.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            size: 3,
+        });
+
+        let task = prepare(task, &allow_all(), 1_000).unwrap();
+        assert_eq!(task.title, "Buy milk");
+        assert_eq!(task.tags, vec!["home"]);
+        assert_eq!(task.created_at, Some(1_000));
+        assert_eq!(task.completed_at, None);
+        assert_eq!(task.deleted_at, None);
+        assert!(!task.flagged);
+        assert_eq!(task.version, 0);
+        assert!(task.attachments.is_empty());
+    }
+
+    #[test]
+    fn prepare_keeps_a_completed_tasks_dates() {
+        let mut task = Task::new("Buy milk".to_string(), String::new());
+        task.completed = true;
+        task.created_at = Some(10);
+        task.completed_at = Some(20);
+
+        let task = prepare(task, &allow_all(), 1_000).unwrap();
+        assert_eq!(task.created_at, Some(10));
+        assert_eq!(task.completed_at, Some(20));
+    }
+
+    #[test]
+    fn prepare_refuses_invalid_and_filtered_tasks() {
+        let untitled = Task::new("   ".to_string(), String::new());
+        assert!(prepare(untitled, &allow_all(), 1_000).is_err());
+
+        let strict = Moderation::new(Arc::new(WordList::new(["spam".to_string()])), FilterMode::Reject);
+        let spam = Task::new("Buy spam".to_string(), String::new());
+        let error = prepare(spam, &strict, 1_000).unwrap_err();
+        assert_eq!(error, "Contains words that aren't allowed here");
+    }
+}
@@ -0,0 +1,113 @@
+//! The trash.
+//!
+//! Deleting a task moves it here with `deleted_at` set instead of removing
+//! it. Trashed tasks can be restored or deleted for good until
+//! [`TRASH_TTL_SECS`] after their deletion, when a background job purges
+//! them along with their attachments and comments.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use shared::{ActivityKind, ListResponse, Task, TaskEvent};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::{
+    activity, analytics,
+    attachments::{self, Attachments},
+    comments,
+    error::ApiError,
+    live,
+    pagination::Pagination,
+    snooze,
+    status,
+    store::{Store, TRASH_TTL_SECS},
+    RedisPool,
+};
+
+/// How often the purge job looks for tasks past their time in the trash.
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// The purge job's name on `GET /status`.
+const JOB: &str = "trash_purge";
+
+pub fn spawn_purge_job(store: Store, redis: Option<RedisPool>, storage: Attachments, monitor: status::Monitor) {
+    monitor.register(JOB, PURGE_INTERVAL);
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            interval.tick().await;
+            monitor.track(JOB, purge_expired(&store, &redis, &storage)).await;
+        }
+    });
+}
+
+/// Returns whether the expired tasks could be purged.
+#[tracing::instrument(skip_all)]
+async fn purge_expired(store: &Store, redis: &Option<RedisPool>, storage: &Attachments) -> bool {
+    let before = snooze::now_secs().saturating_sub(TRASH_TTL_SECS);
+    match store.purge_trashed_before(before).await {
+        Ok(tasks) => {
+            for task in &tasks {
+                attachments::purge(storage, task).await;
+                comments::forget(redis, task.id).await;
+            }
+            if !tasks.is_empty() {
+                tracing::info!(count = tasks.len(), "purged expired tasks from the trash");
+            }
+            true
+        }
+        Err(e) => {
+            tracing::warn!("trash purge job failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Trashed tasks, most recently deleted first.
+#[tracing::instrument(skip(store))]
+pub async fn get_trash(
+    State(store): State<Store>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<ListResponse<Task>>, ApiError> {
+    let tasks = store.list_trash().await?;
+    Ok(Json(pagination.paginate(tasks)))
+}
+
+#[tracing::instrument(skip(store, redis, live))]
+pub async fn restore(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(live): State<live::Hub>,
+) -> Result<Json<Task>, ApiError> {
+    let task = store
+        .restore(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    activity::record(&redis, ActivityKind::Restored, &task).await;
+    analytics::count_tags(&redis, &task).await;
+    live.publish(TaskEvent::Created { task: task.clone() });
+
+    Ok(Json(task))
+}
+
+/// Deletes a trashed task for good.
+#[tracing::instrument(skip(store, redis, storage))]
+pub async fn purge(
+    Path(id): Path<Uuid>,
+    State(store): State<Store>,
+    State(redis): State<Option<RedisPool>>,
+    State(storage): State<Attachments>,
+) -> Result<StatusCode, ApiError> {
+    let task = store
+        .purge(id)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+    attachments::purge(&storage, &task).await;
+    comments::forget(&redis, task.id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,9 @@
+//! Build information for the running backend.
+
+use axum::response::Json;
+use shared::BuildInfo;
+
+#[tracing::instrument]
+pub async fn get_version() -> Json<BuildInfo> {
+    Json(shared::build_info!())
+}
@@ -0,0 +1,346 @@
+//! The task API end to end, through the router.
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use common::TestApp;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+fn titles(list: &Value) -> Vec<&str> {
+    list["data"].as_array().unwrap().iter().map(|task| task["title"].as_str().unwrap()).collect()
+}
+
+#[tokio::test]
+async fn tasks_go_through_create_read_update_and_delete() {
+    let app = TestApp::start().await;
+
+    let created = app.create("Write the integration tests").await;
+    let id = created["id"].as_str().unwrap();
+    assert_eq!(created["version"], 1);
+    assert_eq!(created["completed"], false);
+    assert!(created["created_at"].is_u64());
+
+    let (status, fetched) = app.get(&format!("/api/tasks/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(fetched, created);
+
+    let (status, updated) = app
+        .put(&format!("/api/tasks/{}", id), json!({"title": "Write more tests", "completed": true, "version": 1}))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["title"], "Write more tests");
+    assert_eq!(updated["completed"], true);
+    assert_eq!(updated["version"], 2);
+    assert!(updated["completed_at"].is_u64());
+
+    let (status, patched) = app.patch(&format!("/api/tasks/{}", id), json!({"completed": false, "tags": ["work"]})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(patched["completed"], false);
+    assert_eq!(patched["tags"], json!(["work"]));
+    assert!(patched.get("completed_at").is_none());
+
+    let (status, list) = app.get("/api/tasks").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(titles(&list), ["Write more tests"]);
+    assert_eq!(list["total"], 1);
+
+    let (status, _) = app.delete(&format!("/api/tasks/{}", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app.get(&format!("/api/tasks/{}", id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, list) = app.get("/api/tasks").await;
+    assert_eq!(list["total"], 0);
+
+    let (_, trash) = app.get("/api/trash").await;
+    assert_eq!(titles(&trash), ["Write more tests"]);
+    let (status, restored) = app.post(&format!("/api/tasks/{}/restore", id), json!(null)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(restored["id"], id);
+    let (_, list) = app.get("/api/tasks").await;
+    assert_eq!(list["total"], 1);
+}
+
+#[tokio::test]
+async fn completed_tasks_are_archived_and_restored() {
+    let app = TestApp::start().await;
+    let done = app.create("File the receipts").await;
+    let id = done["id"].as_str().unwrap();
+    app.create("Still to do").await;
+    let (status, _) = app.patch(&format!("/api/tasks/{}", id), json!({"completed": true})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // A minute on, so "completed before now" includes the task just completed
+    let now = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_secs() + 60;
+    let archived = app.store.archive_completed_before(now, now).await.unwrap();
+    assert_eq!(archived.len(), 1);
+    assert_eq!(archived[0].archived_at, Some(now));
+
+    let (_, list) = app.get("/api/tasks").await;
+    assert_eq!(titles(&list), ["Still to do"]);
+    let (status, archive) = app.get("/api/archive?per_page=10").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(titles(&archive), ["File the receipts"]);
+    assert_eq!(archive["data"][0]["archived_at"], now);
+
+    let (status, restored) = app.post(&format!("/api/archive/{}/restore", id), json!(null)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(restored["completed"], true);
+    assert!(restored.get("archived_at").is_none());
+    let (_, list) = app.get("/api/tasks").await;
+    assert_eq!(list["total"], 2);
+    let (_, archive) = app.get("/api/archive").await;
+    assert_eq!(archive["total"], 0);
+    let (status, _) = app.post(&format!("/api/archive/{}/restore", id), json!(null)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // An archived task is still there to be restored, so importing it again is a duplicate
+    app.store.archive_completed_before(now, now).await.unwrap();
+    let file = serde_json::to_vec(&json!([restored])).unwrap();
+    let (status, summary) = app.upload("/api/tasks/import", "tasks.json", &file).await;
+    assert_eq!(status, StatusCode::OK, "{}", summary);
+    assert_eq!(summary["duplicates"], 1);
+    assert_eq!(summary["imported"], 0);
+}
+
+#[tokio::test]
+async fn deleting_honours_if_match() {
+    let app = TestApp::start().await;
+    let task = app.create("Keep me").await;
+    let uri = format!("/api/tasks/{}", task["id"].as_str().unwrap());
+
+    let (status, _) = app.send_with_headers(Method::DELETE, &uri, None, &[("if-match", "\"stale\"")]).await;
+    assert_eq!(status, StatusCode::PRECONDITION_FAILED);
+    let (status, _) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app.send_with_headers(Method::DELETE, &uri, None, &[("if-match", "*")]).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, trash) = app.get("/api/trash").await;
+    assert_eq!(titles(&trash), ["Keep me"]);
+}
+
+#[tokio::test]
+async fn importing_a_file_twice_adds_its_tasks_once() {
+    let app = TestApp::start().await;
+    let id = Uuid::new_v4();
+    let file = format!("id,title,priority\n{id},Buy milk,high\n{id},Buy milk again,\n,Call mum,urgentish\n");
+
+    let (status, summary) = app.upload("/api/tasks/import", "tasks.csv", file.as_bytes()).await;
+    assert_eq!(status, StatusCode::OK, "{}", summary);
+    assert_eq!(summary["imported"], 1);
+    assert_eq!(summary["duplicates"], 1);
+    assert_eq!(summary["errors"][0]["record"], 3);
+
+    let (_, summary) = app.upload("/api/tasks/import", "tasks.csv", file.as_bytes()).await;
+    assert_eq!(summary["imported"], 0);
+    assert_eq!(summary["duplicates"], 2);
+    let (_, list) = app.get("/api/tasks").await;
+    assert_eq!(titles(&list), ["Buy milk"]);
+}
+
+#[tokio::test]
+async fn tasks_are_listed_in_pages() {
+    let app = TestApp::start().await;
+    for n in 0..5 {
+        app.create(&format!("Task {}", n)).await;
+    }
+
+    let (_, first) = app.get("/api/tasks?page=1&per_page=2").await;
+    let (_, last) = app.get("/api/tasks?page=3&per_page=2").await;
+    assert_eq!(first["data"].as_array().unwrap().len(), 2);
+    assert_eq!(last["data"].as_array().unwrap().len(), 1);
+    assert_eq!(first["total"], 5);
+    assert_eq!(last["page"], 3);
+
+    // Snoozed tasks are left out of the pages and the total
+    let snoozed = [&first["data"][0], &last["data"][0]];
+    for task in snoozed {
+        app.put(&format!("/api/tasks/{}", task["id"].as_str().unwrap()), json!({"snoozed_until": 4_102_444_800u64})).await;
+    }
+    let mut awake = Vec::new();
+    for page in 1..=2 {
+        let (_, list) = app.get(&format!("/api/tasks?page={}&per_page=2", page)).await;
+        assert_eq!(list["total"], 3);
+        awake.extend(list["data"].as_array().unwrap().iter().map(|task| task["title"].clone()));
+    }
+    assert_eq!(awake.len(), 3);
+    assert!(snoozed.iter().all(|task| !awake.contains(&task["title"])));
+    let (_, all) = app.get("/api/tasks?include_snoozed=true").await;
+    assert_eq!(all["total"], 5);
+}
+
+#[tokio::test]
+async fn missing_tasks_answer_404() {
+    let app = TestApp::start().await;
+    let uri = format!("/api/tasks/{}", Uuid::new_v4());
+
+    let (status, body) = app.get(&uri).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"], "not_found");
+
+    assert_eq!(app.put(&uri, json!({"title": "Nope"})).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(app.patch(&uri, json!({"title": "Nope"})).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(app.delete(&uri).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn invalid_tasks_are_refused_with_the_fields_at_fault() {
+    let app = TestApp::start().await;
+
+    let (status, body) = app.post("/api/tasks", json!({"title": "   ", "description": ""})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"], "validation");
+    assert_eq!(body["fields"][0]["field"], "title");
+
+    let task = app.create("Valid").await;
+    let uri = format!("/api/tasks/{}", task["id"].as_str().unwrap());
+    let (status, body) = app.put(&uri, json!({"title": ""})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["fields"][0]["field"], "title");
+
+    let (status, _) = app.put(&uri, json!({"recurrence": {"kind": "cron", "expression": "not cron"}})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (status, _) = app.patch(&uri, json!({"priority": "whenever"})).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let (_, unchanged) = app.get(&uri).await;
+    assert_eq!(unchanged, task);
+}
+
+#[tokio::test]
+async fn stale_updates_get_409_with_the_latest_task() {
+    let app = TestApp::start().await;
+    let task = app.create("Shared task").await;
+    let uri = format!("/api/tasks/{}", task["id"].as_str().unwrap());
+
+    app.put(&uri, json!({"title": "Edited elsewhere", "version": 1})).await;
+    let (status, latest) = app.put(&uri, json!({"title": "Edited here", "version": 1})).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(latest["title"], "Edited elsewhere");
+    assert_eq!(latest["version"], 2);
+}
+
+#[tokio::test]
+async fn concurrent_updates_to_one_version_only_let_one_through() {
+    let app = TestApp::start().await;
+    let task = app.create("Contended task").await;
+    let uri = format!("/api/tasks/{}", task["id"].as_str().unwrap());
+
+    let updates = (0..8).map(|n| app.put(&uri, json!({"title": format!("Edit {}", n), "version": 1})));
+    let results = futures_util::future::join_all(updates).await;
+
+    let winners: Vec<&Value> = results.iter().filter(|(status, _)| *status == StatusCode::OK).map(|(_, task)| task).collect();
+    assert_eq!(winners.len(), 1, "{:?}", results);
+    assert!(results.iter().all(|(status, _)| [StatusCode::OK, StatusCode::CONFLICT].contains(status)));
+
+    let (_, stored) = app.get(&uri).await;
+    assert_eq!(&stored, winners[0]);
+    assert_eq!(stored["version"], 2);
+}
+
+#[tokio::test]
+async fn batches_against_a_stale_version_write_nothing() {
+    let app = TestApp::start().await;
+    let first: Uuid = app.create("First").await["id"].as_str().unwrap().parse().unwrap();
+    let second: Uuid = app.create("Second").await["id"].as_str().unwrap().parse().unwrap();
+    let mut batch = [app.store.get(first).await.unwrap().unwrap(), app.store.get(second).await.unwrap().unwrap()];
+    let expected = batch.iter().map(|task| (task.id, task.version)).collect();
+    for task in &mut batch {
+        task.completed = true;
+    }
+
+    app.put(&format!("/api/tasks/{}", second), json!({"title": "Edited elsewhere", "version": 1})).await;
+    assert!(!app.store.apply_batch(&mut batch.clone(), &[], &expected).await.unwrap());
+    let (_, list) = app.get("/api/tasks").await;
+    assert!(list["data"].as_array().unwrap().iter().all(|task| task["completed"] == false));
+
+    let (status, bulk) = app.post("/api/tasks/bulk", json!({"operations": [{"op": "complete", "id": first}]})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bulk["updated"][0]["version"], 2);
+}
+
+#[tokio::test]
+async fn redis_only_features_work_with_redis() {
+    let app = TestApp::start().await;
+    let (status, _) = app.admin(Method::GET, "/api/admin/client-errors", None).await;
+    if app.has_redis() {
+        assert_eq!(status, StatusCode::OK);
+    } else {
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
+
+#[tokio::test]
+async fn admin_endpoints_need_the_admin_token() {
+    let app = TestApp::start().await;
+    for uri in ["/api/admin/overview", "/api/admin/config", "/api/admin/logs"] {
+        let (status, _) = app.get(uri).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", uri);
+        let (status, _) = app.admin(Method::GET, uri, None).await;
+        assert_eq!(status, StatusCode::OK, "{}", uri);
+    }
+}
+
+#[tokio::test]
+async fn only_admins_can_change_the_content_filter() {
+    let app = TestApp::start().await;
+    let request = json!({"mode": "off"});
+    let (status, _) = app.put("/api/admin/content-filter", request.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app.admin(Method::PUT, "/api/admin/content-filter", Some(request)).await;
+    let expected = if app.has_redis() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    assert_eq!(status, expected);
+}
+
+#[tokio::test]
+async fn only_admins_can_toggle_flags() {
+    let app = TestApp::start().await;
+    let request = json!({"enabled": true});
+    let (status, _) = app.put("/api/flags/crdt_sync", request.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, flags) = app.admin(Method::PUT, "/api/flags/crdt_sync", Some(request)).await;
+    if app.has_redis() {
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(flags["crdt_sync"], true);
+    } else {
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
+
+#[tokio::test]
+async fn only_admins_can_publish_or_delete_announcements() {
+    let app = TestApp::start().await;
+    let request = json!({"title": "Free pizza", "body": "Click here"});
+    let (status, _) = app.post("/api/admin/announcements", request.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app.delete(&format!("/api/admin/announcements/{}", Uuid::new_v4())).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, announcement) = app.admin(Method::POST, "/api/admin/announcements", Some(request)).await;
+    if !app.has_redis() {
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        return;
+    }
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/admin/announcements/{}", announcement["id"].as_str().unwrap());
+    let (status, _) = app.delete(&uri).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = app.admin(Method::DELETE, &uri, None).await;
+    assert!(status.is_success(), "{}", status);
+}
+
+#[tokio::test]
+async fn only_admins_can_replace_automation_rules() {
+    let app = TestApp::start().await;
+    let request = json!({"rules": []});
+    let (status, _) = app.put("/api/admin/automations", request.clone()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, _) = app.admin(Method::PUT, "/api/admin/automations", Some(request)).await;
+    let expected = if app.has_redis() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    assert_eq!(status, expected);
+}
@@ -0,0 +1,52 @@
+//! Attachments on local disk. Its own test binary, since it sets
+//! `ATTACHMENTS_DIR` and `ATTACHMENTS_MAX_MB` for the whole process.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn attachments_are_uploaded_downloaded_and_removed() {
+    let dir = std::env::temp_dir().join(format!("backend-attachments-{}", Uuid::new_v4()));
+    std::env::set_var("ATTACHMENTS_DIR", &dir);
+    std::env::set_var("ATTACHMENTS_MAX_MB", "1");
+    let app = TestApp::start().await;
+    let task = app.create("With a file").await;
+    let id = task["id"].as_str().unwrap();
+
+    let (status, attachment) = app.upload(&format!("/api/tasks/{}/attachments", id), "notes.txt", b"hello").await;
+    assert_eq!(status, StatusCode::OK, "{}", attachment);
+    assert_eq!(attachment["filename"], "notes.txt");
+    assert_eq!(attachment["size"], 5);
+    let url = format!("/api/tasks/{}/attachments/{}", id, attachment["id"].as_str().unwrap());
+
+    let (_, task) = app.get(&format!("/api/tasks/{}", id)).await;
+    assert_eq!(task["attachments"][0]["id"], attachment["id"]);
+    let (status, data) = app.get_html(&url).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(data, "hello");
+
+    let too_big = vec![b'x'; 1024 * 1024 + 1];
+    let (status, _) = app.upload(&format!("/api/tasks/{}/attachments", id), "big.txt", &too_big).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    let (status, _) = app.delete(&url).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = app.get_html(&url).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, task) = app.get(&format!("/api/tasks/{}", id)).await;
+    assert_eq!(task["attachments"].as_array().map(Vec::len), Some(0));
+
+    // Uploads finishing together each land on the task
+    let uri = format!("/api/tasks/{}/attachments", id);
+    let names: Vec<String> = (0..4).map(|n| format!("{}.txt", n)).collect();
+    let uploads = names.iter().map(|name| app.upload(&uri, name, b"hello"));
+    let results = futures_util::future::join_all(uploads).await;
+    assert!(results.iter().all(|(status, _)| *status == StatusCode::OK), "{:?}", results);
+    let (_, task) = app.get(&format!("/api/tasks/{}", id)).await;
+    assert_eq!(task["attachments"].as_array().map(Vec::len), Some(4));
+
+    let _ = std::fs::remove_dir_all(dir);
+}
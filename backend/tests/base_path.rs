@@ -0,0 +1,36 @@
+//! The app served under a path prefix. Its own test binary, since it sets
+//! `BASE_PATH` for the whole process.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use serde_json::json;
+
+#[tokio::test]
+async fn everything_is_served_under_the_base_path() {
+    std::env::set_var("BASE_PATH", "/todo/");
+    let app = TestApp::start().await;
+    let (status, _) = app.post("/todo/api/tasks", json!({"title": "Behind the proxy", "description": ""})).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, list) = app.get("/todo/api/tasks").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(list["total"], 1);
+    let (status, _) = app.get("/api/tasks").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, summary) = app.upload("/todo/api/tasks/import", "tasks.csv", b"title\nImported behind the proxy\n").await;
+    assert_eq!(status, StatusCode::OK, "{}", summary);
+    assert_eq!(summary["imported"], 1);
+    let (status, _) = app.upload("/api/tasks/import", "tasks.csv", b"title\nMissed the prefix\n").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    for page in ["/todo", "/todo/", "/todo/my-day"] {
+        let (status, html) = app.get_html(page).await;
+        assert_eq!(status, StatusCode::OK, "{}", page);
+        assert!(html.contains("<base href=\"/todo/\">"), "{}", page);
+    }
+    let (status, _) = app.get_html("/my-day").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
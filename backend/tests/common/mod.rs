@@ -0,0 +1,227 @@
+//! A backend per test, on its own throwaway storage.
+//!
+//! [`TestApp::start`] runs a `redis-server` child process on a free port and
+//! builds the app on it with [`backend::build_app`]. Requests go straight to
+//! the router, without a socket. Where `redis-server` isn't installed, the
+//! app runs on a SQLite file in a temporary directory instead, so the suite
+//! still runs, minus the Redis-only features.
+
+// Each test file uses its own share of the helpers
+#![allow(dead_code)]
+
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use backend::{
+    config::RedisPoolTuning,
+    redis_pool::RedisPool,
+    store::{RedisStore, SqliteStore, Store},
+};
+use serde_json::Value;
+use std::{
+    net::TcpListener,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Once},
+    time::Duration,
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+/// `ADMIN_TOKEN` of every test app, sent by [`TestApp::admin`].
+pub const ADMIN_TOKEN: &str = "integration-tests-admin";
+
+pub struct TestApp {
+    app: Router,
+    /// The app's store, for what no endpoint does, like the background jobs.
+    pub store: Store,
+    /// The Redis child process or the SQLite directory, removed on drop.
+    _storage: Storage,
+}
+
+enum Storage {
+    Redis(Child),
+    Sqlite(PathBuf),
+}
+
+impl Drop for Storage {
+    fn drop(&mut self) {
+        match self {
+            Storage::Redis(child) => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            Storage::Sqlite(dir) => {
+                let _ = std::fs::remove_dir_all(dir);
+            }
+        }
+    }
+}
+
+impl TestApp {
+    pub async fn start() -> Self {
+        static ADMIN: Once = Once::new();
+        ADMIN.call_once(|| std::env::set_var("ADMIN_TOKEN", ADMIN_TOKEN));
+
+        match start_redis().await {
+            Some((child, url)) => {
+                let pool = RedisPool::new(&url, &RedisPoolTuning::default()).expect("test Redis pool");
+                let store: Store = Arc::new(RedisStore::new(pool.clone()));
+                TestApp {
+                    app: backend::build_app(Some(pool), store.clone()),
+                    store,
+                    _storage: Storage::Redis(child),
+                }
+            }
+            None => {
+                let dir = std::env::temp_dir().join(format!("backend-test-{}", Uuid::new_v4()));
+                std::fs::create_dir_all(&dir).expect("test directory");
+                let store: Store = Arc::new(SqliteStore::open(&dir.join("tasks.db")).expect("test SQLite store"));
+                TestApp {
+                    app: backend::build_app(None, store.clone()),
+                    store,
+                    _storage: Storage::Sqlite(dir),
+                }
+            }
+        }
+    }
+
+    /// Whether Redis-only endpoints are available.
+    pub fn has_redis(&self) -> bool {
+        matches!(self._storage, Storage::Redis(_))
+    }
+
+    /// Sends a request, with `body` as JSON, and returns the status and the
+    /// JSON answer (`Null` when it isn't JSON).
+    pub async fn send(&self, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        self.send_with_headers(method, uri, body, &[]).await
+    }
+
+    /// Like [`send`](Self::send), as an admin with [`ADMIN_TOKEN`].
+    pub async fn admin(&self, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let authorization = format!("Bearer {}", ADMIN_TOKEN);
+        self.send_with_headers(method, uri, body, &[("authorization", &authorization)]).await
+    }
+
+    /// Like [`send`](Self::send), with extra request headers.
+    pub async fn send_with_headers(
+        &self,
+        method: Method,
+        uri: &str,
+        body: Option<Value>,
+        headers: &[(&str, &str)],
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            // Each app gets its own throttle, so one client id is enough
+            .header("x-client-id", "integration-tests");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .expect("test request");
+
+        let response = self.app.clone().oneshot(request).await.expect("router is infallible");
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("response body");
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    /// Fetches a page of the frontend, as text.
+    pub async fn get_html(&self, uri: &str) -> (StatusCode, String) {
+        let request = Request::get(uri).body(Body::empty()).expect("test request");
+        let response = self.app.clone().oneshot(request).await.expect("router is infallible");
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("response body");
+        (status, String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Uploads `data` as the multipart field `file`, as the attachment form
+    /// does, and returns the status and the JSON answer.
+    pub async fn upload(&self, uri: &str, filename: &str, data: &[u8]) -> (StatusCode, Value) {
+        const BOUNDARY: &str = "integration-tests-boundary";
+        let mut body = format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: text/plain\r\n\r\n",
+            BOUNDARY, filename
+        )
+        .into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+
+        let request = Request::post(uri)
+            .header("x-client-id", "integration-tests")
+            .header("content-type", format!("multipart/form-data; boundary={}", BOUNDARY))
+            .body(Body::from(body))
+            .expect("test request");
+        let response = self.app.clone().oneshot(request).await.expect("router is infallible");
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("response body");
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    pub async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        self.send(Method::GET, uri, None).await
+    }
+
+    pub async fn post(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.send(Method::POST, uri, Some(body)).await
+    }
+
+    pub async fn put(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.send(Method::PUT, uri, Some(body)).await
+    }
+
+    pub async fn patch(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.send(Method::PATCH, uri, Some(body)).await
+    }
+
+    pub async fn delete(&self, uri: &str) -> (StatusCode, Value) {
+        self.send(Method::DELETE, uri, None).await
+    }
+
+    /// Creates a task and returns it, failing the test if that doesn't work.
+    pub async fn create(&self, title: &str) -> Value {
+        let (status, task) = self.post("/api/tasks", serde_json::json!({"title": title, "description": ""})).await;
+        assert_eq!(status, StatusCode::OK, "creating {:?}: {}", title, task);
+        task
+    }
+}
+
+/// Starts `redis-server` without persistence on a free port and waits for
+/// it to answer. `None` when it isn't installed.
+async fn start_redis() -> Option<(Child, String)> {
+    let port = TcpListener::bind("127.0.0.1:0").ok()?.local_addr().ok()?.port();
+    let child = Command::new("redis-server")
+        .args(["--port", &port.to_string(), "--bind", "127.0.0.1", "--save", "", "--appendonly", "no"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            eprintln!("redis-server not found, running on SQLite");
+            return None;
+        }
+        Err(e) => panic!("failed to start redis-server: {}", e),
+    };
+
+    let url = format!("redis://127.0.0.1:{}", port);
+    let client = redis::Client::open(url.as_str()).expect("Redis URL");
+    for _ in 0..100 {
+        if let Ok(mut conn) = client.get_multiplexed_async_connection().await {
+            if redis::cmd("PING").query_async::<_, String>(&mut conn).await.is_ok() {
+                return Some((child, url));
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("redis-server didn't start on port {}", port);
+}
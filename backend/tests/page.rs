@@ -0,0 +1,91 @@
+//! The app's page, as the server streams it ahead of the app.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::TestApp;
+use shared::InitialTasks;
+
+/// The JSON the page carries for the app to start from.
+fn initial_tasks(html: &str) -> Option<InitialTasks> {
+    let json = html
+        .split("<script type=\"application/json\" id=\"initial-tasks\">")
+        .nth(1)?
+        .split("</script>")
+        .next()?;
+    Some(serde_json::from_str(json).expect("initial tasks JSON"))
+}
+
+#[tokio::test]
+async fn the_tasks_page_comes_with_the_first_page_of_tasks() {
+    let app = TestApp::start().await;
+    app.create("Buy <b>milk</b> & eggs</script>").await;
+    app.create("Call the bank").await;
+
+    let (status, html) = app.get_html("/").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("id=\"shell-skeleton\""));
+    assert!(html.contains("Buy &lt;b&gt;milk&lt;/b&gt; &amp; eggs&lt;/script&gt;"));
+    assert!(html.trim_end().ends_with("</html>"));
+
+    let initial = initial_tasks(&html).expect("the page should carry the tasks");
+    let mut titles: Vec<&str> = initial.list.data.iter().map(|task| task.title.as_str()).collect();
+    // Created in the same second, so the page orders them by id
+    titles.sort();
+    assert_eq!(titles, ["Buy <b>milk</b> & eggs</script>", "Call the bank"]);
+    assert_eq!(initial.list.page, 1);
+    assert!(initial.version.is_some());
+}
+
+#[tokio::test]
+async fn other_pages_only_get_the_skeleton() {
+    let app = TestApp::start().await;
+    app.create("Call the bank").await;
+
+    let (status, html) = app.get_html("/dashboard").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(html.contains("id=\"shell-skeleton\""));
+    assert!(initial_tasks(&html).is_none());
+
+    let (status, _) = app.get_html("/missing.js").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn pages_carry_their_own_preview_tags() {
+    let app = TestApp::start().await;
+    let task = app.create("Plan the <trip>").await;
+
+    let (_, html) = app.get_html("/my-day").await;
+    assert!(html.contains("<title>My Day · Task Manager</title>"));
+    assert!(html.contains("<meta property=\"og:title\" content=\"My Day · Task Manager\">"));
+    assert!(html.contains("<meta name=\"twitter:card\" content=\"summary\">"));
+    assert_eq!(html.matches("<title>").count(), 1);
+
+    let (_, html) = app.get_html(&format!("/?task={}", task["id"].as_str().unwrap())).await;
+    assert!(html.contains("<meta property=\"og:title\" content=\"Plan the &lt;trip&gt; · Task Manager\">"));
+    assert!(html.contains("<meta property=\"og:type\" content=\"article\">"));
+}
+
+#[tokio::test]
+async fn crawlers_get_robots_and_a_sitemap() {
+    let app = TestApp::start().await;
+
+    let (status, robots) = app.get_html("/robots.txt").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(robots.contains("Disallow: /api/"));
+
+    let (status, sitemap) = app.get_html("/sitemap.xml").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(sitemap.contains("/my-day</loc>"));
+    assert!(!sitemap.contains("/admin"));
+}
+
+#[tokio::test]
+async fn the_service_worker_is_served_with_this_build_in_its_cache_name() {
+    let app = TestApp::start().await;
+    let (status, script) = app.get_html("/sw.js").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(script.contains(&format!("const CACHE = \"tasks-{}-", shared::GIT_HASH)), "{}", script);
+    assert!(!script.contains("{{BUILD}}"));
+}
@@ -8,8 +8,8 @@ npm install
 echo "Building Tailwind CSS..."
 npm run build-css
 
-echo "Copying HTML template..."
-cp index.html dist/
+echo "Copying HTML template and web app manifest..."
+cp index.html manifest.webmanifest icon.svg dist/
 
 echo "Building frontend WebAssembly..."
 wasm-pack build --target web --out-dir dist --out-name frontend --no-opt #keep args
@@ -0,0 +1,325 @@
+//! Sending API requests, with retries and a running view of backend health.
+//!
+//! Idempotent requests (`GET`, `PUT`, `DELETE`) that fail on the network or
+//! come back 429/502/503/504 are retried up to [`MAX_ATTEMPTS`] times,
+//! waiting as long as `Retry-After` asks or backing off exponentially.
+//! `POST`s go out once, since repeating one could create a task twice.
+//!
+//! After [`DEGRADED_AFTER`] failures in a row with no success in between,
+//! [`degraded`] reports the backend as down so the app can show one banner
+//! instead of an error per request.
+//!
+//! [`request`] wraps all of that for JSON endpoints, and the task endpoints
+//! the list is built on have their own functions below it.
+
+use serde::{de::DeserializeOwned, de::IgnoredAny, Serialize};
+use shared::{validation::FieldError, CreateTaskRequest, ErrorBody, ListResponse, Task, UpdateTaskRequest};
+use std::cell::Cell;
+use uuid::Uuid;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, Response};
+
+const MAX_ATTEMPTS: u32 = 3;
+const BASE_DELAY_MS: i32 = 500;
+/// Longest `Retry-After` honoured; anything longer fails straight away.
+const MAX_RETRY_AFTER_SECS: i32 = 30;
+const DEGRADED_AFTER: u32 = 3;
+
+thread_local! {
+    static FAILURES: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Whether the backend has failed several times in a row.
+pub fn degraded() -> bool {
+    FAILURES.with(|failures| failures.get() >= DEGRADED_AFTER)
+}
+
+/// For [`request`]s without a body.
+pub const NO_BODY: Option<&()> = None;
+
+/// Why a [`request`] failed.
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// No response came back, or it couldn't be read.
+    Network,
+    /// The server answered with an error status.
+    Status {
+        status: u16,
+        /// The response text, usually an [`ErrorBody`].
+        body: String,
+        trace_id: Option<String>,
+        /// Seconds `Retry-After` asked to wait.
+        retry_after: Option<i32>,
+    },
+    /// The JSON sent or received didn't fit its type.
+    Parse(String),
+}
+
+impl Error {
+    /// Reads the error out of a non-2xx response, using up its body.
+    pub async fn from_response(response: &Response) -> Self {
+        Error::Status {
+            status: response.status(),
+            body: text(response).await.unwrap_or_default(),
+            trace_id: response.headers().get("x-trace-id").ok().flatten(),
+            retry_after: retry_after(response),
+        }
+    }
+
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// The JSON error body the backend answers failed requests with, if the
+    /// response had one.
+    pub fn body(&self) -> Option<ErrorBody> {
+        self.json()
+    }
+
+    /// The error response parsed as something other than an [`ErrorBody`],
+    /// such as the current task a 409 answers with.
+    pub fn json<T: DeserializeOwned>(&self) -> Option<T> {
+        match self {
+            Error::Status { body, .. } => serde_json::from_str(body).ok(),
+            _ => None,
+        }
+    }
+
+    /// Message for the user, led by `context`: the backend's own message
+    /// when it sent one, tagged with the error id or trace id so users can
+    /// quote it when reporting problems.
+    pub fn describe(&self, context: &str) -> String {
+        let (status, trace_id) = match self {
+            Error::Network => return context.to_string(),
+            Error::Parse(e) => return format!("Failed to parse JSON: {}", e),
+            Error::Status { status, trace_id, .. } => (status, trace_id),
+        };
+        let body = self.body();
+        let reference = match (body.as_ref().and_then(|body| body.error_id.clone()), trace_id) {
+            (Some(error_id), _) => format!("HTTP {}, error {}", status, error_id),
+            (None, Some(trace_id)) => format!("HTTP {}, trace {}", status, trace_id),
+            (None, None) => format!("HTTP {}", status),
+        };
+        match body {
+            Some(body) => format!("{}: {} ({})", context, body.message, reference),
+            None => format!("{} ({})", context, reference),
+        }
+    }
+}
+
+/// Sends `body` as JSON to `url` and parses the JSON answer as a `T`. An
+/// empty answer reads as `null`, and [`IgnoredAny`] takes any answer, for
+/// endpoints whose answer isn't needed. `PATCH` bodies go as JSON merge
+/// patches. Retried like [`send`].
+pub async fn request<T: DeserializeOwned>(method: &str, url: &str, body: Option<&impl Serialize>) -> Result<T, Error> {
+    let init = RequestInit::new();
+    init.set_method(method);
+    if let Some(body) = body {
+        let body = serde_json::to_string(body).map_err(|e| Error::Parse(e.to_string()))?;
+        init.set_body(&JsValue::from_str(&body));
+    }
+    let request = Request::new_with_str_and_init(&crate::base::url(url), &init).map_err(|_| Error::Network)?;
+
+    let headers = request.headers();
+    if body.is_some() {
+        let content_type = if method == "PATCH" { "application/merge-patch+json" } else { "application/json" };
+        headers.set("Content-Type", content_type).map_err(|_| Error::Network)?;
+    }
+    // Creation limits and experiments are per client
+    headers.set("X-Client-Id", &crate::client_id()).map_err(|_| Error::Network)?;
+    if needs_admin(url) {
+        if let Some(token) = crate::preferences::load_admin_token() {
+            headers.set("Authorization", &format!("Bearer {}", token)).map_err(|_| Error::Network)?;
+        }
+    }
+
+    let response = send(&request).await.map_err(|_| Error::Network)?;
+    if !response.ok() {
+        return Err(Error::from_response(&response).await);
+    }
+
+    let text = text(&response).await.ok_or(Error::Network)?;
+    serde_json::from_str(if text.is_empty() { "null" } else { &text }).map_err(|e| Error::Parse(e.to_string()))
+}
+
+/// Whether `url` is one of the endpoints that take the admin token.
+fn needs_admin(url: &str) -> bool {
+    url.starts_with("/api/admin/")
+}
+
+async fn text(response: &Response) -> Option<String> {
+    JsFuture::from(response.text().ok()?).await.ok()?.as_string()
+}
+
+/// `GET`s `url`, a path under the base path.
+pub async fn get(url: &str) -> Result<Response, JsValue> {
+    send(&Request::new_with_str(&crate::base::url(url))?).await
+}
+
+/// Sends `request`, retrying it when that is safe. Like `fetch`, only
+/// network failures are errors; check the response status as usual.
+pub async fn send(request: &Request) -> Result<Response, JsValue> {
+    let idempotent = matches!(request.method().as_str(), "GET" | "HEAD" | "PUT" | "DELETE" | "OPTIONS");
+    let mut attempt = 1;
+    loop {
+        // Each attempt needs its own copy, as sending uses up the body
+        let result = fetch_once(&request.clone()?).await;
+        record(&result);
+        if !idempotent || attempt >= MAX_ATTEMPTS {
+            return result;
+        }
+
+        let backoff = BASE_DELAY_MS * 2i32.pow(attempt - 1);
+        let delay = match &result {
+            Err(_) => backoff,
+            Ok(response) if matches!(response.status(), 429 | 502 | 503 | 504) => match retry_after(response) {
+                Some(secs) if secs > MAX_RETRY_AFTER_SECS => return result,
+                Some(secs) => secs * 1000,
+                None => backoff,
+            },
+            Ok(_) => return result,
+        };
+        // Jitter keeps many tabs from retrying in lockstep
+        crate::sleep(delay + (js_sys::Math::random() * BASE_DELAY_MS as f64) as i32).await;
+        attempt += 1;
+    }
+}
+
+/// Sends `request` once, without retrying or counting towards
+/// [`degraded`], for when the caller wants to see exactly what came back.
+pub async fn send_once(request: &Request) -> Result<Response, JsValue> {
+    fetch_once(request).await
+}
+
+async fn fetch_once(request: &Request) -> Result<Response, JsValue> {
+    let window = web_sys::window().ok_or_else(|| JsValue::from_str("no window"))?;
+    Ok(JsFuture::from(window.fetch_with_request(request)).await?.into())
+}
+
+fn record(result: &Result<Response, JsValue>) {
+    let failed = match result {
+        Ok(response) => response.status() >= 500,
+        Err(_) => true,
+    };
+    FAILURES.with(|failures| failures.set(if failed { failures.get() + 1 } else { 0 }));
+}
+
+/// `Retry-After` in seconds; the HTTP-date form isn't used by our backend.
+fn retry_after(response: &Response) -> Option<i32> {
+    response.headers().get("retry-after").ok().flatten()?.trim().parse().ok()
+}
+
+/// Loads every task, following the list endpoint's pages.
+pub async fn fetch_tasks() -> Result<Vec<Task>, String> {
+    let mut tasks = Vec::new();
+    let mut page = 1;
+    loop {
+        let list = fetch_tasks_page(page, 200).await?;
+        let has_more = list.has_more() && !list.data.is_empty();
+        tasks.extend(list.data);
+        if !has_more {
+            return Ok(tasks);
+        }
+        page += 1;
+    }
+}
+
+pub async fn fetch_tasks_page(page: u32, per_page: u32) -> Result<ListResponse<Task>, String> {
+    let url = format!("/api/tasks?include_snoozed=true&page={}&per_page={}", page, per_page);
+    request("GET", &url, NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to fetch tasks"))
+}
+
+/// A single task, or `None` if it no longer exists.
+pub async fn fetch_task(id: Uuid) -> Result<Option<Task>, String> {
+    match request("GET", &format!("/api/tasks/{}", id), NO_BODY).await {
+        Ok(task) => Ok(Some(task)),
+        Err(e) if e.status() == Some(404) => Ok(None),
+        Err(e) => Err(e.describe("Failed to fetch task")),
+    }
+}
+
+/// Why `POST /api/tasks` didn't create a task.
+pub enum CreateError {
+    /// Too many creates too quickly: the server's message and the seconds
+    /// to wait before trying again.
+    Throttled(String, i32),
+    /// Refused by validation or the content filter, with the server's
+    /// message and any fields it objected to.
+    Rejected(String, Vec<FieldError>),
+    Failed(String),
+}
+
+pub async fn create_task(task: CreateTaskRequest) -> Result<Task, CreateError> {
+    request("POST", "/api/tasks", Some(&task)).await.map_err(|e| match e.status() {
+        Some(422) => {
+            let (message, fields) = match e.body() {
+                Some(body) => (body.message, body.fields),
+                None => ("This task can't be added as written.".to_string(), Vec::new()),
+            };
+            CreateError::Rejected(message, fields)
+        }
+        Some(429) => {
+            let message = e.body().map(|body| body.message);
+            let retry_after = match e {
+                Error::Status { retry_after, .. } => retry_after.unwrap_or(5),
+                _ => 5,
+            };
+            CreateError::Throttled(
+                message.unwrap_or_else(|| "Slow down a little and try again shortly.".to_string()),
+                retry_after,
+            )
+        }
+        _ => CreateError::Failed(e.describe("Failed to create task")),
+    })
+}
+
+/// Why a `PATCH /api/tasks/:id` didn't go through.
+pub enum UpdateError {
+    /// The request named a version the task has since moved past; carries
+    /// the task as it is now.
+    Conflict(Task),
+    /// Fields of the edit were out of bounds.
+    Invalid(Vec<FieldError>),
+    Failed(String),
+}
+
+pub async fn update_task(id: Uuid, changes: UpdateTaskRequest) -> Result<Task, String> {
+    patch_task(id, changes).await.map_err(|e| match e {
+        UpdateError::Conflict(_) => "Task was changed by someone else".to_string(),
+        UpdateError::Invalid(fields) => fields.into_iter().map(|f| f.message).collect::<Vec<_>>().join(" "),
+        UpdateError::Failed(e) => e,
+    })
+}
+
+pub async fn patch_task(id: Uuid, changes: UpdateTaskRequest) -> Result<Task, UpdateError> {
+    request("PATCH", &format!("/api/tasks/{}", id), Some(&changes))
+        .await
+        .map_err(|e| match (e.status(), e.body()) {
+            (Some(409), _) => match e.json() {
+                Some(task) => UpdateError::Conflict(task),
+                None => UpdateError::Failed(e.describe("Failed to update task")),
+            },
+            (Some(422), Some(body)) if !body.fields.is_empty() => UpdateError::Invalid(body.fields),
+            _ => UpdateError::Failed(e.describe("Failed to update task")),
+        })
+}
+
+pub async fn delete_task(id: Uuid) -> Result<(), String> {
+    request::<IgnoredAny>("DELETE", &format!("/api/tasks/{}", id), NO_BODY)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.describe("Failed to delete task"))
+}
+
+pub async fn delete_attachment(task_id: Uuid, attachment_id: Uuid) -> Result<(), String> {
+    request::<IgnoredAny>("DELETE", &format!("/api/tasks/{}/attachments/{}", task_id, attachment_id), NO_BODY)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.describe("Failed to remove attachment"))
+}
@@ -0,0 +1,166 @@
+//! The Admin page's form for adding an automation rule.
+//!
+//! The form builds rules with one condition, at most, and one action; the
+//! API takes any number of each.
+
+use shared::{
+    automation::{AutomationRule, RuleAction, RuleCondition, RuleTrigger},
+    Priority,
+};
+use uuid::Uuid;
+
+/// Trigger choices, by the value of their `<option>`.
+pub const TRIGGERS: [(&str, &str); 5] = [
+    ("created", "When created"),
+    ("completed", "When completed"),
+    ("reopened", "When reopened"),
+    ("snooze_ended", "When its snooze ends"),
+    ("stale", "When open for some days"),
+    ("due_passed", "When its due date passes"),
+];
+
+pub const CONDITIONS: [(&str, &str); 4] = [
+    ("", "Any task"),
+    ("has_tag", "Tagged"),
+    ("lacks_tag", "Not tagged"),
+    ("priority_at_least", "Priority at least"),
+];
+
+pub const ACTIONS: [(&str, &str); 6] = [
+    ("add_tag", "Add tag"),
+    ("remove_tag", "Remove tag"),
+    ("set_priority", "Set priority"),
+    ("clear_snooze", "Clear the snooze"),
+    ("notify", "Notify"),
+    ("webhook", "Call a webhook"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RuleField {
+    Name,
+    Trigger,
+    Days,
+    Condition,
+    ConditionValue,
+    Action,
+    ActionValue,
+}
+
+/// What's typed into the form so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleDraft {
+    pub name: String,
+    pub trigger: String,
+    pub days: String,
+    pub condition: String,
+    pub condition_value: String,
+    pub action: String,
+    pub action_value: String,
+}
+
+impl Default for RuleDraft {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            trigger: "completed".to_string(),
+            days: "7".to_string(),
+            condition: String::new(),
+            condition_value: String::new(),
+            action: "add_tag".to_string(),
+            action_value: String::new(),
+        }
+    }
+}
+
+/// What the value field next to a condition or action asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    Tag,
+    Priority,
+    Text(&'static str),
+}
+
+impl RuleDraft {
+    pub fn set(&mut self, field: RuleField, value: String) {
+        match field {
+            RuleField::Name => self.name = value,
+            RuleField::Trigger => self.trigger = value,
+            RuleField::Days => self.days = value,
+            RuleField::Condition => {
+                self.condition = value;
+                self.condition_value.clear();
+            }
+            RuleField::ConditionValue => self.condition_value = value,
+            RuleField::Action => {
+                self.action = value;
+                self.action_value.clear();
+            }
+            RuleField::ActionValue => self.action_value = value,
+        }
+    }
+
+    pub fn condition_value(&self) -> Option<ValueKind> {
+        match self.condition.as_str() {
+            "has_tag" | "lacks_tag" => Some(ValueKind::Tag),
+            "priority_at_least" => Some(ValueKind::Priority),
+            _ => None,
+        }
+    }
+
+    pub fn action_value(&self) -> Option<ValueKind> {
+        match self.action.as_str() {
+            "add_tag" | "remove_tag" => Some(ValueKind::Tag),
+            "set_priority" => Some(ValueKind::Priority),
+            "notify" => Some(ValueKind::Text("Message")),
+            "webhook" => Some(ValueKind::Text("https://…")),
+            _ => None,
+        }
+    }
+
+    /// The rule, or what's missing from the form. The backend checks the
+    /// rest when it's saved.
+    pub fn build(&self) -> Result<AutomationRule, String> {
+        let trigger = match self.trigger.as_str() {
+            "created" => RuleTrigger::Created,
+            "reopened" => RuleTrigger::Reopened,
+            "snooze_ended" => RuleTrigger::SnoozeEnded,
+            "due_passed" => RuleTrigger::DuePassed,
+            "stale" => RuleTrigger::Stale {
+                days: self.days.trim().parse().map_err(|_| "Enter a number of days.".to_string())?,
+            },
+            _ => RuleTrigger::Completed,
+        };
+
+        let value = self.condition_value.trim().to_string();
+        let condition = match self.condition.as_str() {
+            "has_tag" => Some(RuleCondition::HasTag { tag: value }),
+            "lacks_tag" => Some(RuleCondition::LacksTag { tag: value }),
+            "priority_at_least" => Some(RuleCondition::PriorityAtLeast { priority: priority(&value) }),
+            _ => None,
+        };
+
+        let value = self.action_value.trim().to_string();
+        let action = match self.action.as_str() {
+            "remove_tag" => RuleAction::RemoveTag { tag: value },
+            "set_priority" => RuleAction::SetPriority { priority: priority(&value) },
+            "clear_snooze" => RuleAction::ClearSnooze,
+            "notify" => RuleAction::Notify { message: value },
+            "webhook" => RuleAction::Webhook { url: value },
+            _ => RuleAction::AddTag { tag: value },
+        };
+
+        Ok(AutomationRule {
+            id: Uuid::new_v4(),
+            name: self.name.trim().to_string(),
+            enabled: true,
+            trigger,
+            conditions: condition.into_iter().collect(),
+            actions: vec![action],
+        })
+    }
+}
+
+/// A priority select left untouched shows the first option.
+fn priority(name: &str) -> Priority {
+    Priority::from_name(name).unwrap_or(Priority::ALL[0])
+}
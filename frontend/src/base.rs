@@ -0,0 +1,41 @@
+//! The path the app is served under, `BASE_PATH` on the server, read from
+//! the `<base>` element the server puts in the page. Every absolute path the
+//! app requests, links to or shows in the address bar goes through [`url`];
+//! routes are matched on what [`route`] leaves of the address.
+
+use std::cell::OnceCell;
+use web_sys::window;
+
+thread_local! {
+    static PATH: OnceCell<String> = const { OnceCell::new() };
+}
+
+/// `/todo` behind a reverse proxy, empty at the root.
+fn path() -> String {
+    PATH.with(|path| {
+        path.get_or_init(|| {
+            window()
+                .and_then(|w| w.document())
+                .and_then(|document| document.query_selector("base").ok().flatten())
+                .and_then(|base| base.get_attribute("href"))
+                .map(|href| href.trim_end_matches('/').to_string())
+                .unwrap_or_default()
+        })
+        .clone()
+    })
+}
+
+/// `path`, like `/api/tasks`, under the base path.
+pub fn url(path: &str) -> String {
+    format!("{}{}", self::path(), path)
+}
+
+/// The app's own route in `pathname`, like `/my-day` for `/todo/my-day`.
+pub fn route(pathname: &str) -> String {
+    let base = path();
+    match pathname.strip_prefix(&base) {
+        Some(route) if route.is_empty() => "/".to_string(),
+        Some(route) if route.starts_with('/') => route.to_string(),
+        _ => pathname.to_string(),
+    }
+}
@@ -0,0 +1,129 @@
+//! Async work that ends in a message.
+//!
+//! Most commands await a request and turn its result into a message, with
+//! `Msg::Error` for a failure so it shows as a toast. [`run`] does that, and
+//! [`Job`] adds a time limit, another message for failures, or a [`Token`]
+//! that drops the result when the work has been superseded.
+
+use sauron::Cmd;
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    future::Future,
+    pin::{pin, Pin},
+    rc::Rc,
+    task::Poll,
+};
+
+use crate::Msg;
+
+/// Runs `task`, making its value a message with `done`.
+pub fn run<T: 'static>(
+    task: impl Future<Output = Result<T, String>> + 'static,
+    done: impl FnOnce(T) -> Msg + 'static,
+) -> Cmd<Msg> {
+    Job::new(task).then(done)
+}
+
+pub struct Job<T> {
+    task: Pin<Box<dyn Future<Output = Result<T, String>>>>,
+    timeout_ms: Option<i32>,
+    token: Option<Token>,
+    failed: Box<dyn FnOnce(String) -> Msg>,
+}
+
+impl<T: 'static> Job<T> {
+    pub fn new(task: impl Future<Output = Result<T, String>> + 'static) -> Self {
+        Self {
+            task: Box::pin(task),
+            timeout_ms: None,
+            token: None,
+            failed: Box::new(Msg::Error),
+        }
+    }
+
+    /// Fails if `task` hasn't finished after `ms`.
+    pub fn timeout(mut self, ms: i32) -> Self {
+        self.timeout_ms = Some(ms);
+        self
+    }
+
+    /// Ends in `Msg::Cancelled` if `token` is cancelled before `task`
+    /// finishes. The work itself still runs to the end.
+    pub fn cancel_with(mut self, token: Token) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    /// The message for a failure, instead of an error toast.
+    pub fn or_else(mut self, failed: impl FnOnce(String) -> Msg + 'static) -> Self {
+        self.failed = Box::new(failed);
+        self
+    }
+
+    pub fn then(self, done: impl FnOnce(T) -> Msg + 'static) -> Cmd<Msg> {
+        let Job { task, timeout_ms, token, failed } = self;
+        Cmd::new(async move {
+            let result = match timeout_ms {
+                Some(ms) => timeout(ms, task).await.unwrap_or_else(|| Err("The request took too long. Please try again.".to_string())),
+                None => task.await,
+            };
+            if token.is_some_and(|token| token.is_cancelled()) {
+                return Msg::Cancelled;
+            }
+            match result {
+                Ok(value) => done(value),
+                Err(e) => failed(e),
+            }
+        })
+    }
+}
+
+/// `task`'s output, or `None` if it takes longer than `ms`.
+pub async fn timeout<T>(ms: i32, task: impl Future<Output = T>) -> Option<T> {
+    let mut task = pin!(task);
+    let mut timer = pin!(crate::sleep(ms));
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(value) = task.as_mut().poll(cx) {
+            return Poll::Ready(Some(value));
+        }
+        timer.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+/// Marks a [`Job`]'s result as no longer wanted.
+#[derive(Debug, Clone, Default)]
+pub struct Token(Rc<Cell<bool>>);
+
+impl Token {
+    pub fn cancel(&self) {
+        self.0.set(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.get()
+    }
+}
+
+/// The tokens of work in flight, by what it's for, kept in the model so
+/// starting the work again or leaving it behind can cancel the last run.
+#[derive(Debug, Clone, Default)]
+pub struct Tokens(HashMap<&'static str, Token>);
+
+impl Tokens {
+    /// A token for a new run of `work`, cancelling the previous one.
+    pub fn fresh(&mut self, work: &'static str) -> Token {
+        let token = Token::default();
+        if let Some(previous) = self.0.insert(work, token.clone()) {
+            previous.cancel();
+        }
+        token
+    }
+
+    pub fn cancel(&mut self, work: &'static str) {
+        if let Some(token) = self.0.remove(work) {
+            token.cancel();
+        }
+    }
+}
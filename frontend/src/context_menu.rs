@@ -0,0 +1,67 @@
+//! Floating context menu shown at the pointer, used for secondary task actions
+//! so the card face only carries the common ones.
+
+use sauron::{html::attributes::*, prelude::*};
+
+pub struct MenuItem<MSG> {
+    pub icon: &'static str,
+    pub label: String,
+    pub msg: MSG,
+    /// Rendered in red, for destructive actions.
+    pub danger: bool,
+}
+
+impl<MSG> MenuItem<MSG> {
+    pub fn new(icon: &'static str, label: impl Into<String>, msg: MSG) -> Self {
+        Self {
+            icon,
+            label: label.into(),
+            msg,
+            danger: false,
+        }
+    }
+
+    pub fn danger(mut self) -> Self {
+        self.danger = true;
+        self
+    }
+}
+
+/// Renders the menu at viewport coordinates `(x, y)`. A transparent backdrop
+/// behind it sends `close` when the user clicks or right-clicks elsewhere.
+pub fn view<MSG: Clone + 'static>(x: i32, y: i32, items: Vec<MenuItem<MSG>>, close: MSG) -> Node<MSG> {
+    let close_on_context = close.clone();
+
+    div([], [
+        div([
+            class("fixed inset-0 z-40"),
+            on_click(move |_| close.clone()),
+            on("contextmenu", move |event| {
+                if let Some(event) = event.as_web() {
+                    event.prevent_default();
+                }
+                close_on_context.clone()
+            }),
+        ], []),
+        div(
+            [
+                class("fixed z-50 min-w-44 bg-ctp-surface0 border border-ctp-surface2 rounded-lg shadow-lg py-1"),
+                attr("style", format!("left: {}px; top: {}px", x, y)),
+            ],
+            items.into_iter().map(|item| {
+                let msg = item.msg;
+                button([
+                    on_click(move |_| msg.clone()),
+                    class(&format!(
+                        "flex items-center gap-2 w-full text-left px-3 py-2 text-sm whitespace-nowrap hover:bg-ctp-surface1 {}",
+                        if item.danger { "text-ctp-red" } else { "text-ctp-text" }
+                    )),
+                    r#type("button"),
+                ], [
+                    span([class("w-5 text-center")], [text(item.icon)]),
+                    text(&item.label),
+                ])
+            }).collect::<Vec<_>>(),
+        ),
+    ])
+}
@@ -0,0 +1,130 @@
+//! The task edit form.
+//!
+//! Either no task is being edited or one is, with what's typed into the form
+//! in its [`EditDraft`]; there are no leftover form contents without a task.
+//! Saving takes the draft out, so the same edit can't be saved twice, and a
+//! save the server refuses hands the draft back in its message for the form
+//! to reopen with.
+
+use shared::{validation::FieldError, Priority, Task};
+use uuid::Uuid;
+
+use crate::{geolocation, repeat_fields};
+
+#[derive(Debug, Clone, Default)]
+pub enum EditState {
+    #[default]
+    NotEditing,
+    Editing { id: Uuid, draft: EditDraft },
+}
+
+#[derive(Debug, Clone)]
+pub struct EditDraft {
+    pub title: String,
+    pub description: String,
+    pub priority: Priority,
+    /// Comma-separated tags as typed.
+    pub tags: String,
+    pub location_name: String,
+    pub location_coordinates: String,
+    pub location_radius: String,
+    /// `daily`, `weekly`, `monthly`, `cron`, or empty for no repeat.
+    pub repeat: String,
+    pub cron: String,
+    /// Version of the task when editing started, sent so the server can
+    /// refuse to overwrite changes made since.
+    pub version: u64,
+    /// The last save was refused because the task had changed meanwhile.
+    pub conflict: bool,
+    /// Problems with the form, shown under its inputs.
+    pub errors: Vec<FieldError>,
+    /// Changed since the form opened, and not saved.
+    pub dirty: bool,
+}
+
+impl EditDraft {
+    /// The form filled in with the task as it is.
+    pub fn from_task(task: &Task) -> Self {
+        let (repeat, cron) = repeat_fields(task.recurrence.as_ref());
+        Self {
+            title: task.title.clone(),
+            description: task.description.clone(),
+            priority: task.priority,
+            tags: task.tags.join(", "),
+            location_name: task.location.as_ref().map(|l| l.name.clone()).unwrap_or_default(),
+            location_coordinates: task
+                .location
+                .as_ref()
+                .map(|l| geolocation::format_coordinates(l.lat, l.lng))
+                .unwrap_or_default(),
+            location_radius: task.location.as_ref().map(|l| l.radius_m.to_string()).unwrap_or_default(),
+            repeat,
+            cron,
+            version: task.version,
+            conflict: false,
+            errors: Vec::new(),
+            dirty: false,
+        }
+    }
+}
+
+impl EditState {
+    /// The task being edited.
+    pub fn editing(&self) -> Option<Uuid> {
+        match self {
+            EditState::NotEditing => None,
+            EditState::Editing { id, .. } => Some(*id),
+        }
+    }
+
+    /// The draft, if `id` is the task being edited.
+    pub fn draft(&self, id: Uuid) -> Option<&EditDraft> {
+        match self {
+            EditState::Editing { id: editing, draft } if *editing == id => Some(draft),
+            _ => None,
+        }
+    }
+
+    pub fn draft_mut(&mut self) -> Option<&mut EditDraft> {
+        match self {
+            EditState::NotEditing => None,
+            EditState::Editing { draft, .. } => Some(draft),
+        }
+    }
+
+    /// Applies a change typed into the form, marking it unsaved.
+    pub fn change(&mut self, change: impl FnOnce(&mut EditDraft)) {
+        if let Some(draft) = self.draft_mut() {
+            change(draft);
+            draft.dirty = true;
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        matches!(self, EditState::Editing { draft, .. } if draft.dirty)
+    }
+
+    /// Closes the form of `id`, returning its draft, if it's the one open.
+    pub fn take(&mut self, id: Uuid) -> Option<EditDraft> {
+        match std::mem::take(self) {
+            EditState::Editing { id: editing, draft } if editing == id => Some(draft),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+
+    /// Reopens the form of `id` with a draft whose save was refused, unless
+    /// another edit was started meanwhile. Returns whether it was reopened.
+    pub fn reopen(&mut self, id: Uuid, draft: EditDraft) -> bool {
+        if self.editing().is_some() {
+            return false;
+        }
+        *self = EditState::Editing {
+            id,
+            draft: EditDraft { dirty: true, ..draft },
+        };
+        true
+    }
+}
@@ -0,0 +1,71 @@
+//! Browser geolocation and the text form of a task location.
+//!
+//! Locations are entered as plain text (`lat, lng`) rather than on a map, so
+//! parsing and formatting live here next to the position lookup.
+
+use shared::Location;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Position;
+
+/// Asks the browser for the current position. The first call shows the
+/// permission prompt; a refusal comes back as an error.
+pub async fn current_position() -> Result<(f64, f64), String> {
+    let geolocation = web_sys::window()
+        .ok_or("No window")?
+        .navigator()
+        .geolocation()
+        .map_err(|_| "Geolocation is not available in this browser")?;
+
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        if geolocation
+            .get_current_position_with_error_callback(&resolve, Some(&reject))
+            .is_err()
+        {
+            let _ = reject.call0(&JsValue::NULL);
+        }
+    });
+
+    let position: Position = JsFuture::from(promise)
+        .await
+        .map_err(|_| "Location permission was denied or the position is unavailable")?
+        .unchecked_into();
+    let coords = position.coords();
+    Ok((coords.latitude(), coords.longitude()))
+}
+
+pub fn format_coordinates(lat: f64, lng: f64) -> String {
+    format!("{:.5}, {:.5}", lat, lng)
+}
+
+/// Builds a location from the edit form fields. Blank name and coordinates
+/// mean "no location"; a blank radius uses the default.
+pub fn parse_location(name: &str, coordinates: &str, radius: &str) -> Result<Option<Location>, String> {
+    let (name, coordinates, radius) = (name.trim(), coordinates.trim(), radius.trim());
+    if name.is_empty() && coordinates.is_empty() {
+        return Ok(None);
+    }
+
+    let (lat, lng) = coordinates
+        .split_once(',')
+        .and_then(|(lat, lng)| Some((lat.trim().parse::<f64>().ok()?, lng.trim().parse::<f64>().ok()?)))
+        .filter(|(lat, lng)| (-90.0..=90.0).contains(lat) && (-180.0..=180.0).contains(lng))
+        .ok_or("Coordinates must look like \"52.52, 13.405\"")?;
+
+    let radius_m = if radius.is_empty() {
+        Location::DEFAULT_RADIUS_M
+    } else {
+        radius
+            .parse::<f64>()
+            .ok()
+            .filter(|r| r.is_finite() && *r > 0.0)
+            .ok_or("Radius must be a positive number of metres")?
+    };
+
+    Ok(Some(Location {
+        name: if name.is_empty() { format_coordinates(lat, lng) } else { name.to_string() },
+        lat,
+        lng,
+        radius_m,
+    }))
+}
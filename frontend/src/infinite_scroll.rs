@@ -0,0 +1,58 @@
+//! Infinite scroll via `IntersectionObserver`.
+//!
+//! A list renders a sentinel row after its last item while more pages
+//! remain. [`sentinel_visible`] resolves once that row comes within
+//! [`ROOT_MARGIN`] of the viewport, so the caller can turn it into the
+//! "load next page" message.
+
+use std::{cell::RefCell, rc::Rc};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IntersectionObserver, IntersectionObserverEntry, IntersectionObserverInit};
+
+/// Start loading a little before the sentinel actually scrolls into view.
+const ROOT_MARGIN: &str = "300px";
+
+/// Waits until the element with `id` nears the viewport. Returns `false` if
+/// it isn't in the document, e.g. because the user navigated away.
+pub async fn sentinel_visible(id: &str) -> bool {
+    // The sentinel is rendered by the view that follows the current update
+    crate::sleep(0).await;
+
+    let Some(sentinel) = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(id))
+    else {
+        return false;
+    };
+
+    let resolve: Rc<RefCell<Option<js_sys::Function>>> = Rc::new(RefCell::new(None));
+    let callback = Closure::wrap(Box::new({
+        let resolve = resolve.clone();
+        move |entries: js_sys::Array, observer: IntersectionObserver| {
+            let intersecting = entries
+                .iter()
+                .any(|entry| entry.unchecked_into::<IntersectionObserverEntry>().is_intersecting());
+            if intersecting {
+                observer.disconnect();
+                if let Some(resolve) = resolve.borrow_mut().take() {
+                    let _ = resolve.call0(&JsValue::NULL);
+                }
+            }
+        }
+    }) as Box<dyn FnMut(js_sys::Array, IntersectionObserver)>);
+
+    let options = IntersectionObserverInit::new();
+    options.set_root_margin(ROOT_MARGIN);
+    let Ok(observer) = IntersectionObserver::new_with_options(callback.as_ref().unchecked_ref(), &options) else {
+        return false;
+    };
+
+    let promise = js_sys::Promise::new(&mut |resolve_fn, _reject| {
+        *resolve.borrow_mut() = Some(resolve_fn);
+    });
+    observer.observe(&sentinel);
+
+    // `callback` stays alive until the sentinel has been seen
+    JsFuture::from(promise).await.is_ok()
+}
@@ -1,16 +1,213 @@
+mod api;
+mod automations;
+mod base;
+mod cmd;
+mod context_menu;
+mod editing;
+mod geolocation;
+mod infinite_scroll;
+mod live;
+mod local_store;
+mod memo;
+mod notifications;
+mod outbox;
+mod perf;
+mod playground;
+mod preferences;
+mod prefetch;
+mod reporting;
+mod share;
+mod shell;
+#[cfg(test)]
+mod snapshots;
+mod sync;
+mod tour;
+mod triage;
+mod unsaved;
+mod uploads;
+
 use sauron::{
     html::{attributes, attributes::*, *},
     prelude::*,
 };
-use shared::{CreateTaskRequest, Task, UpdateTaskRequest};
+use shared::{
+    automation::{AutomationRule, AutomationSettings, RuleExecution, SetAutomationRulesRequest}, crdt::{Stamp, TaskEdit}, experiments, recurrence::RecurrenceRule, validation::{self, FieldError}, flags, ActivityEvent, AdminOverview, Announcement, BulkOperation, BulkRequest, BulkResponse, CreateAnnouncementRequest, ActivityKind, Attachment, BuildInfo, DayPlan, RollOverRequest, SavePlanRequest, ClientErrorKind, ConfigEntry, ClientErrorReport, Comment, CreateCommentRequest, CreateTaskRequest, ExperimentAssignments,
+    ExperimentResults, ExposureRequest, FeatureFlags, Health, JobHealth, ServiceStatus, ContentFilterSettings, FilterMode, SetFilterModeRequest, ListResponse, LogEntry, LogLevel, AnalyticsSummary, ImportStatus, ImportSummary, Priority, TagStats, Task, TaskChanges, TaskEvent, UpdateTaskRequest, InitialTasks, TASK_PAGE_SIZE, TRASH_TTL_DAYS,
+};
+use automations::{RuleDraft, RuleField, ValueKind};
+use editing::{EditDraft, EditState};
+use notifications::{Severity, Toast};
+use outbox::Mutation;
+use preferences::{ConfirmAction, ConfirmPreferences, Theme};
+use prefetch::Prefetched;
+use serde::de::IgnoredAny;
 use uuid::Uuid;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{console, window, Request, RequestInit, Response};
 
+/// How often to ask the backend whether a new version has been deployed.
+const VERSION_CHECK_INTERVAL_MS: i32 = 5 * 60 * 1000;
+
+/// How often upload progress bars are redrawn while uploads are running.
+const UPLOAD_PROGRESS_INTERVAL_MS: i32 = 200;
+
+/// How often the Admin page re-fetches backend logs while it is open.
+const LOG_POLL_INTERVAL_MS: i32 = 5000;
+
+/// How often to check whether the backend is back while it is unreachable.
+const BACKEND_PROBE_INTERVAL_MS: i32 = 10_000;
+/// How long a probe waits for an answer before counting as a failure.
+const BACKEND_PROBE_TIMEOUT_MS: i32 = 15_000;
+
+/// Rows of an import preview shown before the rest are summed up.
+const IMPORT_PREVIEW_ROWS: usize = 100;
+
+/// Snooze presets offered in the task context menu and as due dates in
+/// triage.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnoozePreset {
+    OneHour,
+    Tomorrow,
+    NextWeek,
+}
+
+impl SnoozePreset {
+    fn label(self) -> &'static str {
+        match self {
+            SnoozePreset::OneHour => "Snooze 1 hour",
+            SnoozePreset::Tomorrow => "Snooze until tomorrow",
+            SnoozePreset::NextWeek => "Snooze until next week",
+        }
+    }
+
+    /// The same time as a due date, for triage.
+    fn due_label(self) -> &'static str {
+        match self {
+            SnoozePreset::OneHour => "Due in 1 hour",
+            SnoozePreset::Tomorrow => "Due tomorrow",
+            SnoozePreset::NextWeek => "Due next week",
+        }
+    }
+
+    /// Unix time (seconds) the task should come back; mornings are 9:00 local.
+    fn wake_time(self) -> u64 {
+        let date = js_sys::Date::new_0();
+        match self {
+            SnoozePreset::OneHour => return now_secs() + 60 * 60,
+            SnoozePreset::Tomorrow => {
+                date.set_date(date.get_date() + 1);
+            }
+            SnoozePreset::NextWeek => {
+                // Next Monday
+                let days_ahead = (8 - date.get_day()) % 7;
+                date.set_date(date.get_date() + if days_ahead == 0 { 7 } else { days_ahead });
+            }
+        }
+        date.set_hours(9);
+        date.set_minutes(0);
+        date.set_seconds(0);
+        (date.get_time() / 1000.0) as u64
+    }
+}
+
+/// Order of the task list. Pinned tasks stay on top whichever is picked.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TaskSort {
+    Newest,
+    /// The order the backend lists tasks in.
+    #[default]
+    Oldest,
+    RecentlyUpdated,
+}
+
+impl TaskSort {
+    const ALL: [TaskSort; 3] = [TaskSort::Newest, TaskSort::Oldest, TaskSort::RecentlyUpdated];
+
+    fn name(self) -> &'static str {
+        match self {
+            TaskSort::Newest => "newest",
+            TaskSort::Oldest => "oldest",
+            TaskSort::RecentlyUpdated => "updated",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|sort| sort.name() == name)
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            TaskSort::Newest => "Newest first",
+            TaskSort::Oldest => "Oldest first",
+            TaskSort::RecentlyUpdated => "Recently updated",
+        }
+    }
+
+    fn sort(self, tasks: &mut [&Task]) {
+        match self {
+            TaskSort::Newest => tasks.sort_by_key(|t| std::cmp::Reverse(t.created_at)),
+            TaskSort::Oldest => tasks.sort_by_key(|t| t.created_at),
+            TaskSort::RecentlyUpdated => tasks.sort_by_key(|t| std::cmp::Reverse(t.updated_at.or(t.created_at))),
+        }
+    }
+}
+
+/// One-click filters above the task list, each a `GET /api/tasks/recent`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuickFilter {
+    CompletedToday,
+    AddedThisWeek,
+    RecentlyDeleted,
+}
+
+impl QuickFilter {
+    const ALL: [QuickFilter; 3] = [QuickFilter::CompletedToday, QuickFilter::AddedThisWeek, QuickFilter::RecentlyDeleted];
+
+    fn label(self) -> &'static str {
+        match self {
+            QuickFilter::CompletedToday => "Completed today",
+            QuickFilter::AddedThisWeek => "Added this week",
+            QuickFilter::RecentlyDeleted => "Recently deleted",
+        }
+    }
+
+    /// The `by` parameter.
+    fn timestamp(self) -> &'static str {
+        match self {
+            QuickFilter::CompletedToday => "completed",
+            QuickFilter::AddedThisWeek => "created",
+            QuickFilter::RecentlyDeleted => "deleted",
+        }
+    }
+
+    /// Unix time (seconds) the filter starts at: local midnight, the last
+    /// Monday, or a week ago.
+    fn since(self) -> u64 {
+        let date = js_sys::Date::new_0();
+        let days_back = match self {
+            QuickFilter::CompletedToday => 0,
+            QuickFilter::AddedThisWeek => (date.get_day() + 6) % 7,
+            QuickFilter::RecentlyDeleted => return now_secs().saturating_sub(7 * 86_400),
+        };
+        date.set_hours(0);
+        date.set_minutes(0);
+        date.set_seconds(0);
+        date.set_milliseconds(0);
+        ((date.get_time() / 1000.0) as u64).saturating_sub(days_back as u64 * 86_400)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Page {
     Dashboard,
     Tasks,
+    MyDay,
+    Activity,
+    Trash,
+    Archive,
+    Settings,
+    Admin,
 }
 
 impl Page {
@@ -18,6 +215,12 @@ impl Page {
         match self {
             Page::Tasks => "/",
             Page::Dashboard => "/dashboard",
+            Page::MyDay => "/my-day",
+            Page::Activity => "/activity",
+            Page::Trash => "/trash",
+            Page::Archive => "/archive",
+            Page::Settings => "/settings",
+            Page::Admin => "/admin",
         }
     }
     
@@ -25,6 +228,14 @@ impl Page {
         match path {
             "/" => Page::Tasks,
             "/dashboard" => Page::Dashboard,
+            "/my-day" => Page::MyDay,
+            "/activity" => Page::Activity,
+            "/trash" => Page::Trash,
+            "/archive" => Page::Archive,
+            "/settings" => Page::Settings,
+            "/admin" => Page::Admin,
+            // Share target intake; `init` prefills the create form from it
+            share::PATH => Page::Tasks,
             _ => Page::Tasks, // Default fallback
         }
     }
@@ -32,33 +243,308 @@ impl Page {
 
 #[derive(Debug, Clone)]
 pub enum Msg {
-    // Navigation
+    Nav(NavMsg),
+    Task(TaskMsg),
+    Form(FormMsg),
+    Settings(SettingsMsg),
+    // Nearby tasks
+    FindNearby,
+    NearbyLoaded(Vec<Task>),
+    LocateFailed(String),
+    // Attachments
+    SetDropTarget(Option<Uuid>),
+    UploadFiles(Uuid, Vec<web_sys::File>),
+    UploadTick,
+    CancelUpload(u32),
+    UploadFinished(u32, Result<Attachment, String>),
+    DeleteAttachment(Uuid, Uuid),
+    AttachmentDeleted(Uuid, Uuid, Result<(), String>),
+    // Comments
+    /// Shows or hides a task's comments, loading them when shown.
+    ToggleComments(Uuid),
+    CommentsLoaded(Uuid, Result<Vec<Comment>, String>),
+    SetCommentDraft(Uuid, String),
+    PostComment(Uuid),
+    CommentPosted(Result<Comment, String>),
+    // Multi-select
+    ToggleSelectionMode,
+    /// Click on a card while selecting: `(id, shift, ctrl_or_cmd)`.
+    SelectTask(Uuid, bool, bool),
+    ClearSelection,
+    CompleteSelected,
+    DeleteSelected,
+    SetBulkTag(String),
+    /// Adds (`true`) or removes the typed tag on every selected task.
+    RetagSelected(bool),
+    BulkApplied(BulkResponse),
+    /// A bulk request failed and changed nothing; clears the tasks' spinners.
+    BulkFailed(Vec<Uuid>, String),
+    // Triage
+    StartTriage,
+    /// A key pressed during the triage session with this id.
+    TriageKey(u32, String),
+    SetTriageTag(String),
+    TriageApplied(Task),
+    EndTriage,
+    // API playground
+    LoadApiEndpoints,
+    LoadStatus,
+    StatusLoaded(Result<ServiceStatus, String>),
+    ApiEndpointsLoaded(Vec<playground::Endpoint>),
+    SelectEndpoint(Option<usize>),
+    SetPlaygroundParam(String, String),
+    SetPlaygroundBody(String),
+    SendPlaygroundRequest,
+    PlaygroundReplied(Result<playground::Reply, String>),
+    // Onboarding tour
+    StartTour,
+    ShowTourStep(tour::Step),
+    /// Finishes or skips the tour; it won't start by itself again.
+    EndTour,
+    TourStepShown,
+    // Context menu
+    OpenTaskMenu(Uuid, i32, i32),
+    CloseTaskMenu,
+    DuplicateTask(Uuid),
+    TogglePin(Uuid),
+    TaskPinned(Task),
+    SnoozeTask(Uuid, SnoozePreset),
+    TaskSnoozed(Task),
+    SnoozeExpired,
+    // Activity log
+    LoadActivity,
+    LoadMoreActivity(String),
+    ActivityLoaded(ListResponse<ActivityEvent>),
+    /// An infinite-scroll sentinel left the page before it was reached.
+    SentinelDetached,
+    // Trash
+    LoadTrash,
+    TrashLoaded(Vec<Task>),
+    RestoreTask(Uuid),
+    TaskRestored(Task),
+    /// Delete a trashed task for good.
+    PurgeTask(Uuid),
+    TaskPurged(Uuid),
+    // Archive
+    LoadArchive,
+    ArchiveLoaded(Vec<Task>),
+    /// Move an archived task back into the task list.
+    UnarchiveTask(Uuid),
+    TaskUnarchived(Task),
+    // Delta sync
+    /// The tab was shown again or the network came back.
+    SyncChanges,
+    ChangesSynced(TaskChanges, Vec<Task>),
+    SyncFailed(String),
+    // Live updates
+    LiveConnected,
+    LiveEvent(TaskEvent),
+    /// The socket closed or couldn't open; retried after a delay.
+    LiveDisconnected,
+    /// The offline copy from the last session, read on startup.
+    LocalStoreLoaded(Option<local_store::Snapshot>),
+    /// The browser went offline or came back online.
+    ConnectivityChanged,
+    /// Send the oldest change made offline, if there's a connection.
+    ReplayOutbox,
+    OutboxReplayed(outbox::Outcome),
+    /// Write the offline copy now that changes have settled.
+    SaveLocalStore,
+    // Analytics
+    LoadTagStats,
+    TagStatsLoaded(Vec<TagStats>),
+    LoadAnalytics,
+    SetReleaseFrom(String),
+    SetReleaseTo(String),
+    /// `None` when the summary isn't available, e.g. without Redis.
+    AnalyticsLoaded(Option<AnalyticsSummary>),
+    // My day
+    LoadPlan,
+    PlanLoaded(DayPlan),
+    AddToPlan(Uuid),
+    RemoveFromPlan(Uuid),
+    PlanSaved(DayPlan),
+    RollOverPlan,
+    PlanRolledOver(DayPlan),
+    CopyTaskLink(Uuid),
+    ShowTaskQr(Uuid),
+    CloseTaskQr,
+    CopyTaskMarkdown(Uuid),
+    TaskFocused,
+    ShowToast(Severity, String),
+    DismissToast(u32),
+    // Feature flags
+    LoadFlags,
+    FlagsLoaded(FeatureFlags),
+    // Experiments
+    LoadExperiments,
+    ExperimentsLoaded(ExperimentAssignments),
+    ExposureLogged,
+    LoadExperimentResults,
+    ExperimentResultsLoaded(Vec<ExperimentResults>),
+    // Announcements
+    LoadAnnouncements,
+    AnnouncementsLoaded(Vec<Announcement>),
+    /// Opening the "What's new" popover marks everything in it as read.
+    ToggleAnnouncements,
+    SetAnnouncementTitle(String),
+    SetAnnouncementBody(String),
+    PublishAnnouncement,
+    AnnouncementPublished(Announcement),
+    DeleteAnnouncement(Uuid),
+    AnnouncementDeleted(Uuid),
+    // Admin
+    SetAdminTokenDraft(String),
+    /// Signs in to the Admin page with the drafted token.
+    SignInAdmin,
+    SignOutAdmin,
+    LoadOverview,
+    OverviewLoaded(AdminOverview),
+    LoadClientErrors,
+    ClientErrorsLoaded(Vec<ClientErrorReport>),
+    LoadContentFilter,
+    ContentFilterLoaded(ContentFilterSettings),
+    /// Sets the mode globally (`None`) or for one workspace.
+    SetFilterMode(Option<String>, FilterMode),
+    SetFilterWorkspace(String),
+    LoadAutomations,
+    AutomationsLoaded(AutomationSettings),
+    AutomationLogLoaded(Vec<RuleExecution>),
+    /// Picks the workspace whose rules are edited; empty for the global ones.
+    SetAutomationWorkspace(String),
+    SetRuleField(RuleField, String),
+    AddAutomationRule,
+    ToggleAutomationRule(Uuid),
+    DeleteAutomationRule(Uuid),
+    LoadServerConfig,
+    ServerConfigLoaded(Vec<ConfigEntry>),
+    LoadLogs,
+    LogsLoaded(Vec<LogEntry>),
+    SetLogLevel(LogLevel),
+    /// Time to tail the logs again, if the Admin page is still open.
+    PollLogs,
+    // Version
+    CheckVersion,
+    VersionLoaded(BuildInfo),
+    ReloadApp,
+    // Backend health
+    /// Checks whether the backend answers again after repeated failures.
+    ProbeBackend,
+    BackendRecovered,
+    // Performance overlay
+    TogglePerfOverlay,
+    /// Time to refresh the overlay opened `n`th, if it's still open.
+    PerfTick(u32),
+    ResetPerfSlowest,
+    /// A [`cmd::Job`] finished after its token was cancelled.
+    Cancelled,
+    Error(String),
+}
+
+/// Moving between pages.
+#[derive(Debug, Clone)]
+pub enum NavMsg {
     NavigateTo(Page),
     RouteChanged(String),
-    
-    // Tasks
+    Prefetch(Page),
+    PrefetchDone(Page, Option<Prefetched>),
+}
+
+/// Loading the task list and changes made straight from it.
+#[derive(Debug, Clone)]
+pub enum TaskMsg {
+    /// First page of tasks, for the Tasks page.
     LoadTasks,
-    TasksLoaded(Vec<Task>),
-    SetNewTaskTitle(String),
-    SetNewTaskDescription(String),
-    CreateTask,
-    TaskCreated(Task),
+    /// Every task, for views that summarise the whole list.
+    LoadAllTasks,
+    LoadMoreTasks(u32),
+    /// A page of tasks, with the change number to sync from when it's the first.
+    TaskPageLoaded(ListResponse<Task>, Option<u64>),
+    TasksLoaded(Vec<Task>, Option<u64>),
     ToggleTask(Uuid),
     TaskUpdated(Task),
     RevertTaskToggle(Uuid, bool),
+    /// Moves a task to the trash straight away, offering to undo it.
     DeleteTask(Uuid),
-    TaskDeleted(Uuid),
+    /// The server trashed these tasks; shows the toast that can undo it.
+    TasksTrashed(Vec<Uuid>, String),
+    /// Trashing failed; puts the tasks back in the list.
+    DeleteFailed(Vec<Task>, String),
+    /// "Undo" on the toast with this id.
+    UndoDelete(u32),
+    DeleteUndone(Vec<Task>),
+    ClearCompleted,
+    ToggleCompletedSection,
+    /// Shows the tasks a quick filter matches, or the whole list again.
+    SetQuickFilter(Option<QuickFilter>),
+    QuickFilterLoaded(Vec<Task>),
+    SetTaskSort(TaskSort),
+    SetTaskLoading(Uuid, bool),
+}
+
+/// The create and edit forms.
+#[derive(Debug, Clone)]
+pub enum FormMsg {
+    SetNewTaskTitle(String),
+    SetNewTaskDescription(String),
+    SetNewTaskPriority(Priority),
+    CreateTask,
+    TaskCreated(Task),
+    /// The server turned a create away as too fast or repetitive; carries
+    /// the message to show, seconds to wait, and the form to restore.
+    CreateThrottled(String, i32, Option<CreateTaskRequest>),
+    CreateResumed,
+    /// The server refused the task; carries its message, the fields it
+    /// objected to, and the form to restore.
+    CreateRejected(String, Vec<FieldError>, Option<CreateTaskRequest>),
     EditTask(Uuid),
+    /// The server refused the edit to the task over these fields; carries
+    /// the draft to reopen the form with.
+    EditInvalid(Uuid, Vec<FieldError>, EditDraft),
     SetEditTitle(String),
     SetEditDescription(String),
+    SetEditPriority(Priority),
+    SetEditTags(String),
+    SetEditLocationName(String),
+    SetEditRepeat(String),
+    SetEditCron(String),
+    SetEditLocationCoordinates(String),
+    SetEditLocationRadius(String),
+    UseCurrentLocation,
     SaveEdit(Uuid),
     TaskSaved(Task),
+    /// The task changed elsewhere while it was being edited; carries its
+    /// current state and the draft that was being saved.
+    EditConflict(Task, EditDraft),
     CancelEdit,
-    ClearCompleted,
-    ToggleCompletedSection,
-    // Task loading states
-    SetTaskLoading(Uuid, bool),
-    Error(String),
+}
+
+/// Preferences and importing, on the Settings page.
+#[derive(Debug, Clone)]
+pub enum SettingsMsg {
+    SetConfirmPreference(ConfirmAction, bool),
+    SetTheme(Theme),
+    /// Checks the first file picked on the Settings page, for a preview
+    /// before importing it.
+    ImportTasks(Vec<web_sys::File>),
+    ImportPreviewed(Result<ImportSummary, String>),
+    ConfirmImport,
+    CancelImport,
+    TasksImported(Result<ImportSummary, String>),
+}
+
+impl Msg {
+    /// The variant name for the performance overlay, e.g. `TasksLoaded`
+    /// rather than `Task`.
+    fn kind(&self) -> String {
+        match self {
+            Msg::Nav(msg) => perf::msg_kind(msg),
+            Msg::Task(msg) => perf::msg_kind(msg),
+            Msg::Form(msg) => perf::msg_kind(msg),
+            Msg::Settings(msg) => perf::msg_kind(msg),
+            msg => perf::msg_kind(msg),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -67,12 +553,146 @@ pub struct Model {
     tasks: Vec<Task>,
     new_task_title: String,
     new_task_description: String,
-    editing_task: Option<Uuid>,
-    edit_title: String,
-    edit_description: String,
+    new_task_priority: Priority,
+    /// Problems with the create form, shown under its inputs.
+    create_errors: Vec<FieldError>,
+    edit: EditState,
+    quick_filter: Option<QuickFilter>,
+    task_sort: TaskSort,
+    /// What the quick filter matched, once it has loaded.
+    quick_filter_tasks: Option<Vec<Task>>,
+    /// Tasks at the user's last looked-up position, once they asked for it.
+    nearby: Option<Vec<Task>>,
+    locating: bool,
     loading: bool,
+    /// Next page of tasks to fetch on scroll; `None` when all are loaded.
+    tasks_next_page: Option<u32>,
+    loading_more_tasks: bool,
+    /// Change number the loaded tasks are current as of.
+    sync_version: Option<u64>,
+    /// Whether a resync is already waiting for the tab to come back.
+    sync_pending: bool,
+    local_save_pending: bool,
     show_completed: bool,
     task_loading_states: std::collections::HashMap<Uuid, bool>, // Track loading state for individual tasks
+    uploads: Vec<uploads::Upload>,
+    /// Comments of the tasks whose comment section has been opened, oldest
+    /// first.
+    comments: std::collections::HashMap<Uuid, Vec<Comment>>,
+    open_comments: std::collections::HashSet<Uuid>,
+    comment_drafts: std::collections::HashMap<Uuid, String>,
+    importing: bool,
+    /// The file picked for import, until it's imported or dropped.
+    import_file: Option<web_sys::File>,
+    /// Dry-run report on `import_file`, shown for the user to confirm.
+    import_preview: Option<ImportSummary>,
+    /// Outcome of the last import, shown on the Settings page.
+    import_summary: Option<ImportSummary>,
+    next_upload_id: u32,
+    drop_target: Option<Uuid>,
+    confirm_preferences: ConfirmPreferences,
+    theme: Theme,
+    api_endpoints: Vec<playground::Endpoint>,
+    /// Index into `api_endpoints` of the endpoint being tried out.
+    playground_endpoint: Option<usize>,
+    /// Path and query parameter values by name.
+    playground_params: std::collections::BTreeMap<String, String>,
+    playground_body: String,
+    playground_sending: bool,
+    playground_reply: Option<Result<playground::Reply, String>>,
+    /// Current step of the onboarding tour, while it's running.
+    tour: Option<tour::Step>,
+    selecting: bool,
+    selected_tasks: std::collections::HashSet<Uuid>,
+    /// Last plainly clicked or Ctrl/Cmd-clicked task; shift-click ranges start here.
+    selection_anchor: Option<Uuid>,
+    /// Tag typed into the selection toolbar.
+    bulk_tag: String,
+    triage: Option<triage::Session>,
+    /// Id of the last triage session started.
+    triage_count: u32,
+    tag_stats: Vec<TagStats>,
+    analytics: Option<AnalyticsSummary>,
+    /// Release notes range, `YYYY-MM-DD`; empty leaves it to the backend.
+    release_from: String,
+    release_to: String,
+    prefetched: prefetch::Cache<Page>,
+    /// Pages whose data is being prefetched right now.
+    prefetching: Vec<Page>,
+    activity: Vec<ActivityEvent>,
+    /// Where the next activity page starts; `None` once the log is exhausted.
+    activity_cursor: Option<String>,
+    activity_loading: bool,
+    /// Deleted tasks, most recently deleted first.
+    trash: Vec<Task>,
+    trash_loading: bool,
+    /// Archived tasks, most recently archived first.
+    archive: Vec<Task>,
+    archive_loading: bool,
+    /// Today's "My day" plan, once loaded.
+    plan: Option<DayPlan>,
+    /// When the next snoozed task is due back, if a re-render is scheduled for it.
+    snooze_wake_at: Option<u64>,
+    /// Task whose context menu is open, with the viewport position to show it at.
+    open_task_menu: Option<(Uuid, i32, i32)>,
+    /// Task whose share-link QR code is shown.
+    qr_task: Option<Uuid>,
+    /// Task named in a deep link (`/?task=<id>`), highlighted once loaded.
+    focused_task: Option<Uuid>,
+    /// Oldest first.
+    toasts: Vec<notifications::Toast>,
+    /// Adding tasks is paused after the server asked us to slow down.
+    create_paused: bool,
+    next_toast_id: u32,
+    flags: FeatureFlags,
+    experiments: ExperimentAssignments,
+    experiment_results: Vec<ExperimentResults>,
+    /// Newest first.
+    announcements: Vec<Announcement>,
+    announcements_open: bool,
+    /// `published_at` of the newest announcement the user has opened.
+    announcements_seen: u64,
+    new_announcement_title: String,
+    new_announcement_body: String,
+    /// Whether an admin token is stored; the Admin page is hidden without one.
+    admin_signed_in: bool,
+    admin_token_draft: String,
+    client_errors: Vec<ClientErrorReport>,
+    logs: Vec<LogEntry>,
+    log_level: LogLevel,
+    log_poll_pending: bool,
+    server_config: Vec<ConfigEntry>,
+    overview: Option<AdminOverview>,
+    content_filter: Option<ContentFilterSettings>,
+    /// Workspace typed in to give it its own filter mode.
+    filter_workspace: String,
+    automations: Option<AutomationSettings>,
+    /// Workspace whose automation rules are shown; empty for the global ones.
+    automation_workspace: String,
+    rule_draft: RuleDraft,
+    /// Latest runs of the rules, newest first.
+    automation_log: Vec<RuleExecution>,
+    frontend_build: BuildInfo,
+    backend_build: Option<BuildInfo>,
+    /// From `GET /status`, for the badge at the foot of the Settings page.
+    service_status: Option<Result<ServiceStatus, String>>,
+    update_available: bool,
+    /// The backend keeps failing; shown as a banner instead of per-request errors.
+    backend_degraded: bool,
+    /// Changes made offline, oldest first; see [`outbox`].
+    outbox: Vec<Mutation>,
+    /// The oldest change in the outbox is on its way.
+    outbox_sending: bool,
+    /// Latest sample shown by the performance overlay, while it's open.
+    perf: Option<perf::Stats>,
+    /// Times the overlay has been opened, telling its refreshes apart.
+    perf_count: u32,
+    cards: memo::Cards,
+    /// Cancels superseded async work; see [`cmd::Tokens`].
+    jobs: cmd::Tokens,
+    /// The first page of tasks the server sent with the page, until the
+    /// app has decided whether to start from it; see [`shell`].
+    initial_tasks: Option<InitialTasks>,
 }
 
 impl Default for Model {
@@ -82,12 +702,102 @@ impl Default for Model {
             tasks: Vec::new(),
             new_task_title: String::new(),
             new_task_description: String::new(),
-            editing_task: None,
-            edit_title: String::new(),
-            edit_description: String::new(),
+            new_task_priority: Priority::default(),
+            create_errors: Vec::new(),
+            edit: EditState::NotEditing,
+            nearby: None,
+            locating: false,
             loading: false,
+            tasks_next_page: None,
+            loading_more_tasks: false,
+            sync_version: None,
+            sync_pending: false,
+            local_save_pending: false,
             show_completed: true,
             task_loading_states: std::collections::HashMap::new(),
+            uploads: Vec::new(),
+            comments: std::collections::HashMap::new(),
+            open_comments: std::collections::HashSet::new(),
+            comment_drafts: std::collections::HashMap::new(),
+            importing: false,
+            import_file: None,
+            import_preview: None,
+            import_summary: None,
+            next_upload_id: 0,
+            drop_target: None,
+            confirm_preferences: ConfirmPreferences::default(),
+            theme: Theme::default(),
+            api_endpoints: Vec::new(),
+            playground_endpoint: None,
+            playground_params: std::collections::BTreeMap::new(),
+            playground_body: String::new(),
+            playground_sending: false,
+            playground_reply: None,
+            tour: None,
+            selecting: false,
+            selected_tasks: std::collections::HashSet::new(),
+            selection_anchor: None,
+            bulk_tag: String::new(),
+            tag_stats: Vec::new(),
+            analytics: None,
+            release_from: String::new(),
+            release_to: String::new(),
+            prefetched: prefetch::Cache::default(),
+            prefetching: Vec::new(),
+            activity: Vec::new(),
+            activity_cursor: None,
+            activity_loading: false,
+            trash: Vec::new(),
+            trash_loading: false,
+            archive: Vec::new(),
+            archive_loading: false,
+            plan: None,
+            snooze_wake_at: None,
+            open_task_menu: None,
+            qr_task: None,
+            quick_filter: None,
+            task_sort: TaskSort::default(),
+            quick_filter_tasks: None,
+            triage: None,
+            triage_count: 0,
+            focused_task: None,
+            toasts: Vec::new(),
+            create_paused: false,
+            next_toast_id: 0,
+            flags: FeatureFlags::default(),
+            experiments: ExperimentAssignments::default(),
+            experiment_results: Vec::new(),
+            announcements: Vec::new(),
+            announcements_open: false,
+            announcements_seen: 0,
+            new_announcement_title: String::new(),
+            new_announcement_body: String::new(),
+            admin_signed_in: false,
+            admin_token_draft: String::new(),
+            client_errors: Vec::new(),
+            logs: Vec::new(),
+            log_level: LogLevel::default(),
+            log_poll_pending: false,
+            server_config: Vec::new(),
+            overview: None,
+            content_filter: None,
+            filter_workspace: String::new(),
+            automations: None,
+            automation_workspace: String::new(),
+            rule_draft: RuleDraft::default(),
+            automation_log: Vec::new(),
+            frontend_build: shared::build_info!(),
+            backend_build: None,
+            service_status: None,
+            update_available: false,
+            backend_degraded: false,
+            outbox: Vec::new(),
+            outbox_sending: false,
+            perf: None,
+            perf_count: 0,
+            cards: memo::Cards::default(),
+            jobs: cmd::Tokens::default(),
+            initial_tasks: None,
         }
     }
 }
@@ -96,445 +806,4039 @@ impl Application for Model {
     type MSG = Msg;
 
     fn init(&mut self) -> Cmd<Msg> {
+        let mut shared = None;
         // Initialize current page from URL
         if let Some(window) = window() {
             let location = window.location();
             if let Ok(pathname) = location.pathname() {
-                self.current_page = Page::from_path(&pathname);
+                let route = base::route(&pathname);
+                self.current_page = Page::from_path(&route);
+                if route == share::PATH {
+                    shared = share::intake();
+                    share::leave_intake_route();
+                }
             }
+            self.focused_task = location
+                .search()
+                .ok()
+                .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok())
+                .and_then(|params| params.get("task"))
+                .and_then(|id| id.parse().ok());
+        }
+        if let Some(shared) = &shared {
+            self.new_task_title = shared.title.clone();
+            self.new_task_description = shared.description.clone();
         }
         
+        self.initial_tasks = shell::take();
+        self.confirm_preferences = ConfirmPreferences::load();
+        self.theme = Theme::load();
+        self.theme.apply();
+        self.announcements_seen = preferences::load_announcements_seen();
+        self.admin_signed_in = preferences::load_admin_token().is_some();
+        if shared.is_none() && !preferences::load_tour_dismissed() && self.current_page == tour::Step::first().page() {
+            self.tour = Some(tour::Step::first());
+        }
+
         // Set up popstate listener for browser back/forward buttons
         setup_popstate_listener();
+        unsaved::install();
         
-        // Load tasks for dashboard stats, but don't show loading state
-        Cmd::new(async { Msg::LoadTasks })
+        let mut cmds = vec![
+            // Tasks load once we know whether there's an offline copy to start from
+            Cmd::new(async { Msg::LocalStoreLoaded(local_store::load().await) }),
+            Cmd::new(async { Msg::ConnectivityChanged }),
+            Cmd::new(async { Msg::LoadFlags }),
+            Cmd::new(async { Msg::LoadExperiments }),
+            Cmd::new(async { Msg::CheckVersion }),
+            Cmd::new(async { Msg::LoadAnnouncements }),
+            Cmd::new(connect_live()),
+            Cmd::new(async {
+                perf::next_toggle().await;
+                Msg::TogglePerfOverlay
+            }),
+        ];
+        if self.current_page == Page::Admin {
+            cmds.push(self.load_admin_data());
+        }
+        if self.current_page == Page::MyDay {
+            cmds.push(Cmd::new(async { Msg::LoadPlan }));
+        }
+        if self.current_page == Page::Dashboard {
+            cmds.push(Cmd::new(async { Msg::LoadTagStats }));
+            cmds.push(Cmd::new(async { Msg::LoadAnalytics }));
+        }
+        if self.current_page == Page::Activity {
+            cmds.push(Cmd::new(async { Msg::LoadActivity }));
+        }
+        if self.current_page == Page::Trash {
+            cmds.push(Cmd::new(async { Msg::LoadTrash }));
+        }
+        if self.current_page == Page::Archive {
+            cmds.push(Cmd::new(async { Msg::LoadArchive }));
+        }
+        if self.current_page == Page::Settings {
+            cmds.push(Cmd::new(async { Msg::LoadApiEndpoints }));
+            cmds.push(Cmd::new(async { Msg::LoadStatus }));
+        }
+        if shared.is_some() {
+            cmds.push(Cmd::new(async {
+                Msg::ShowToast(Severity::Info, "Shared content is in the form below. Review it and add the task.".to_string())
+            }));
+        }
+        Cmd::batch(cmds)
     }
 
     fn update(&mut self, msg: Msg) -> Cmd<Msg> {
+        let started = perf::now();
+        let kind = self.perf.is_some().then(|| msg.kind());
+        reporting::record_msg(&msg);
+
+        let save = match msg {
+            Msg::SaveLocalStore => Cmd::none(),
+            _ => self.schedule_local_save(),
+        };
+        let cmd = self.handle(msg);
+        unsaved::set(self.edit.is_dirty());
+        perf::updated(started, kind);
+        Cmd::batch([cmd, save])
+    }
+
+    fn view(&self) -> Node<Msg> {
+        let started = perf::now();
+        let node = div(
+            [class("min-h-screen bg-ctp-base text-ctp-text")],
+            [
+                self.view_header(),
+                self.view_update_banner(),
+                self.view_degraded_banner(),
+                div(
+                    [class("max-w-6xl mx-auto px-6 py-8")],
+                    [
+                        match self.current_page {
+                            Page::Dashboard => self.view_dashboard(),
+                            Page::Tasks => self.view_tasks_page(),
+                            Page::MyDay => self.view_my_day_page(),
+                            Page::Activity => self.view_activity_page(),
+                            Page::Trash => self.view_trash_page(),
+                            Page::Archive => self.view_archive_page(),
+                            Page::Settings => self.view_settings_page(),
+                            Page::Admin => self.view_admin_page(),
+                        }
+                    ]
+                ),
+                self.view_task_context_menu(),
+                self.view_task_qr(),
+                self.view_triage(),
+                self.view_tour(),
+                self.view_toasts(),
+                self.view_perf_overlay(),
+            ],
+        );
+        perf::rendered(started);
+        node
+    }
+}
+
+impl Model {
+    /// Messages of the domain enums go to their own `handle_*`; the rest are
+    /// handled here.
+    fn handle(&mut self, msg: Msg) -> Cmd<Msg> {
         match msg {
-            Msg::NavigateTo(page) => {
-                self.current_page = page.clone();
-                
-                // Update browser URL without page reload
-                if let Some(history) = window().and_then(|w| w.history().ok()) {
-                    let _ = history.push_state_with_url(
-                        &wasm_bindgen::JsValue::NULL,
-                        "",
-                        Some(page.to_path())
-                    );
+            Msg::Nav(msg) => self.handle_nav(msg),
+            Msg::Task(msg) => self.handle_task(msg),
+            Msg::Form(msg) => self.handle_form(msg),
+            Msg::Settings(msg) => self.handle_settings(msg),
+            Msg::SetDropTarget(target) => {
+                self.drop_target = target;
+                Cmd::none()
+            }
+            Msg::UploadFiles(task_id, files) => {
+                self.drop_target = None;
+                let was_idle = self.uploads.is_empty();
+
+                let mut cmds = Vec::new();
+                for file in files {
+                    self.next_upload_id += 1;
+                    match uploads::Upload::start(self.next_upload_id, task_id, &file) {
+                        Ok(upload) => {
+                            self.uploads.push(upload.clone());
+                            cmds.push(Cmd::new(async move {
+                                let result = upload.finish().await;
+                                Msg::UploadFinished(upload.id, result)
+                            }));
+                        }
+                        Err(e) => cmds.push(Cmd::new(async move { Msg::Error(e) })),
+                    }
                 }
-                
-                // Load tasks when navigating to tasks page
-                if self.current_page == Page::Tasks && self.tasks.is_empty() {
-                    Cmd::new(async { Msg::LoadTasks })
-                } else {
+
+                if was_idle && !self.uploads.is_empty() {
+                    cmds.push(Cmd::new(async { Msg::UploadTick }));
+                }
+                Cmd::batch(cmds)
+            }
+            Msg::UploadTick => {
+                // Re-render progress bars until every upload has finished
+                if self.uploads.is_empty() {
                     Cmd::none()
+                } else {
+                    Cmd::new(async {
+                        sleep(UPLOAD_PROGRESS_INTERVAL_MS).await;
+                        Msg::UploadTick
+                    })
                 }
             }
-            Msg::RouteChanged(path) => {
-                let new_page = Page::from_path(&path);
-                if new_page != self.current_page {
-                    self.current_page = new_page;
-                    // Load tasks if navigating to tasks page
-                    if self.current_page == Page::Tasks && self.tasks.is_empty() {
-                        Cmd::new(async { Msg::LoadTasks })
-                    } else {
+            Msg::CancelUpload(upload_id) => {
+                if let Some(upload) = self.uploads.iter().find(|u| u.id == upload_id) {
+                    upload.cancel();
+                }
+                Cmd::none()
+            }
+            Msg::UploadFinished(upload_id, result) => {
+                let Some(index) = self.uploads.iter().position(|u| u.id == upload_id) else {
+                    return Cmd::none();
+                };
+                let upload = self.uploads.remove(index);
+
+                match result {
+                    Ok(attachment) => {
+                        if let Some(task) = self.tasks.iter_mut().find(|t| t.id == upload.task_id) {
+                            task.attachments.push(attachment);
+                        }
                         Cmd::none()
                     }
-                } else {
-                    Cmd::none()
+                    Err(e) if e == uploads::CANCELLED => Cmd::none(),
+                    Err(e) => Cmd::new(async move { Msg::Error(e) }),
                 }
             }
-            Msg::LoadTasks => {
-                self.loading = true;
-                Cmd::new(async {
-                    match fetch_tasks().await {
-                        Ok(tasks) => Msg::TasksLoaded(tasks),
-                        Err(e) => Msg::Error(e),
+            Msg::DeleteAttachment(task_id, attachment_id) => Cmd::new(async move {
+                Msg::AttachmentDeleted(task_id, attachment_id, api::delete_attachment(task_id, attachment_id).await)
+            }),
+            Msg::AttachmentDeleted(task_id, attachment_id, result) => match result {
+                Ok(()) => {
+                    if let Some(task) = self.tasks.iter_mut().find(|t| t.id == task_id) {
+                        task.attachments.retain(|attachment| attachment.id != attachment_id);
                     }
-                })
-            }
-            Msg::TasksLoaded(tasks) => {
-                console::log_1(&format!("[DEBUG] TasksLoaded - {} tasks received", tasks.len()).into());
-                for (i, task) in tasks.iter().enumerate() {
-                    console::log_1(&format!("[DEBUG] Task {}: ID={}, Title='{}', Completed={}", 
-                        i, task.id, task.title, task.completed).into());
+                    Cmd::none()
                 }
-                self.tasks = tasks;
-                self.loading = false;
-                Cmd::none()
-            }
-            Msg::SetNewTaskTitle(task_title) => {
-                self.new_task_title = task_title;
-                Cmd::none()
+                Err(e) => Cmd::new(async move { Msg::Error(e) }),
+            },
+            Msg::ToggleComments(task_id) => {
+                if !self.open_comments.insert(task_id) {
+                    self.open_comments.remove(&task_id);
+                    return Cmd::none();
+                }
+                Cmd::new(async move { Msg::CommentsLoaded(task_id, fetch_comments(task_id).await) })
             }
-            Msg::SetNewTaskDescription(description) => {
-                self.new_task_description = description;
+            Msg::CommentsLoaded(task_id, result) => match result {
+                Ok(comments) => {
+                    self.comments.insert(task_id, comments);
+                    Cmd::none()
+                }
+                Err(e) => {
+                    self.open_comments.remove(&task_id);
+                    self.notify(Severity::Error, e)
+                }
+            },
+            Msg::SetCommentDraft(task_id, body) => {
+                self.comment_drafts.insert(task_id, body);
                 Cmd::none()
             }
-            Msg::CreateTask => {
-                let task_title = self.new_task_title.clone();
-                let description = self.new_task_description.clone();
-                
-                if task_title.trim().is_empty() {
+            Msg::PostComment(task_id) => {
+                let body = self.comment_drafts.get(&task_id).map(|body| body.trim().to_string()).unwrap_or_default();
+                if body.is_empty() {
                     return Cmd::none();
                 }
-                
-                self.new_task_title.clear();
-                self.new_task_description.clear();
-                
-                Cmd::new(async move {
-                    match create_task(task_title, description).await {
-                        Ok(task) => Msg::TaskCreated(task),
-                        Err(e) => Msg::Error(e),
-                    }
-                })
-            }
-            Msg::TaskCreated(task) => {
-                self.tasks.push(task);
-                Cmd::none()
+                Cmd::new(async move { Msg::CommentPosted(post_comment(task_id, CreateCommentRequest { body }).await) })
             }
-            Msg::ToggleTask(id) => {
-                console::log_1(&format!("[DEBUG] ToggleTask called for ID: {}", id).into());
-                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
-                    let old_completed = task.completed;
-                    let new_completed = !task.completed;
-                    console::log_1(&format!("[DEBUG] Task found - Title: '{}', Old completed: {}, New completed: {}", 
-                        task.title, old_completed, new_completed).into());
-                    
-                    // Set task as loading
-                    self.task_loading_states.insert(id, true);
-                    
-                    // OPTIMISTIC UPDATE: Update local state immediately for responsive UI
-                    task.completed = new_completed;
-                    console::log_1(&format!("[DEBUG] Optimistic update applied locally").into());
-                    
-                    // Then sync with server in background
-                    Cmd::new(async move {
-                        console::log_1(&format!("[DEBUG] Sending background sync request for task {}", id).into());
-                        match update_task(id, None, None, Some(new_completed)).await {
-                            Ok(updated_task) => {
-                                console::log_1(&format!("[DEBUG] Background sync successful - Task: '{}', Completed: {}", 
-                                    updated_task.title, updated_task.completed).into());
-                                // We could add a message to handle server-client sync conflicts if needed
-                                Msg::TaskUpdated(updated_task)
-                            },
-                            Err(e) => {
-                                console::log_1(&format!("[DEBUG] Background sync failed: {}, reverting optimistic update", e).into());
-                                // On error, revert the optimistic update
-                                Msg::RevertTaskToggle(id, old_completed)
-                            },
-                        }
-                    })
-                } else {
-                    console::log_1(&format!("[DEBUG] Task with ID {} not found in local state!", id).into());
+            Msg::CommentPosted(result) => match result {
+                Ok(comment) => {
+                    self.comment_drafts.remove(&comment.task_id);
+                    self.comments.entry(comment.task_id).or_default().push(comment);
                     Cmd::none()
                 }
+                Err(e) => self.notify(Severity::Error, e),
+            },
+            Msg::ToggleSelectionMode => {
+                self.selecting = !self.selecting;
+                self.selected_tasks.clear();
+                self.selection_anchor = None;
+                Cmd::none()
             }
-            Msg::TaskUpdated(updated_task) => {
-                console::log_1(&format!("[DEBUG] TaskUpdated received - ID: {}, Title: '{}', Completed: {}", 
-                    updated_task.id, updated_task.title, updated_task.completed).into());
-                
-                // Remove loading state for this task
-                self.task_loading_states.remove(&updated_task.id);
-                
-                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == updated_task.id) {
-                    // Only update if the server response differs from our current state
-                    // This prevents race conditions where stale responses overwrite newer state
-                    if task.completed != updated_task.completed {
-                        console::log_1(&format!("[DEBUG] Updating task state from server response").into());
-                        *task = updated_task;
-                    } else {
-                        console::log_1(&format!("[DEBUG] Server response matches current state, no update needed").into());
+            Msg::SelectTask(id, shift, toggle) => {
+                let anchor = self.selection_anchor.filter(|_| shift);
+                match anchor {
+                    // Shift-click selects everything between the anchor and here, in display order
+                    Some(anchor) => {
+                        let order = self.visible_task_ids();
+                        if let (Some(from), Some(to)) = (
+                            order.iter().position(|t| *t == anchor),
+                            order.iter().position(|t| *t == id),
+                        ) {
+                            if !toggle {
+                                self.selected_tasks.clear();
+                            }
+                            self.selected_tasks.extend(&order[from.min(to)..=from.max(to)]);
+                        }
+                    }
+                    None if toggle => {
+                        if !self.selected_tasks.remove(&id) {
+                            self.selected_tasks.insert(id);
+                        }
+                        self.selection_anchor = Some(id);
+                    }
+                    None => {
+                        self.selected_tasks.clear();
+                        self.selected_tasks.insert(id);
+                        self.selection_anchor = Some(id);
                     }
-                } else {
-                    console::log_1(&format!("[DEBUG] WARNING: Could not find task {} in local state to update!", updated_task.id).into());
                 }
                 Cmd::none()
             }
-            Msg::RevertTaskToggle(id, original_completed) => {
-                console::log_1(&format!("[DEBUG] Reverting optimistic update for task {} to completed: {}", id, original_completed).into());
-                // Remove loading state for this task
-                self.task_loading_states.remove(&id);
-                
-                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
-                    task.completed = original_completed;
-                    console::log_1(&format!("[DEBUG] Optimistic update reverted successfully").into());
-                } else {
-                    console::log_1(&format!("[DEBUG] WARNING: Could not find task {} to revert!", id).into());
-                }
+            Msg::ClearSelection => {
+                self.selected_tasks.clear();
+                self.selection_anchor = None;
                 Cmd::none()
             }
-            Msg::TaskSaved(saved_task) => {
-                // Remove loading state for this task
-                self.task_loading_states.remove(&saved_task.id);
-                
-                // Update the task in the list
-                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == saved_task.id) {
-                    *task = saved_task;
+            Msg::CompleteSelected => {
+                let ids: Vec<Uuid> = self
+                    .tasks
+                    .iter()
+                    .filter(|t| self.selected_tasks.contains(&t.id) && !t.completed)
+                    .map(|t| t.id)
+                    .collect();
+                self.selected_tasks.clear();
+                self.selection_anchor = None;
+
+                self.apply_bulk(ids.into_iter().map(|id| BulkOperation::Complete { id }).collect())
+            }
+            Msg::DeleteSelected => {
+                let count = self.selected_tasks.len();
+                if count == 0
+                    || !self.confirm(
+                        ConfirmAction::BulkDelete,
+                        &format!("Are you sure you want to delete {} selected tasks?", count),
+                    )
+                {
+                    return Cmd::none();
                 }
-                // Clear edit fields (edit mode was already exited in SaveEdit)
-                self.edit_title.clear();
-                self.edit_description.clear();
+
+                let ids: Vec<Uuid> = self.selected_tasks.drain().collect();
+                self.selection_anchor = None;
+
+                self.apply_bulk(ids.into_iter().map(|id| BulkOperation::Delete { id }).collect())
+            }
+            Msg::SetBulkTag(tag) => {
+                self.bulk_tag = tag;
                 Cmd::none()
             }
-            Msg::DeleteTask(id) => {
-                if window().unwrap().confirm_with_message("Are you sure you want to delete this task?").unwrap() {
-                    self.task_loading_states.insert(id, true);
-                    Cmd::new(async move {
-                        match delete_task(id).await {
-                            Ok(_) => Msg::TaskDeleted(id),
-                            Err(e) => Msg::Error(e),
-                        }
+            Msg::RetagSelected(add) => {
+                let tags = shared::normalize_tags([self.bulk_tag.as_str()]);
+                if tags.is_empty() || self.selected_tasks.is_empty() {
+                    return Cmd::none();
+                }
+                self.bulk_tag.clear();
+
+                let operations = self
+                    .selected_tasks
+                    .iter()
+                    .map(|&id| {
+                        let (add, remove) = if add { (tags.clone(), Vec::new()) } else { (Vec::new(), tags.clone()) };
+                        BulkOperation::Retag { id, add, remove }
                     })
+                    .collect();
+                self.apply_bulk(operations)
+            }
+            Msg::BulkApplied(response) => {
+                for id in &response.deleted {
+                    self.task_loading_states.remove(id);
+                }
+                self.tasks.retain(|t| !response.deleted.contains(&t.id));
+                for task in response.updated {
+                    self.task_loading_states.remove(&task.id);
+                    self.upsert_task(task, false);
+                }
+                Cmd::none()
+            }
+            Msg::BulkFailed(ids, error) => {
+                for id in &ids {
+                    self.task_loading_states.remove(id);
+                }
+                self.handle(Msg::Error(error))
+            }
+            Msg::StartTriage => {
+                self.triage_count += 1;
+                let session = triage::Session::new(self.triage_count, &self.tasks);
+                if session.current().is_none() {
+                    return self.notify(Severity::Info, "Every open task already has tags and a due date.".to_string());
+                }
+                self.triage = Some(session);
+                self.listen_for_triage_key()
+            }
+            Msg::TriageKey(session_id, key) => {
+                let Some(session) = self.triage.as_mut().filter(|session| session.id == session_id) else {
+                    return Cmd::none();
+                };
+                let Some(id) = session.current() else {
+                    return Cmd::none();
+                };
+                let cmd = match session.key(&key) {
+                    None => Cmd::none(),
+                    Some(triage::Action::SetDue(preset)) => {
+                        self.task_loading_states.insert(id, true);
+                        let request = UpdateTaskRequest {
+                            due_at: Some(Some(preset.wake_time())),
+                            ..Default::default()
+                        };
+                        cmd::run(api::update_task(id, request), Msg::TriageApplied)
+                    }
+                    Some(triage::Action::SetPriority(priority)) => {
+                        self.task_loading_states.insert(id, true);
+                        let request = UpdateTaskRequest {
+                            priority: Some(priority),
+                            ..Default::default()
+                        };
+                        cmd::run(api::update_task(id, request), Msg::TriageApplied)
+                    }
+                    Some(triage::Action::AddTag(tag)) => self.apply_bulk(vec![BulkOperation::Retag {
+                        id,
+                        add: vec![tag],
+                        remove: Vec::new(),
+                    }]),
+                    Some(triage::Action::Complete) => {
+                        let complete = self.apply_bulk(vec![BulkOperation::Complete { id }]);
+                        Cmd::batch([complete, self.advance_triage()])
+                    }
+                    Some(triage::Action::Next) => self.advance_triage(),
+                    Some(triage::Action::Exit) => {
+                        self.triage = None;
+                        Cmd::none()
+                    }
+                };
+                Cmd::batch([cmd, self.listen_for_triage_key()])
+            }
+            Msg::SetTriageTag(tag) => {
+                if let Some(session) = self.triage.as_mut() {
+                    session.tag = tag;
+                }
+                Cmd::none()
+            }
+            Msg::TriageApplied(task) => {
+                self.task_loading_states.remove(&task.id);
+                self.upsert_task(task, false);
+                Cmd::none()
+            }
+            Msg::EndTriage => {
+                self.triage = None;
+                Cmd::none()
+            }
+            Msg::SnoozeTask(id, preset) => {
+                self.open_task_menu = None;
+                let snoozed_until = preset.wake_time();
+                self.task_loading_states.insert(id, true);
+                let request = UpdateTaskRequest {
+                    snoozed_until: Some(Some(snoozed_until)),
+                    ..Default::default()
+                };
+                cmd::run(api::update_task(id, request), Msg::TaskSnoozed)
+            }
+            Msg::TaskSnoozed(updated_task) => {
+                self.task_loading_states.remove(&updated_task.id);
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == updated_task.id) {
+                    task.snoozed_until = updated_task.snoozed_until;
+                }
+                Cmd::batch([
+                    Cmd::new(async { Msg::ShowToast(Severity::Info, "Task snoozed".to_string()) }),
+                    self.schedule_snooze_wake(),
+                ])
+            }
+            Msg::SnoozeExpired => {
+                // Re-rendering brings the woken task back; then wait for the next one
+                self.snooze_wake_at = None;
+                self.schedule_snooze_wake()
+            }
+            Msg::FindNearby => {
+                self.locating = true;
+                Cmd::new(async {
+                    let (lat, lng) = match geolocation::current_position().await {
+                        Ok(position) => position,
+                        Err(e) => return Msg::LocateFailed(e),
+                    };
+                    match fetch_nearby(lat, lng).await {
+                        Ok(tasks) => Msg::NearbyLoaded(tasks),
+                        Err(e) => Msg::LocateFailed(e),
+                    }
+                })
+            }
+            Msg::NearbyLoaded(tasks) => {
+                self.locating = false;
+                self.nearby = Some(tasks);
+                Cmd::none()
+            }
+            Msg::LocateFailed(error) => {
+                self.locating = false;
+                Cmd::new(async move { Msg::ShowToast(Severity::Warning, error) })
+            }
+            Msg::LoadActivity => {
+                self.activity.clear();
+                self.activity_cursor = None;
+                self.activity_loading = true;
+                if let Some(Prefetched::Activity(list)) = self.prefetched.take(&Page::Activity) {
+                    return Cmd::new(async move { Msg::ActivityLoaded(list) });
+                }
+                cmd::run(fetch_activity(None), Msg::ActivityLoaded)
+            }
+            Msg::LoadMoreActivity(cursor) => {
+                // A stale observer from an earlier visit can fire late
+                if self.activity_loading || self.activity_cursor.as_ref() != Some(&cursor) {
+                    return Cmd::none();
+                }
+                self.activity_loading = true;
+                cmd::run(async move { fetch_activity(Some(&cursor)).await }, Msg::ActivityLoaded)
+            }
+            Msg::ActivityLoaded(list) => {
+                self.activity_loading = false;
+                self.activity.extend(list.data);
+                self.activity_cursor = list.next_cursor;
+                match self.activity_cursor.clone() {
+                    Some(cursor) => Cmd::new(async move {
+                        if infinite_scroll::sentinel_visible("activity-sentinel").await {
+                            Msg::LoadMoreActivity(cursor)
+                        } else {
+                            Msg::SentinelDetached
+                        }
+                    }),
+                    None => Cmd::none(),
+                }
+            }
+            Msg::SentinelDetached => Cmd::none(),
+            Msg::LoadTrash => {
+                self.trash_loading = true;
+                cmd::run(fetch_trash(), Msg::TrashLoaded)
+            }
+            Msg::TrashLoaded(tasks) => {
+                self.trash_loading = false;
+                self.trash = tasks;
+                Cmd::none()
+            }
+            Msg::RestoreTask(id) => cmd::run(restore_task(id), Msg::TaskRestored),
+            Msg::TaskRestored(task) => {
+                self.trash.retain(|t| t.id != task.id);
+                if self.quick_filter == Some(QuickFilter::RecentlyDeleted) {
+                    if let Some(tasks) = &mut self.quick_filter_tasks {
+                        tasks.retain(|t| t.id != task.id);
+                    }
+                }
+                let message = format!("Restored \"{}\"", task.title);
+                // The live feed may have brought it back already
+                if !self.tasks.iter().any(|t| t.id == task.id) {
+                    self.tasks.push(task);
+                }
+                self.notify(Severity::Info, message)
+            }
+            Msg::PurgeTask(id) => {
+                let confirmed = window()
+                    .and_then(|w| w.confirm_with_message("Delete this task for good? This can't be undone.").ok())
+                    .unwrap_or(false);
+                if !confirmed {
+                    return Cmd::none();
+                }
+                cmd::run(purge_task(id), move |()| Msg::TaskPurged(id))
+            }
+            Msg::TaskPurged(id) => {
+                self.trash.retain(|t| t.id != id);
+                Cmd::none()
+            }
+            Msg::LoadArchive => {
+                self.archive_loading = true;
+                cmd::run(fetch_archive(), Msg::ArchiveLoaded)
+            }
+            Msg::ArchiveLoaded(tasks) => {
+                self.archive_loading = false;
+                self.archive = tasks;
+                Cmd::none()
+            }
+            Msg::UnarchiveTask(id) => cmd::run(unarchive_task(id), Msg::TaskUnarchived),
+            Msg::TaskUnarchived(task) => {
+                self.archive.retain(|t| t.id != task.id);
+                let message = format!("Restored \"{}\" from the archive", task.title);
+                // The live feed may have brought it back already
+                if !self.tasks.iter().any(|t| t.id == task.id) {
+                    self.tasks.push(task);
+                }
+                self.notify(Severity::Info, message)
+            }
+            Msg::SyncChanges => {
+                self.sync_pending = false;
+                self.sync_changes()
+            }
+            Msg::ChangesSynced(changes, tasks) => {
+                // The counter only goes back if the server's data was replaced
+                if self.sync_version.is_some_and(|since| changes.version < since) {
+                    return if self.current_page == Page::Tasks {
+                        Cmd::new(async { Msg::Task(TaskMsg::LoadTasks) })
+                    } else {
+                        Cmd::new(async { Msg::Task(TaskMsg::LoadAllTasks) })
+                    };
+                }
+
+                self.tasks.retain(|t| !changes.deleted.contains(&t.id));
+                for task in tasks {
+                    self.upsert_task(task, true);
+                }
+                self.sync_version = Some(changes.version);
+                Cmd::batch([self.schedule_snooze_wake(), self.watch_for_resume()])
+            }
+            Msg::SyncFailed(error) => {
+                // Try again next time rather than giving up on syncing
+                let watch = self.watch_for_resume();
+                Cmd::batch([watch, Cmd::new(async move { Msg::Error(error) })])
+            }
+            Msg::LiveConnected => {
+                // Catch up on whatever happened while disconnected
+                Cmd::batch([next_live_event(), self.sync_changes()])
+            }
+            Msg::LiveEvent(event) => {
+                let mut notice = Cmd::none();
+                match event {
+                    TaskEvent::Created { task } => self.upsert_task(task, true),
+                    // Tasks that haven't paged in yet will arrive with their page
+                    TaskEvent::Updated { task } => {
+                        let all_loaded = self.tasks_next_page.is_none();
+                        self.upsert_task(task, all_loaded)
+                    }
+                    TaskEvent::Deleted { id } => {
+                        self.tasks.retain(|t| t.id != id);
+                        self.selected_tasks.remove(&id);
+                    }
+                    TaskEvent::Notice { task_id, message } => {
+                        let message = match self.tasks.iter().find(|t| t.id == task_id) {
+                            Some(task) => format!("{}: {}", task.title, message),
+                            None => message,
+                        };
+                        notice = self.notify(Severity::Info, message);
+                    }
+                }
+                Cmd::batch([next_live_event(), self.schedule_snooze_wake(), notice])
+            }
+            Msg::LiveDisconnected => Cmd::new(async {
+                sleep(live::RECONNECT_DELAY_MS).await;
+                connect_live().await
+            }),
+            Msg::LocalStoreLoaded(mut snapshot) => {
+                // Changes made offline last time are sent whether or not
+                // the rest of the copy is still of use
+                if let Some(snapshot) = &mut snapshot {
+                    self.outbox = std::mem::take(&mut snapshot.meta.outbox);
+                }
+                let replay = Cmd::new(async { Msg::ReplayOutbox });
+
+                // Show what was there last time, then only catch up on what
+                // changed. The Tasks page pages in as it scrolls; everything
+                // else needs the full list (dashboard stats, My day).
+                let hydrated = snapshot.is_some_and(|snapshot| self.hydrate(snapshot));
+                let initial = self.initial_tasks.take();
+                if hydrated && (self.current_page == Page::Tasks || self.tasks_next_page.is_none()) {
+                    Cmd::batch([Cmd::new(async { Msg::SyncChanges }), self.watch_task_sentinel(), replay])
+                } else if self.current_page == Page::Tasks {
+                    // The page came with the first page of tasks in it
+                    let load = match initial {
+                        Some(InitialTasks { list, version }) => Msg::Task(TaskMsg::TaskPageLoaded(list, version)),
+                        None => Msg::Task(TaskMsg::LoadTasks),
+                    };
+                    Cmd::batch([Cmd::new(async { load }), replay])
                 } else {
-                    Cmd::none()
+                    Cmd::batch([Cmd::new(async { Msg::Task(TaskMsg::LoadAllTasks) }), replay])
                 }
             }
-            Msg::TaskDeleted(id) => {
-                self.tasks.retain(|t| t.id != id);
-                // Remove loading state for this task
-                self.task_loading_states.remove(&id);
+            Msg::ConnectivityChanged => {
+                let watch = Cmd::new(async {
+                    outbox::connectivity_changed().await;
+                    Msg::ConnectivityChanged
+                });
+                if outbox::offline() {
+                    watch
+                } else {
+                    Cmd::batch([watch, Cmd::new(async { Msg::ReplayOutbox })])
+                }
+            }
+            Msg::ReplayOutbox => {
+                if self.outbox_sending || outbox::offline() {
+                    return Cmd::none();
+                }
+                let Some(mutation) = self.outbox.first().cloned() else {
+                    return Cmd::none();
+                };
+                self.outbox_sending = true;
+                Cmd::new(async move { Msg::OutboxReplayed(mutation.send().await) })
+            }
+            Msg::OutboxReplayed(outcome) => {
+                self.outbox_sending = false;
+                if self.outbox.is_empty() {
+                    return Cmd::none();
+                }
+                match outcome {
+                    // The browser thinks it's online, so the backend is down
+                    outbox::Outcome::Offline => Cmd::new(async {
+                        sleep(BACKEND_PROBE_INTERVAL_MS).await;
+                        Msg::ReplayOutbox
+                    }),
+                    outbox::Outcome::Refused(message) => {
+                        // A task that couldn't be created can't be changed either
+                        if let Mutation::Create { local_id, .. } = self.outbox.remove(0) {
+                            self.outbox.retain(|m| m.task_id() != local_id);
+                            self.tasks.retain(|t| t.id != local_id);
+                        }
+                        Cmd::batch([self.notify(Severity::Warning, message), Cmd::new(async { Msg::ReplayOutbox })])
+                    }
+                    outbox::Outcome::Sent(task) => {
+                        let sent = self.outbox.remove(0);
+                        if let Some(task) = task {
+                            if let Mutation::Create { local_id, .. } = sent {
+                                self.tasks.retain(|t| t.id != local_id);
+                                self.selected_tasks.remove(&local_id);
+                                for mutation in &mut self.outbox {
+                                    mutation.rename(local_id, task.id);
+                                }
+                            }
+                            self.upsert_task(task, true);
+                        }
+                        if self.outbox.is_empty() {
+                            // Catch up on what others changed meanwhile
+                            self.sync_changes()
+                        } else {
+                            Cmd::new(async { Msg::ReplayOutbox })
+                        }
+                    }
+                }
+            }
+            Msg::SaveLocalStore => {
+                self.local_save_pending = false;
+                // Nothing loaded yet, or nothing a later session could catch up
+                if self.sync_version.is_some() {
+                    let meta = local_store::Meta {
+                        tasks_next_page: self.tasks_next_page,
+                        sync_version: self.sync_version,
+                        show_completed: self.show_completed,
+                        flags: self.flags.clone(),
+                        outbox: self.outbox.clone(),
+                    };
+                    local_store::save(&self.tasks, &meta);
+                }
                 Cmd::none()
             }
-            Msg::EditTask(id) => {
-                if let Some(task) = self.tasks.iter().find(|t| t.id == id) {
-                    self.editing_task = Some(id);
-                    self.edit_title = task.title.clone();
-                    self.edit_description = task.description.clone();
+            Msg::LoadTagStats => {
+                if let Some(Prefetched::TagStats(stats)) = self.prefetched.take(&Page::Dashboard) {
+                    self.tag_stats = stats;
+                    return Cmd::none();
                 }
+                cmd::run(fetch_tag_stats(), Msg::TagStatsLoaded)
+            }
+            Msg::TagStatsLoaded(stats) => {
+                self.tag_stats = stats;
+                Cmd::none()
+            }
+            Msg::SetReleaseFrom(date) => {
+                self.release_from = date;
+                Cmd::none()
+            }
+            Msg::SetReleaseTo(date) => {
+                self.release_to = date;
                 Cmd::none()
             }
-            Msg::SetEditTitle(task_title) => {
-                self.edit_title = task_title;
+            Msg::LoadAnalytics => Cmd::new(async { Msg::AnalyticsLoaded(fetch_analytics().await.ok()) }),
+            Msg::AnalyticsLoaded(analytics) => {
+                self.analytics = analytics;
                 Cmd::none()
             }
-            Msg::SetEditDescription(description) => {
-                self.edit_description = description;
+            Msg::LoadPlan => {
+                let date = local_date(0);
+                // A plan prefetched just before midnight belongs to yesterday
+                if let Some(Prefetched::Plan(plan)) = self.prefetched.take(&Page::MyDay) {
+                    if plan.date == date {
+                        self.plan = Some(plan);
+                        return Cmd::none();
+                    }
+                }
+                cmd::run(async move { fetch_plan(&date).await }, Msg::PlanLoaded)
+            }
+            Msg::PlanLoaded(plan) | Msg::PlanSaved(plan) => {
+                self.plan = Some(plan);
                 Cmd::none()
             }
-            Msg::SaveEdit(id) => {
-                // Guard: only save if we're actually editing this task
-                if self.editing_task != Some(id) {
+            Msg::AddToPlan(id) => {
+                let Some(plan) = self.plan.as_mut() else {
                     return Cmd::none();
+                };
+                if !plan.task_ids.contains(&id) {
+                    plan.task_ids.push(id);
                 }
-                
-                let task_title = self.edit_title.clone();
-                let description = self.edit_description.clone();
-                
-                // Set task as loading
-                self.task_loading_states.insert(id, true);
-                
-                // Exit edit mode immediately to prevent double-saves
-                self.editing_task = None;
-                
+                self.save_plan()
+            }
+            Msg::RemoveFromPlan(id) => {
+                let Some(plan) = self.plan.as_mut() else {
+                    return Cmd::none();
+                };
+                plan.task_ids.retain(|t| *t != id);
+                self.save_plan()
+            }
+            Msg::RollOverPlan => {
+                let date = local_date(0);
+                let to = local_date(1);
+                cmd::run(async move { roll_over_plan(&date, to).await }, Msg::PlanRolledOver)
+            }
+            Msg::PlanRolledOver(tomorrow) => {
+                let moved = tomorrow.task_ids.len();
+                Cmd::batch([
+                    Cmd::new(async { Msg::LoadPlan }),
+                    Cmd::new(async move { Msg::ShowToast(Severity::Info, format!("{} tasks planned for tomorrow", moved)) }),
+                ])
+            }
+            Msg::OpenTaskMenu(id, x, y) => {
+                self.open_task_menu = Some((id, x, y));
+                Cmd::none()
+            }
+            Msg::CloseTaskMenu => {
+                self.open_task_menu = None;
+                Cmd::none()
+            }
+            Msg::DuplicateTask(id) => {
+                self.open_task_menu = None;
+                let Some(task) = self.tasks.iter().find(|t| t.id == id) else {
+                    return Cmd::none();
+                };
+                let request = CreateTaskRequest {
+                    title: format!("{} (copy)", task.title),
+                    description: task.description.clone(),
+                    tags: task.tags.clone(),
+                    priority: task.priority,
+                };
                 Cmd::new(async move {
-                    match update_task(id, Some(task_title), Some(description), None).await {
-                        Ok(task) => Msg::TaskSaved(task),
-                        Err(e) => Msg::Error(e),
+                    match api::create_task(request).await {
+                        Ok(task) => Msg::Form(FormMsg::TaskCreated(task)),
+                        Err(api::CreateError::Throttled(message, retry_after)) => Msg::Form(FormMsg::CreateThrottled(message, retry_after, None)),
+                        Err(api::CreateError::Rejected(message, fields)) => Msg::Form(FormMsg::CreateRejected(message, fields, None)),
+                        Err(api::CreateError::Failed(e)) => Msg::Error(e),
                     }
                 })
             }
-            Msg::CancelEdit => {
-                self.editing_task = None;
+            Msg::TogglePin(id) => {
+                self.open_task_menu = None;
+                let Some(task) = self.tasks.iter().find(|t| t.id == id) else {
+                    return Cmd::none();
+                };
+                let pinned = !task.pinned;
+                self.task_loading_states.insert(id, true);
+                cmd::run(api::update_task(id, UpdateTaskRequest { pinned: Some(pinned), ..Default::default() }), Msg::TaskPinned)
+            }
+            Msg::TaskPinned(updated_task) => {
+                self.task_loading_states.remove(&updated_task.id);
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == updated_task.id) {
+                    task.pinned = updated_task.pinned;
+                }
                 Cmd::none()
             }
-            Msg::ClearCompleted => {
-                if window().unwrap().confirm_with_message("Are you sure you want to clear all completed tasks?").unwrap() {
-                    let completed_ids: Vec<Uuid> = self.tasks.iter()
-                        .filter(|t| t.completed)
-                        .map(|t| t.id)
-                        .collect();
-                    
-                    for id in &completed_ids {
-                        self.task_loading_states.insert(*id, true);
-                    }
-                    
-                    self.tasks.retain(|t| !t.completed);
-                    
-                    Cmd::batch(
-                        completed_ids.into_iter()
-                            .map(|id| Cmd::new(async move {
-                                match delete_task(id).await {
-                                    Ok(_) => Msg::TaskDeleted(id),
-                                    Err(e) => Msg::Error(e),
-                                }
-                            }))
-                            .collect::<Vec<_>>(),
-                    )
+            Msg::CopyTaskLink(id) => {
+                self.open_task_menu = None;
+                let Some(origin) = window().and_then(|w| w.location().origin().ok()) else {
+                    return Cmd::none();
+                };
+                let link = format!("{}{}", origin, base::url(&format!("/?task={}", id)));
+                cmd::run(async move { copy_to_clipboard(&link).await }, |()| {
+                    Msg::ShowToast(Severity::Info, "Link copied to clipboard".to_string())
+                })
+            }
+            Msg::ShowTaskQr(id) => {
+                self.open_task_menu = None;
+                self.qr_task = Some(id);
+                Cmd::none()
+            }
+            Msg::CloseTaskQr => {
+                self.qr_task = None;
+                Cmd::none()
+            }
+            Msg::CopyTaskMarkdown(id) => {
+                self.open_task_menu = None;
+                let Some(markdown) = self.tasks.iter().find(|t| t.id == id).map(Task::to_markdown) else {
+                    return Cmd::none();
+                };
+                cmd::run(async move { copy_to_clipboard(&markdown).await }, |()| {
+                    Msg::ShowToast(Severity::Info, "Markdown copied to clipboard".to_string())
+                })
+            }
+            Msg::TaskFocused => Cmd::none(),
+            Msg::LoadApiEndpoints => cmd::run(playground::fetch_endpoints(), Msg::ApiEndpointsLoaded),
+            Msg::LoadStatus => Cmd::new(async { Msg::StatusLoaded(fetch_status().await) }),
+            Msg::StatusLoaded(status) => {
+                self.service_status = Some(status);
+                Cmd::none()
+            }
+            Msg::ApiEndpointsLoaded(endpoints) => {
+                self.api_endpoints = endpoints;
+                self.playground_endpoint = None;
+                Cmd::none()
+            }
+            Msg::SelectEndpoint(index) => {
+                // Parameter values carry over, so an id only needs typing once
+                self.playground_endpoint = index;
+                self.playground_body = index
+                    .and_then(|i| self.api_endpoints.get(i))
+                    .and_then(|e| e.example_body.clone())
+                    .unwrap_or_default();
+                self.playground_reply = None;
+                Cmd::none()
+            }
+            Msg::SetPlaygroundParam(name, value) => {
+                self.playground_params.insert(name, value);
+                Cmd::none()
+            }
+            Msg::SetPlaygroundBody(body) => {
+                self.playground_body = body;
+                Cmd::none()
+            }
+            Msg::SendPlaygroundRequest => {
+                let Some(endpoint) = self.playground_endpoint.and_then(|i| self.api_endpoints.get(i)).cloned() else {
+                    return Cmd::none();
+                };
+                self.playground_sending = true;
+                let params = self.playground_params.clone();
+                let body = self.playground_body.clone();
+                Cmd::new(async move { Msg::PlaygroundReplied(playground::send(&endpoint, &params, &body).await) })
+            }
+            Msg::PlaygroundReplied(reply) => {
+                self.playground_sending = false;
+                self.playground_reply = Some(reply);
+                Cmd::none()
+            }
+            Msg::StartTour => self.handle(Msg::ShowTourStep(tour::Step::first())),
+            Msg::ShowTourStep(step) => {
+                self.tour = Some(step);
+                let navigate = if self.current_page == step.page() {
+                    Cmd::none()
+                } else {
+                    self.handle(Msg::Nav(NavMsg::NavigateTo(step.page())))
+                };
+                let scroll = Cmd::new(async move {
+                    // Wait for the step's page to render before scrolling to it
+                    sleep(0).await;
+                    tour::scroll_to(step);
+                    Msg::TourStepShown
+                });
+                Cmd::batch([navigate, scroll])
+            }
+            Msg::EndTour => {
+                self.tour = None;
+                preferences::save_tour_dismissed(true);
+                Cmd::none()
+            }
+            Msg::TourStepShown => Cmd::none(),
+            Msg::ShowToast(severity, message) => self.notify(severity, message),
+            Msg::DismissToast(toast_id) => {
+                // Already gone if it was closed by hand or pushed out
+                self.toasts.retain(|toast| toast.id != toast_id);
+                Cmd::none()
+            }
+            Msg::LoadFlags => {
+                cmd::run(fetch_flags(), Msg::FlagsLoaded)
+            }
+            Msg::FlagsLoaded(flags) => {
+                self.flags = flags;
+                Cmd::none()
+            }
+            Msg::LoadExperiments => {
+                cmd::run(fetch_experiments(), Msg::ExperimentsLoaded)
+            }
+            Msg::ExperimentsLoaded(assignments) => {
+                // The create form is always rendered on load, so this is the exposure point
+                let exposure = assignments.variant(experiments::CREATE_FORM).map(|variant| ExposureRequest {
+                    experiment: experiments::CREATE_FORM.to_string(),
+                    variant: variant.to_string(),
+                });
+                self.experiments = assignments;
+
+                match exposure {
+                    Some(exposure) => cmd::run(log_exposure(exposure), |()| Msg::ExposureLogged),
+                    None => Cmd::none(),
+                }
+            }
+            Msg::ExposureLogged => Cmd::none(),
+            Msg::LoadExperimentResults => {
+                cmd::run(fetch_experiment_results(), Msg::ExperimentResultsLoaded)
+            }
+            Msg::ExperimentResultsLoaded(results) => {
+                self.experiment_results = results;
+                Cmd::none()
+            }
+            Msg::LoadClientErrors => {
+                cmd::run(fetch_client_errors(), Msg::ClientErrorsLoaded)
+            }
+            Msg::ClientErrorsLoaded(reports) => {
+                self.client_errors = reports;
+                Cmd::none()
+            }
+            Msg::LoadLogs => {
+                let level = self.log_level;
+                cmd::run(fetch_logs(level), Msg::LogsLoaded)
+            }
+            Msg::LogsLoaded(entries) => {
+                self.logs = entries;
+                if self.current_page != Page::Admin || self.log_poll_pending {
+                    return Cmd::none();
+                }
+                self.log_poll_pending = true;
+                Cmd::new(async {
+                    sleep(LOG_POLL_INTERVAL_MS).await;
+                    Msg::PollLogs
+                })
+            }
+            Msg::SetLogLevel(level) => {
+                self.log_level = level;
+                Cmd::new(async { Msg::LoadLogs })
+            }
+            Msg::PollLogs => {
+                self.log_poll_pending = false;
+                if self.current_page == Page::Admin {
+                    Cmd::new(async { Msg::LoadLogs })
+                } else {
+                    Cmd::none()
+                }
+            }
+            Msg::LoadAnnouncements => {
+                cmd::run(fetch_announcements(), Msg::AnnouncementsLoaded)
+            }
+            Msg::AnnouncementsLoaded(announcements) => {
+                self.announcements = announcements;
+                Cmd::none()
+            }
+            Msg::ToggleAnnouncements => {
+                self.announcements_open = !self.announcements_open;
+                if let Some(newest) = self.announcements.first().filter(|_| self.announcements_open) {
+                    self.announcements_seen = self.announcements_seen.max(newest.published_at);
+                    preferences::save_announcements_seen(self.announcements_seen);
+                }
+                Cmd::none()
+            }
+            Msg::SetAnnouncementTitle(title) => {
+                self.new_announcement_title = title;
+                Cmd::none()
+            }
+            Msg::SetAnnouncementBody(body) => {
+                self.new_announcement_body = body;
+                Cmd::none()
+            }
+            Msg::PublishAnnouncement => {
+                if self.new_announcement_title.trim().is_empty() {
+                    return Cmd::none();
+                }
+                let request = CreateAnnouncementRequest {
+                    title: std::mem::take(&mut self.new_announcement_title),
+                    body: std::mem::take(&mut self.new_announcement_body),
+                };
+                cmd::run(publish_announcement(request), Msg::AnnouncementPublished)
+            }
+            Msg::AnnouncementPublished(announcement) => {
+                self.announcements.insert(0, announcement);
+                Cmd::none()
+            }
+            Msg::DeleteAnnouncement(id) => {
+                cmd::run(delete_announcement(id), move |()| Msg::AnnouncementDeleted(id))
+            }
+            Msg::AnnouncementDeleted(id) => {
+                self.announcements.retain(|a| a.id != id);
+                Cmd::none()
+            }
+            Msg::SetAdminTokenDraft(token) => {
+                self.admin_token_draft = token;
+                Cmd::none()
+            }
+            Msg::SignInAdmin => {
+                let token = self.admin_token_draft.trim().to_string();
+                if token.is_empty() {
+                    return Cmd::none();
+                }
+                preferences::save_admin_token(Some(&token));
+                self.admin_token_draft.clear();
+                self.admin_signed_in = true;
+                self.load_admin_data()
+            }
+            Msg::SignOutAdmin => {
+                preferences::save_admin_token(None);
+                self.admin_signed_in = false;
+                self.overview = None;
+                self.server_config.clear();
+                self.client_errors.clear();
+                self.logs.clear();
+                Cmd::none()
+            }
+            Msg::LoadOverview => {
+                cmd::run(fetch_overview(), Msg::OverviewLoaded)
+            }
+            Msg::OverviewLoaded(overview) => {
+                self.overview = Some(overview);
+                Cmd::none()
+            }
+            Msg::LoadContentFilter => {
+                cmd::run(fetch_content_filter(), Msg::ContentFilterLoaded)
+            }
+            Msg::ContentFilterLoaded(settings) => {
+                self.content_filter = Some(settings);
+                Cmd::none()
+            }
+            Msg::SetFilterMode(workspace, mode) => {
+                if workspace.is_some() {
+                    self.filter_workspace.clear();
+                }
+                cmd::run(set_filter_mode(SetFilterModeRequest { mode, workspace }), Msg::ContentFilterLoaded)
+            }
+            Msg::SetFilterWorkspace(workspace) => {
+                self.filter_workspace = workspace;
+                Cmd::none()
+            }
+            Msg::LoadAutomations => Cmd::batch([
+                cmd::run(fetch_automations(), Msg::AutomationsLoaded),
+                cmd::run(fetch_automation_log(), Msg::AutomationLogLoaded),
+            ]),
+            Msg::AutomationsLoaded(settings) => {
+                self.automations = Some(settings);
+                Cmd::none()
+            }
+            Msg::AutomationLogLoaded(runs) => {
+                self.automation_log = runs;
+                Cmd::none()
+            }
+            Msg::SetAutomationWorkspace(workspace) => {
+                self.automation_workspace = workspace;
+                Cmd::none()
+            }
+            Msg::SetRuleField(field, value) => {
+                self.rule_draft.set(field, value);
+                Cmd::none()
+            }
+            Msg::AddAutomationRule => match self.rule_draft.build() {
+                Ok(rule) => {
+                    let (workspace, mut rules) = self.scoped_automation_rules();
+                    rules.push(rule);
+                    self.rule_draft = RuleDraft::default();
+                    save_automation_rules(workspace, rules)
+                }
+                Err(e) => self.notify(Severity::Warning, e),
+            },
+            Msg::ToggleAutomationRule(id) => {
+                let (workspace, mut rules) = self.scoped_automation_rules();
+                for rule in rules.iter_mut().filter(|rule| rule.id == id) {
+                    rule.enabled = !rule.enabled;
+                }
+                save_automation_rules(workspace, rules)
+            }
+            Msg::DeleteAutomationRule(id) => {
+                let (workspace, mut rules) = self.scoped_automation_rules();
+                rules.retain(|rule| rule.id != id);
+                save_automation_rules(workspace, rules)
+            }
+            Msg::LoadServerConfig => {
+                cmd::run(fetch_server_config(), Msg::ServerConfigLoaded)
+            }
+            Msg::ServerConfigLoaded(entries) => {
+                self.server_config = entries;
+                Cmd::none()
+            }
+            Msg::CheckVersion => {
+                Cmd::new(async {
+                    match fetch_version().await {
+                        Ok(info) => Msg::VersionLoaded(info),
+                        Err(e) => {
+                            // Transient; try again on the next tick rather than surfacing it
+                            console::log_1(&format!("Version check failed: {}", e).into());
+                            sleep(VERSION_CHECK_INTERVAL_MS).await;
+                            Msg::CheckVersion
+                        }
+                    }
+                })
+            }
+            Msg::VersionLoaded(info) => {
+                // Builds without git metadata can't be compared meaningfully
+                if info.git_hash != "unknown"
+                    && self.frontend_build.git_hash != "unknown"
+                    && info.git_hash != self.frontend_build.git_hash
+                {
+                    self.update_available = true;
+                }
+                self.backend_build = Some(info);
+
+                Cmd::new(async {
+                    sleep(VERSION_CHECK_INTERVAL_MS).await;
+                    Msg::CheckVersion
+                })
+            }
+            Msg::ReloadApp => {
+                if let Some(window) = window() {
+                    let _ = window.location().reload();
+                }
+                Cmd::none()
+            }
+            Msg::ProbeBackend => cmd::Job::new(async {
+                sleep(BACKEND_PROBE_INTERVAL_MS).await;
+                // Retries of a hung backend could otherwise hold the probe up
+                cmd::timeout(BACKEND_PROBE_TIMEOUT_MS, fetch_version())
+                    .await
+                    .unwrap_or_else(|| Err("timed out".to_string()))
+            })
+            .or_else(|_| Msg::ProbeBackend)
+            .then(|_| Msg::BackendRecovered),
+            Msg::BackendRecovered => {
+                self.backend_degraded = false;
+                // Pick up whatever changed while we couldn't ask
+                Cmd::batch([self.sync_changes(), Cmd::new(async { Msg::ReplayOutbox })])
+            }
+            Msg::TogglePerfOverlay => {
+                let listen = Cmd::new(async {
+                    perf::next_toggle().await;
+                    Msg::TogglePerfOverlay
+                });
+                if self.perf.take().is_some() {
+                    return listen;
+                }
+                self.perf = Some(perf::sample());
+                self.perf_count += 1;
+                let count = self.perf_count;
+                Cmd::batch([listen, Cmd::new(async move {
+                    sleep(perf::SAMPLE_INTERVAL_MS).await;
+                    Msg::PerfTick(count)
+                })])
+            }
+            Msg::PerfTick(count) => {
+                if self.perf.is_none() || count != self.perf_count {
+                    return Cmd::none();
+                }
+                self.perf = Some(perf::sample());
+                Cmd::new(async move {
+                    sleep(perf::SAMPLE_INTERVAL_MS).await;
+                    Msg::PerfTick(count)
+                })
+            }
+            Msg::ResetPerfSlowest => {
+                perf::reset_slowest();
+                Cmd::none()
+            }
+            Msg::Cancelled => Cmd::none(),
+            Msg::Error(error) => {
+                console::log_1(&format!("Error: {}", error).into());
+                if self.backend_degraded {
+                    // Already showing the banner; each failure would say the same
+                    return Cmd::none();
+                }
+                reporting::report(ClientErrorKind::Api, &error);
+                if api::degraded() {
+                    self.backend_degraded = true;
+                    return Cmd::new(async { Msg::ProbeBackend });
+                }
+                self.notify(Severity::Error, error)
+            }
+        }
+    }
+
+    fn handle_nav(&mut self, msg: NavMsg) -> Cmd<Msg> {
+        match msg {
+            NavMsg::NavigateTo(page) => {
+                self.current_page = page.clone();
+                
+                // Update browser URL without page reload
+                if let Some(history) = window().and_then(|w| w.history().ok()) {
+                    let _ = history.push_state_with_url(
+                        &wasm_bindgen::JsValue::NULL,
+                        "",
+                        Some(&base::url(page.to_path()))
+                    );
+                }
+                
+                // Load tasks when navigating to tasks page
+                if self.current_page == Page::Tasks && self.tasks.is_empty() {
+                    Cmd::new(async { Msg::Task(TaskMsg::LoadTasks) })
+                } else if self.current_page == Page::MyDay {
+                    Cmd::batch([Cmd::new(async { Msg::Task(TaskMsg::LoadAllTasks) }), Cmd::new(async { Msg::LoadPlan })])
+                } else if self.current_page == Page::Dashboard {
+                    self.load_dashboard_data()
+                } else if self.current_page == Page::Activity {
+                    Cmd::new(async { Msg::LoadActivity })
+                } else if self.current_page == Page::Trash {
+                    Cmd::new(async { Msg::LoadTrash })
+                } else if self.current_page == Page::Archive {
+                    Cmd::new(async { Msg::LoadArchive })
+                } else if self.current_page == Page::Admin {
+                    self.load_admin_data()
+                } else if self.current_page == Page::Settings {
+                    let status = Cmd::new(async { Msg::LoadStatus });
+                    if self.api_endpoints.is_empty() {
+                        Cmd::batch([status, Cmd::new(async { Msg::LoadApiEndpoints })])
+                    } else {
+                        status
+                    }
+                } else {
+                    Cmd::none()
+                }
+            }
+            NavMsg::RouteChanged(path) => {
+                let new_page = Page::from_path(&path);
+                if new_page != self.current_page {
+                    self.current_page = new_page;
+                    // Load tasks if navigating to tasks page
+                    if self.current_page == Page::Tasks && self.tasks.is_empty() {
+                        Cmd::new(async { Msg::Task(TaskMsg::LoadTasks) })
+                    } else if self.current_page == Page::MyDay {
+                        Cmd::batch([Cmd::new(async { Msg::Task(TaskMsg::LoadAllTasks) }), Cmd::new(async { Msg::LoadPlan })])
+                    } else if self.current_page == Page::Dashboard {
+                        self.load_dashboard_data()
+                    } else if self.current_page == Page::Activity {
+                        Cmd::new(async { Msg::LoadActivity })
+                    } else if self.current_page == Page::Trash {
+                        Cmd::new(async { Msg::LoadTrash })
+                    } else if self.current_page == Page::Archive {
+                        Cmd::new(async { Msg::LoadArchive })
+                    } else if self.current_page == Page::Admin {
+                        self.load_admin_data()
+                    } else {
+                        Cmd::none()
+                    }
+                } else {
+                    Cmd::none()
+                }
+            }
+            NavMsg::Prefetch(page) => {
+                if page == self.current_page || self.prefetching.contains(&page) || self.prefetched.has_fresh(&page) {
+                    return Cmd::none();
+                }
+                let today = local_date(0);
+                let fetch = page.clone();
+                let cmd = match page {
+                    Page::Dashboard => Cmd::new(async move {
+                        Msg::Nav(NavMsg::PrefetchDone(fetch, fetch_tag_stats().await.ok().map(Prefetched::TagStats)))
+                    }),
+                    Page::MyDay => Cmd::new(async move {
+                        Msg::Nav(NavMsg::PrefetchDone(fetch, fetch_plan(&today).await.ok().map(Prefetched::Plan)))
+                    }),
+                    Page::Activity => Cmd::new(async move {
+                        Msg::Nav(NavMsg::PrefetchDone(fetch, fetch_activity(None).await.ok().map(Prefetched::Activity)))
+                    }),
+                    _ => return Cmd::none(),
+                };
+                self.prefetching.push(page);
+                cmd
+            }
+            NavMsg::PrefetchDone(page, data) => {
+                self.prefetching.retain(|p| *p != page);
+                if let Some(data) = data {
+                    self.prefetched.insert(page, data);
+                }
+                Cmd::none()
+            }
+        }
+    }
+
+    fn handle_task(&mut self, msg: TaskMsg) -> Cmd<Msg> {
+        match msg {
+            TaskMsg::LoadTasks => {
+                self.loading = true;
+                Cmd::new(async {
+                    // Taken before the list so nothing saved meanwhile is missed
+                    let version = fetch_changes(None).await.ok().map(|changes| changes.version);
+                    match api::fetch_tasks_page(1, TASK_PAGE_SIZE).await {
+                        Ok(list) => Msg::Task(TaskMsg::TaskPageLoaded(list, version)),
+                        Err(e) => Msg::Error(e),
+                    }
+                })
+            }
+            TaskMsg::LoadAllTasks => Cmd::new(async {
+                let version = fetch_changes(None).await.ok().map(|changes| changes.version);
+                match api::fetch_tasks().await {
+                    Ok(tasks) => Msg::Task(TaskMsg::TasksLoaded(tasks, version)),
+                    Err(e) => Msg::Error(e),
+                }
+            }),
+            TaskMsg::LoadMoreTasks(page) => {
+                // A stale observer from an earlier visit can fire late
+                if self.loading_more_tasks || self.tasks_next_page != Some(page) {
+                    return Cmd::none();
+                }
+                self.loading_more_tasks = true;
+                cmd::run(api::fetch_tasks_page(page, TASK_PAGE_SIZE), move |list| Msg::Task(TaskMsg::TaskPageLoaded(list, None)))
+            }
+            TaskMsg::TaskPageLoaded(list, version) => {
+                self.tasks_next_page = list.has_more().then_some(list.page + 1);
+                self.loading_more_tasks = false;
+                if list.page == 1 {
+                    return Cmd::batch([self.replace_tasks(list.data, version), self.watch_task_sentinel()]);
+                }
+
+                // Deletes since the last page shift later pages back, so a
+                // task may come round twice
+                let new_tasks: Vec<Task> = list
+                    .data
+                    .into_iter()
+                    .filter(|task| !self.tasks.iter().any(|t| t.id == task.id))
+                    .collect();
+                self.tasks.extend(new_tasks);
+                self.watch_task_sentinel()
+            }
+            TaskMsg::TasksLoaded(tasks, version) => {
+                self.tasks_next_page = None;
+                self.replace_tasks(tasks, version)
+            }
+            TaskMsg::ToggleTask(id) => {
+                console::log_1(&format!("[DEBUG] ToggleTask called for ID: {}", id).into());
+                let stamped = self.flags.is_enabled(flags::CRDT_SYNC);
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                    let old_completed = task.completed;
+                    let new_completed = !task.completed;
+                    console::log_1(&format!("[DEBUG] Task found - Title: '{}', Old completed: {}, New completed: {}", 
+                        task.title, old_completed, new_completed).into());
+                    
+                    // Set task as loading
+                    self.task_loading_states.insert(id, true);
+                    
+                    // OPTIMISTIC UPDATE: Update local state immediately for responsive UI
+                    task.completed = new_completed;
+                    console::log_1(&format!("[DEBUG] Optimistic update applied locally").into());
+
+                    if outbox::offline() {
+                        self.task_loading_states.remove(&id);
+                        let request = UpdateTaskRequest { completed: Some(new_completed), ..Default::default() };
+                        self.outbox.push(Mutation::Update { id, request });
+                        return Cmd::none();
+                    }
+                    
+                    // Then sync with server in background
+                    Cmd::new(async move {
+                        console::log_1(&format!("[DEBUG] Sending background sync request for task {}", id).into());
+                        let result = if stamped {
+                            let edit = TaskEdit { stamp: edit_stamp(), completed: Some(new_completed), ..Default::default() };
+                            send_edits(id, vec![edit]).await
+                        } else {
+                            api::update_task(id, UpdateTaskRequest { completed: Some(new_completed), ..Default::default() }).await
+                        };
+                        match result {
+                            Ok(updated_task) => {
+                                console::log_1(&format!("[DEBUG] Background sync successful - Task: '{}', Completed: {}", 
+                                    updated_task.title, updated_task.completed).into());
+                                // We could add a message to handle server-client sync conflicts if needed
+                                Msg::Task(TaskMsg::TaskUpdated(updated_task))
+                            },
+                            Err(e) => {
+                                console::log_1(&format!("[DEBUG] Background sync failed: {}, reverting optimistic update", e).into());
+                                // On error, revert the optimistic update
+                                Msg::Task(TaskMsg::RevertTaskToggle(id, old_completed))
+                            },
+                        }
+                    })
+                } else {
+                    console::log_1(&format!("[DEBUG] Task with ID {} not found in local state!", id).into());
+                    Cmd::none()
+                }
+            }
+            TaskMsg::TaskUpdated(updated_task) => {
+                console::log_1(&format!("[DEBUG] TaskUpdated received - ID: {}, Title: '{}', Completed: {}", 
+                    updated_task.id, updated_task.title, updated_task.completed).into());
+                
+                // Remove loading state for this task
+                self.task_loading_states.remove(&updated_task.id);
+                
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == updated_task.id) {
+                    // Only update if the server response differs from our current state
+                    // This prevents race conditions where stale responses overwrite newer state
+                    if task.completed != updated_task.completed {
+                        console::log_1(&format!("[DEBUG] Updating task state from server response").into());
+                        *task = updated_task;
+                    } else {
+                        console::log_1(&format!("[DEBUG] Server response matches current state, no update needed").into());
+                    }
+                } else {
+                    console::log_1(&format!("[DEBUG] WARNING: Could not find task {} in local state to update!", updated_task.id).into());
+                }
+                Cmd::none()
+            }
+            TaskMsg::RevertTaskToggle(id, original_completed) => {
+                console::log_1(&format!("[DEBUG] Reverting optimistic update for task {} to completed: {}", id, original_completed).into());
+                // Remove loading state for this task
+                self.task_loading_states.remove(&id);
+                
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                    task.completed = original_completed;
+                    console::log_1(&format!("[DEBUG] Optimistic update reverted successfully").into());
+                } else {
+                    console::log_1(&format!("[DEBUG] WARNING: Could not find task {} to revert!", id).into());
+                }
+                Cmd::none()
+            }
+            TaskMsg::DeleteTask(id) => {
+                if outbox::offline() {
+                    self.tasks.retain(|t| t.id != id);
+                    self.selected_tasks.remove(&id);
+                    // Never sent, so there's nothing to delete on the server
+                    let created_offline = self.outbox.iter().any(|m| matches!(m, Mutation::Create { local_id, .. } if *local_id == id));
+                    if created_offline {
+                        self.outbox.retain(|m| m.task_id() != id);
+                    } else {
+                        self.outbox.push(Mutation::Delete { id });
+                    }
+                    return Cmd::none();
+                }
+                let Some(task) = self.take_tasks(|t| t.id == id).pop() else {
+                    return Cmd::none();
+                };
+                let message = format!("Moved \"{}\" to the trash", task.title);
+                Cmd::new(async move {
+                    match api::delete_task(id).await {
+                        Ok(()) => Msg::Task(TaskMsg::TasksTrashed(vec![id], message)),
+                        Err(e) => Msg::Task(TaskMsg::DeleteFailed(vec![task], e)),
+                    }
+                })
+            }
+            TaskMsg::TasksTrashed(ids, message) => {
+                for id in &ids {
+                    self.task_loading_states.remove(id);
+                }
+                self.notify_undo(message, ids)
+            }
+            TaskMsg::DeleteFailed(tasks, error) => {
+                for task in tasks {
+                    self.upsert_task(task, true);
+                }
+                self.handle(Msg::Error(error))
+            }
+            TaskMsg::UndoDelete(toast_id) => {
+                let Some(toast) = self.toasts.iter().position(|toast| toast.id == toast_id).map(|i| self.toasts.remove(i)) else {
+                    return Cmd::none();
+                };
+                cmd::run(
+                    async move {
+                        let mut restored = Vec::new();
+                        for id in toast.undo {
+                            restored.push(restore_task(id).await?);
+                        }
+                        Ok::<_, String>(restored)
+                    },
+                    |tasks| Msg::Task(TaskMsg::DeleteUndone(tasks)),
+                )
+            }
+            TaskMsg::DeleteUndone(tasks) => {
+                for task in tasks {
+                    self.trash.retain(|t| t.id != task.id);
+                    // The live feed may have brought it back already
+                    self.upsert_task(task, true);
+                }
+                Cmd::none()
+            }
+            TaskMsg::ClearCompleted => {
+                let completed = self.take_tasks(|t| t.completed);
+                if completed.is_empty() {
+                    return Cmd::none();
+                }
+                let message = match completed.len() {
+                    1 => "Cleared 1 completed task".to_string(),
+                    count => format!("Cleared {} completed tasks", count),
+                };
+                let operations = completed.iter().map(|t| BulkOperation::Delete { id: t.id }).collect();
+                Cmd::new(async move {
+                    match bulk_update(BulkRequest { operations }).await {
+                        Ok(response) => {
+                            // Any the server had no longer are gone all the same
+                            Msg::Task(TaskMsg::TasksTrashed(response.deleted, message))
+                        }
+                        Err(e) => Msg::Task(TaskMsg::DeleteFailed(completed, e)),
+                    }
+                })
+            }
+            TaskMsg::ToggleCompletedSection => {
+                self.show_completed = !self.show_completed;
+                Cmd::none()
+            }
+            TaskMsg::SetTaskLoading(id, loading) => {
+                if loading {
+                    self.task_loading_states.insert(id, true);
+                } else {
+                    self.task_loading_states.remove(&id);
+                }
+                Cmd::none()
+            }
+            TaskMsg::SetQuickFilter(filter) => {
+                self.quick_filter = filter;
+                self.quick_filter_tasks = None;
+                let Some(filter) = filter else {
+                    self.jobs.cancel("quick_filter");
+                    return Cmd::none();
+                };
+                // A filter switched away from meanwhile is dropped when it loads
+                cmd::Job::new(fetch_recent(filter))
+                    .cancel_with(self.jobs.fresh("quick_filter"))
+                    .then(|tasks| Msg::Task(TaskMsg::QuickFilterLoaded(tasks)))
+            }
+            TaskMsg::QuickFilterLoaded(tasks) => {
+                self.quick_filter_tasks = Some(tasks);
+                Cmd::none()
+            }
+            TaskMsg::SetTaskSort(sort) => {
+                self.task_sort = sort;
+                Cmd::none()
+            }
+        }
+    }
+
+    fn handle_form(&mut self, msg: FormMsg) -> Cmd<Msg> {
+        match msg {
+            FormMsg::SetNewTaskTitle(task_title) => {
+                self.new_task_title = task_title;
+                self.create_errors.retain(|e| e.field != validation::TITLE);
+                Cmd::none()
+            }
+            FormMsg::SetNewTaskDescription(description) => {
+                self.new_task_description = description;
+                self.create_errors.retain(|e| e.field != validation::DESCRIPTION);
+                Cmd::none()
+            }
+            FormMsg::SetNewTaskPriority(priority) => {
+                self.new_task_priority = priority;
+                Cmd::none()
+            }
+            FormMsg::CreateTask => {
+                let task_title = self.new_task_title.clone();
+                let description = self.new_task_description.clone();
+                
+                if self.create_paused {
+                    return Cmd::none();
+                }
+                self.create_errors = validation::check(Some(task_title.trim()), Some(description.trim()));
+                if !self.create_errors.is_empty() {
+                    return Cmd::none();
+                }
+                
+                self.new_task_title.clear();
+                self.new_task_description.clear();
+                let request = CreateTaskRequest {
+                    title: task_title,
+                    description,
+                    tags: Vec::new(),
+                    priority: std::mem::take(&mut self.new_task_priority),
+                };
+
+                if outbox::offline() {
+                    let task = Task::new(request.title.trim().to_string(), request.description.trim().to_string())
+                        .with_priority(request.priority)
+                        .created_at(now_secs());
+                    let local_id = task.id;
+                    self.upsert_task(task, true);
+                    self.outbox.push(Mutation::Create { local_id, request });
+                    return Cmd::none();
+                }
+                
+                Cmd::new(async move {
+                    match api::create_task(request.clone()).await {
+                        Ok(task) => Msg::Form(FormMsg::TaskCreated(task)),
+                        Err(api::CreateError::Throttled(message, retry_after)) => Msg::Form(FormMsg::CreateThrottled(message, retry_after, Some(request))),
+                        Err(api::CreateError::Rejected(message, fields)) => Msg::Form(FormMsg::CreateRejected(message, fields, Some(request))),
+                        Err(api::CreateError::Failed(e)) => Msg::Error(e),
+                    }
+                })
+            }
+            FormMsg::CreateThrottled(message, retry_after, request) => {
+                self.restore_create_form(request);
+                self.create_paused = true;
+                Cmd::batch([
+                    Cmd::new(async move { Msg::ShowToast(Severity::Warning, message) }),
+                    Cmd::new(async move {
+                        sleep(retry_after * 1000).await;
+                        Msg::Form(FormMsg::CreateResumed)
+                    }),
+                ])
+            }
+            FormMsg::CreateResumed => {
+                self.create_paused = false;
+                Cmd::none()
+            }
+            FormMsg::CreateRejected(message, fields, request) => {
+                // Field errors go under the inputs, if the task is back in them
+                if self.restore_create_form(request) && !fields.is_empty() {
+                    self.create_errors = fields;
+                    return Cmd::none();
+                }
+                Cmd::new(async move { Msg::ShowToast(Severity::Warning, message) })
+            }
+            FormMsg::TaskCreated(task) => {
+                // The live event for it may have come in first
+                self.upsert_task(task, true);
+                Cmd::none()
+            }
+            FormMsg::TaskSaved(saved_task) => {
+                // Remove loading state for this task
+                self.task_loading_states.remove(&saved_task.id);
+                
+                // Update the task in the list
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == saved_task.id) {
+                    *task = saved_task;
+                }
+                Cmd::none()
+            }
+            FormMsg::EditConflict(current, draft) => {
+                self.task_loading_states.remove(&current.id);
+                // Reopen the form with the user's changes still in it, now
+                // based on the current version, unless they moved on
+                let draft = EditDraft { version: current.version, conflict: true, ..draft };
+                self.edit.reopen(current.id, draft);
+                if let Some(task) = self.tasks.iter_mut().find(|t| t.id == current.id) {
+                    *task = current;
+                }
+                Cmd::none()
+            }
+            FormMsg::EditTask(id) => {
+                let switching = self.edit.editing().is_some_and(|editing| editing != id);
+                if switching && self.edit.is_dirty() && !discard_unsaved_edit() {
+                    return Cmd::none();
+                }
+                if let Some(task) = self.tasks.iter().find(|t| t.id == id) {
+                    self.edit = EditState::Editing { id, draft: EditDraft::from_task(task) };
+                }
+                Cmd::none()
+            }
+            FormMsg::SetEditTitle(task_title) => {
+                self.edit.change(|draft| {
+                    draft.title = task_title;
+                    draft.errors.retain(|e| e.field != validation::TITLE);
+                });
+                Cmd::none()
+            }
+            FormMsg::SetEditDescription(description) => {
+                self.edit.change(|draft| {
+                    draft.description = description;
+                    draft.errors.retain(|e| e.field != validation::DESCRIPTION);
+                });
+                Cmd::none()
+            }
+            FormMsg::SetEditPriority(priority) => {
+                self.edit.change(|draft| draft.priority = priority);
+                Cmd::none()
+            }
+            FormMsg::SetEditTags(tags) => {
+                self.edit.change(|draft| draft.tags = tags);
+                Cmd::none()
+            }
+            FormMsg::SetEditLocationName(name) => {
+                self.edit.change(|draft| draft.location_name = name);
+                Cmd::none()
+            }
+            FormMsg::SetEditRepeat(repeat) => {
+                self.edit.change(|draft| draft.repeat = repeat);
+                Cmd::none()
+            }
+            FormMsg::SetEditCron(expression) => {
+                self.edit.change(|draft| draft.cron = expression);
+                Cmd::none()
+            }
+            FormMsg::SetEditLocationCoordinates(coordinates) => {
+                self.edit.change(|draft| draft.location_coordinates = coordinates);
+                Cmd::none()
+            }
+            FormMsg::SetEditLocationRadius(radius) => {
+                self.edit.change(|draft| draft.location_radius = radius);
+                Cmd::none()
+            }
+            FormMsg::UseCurrentLocation => Cmd::new(async {
+                match geolocation::current_position().await {
+                    Ok((lat, lng)) => Msg::Form(FormMsg::SetEditLocationCoordinates(geolocation::format_coordinates(lat, lng))),
+                    Err(e) => Msg::ShowToast(Severity::Warning, e),
+                }
+            }),
+            FormMsg::SaveEdit(id) => {
+                // Only the open form can be saved, and only once
+                let Some(draft) = self.edit.draft(id) else {
+                    return Cmd::none();
+                };
+                
+                let task_title = draft.title.clone();
+                let description = draft.description.clone();
+                let priority = draft.priority;
+                let tags = shared::normalize_tags(draft.tags.split(','));
+                let location = match geolocation::parse_location(
+                    &draft.location_name,
+                    &draft.location_coordinates,
+                    &draft.location_radius,
+                ) {
+                    Ok(location) => location,
+                    Err(e) => return Cmd::new(async move { Msg::ShowToast(Severity::Warning, e) }),
+                };
+                let recurrence = match recurrence_rule(&draft.repeat, &draft.cron) {
+                    Ok(recurrence) => recurrence,
+                    Err(e) => return Cmd::new(async move { Msg::ShowToast(Severity::Warning, e) }),
+                };
+                // Same rules as the server, which skips fields left as they were
+                let checked = self.tasks.iter().find(|t| t.id == id).map(|before| {
+                    let mut edited = before.clone();
+                    edited.title = task_title.clone();
+                    edited.description = description.clone();
+                    validation::check_task(Some(before), &mut edited)
+                });
+                if let Some(Err(errors)) = checked {
+                    if let Some(draft) = self.edit.draft_mut() {
+                        draft.errors = errors;
+                    }
+                    return Cmd::none();
+                }
+                // Closing the form keeps the draft from being saved twice
+                let Some(draft) = self.edit.take(id) else {
+                    return Cmd::none();
+                };
+                let draft = EditDraft { errors: Vec::new(), ..draft };
+
+                if outbox::offline() {
+                    if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
+                        task.title = task_title.trim().to_string();
+                        task.description = description.trim().to_string();
+                        task.priority = priority;
+                        task.tags = tags.clone();
+                        task.location = location.clone();
+                        task.recurrence = recurrence.clone();
+                    }
+                    let request = UpdateTaskRequest {
+                        title: Some(task_title),
+                        description: Some(description),
+                        priority: Some(priority),
+                        tags: Some(tags),
+                        location: Some(location),
+                        recurrence: Some(recurrence),
+                        ..Default::default()
+                    };
+                    self.outbox.push(Mutation::Update { id, request });
+                    return Cmd::none();
+                }
+                
+                // Set task as loading
+                self.task_loading_states.insert(id, true);
+
+                // Stamped edits merge with ones made on other devices; the
+                // location and repeat rule aren't part of them and are only
+                // sent when changed
+                if self.flags.is_enabled(flags::CRDT_SYNC) {
+                    let Some(before) = self.tasks.iter().find(|t| t.id == id).cloned() else {
+                        return Cmd::none();
+                    };
+                    let mut edit = TaskEdit {
+                        stamp: edit_stamp(),
+                        title: Some(task_title),
+                        description: Some(description),
+                        priority: Some(priority),
+                        ..Default::default()
+                    };
+                    edit.diff_tags(&before.tags, &tags);
+                    return Cmd::new(async move {
+                        let mut result = send_edits(id, vec![edit]).await;
+                        if result.is_ok() && (location != before.location || recurrence != before.recurrence) {
+                            let request = UpdateTaskRequest {
+                                location: (location != before.location).then_some(location),
+                                recurrence: (recurrence != before.recurrence).then_some(recurrence),
+                                ..Default::default()
+                            };
+                            result = api::update_task(id, request).await;
+                        }
+                        match result {
+                            Ok(task) => Msg::Form(FormMsg::TaskSaved(task)),
+                            Err(e) => Msg::Error(e),
+                        }
+                    });
+                }
+                
+                let version = draft.version;
+                Cmd::new(async move {
+                    match api::patch_task(id, UpdateTaskRequest {
+                        title: Some(task_title),
+                        description: Some(description),
+                        priority: Some(priority),
+                        tags: Some(tags),
+                        location: Some(location),
+                        recurrence: Some(recurrence),
+                        version: Some(version),
+                        ..Default::default()
+                    }).await {
+                        Ok(task) => Msg::Form(FormMsg::TaskSaved(task)),
+                        Err(api::UpdateError::Conflict(task)) => Msg::Form(FormMsg::EditConflict(task, draft)),
+                        Err(api::UpdateError::Invalid(fields)) => Msg::Form(FormMsg::EditInvalid(id, fields, draft)),
+                        Err(api::UpdateError::Failed(e)) => Msg::Error(e),
+                    }
+                })
+            }
+            FormMsg::EditInvalid(id, fields, draft) => {
+                self.task_loading_states.remove(&id);
+                // Reopen the form with the user's changes, unless they moved on
+                let draft = EditDraft { errors: fields.clone(), ..draft };
+                if !self.edit.reopen(id, draft) && self.edit.editing() == Some(id) {
+                    if let Some(draft) = self.edit.draft_mut() {
+                        draft.errors = fields;
+                    }
+                }
+                Cmd::none()
+            }
+            FormMsg::CancelEdit => {
+                self.edit = EditState::NotEditing;
+                Cmd::none()
+            }
+        }
+    }
+
+    fn handle_settings(&mut self, msg: SettingsMsg) -> Cmd<Msg> {
+        match msg {
+            SettingsMsg::ImportTasks(files) => {
+                let Some(file) = files.into_iter().next() else {
+                    return Cmd::none();
+                };
+                self.importing = true;
+                self.import_summary = None;
+                self.import_preview = None;
+                self.import_file = Some(file.clone());
+                Cmd::new(async move { Msg::Settings(SettingsMsg::ImportPreviewed(import_tasks(&file, true).await)) })
+            }
+            SettingsMsg::ImportPreviewed(result) => {
+                self.importing = false;
+                match result {
+                    Ok(preview) => {
+                        self.import_preview = Some(preview);
+                        Cmd::none()
+                    }
+                    Err(e) => {
+                        self.import_file = None;
+                        self.handle(Msg::Error(e))
+                    }
+                }
+            }
+            SettingsMsg::ConfirmImport => {
+                let Some(file) = self.import_file.take() else {
+                    return Cmd::none();
+                };
+                self.importing = true;
+                self.import_preview = None;
+                Cmd::new(async move { Msg::Settings(SettingsMsg::TasksImported(import_tasks(&file, false).await)) })
+            }
+            SettingsMsg::CancelImport => {
+                self.import_file = None;
+                self.import_preview = None;
+                Cmd::none()
+            }
+            SettingsMsg::TasksImported(result) => {
+                self.importing = false;
+                match result {
+                    Ok(summary) => {
+                        let imported = summary.imported;
+                        let severity = if summary.errors.is_empty() { Severity::Info } else { Severity::Warning };
+                        self.import_summary = Some(summary);
+                        let toast = self.notify(severity, format!("Imported {} task{}", imported, if imported == 1 { "" } else { "s" }));
+                        if imported > 0 {
+                            return Cmd::batch([toast, Cmd::new(async { Msg::Task(TaskMsg::LoadTasks) })]);
+                        }
+                        toast
+                    }
+                    Err(e) => Cmd::new(async move { Msg::Error(e) }),
+                }
+            }
+            SettingsMsg::SetConfirmPreference(action, enabled) => {
+                self.confirm_preferences.set(action, enabled);
+                self.confirm_preferences.save();
+                Cmd::none()
+            }
+            SettingsMsg::SetTheme(theme) => {
+                self.theme = theme;
+                theme.save();
+                theme.apply();
+                Cmd::none()
+            }
+        }
+    }
+
+    /// Shows a toast that dismisses itself after a while. A message that's
+    /// already on screen isn't stacked again.
+    fn notify(&mut self, severity: Severity, message: String) -> Cmd<Msg> {
+        if self.toasts.iter().any(|toast| toast.severity == severity && toast.message == message) {
+            return Cmd::none();
+        }
+        if self.toasts.len() >= notifications::MAX_VISIBLE {
+            self.toasts.remove(0);
+        }
+
+        self.next_toast_id += 1;
+        let toast_id = self.next_toast_id;
+        self.toasts.push(Toast { id: toast_id, severity, message, undo: Vec::new() });
+        Cmd::new(async move {
+            sleep(severity.duration_ms()).await;
+            Msg::DismissToast(toast_id)
+        })
+    }
+
+    /// A toast whose "Undo" restores the trashed tasks `ids`, up for
+    /// [`notifications::UNDO_MS`].
+    fn notify_undo(&mut self, message: String, ids: Vec<Uuid>) -> Cmd<Msg> {
+        if ids.is_empty() {
+            return Cmd::none();
+        }
+        if self.toasts.len() >= notifications::MAX_VISIBLE {
+            self.toasts.remove(0);
+        }
+
+        self.next_toast_id += 1;
+        let toast_id = self.next_toast_id;
+        self.toasts.push(Toast { id: toast_id, severity: Severity::Info, message, undo: ids });
+        Cmd::new(async move {
+            sleep(notifications::UNDO_MS).await;
+            Msg::DismissToast(toast_id)
+        })
+    }
+
+    /// Takes the tasks `remove` picks out of the list, and out of the
+    /// selection, for a deletion that can still be put back.
+    fn take_tasks(&mut self, remove: impl Fn(&Task) -> bool) -> Vec<Task> {
+        let (taken, kept): (Vec<Task>, Vec<Task>) = std::mem::take(&mut self.tasks).into_iter().partition(remove);
+        self.tasks = kept;
+        for task in &taken {
+            self.selected_tasks.remove(&task.id);
+        }
+        taken
+    }
+
+    fn replace_tasks(&mut self, tasks: Vec<Task>, version: Option<u64>) -> Cmd<Msg> {
+        console::log_1(&format!("[DEBUG] TasksLoaded - {} tasks received", tasks.len()).into());
+        for (i, task) in tasks.iter().enumerate() {
+            console::log_1(&format!("[DEBUG] Task {}: ID={}, Title='{}', Completed={}", 
+                i, task.id, task.title, task.completed).into());
+        }
+        self.tasks = tasks;
+        self.loading = false;
+        if version.is_some() {
+            self.sync_version = version;
+        }
+
+        let focus = match self.focused_task {
+            Some(id) => Cmd::new(async move {
+                // Wait for the list to render before scrolling to the card
+                sleep(0).await;
+                scroll_to_task(id);
+                Msg::TaskFocused
+            }),
+            None => Cmd::none(),
+        };
+        Cmd::batch([focus, self.schedule_snooze_wake(), self.watch_for_resume()])
+    }
+
+    /// Puts a refused task back in the create form, unless the user has
+    /// already started typing the next one. Returns whether it did.
+    fn restore_create_form(&mut self, request: Option<CreateTaskRequest>) -> bool {
+        match request.filter(|_| self.new_task_title.is_empty()) {
+            Some(request) => {
+                self.new_task_title = request.title;
+                self.new_task_description = request.description;
+                self.new_task_priority = request.priority;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replaces the task with the same id, or adds it if `insert` is set.
+    /// Sends `operations` as one all-or-nothing bulk request.
+    fn apply_bulk(&mut self, operations: Vec<BulkOperation>) -> Cmd<Msg> {
+        if operations.is_empty() {
+            return Cmd::none();
+        }
+        let ids: Vec<Uuid> = operations.iter().map(BulkOperation::id).collect();
+        for id in &ids {
+            self.task_loading_states.insert(*id, true);
+        }
+        Cmd::new(async move {
+            match bulk_update(BulkRequest { operations }).await {
+                Ok(response) => Msg::BulkApplied(response),
+                Err(e) => Msg::BulkFailed(ids, e),
+            }
+        })
+    }
+
+    /// Waits for the next key of the running triage session, focusing the
+    /// tag input first if it's asking for a tag.
+    fn listen_for_triage_key(&self) -> Cmd<Msg> {
+        let Some(session) = &self.triage else {
+            return Cmd::none();
+        };
+        let session_id = session.id;
+        let asking_for_tag = session.prompt == Some(triage::Prompt::Tag);
+        Cmd::new(async move {
+            if asking_for_tag {
+                // Wait for the input to render
+                sleep(0).await;
+                triage::focus_tag_input();
+            }
+            Msg::TriageKey(session_id, triage::next_key().await)
+        })
+    }
+
+    /// Moves triage on to the next task, ending it after the last one.
+    fn advance_triage(&mut self) -> Cmd<Msg> {
+        let Some(session) = self.triage.as_mut() else {
+            return Cmd::none();
+        };
+        if session.advance() {
+            return Cmd::none();
+        }
+        let (_, count) = session.progress();
+        self.triage = None;
+        let tasks = if count == 1 { "task" } else { "tasks" };
+        self.notify(Severity::Info, format!("Triage done: went through {} {}.", count, tasks))
+    }
+
+    fn upsert_task(&mut self, task: Task, insert: bool) {
+        match self.tasks.iter_mut().find(|t| t.id == task.id) {
+            Some(existing) => *existing = task,
+            None if insert => self.tasks.push(task),
+            None => {}
+        }
+    }
+
+    /// Fetches tasks changed since the last sync.
+    fn sync_changes(&self) -> Cmd<Msg> {
+        let Some(since) = self.sync_version else {
+            return Cmd::none();
+        };
+        // Tasks that haven't paged in yet will arrive with their page
+        let known: std::collections::HashSet<Uuid> = self.tasks.iter().map(|t| t.id).collect();
+        let all_loaded = self.tasks_next_page.is_none();
+        Cmd::new(async move {
+            let changes = match fetch_changes(Some(since)).await {
+                Ok(changes) => changes,
+                Err(e) => return Msg::SyncFailed(e),
+            };
+            let mut tasks = Vec::new();
+            for id in changes.changed.iter().filter(|id| all_loaded || known.contains(id)) {
+                match api::fetch_task(*id).await {
+                    Ok(Some(task)) => tasks.push(task),
+                    // Deleted since; the next sync reports it
+                    Ok(None) => {}
+                    Err(e) => return Msg::SyncFailed(e),
+                }
+            }
+            Msg::ChangesSynced(changes, tasks)
+        })
+    }
+
+    /// Restores the offline copy unless something newer already arrived.
+    fn hydrate(&mut self, snapshot: local_store::Snapshot) -> bool {
+        if self.sync_version.is_some() {
+            return false;
+        }
+        let meta = snapshot.meta;
+        self.tasks = snapshot.tasks;
+        self.tasks_next_page = meta.tasks_next_page;
+        self.sync_version = meta.sync_version;
+        self.show_completed = meta.show_completed;
+        // Flags may have loaded first
+        if self.flags == FeatureFlags::default() {
+            self.flags = meta.flags;
+        }
+        true
+    }
+
+    /// Writes the offline copy once the current burst of updates is over.
+    fn schedule_local_save(&mut self) -> Cmd<Msg> {
+        if self.local_save_pending {
+            return Cmd::none();
+        }
+        self.local_save_pending = true;
+        Cmd::new(async {
+            sleep(local_store::SAVE_DELAY_MS).await;
+            Msg::SaveLocalStore
+        })
+    }
+
+    /// Resyncs tasks the next time the tab is shown or the network returns.
+    fn watch_for_resume(&mut self) -> Cmd<Msg> {
+        if self.sync_pending || self.sync_version.is_none() {
+            return Cmd::none();
+        }
+        self.sync_pending = true;
+        Cmd::new(async {
+            sync::resumed().await;
+            Msg::SyncChanges
+        })
+    }
+
+    /// Dashboard stats count every task, so finish paging first.
+    fn load_dashboard_data(&self) -> Cmd<Msg> {
+        let mut cmds = vec![Cmd::new(async { Msg::LoadTagStats }), Cmd::new(async { Msg::LoadAnalytics })];
+        if self.tasks_next_page.is_some() {
+            cmds.push(Cmd::new(async { Msg::Task(TaskMsg::LoadAllTasks) }));
+        }
+        Cmd::batch(cmds)
+    }
+
+    /// Loads the next page once the end of the task list scrolls into view.
+    fn watch_task_sentinel(&self) -> Cmd<Msg> {
+        let Some(page) = self.tasks_next_page else {
+            return Cmd::none();
+        };
+        Cmd::new(async move {
+            if infinite_scroll::sentinel_visible("tasks-sentinel").await {
+                Msg::Task(TaskMsg::LoadMoreTasks(page))
+            } else {
+                Msg::SentinelDetached
+            }
+        })
+    }
+
+    /// Persists the current plan; the server echoes it back de-duplicated.
+    fn save_plan(&self) -> Cmd<Msg> {
+        let Some(plan) = self.plan.clone() else {
+            return Cmd::none();
+        };
+        cmd::run(async move { save_plan(&plan.date, plan.task_ids).await }, Msg::PlanSaved)
+    }
+
+    /// Sleeps until the earliest snoozed task is due back, unless a wake-up
+    /// for that time or earlier is already pending.
+    fn schedule_snooze_wake(&mut self) -> Cmd<Msg> {
+        let now = now_secs();
+        let Some(next) = self.tasks.iter().filter_map(|t| t.snoozed_until).filter(|until| *until > now).min() else {
+            return Cmd::none();
+        };
+        if self.snooze_wake_at.is_some_and(|scheduled| scheduled <= next) {
+            return Cmd::none();
+        }
+
+        self.snooze_wake_at = Some(next);
+        // setTimeout can't wait longer than ~24 days; waking early just reschedules
+        let delay_ms = ((next - now) * 1000).min(i32::MAX as u64) as i32;
+        Cmd::new(async move {
+            sleep(delay_ms).await;
+            Msg::SnoozeExpired
+        })
+    }
+
+    /// Asks the user to confirm `action` unless they turned that prompt off.
+    fn confirm(&self, action: ConfirmAction, message: &str) -> bool {
+        !self.confirm_preferences.get(action)
+            || window()
+                .and_then(|w| w.confirm_with_message(message).ok())
+                .unwrap_or(false)
+    }
+
+    /// The workspace whose automation rules are being edited and its rules.
+    /// A workspace without rules of its own starts from the global ones.
+    fn scoped_automation_rules(&self) -> (Option<String>, Vec<AutomationRule>) {
+        let settings = self.automations.clone().unwrap_or_default();
+        match self.automation_workspace.trim() {
+            "" => (None, settings.rules),
+            workspace => {
+                let rules = settings.workspaces.get(workspace).cloned().unwrap_or(settings.rules);
+                (Some(workspace.to_string()), rules)
+            }
+        }
+    }
+
+    fn load_admin_data(&self) -> Cmd<Msg> {
+        if !self.admin_signed_in {
+            return Cmd::none();
+        }
+        Cmd::batch([
+            Cmd::new(async { Msg::LoadOverview }),
+            Cmd::new(async { Msg::LoadExperimentResults }),
+            Cmd::new(async { Msg::LoadClientErrors }),
+            Cmd::new(async { Msg::LoadServerConfig }),
+            Cmd::new(async { Msg::LoadContentFilter }),
+            Cmd::new(async { Msg::LoadAutomations }),
+            Cmd::new(async { Msg::LoadLogs }),
+        ])
+    }
+
+    fn view_header(&self) -> Node<Msg> {
+        header([class("bg-ctp-mantle shadow-lg border-b border-ctp-surface0")], [
+            div([class("max-w-6xl mx-auto px-6 py-4")], [
+                div([class("flex items-center justify-between")], [
+                    h1([class("text-2xl font-bold text-ctp-text")], [text("Full-Stack Rust Demo")]),
+                    div([class("flex items-center space-x-8")], [
+                        nav([class("flex space-x-8")], [
+                            self.nav_link("Tasks", Page::Tasks),
+                            self.nav_link("My Day", Page::MyDay),
+                            self.nav_link("Activity", Page::Activity),
+                            self.nav_link("Trash", Page::Trash),
+                            self.nav_link("Archive", Page::Archive),
+                            self.nav_link("Dashboard", Page::Dashboard),
+                            self.nav_link("Settings", Page::Settings),
+                            if self.admin_signed_in {
+                                self.nav_link("Admin", Page::Admin)
+                            } else {
+                                span([], [])
+                            },
+                        ]),
+                        self.view_sync_status(),
+                        self.view_whats_new(),
+                    ]),
+                ]),
+            ]),
+        ])
+    }
+
+    fn view_task_qr(&self) -> Node<Msg> {
+        let Some(task) = self.qr_task.and_then(|id| self.tasks.iter().find(|t| t.id == id)) else {
+            return span([], []);
+        };
+
+        div([class("fixed inset-0 z-40 flex items-center justify-center")], [
+            div([
+                on_click(|_| Msg::CloseTaskQr),
+                class("absolute inset-0 bg-ctp-crust/60"),
+            ], []),
+            div([class("relative bg-ctp-surface0 border border-ctp-surface1 rounded-lg shadow-lg p-6 text-center")], [
+                h3([class("text-lg font-semibold text-ctp-text mb-4")], [text(&task.title)]),
+                img([
+                    src(&base::url(&format!("/api/tasks/{}/qr", task.id))),
+                    alt("QR code linking to this task"),
+                    class("w-64 h-64 mx-auto bg-white rounded"),
+                ], []),
+                p([class("text-sm text-ctp-subtext0 mt-3")], [text("Scan to open this task on your phone.")]),
+                button([
+                    on_click(|_| Msg::CloseTaskQr),
+                    class("mt-4 bg-ctp-surface1 hover:bg-ctp-surface2 text-ctp-text px-4 py-2 rounded-md text-sm transition-colors duration-200"),
+                ], [text("Close")]),
+            ]),
+        ])
+    }
+
+    fn view_triage(&self) -> Node<Msg> {
+        let Some(session) = &self.triage else {
+            return span([], []);
+        };
+        let task = session.current().and_then(|id| self.tasks.iter().find(|t| t.id == id));
+        let (position, count) = session.progress();
+
+        let key = |key: &str, label: &str| -> Node<Msg> {
+            li([class("flex items-center gap-2")], [
+                kbd([class("min-w-[2rem] text-center bg-ctp-surface1 text-ctp-text px-2 py-0.5 rounded text-xs font-mono")], [text(key)]),
+                span([], [text(label)]),
+            ])
+        };
+        let keys = match session.prompt {
+            None => ul([class("grid grid-cols-2 gap-2 text-sm text-ctp-subtext1")], [
+                key("d", "Set due"),
+                key("p", "Set priority"),
+                key("t", "Add tag"),
+                key("c", "Complete"),
+                key("Enter", "Next task"),
+                key("Esc", "Stop triage"),
+            ]),
+            Some(triage::Prompt::Due) => ul(
+                [class("space-y-2 text-sm text-ctp-subtext1")],
+                triage::DUE_PRESETS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, preset)| key(&(i + 1).to_string(), preset.due_label()))
+                    .chain(std::iter::once(key("Esc", "Cancel")))
+                    .collect::<Vec<_>>(),
+            ),
+            Some(triage::Prompt::Priority) => ul(
+                [class("space-y-2 text-sm text-ctp-subtext1")],
+                Priority::ALL
+                    .iter()
+                    .enumerate()
+                    .map(|(i, priority)| key(&(i + 1).to_string(), priority.label()))
+                    .chain(std::iter::once(key("Esc", "Cancel")))
+                    .collect::<Vec<_>>(),
+            ),
+            Some(triage::Prompt::Tag) => div([class("space-y-2")], [
+                input([
+                    id(triage::TAG_INPUT),
+                    r#type("text"),
+                    placeholder("Tag"),
+                    value(&session.tag),
+                    on_input(|event| Msg::SetTriageTag(event.value())),
+                    class("w-full px-3 py-2 bg-ctp-surface0 border border-ctp-surface2 rounded-md text-sm text-ctp-text placeholder-ctp-subtext0"),
+                ], []),
+                p([class("text-xs text-ctp-subtext0")], [text("Enter adds the tag, Esc cancels.")]),
+            ]),
+        };
+
+        div([class("fixed inset-0 z-40 flex items-center justify-center")], [
+            div([class("absolute inset-0 bg-ctp-crust/60")], []),
+            div([class("relative w-full max-w-lg bg-ctp-surface0 border border-ctp-surface1 rounded-lg shadow-lg p-6 space-y-4")], [
+                div([class("flex items-center justify-between")], [
+                    h3([class("text-sm font-medium text-ctp-subtext0")], [text(&format!("Triage \u{00b7} {} of {}", position, count))]),
+                    button([
+                        on_click(|_| Msg::EndTriage),
+                        class("text-sm text-ctp-subtext0 hover:text-ctp-text"),
+                    ], [text("Close")]),
+                ]),
+                match task {
+                    Some(task) => div([class("space-y-2")], [
+                        h2([class("text-lg font-semibold text-ctp-text")], [text(&task.title)]),
+                        if task.description.is_empty() {
+                            span([], [])
+                        } else {
+                            p([class("text-sm text-ctp-subtext1 line-clamp-3")], [text(&task.description)])
+                        },
+                        div(
+                            [class("flex flex-wrap items-center gap-2")],
+                            std::iter::once(view_priority_badge(task.priority))
+                                .chain(task.tags.iter().map(|tag| {
+                                    span([class("bg-ctp-mauve/20 text-ctp-mauve px-2 py-0.5 rounded-full text-xs")], [text(&format!("#{}", tag))])
+                                }))
+                                .chain(task.due_at.map(|due| {
+                                    span([class("text-xs text-ctp-peach")], [text(&format!("📅 due {}", format_timestamp(due)))])
+                                }))
+                                .chain(task.snoozed_until.map(|until| {
+                                    span([class("text-xs text-ctp-lavender")], [text(&format!("💤 until {}", format_timestamp(until)))])
+                                }))
+                                .collect::<Vec<_>>(),
+                        ),
+                    ]),
+                    None => p([class("text-sm text-ctp-subtext0 italic")], [text("This task was deleted meanwhile. Press Enter to skip it.")]),
+                },
+                keys,
+            ]),
+        ])
+    }
+
+    fn view_sync_status(&self) -> Node<Msg> {
+        let pending = self.outbox.len();
+        let (dot, label) = if outbox::offline() {
+            ("bg-ctp-red", if pending == 0 { "Offline".to_string() } else { format!("Offline \u{00b7} {} pending", pending) })
+        } else if self.outbox_sending {
+            ("bg-ctp-yellow", format!("Syncing {}...", pending))
+        } else if pending > 0 {
+            ("bg-ctp-yellow", format!("{} pending", pending))
+        } else {
+            ("bg-ctp-green", "Synced".to_string())
+        };
+
+        button([
+            on_click(|_| Msg::ReplayOutbox),
+            disabled(pending == 0),
+            attr("title", "Changes made offline are sent when the connection is back"),
+            class("flex items-center gap-2 text-sm text-ctp-subtext0 hover:text-ctp-text disabled:hover:text-ctp-subtext0"),
+        ], [
+            span([class(&format!("inline-block w-2 h-2 rounded-full {}", dot))], []),
+            text(&label),
+        ])
+    }
+
+    fn view_whats_new(&self) -> Node<Msg> {
+        let unread = self.announcements.iter().filter(|a| a.published_at > self.announcements_seen).count();
+
+        div([class("relative")], [
+            button(
+                [
+                    on_click(|_| Msg::ToggleAnnouncements),
+                    class("relative text-sm font-medium text-ctp-subtext1 hover:text-ctp-text transition-colors duration-200"),
+                ],
+                [
+                    text("What's new"),
+                    if unread > 0 {
+                        span(
+                            [class("absolute -top-2 -right-4 bg-ctp-red text-ctp-base text-xs font-bold rounded-full px-1.5")],
+                            [text(unread.to_string())],
+                        )
+                    } else {
+                        span([], [])
+                    },
+                ],
+            ),
+            if self.announcements_open {
+                div(
+                    [class("absolute right-0 mt-3 w-80 max-h-96 overflow-y-auto bg-ctp-surface0 border border-ctp-surface1 rounded-lg shadow-lg p-4 z-20 space-y-4")],
+                    if self.announcements.is_empty() {
+                        vec![p([class("text-sm text-ctp-subtext0 italic")], [text("Nothing new yet.")])]
+                    } else {
+                        self.announcements.iter().map(|announcement| {
+                            div([], [
+                                div([class("flex items-baseline justify-between gap-3")], [
+                                    h3([class("font-semibold text-ctp-text")], [text(&announcement.title)]),
+                                    span([class("text-xs text-ctp-subtext0 whitespace-nowrap")], [text(&format_timestamp(announcement.published_at))]),
+                                ]),
+                                p([class("text-sm text-ctp-subtext1 whitespace-pre-line mt-1")], [text(&announcement.body)]),
+                            ])
+                        }).collect::<Vec<_>>()
+                    },
+                )
+            } else {
+                span([], [])
+            },
+        ])
+    }
+
+    /// Wraps `node` so it can be found and highlighted by the tour's `step`.
+    fn tour_target(&self, step: tour::Step, node: Node<Msg>) -> Node<Msg> {
+        let highlighted = self.tour == Some(step);
+        div([
+            id(step.target()),
+            class(if highlighted { "relative z-40 rounded-lg ring-4 ring-ctp-mauve ring-offset-4 ring-offset-ctp-base" } else { "" }),
+        ], [node])
+    }
+
+    fn view_tour(&self) -> Node<Msg> {
+        let Some(step) = self.tour else {
+            return span([], []);
+        };
+        let next = step.next();
+
+        div([], [
+            div([class("fixed inset-0 bg-ctp-crust/60 z-30")], []),
+            div([class("fixed bottom-6 left-1/2 -translate-x-1/2 w-96 bg-ctp-surface0 border border-ctp-mauve rounded-lg shadow-lg p-5 z-50")], [
+                div([class("flex items-center justify-between mb-2")], [
+                    h3([class("text-lg font-semibold text-ctp-text")], [text(step.title())]),
+                    span([class("text-xs text-ctp-subtext0")], [text(&format!("{} of {}", step.index() + 1, tour::Step::ALL.len()))]),
+                ]),
+                p([class("text-sm text-ctp-subtext1 mb-4")], [text(step.description())]),
+                div([class("flex items-center justify-between")], [
+                    button([
+                        on_click(|_| Msg::EndTour),
+                        class("text-sm text-ctp-subtext0 hover:text-ctp-text transition-colors"),
+                    ], [text("Skip tour")]),
+                    div([class("flex gap-2")], [
+                        match step.previous() {
+                            Some(previous) => button([
+                                on_click(move |_| Msg::ShowTourStep(previous)),
+                                class("px-3 py-1 bg-ctp-surface1 hover:bg-ctp-surface2 rounded-md text-sm text-ctp-text transition-colors"),
+                            ], [text("Back")]),
+                            None => span([], []),
+                        },
+                        button([
+                            on_click(move |_| match next {
+                                Some(next) => Msg::ShowTourStep(next),
+                                None => Msg::EndTour,
+                            }),
+                            class("px-3 py-1 bg-ctp-mauve hover:bg-ctp-pink rounded-md text-sm text-ctp-base font-medium transition-colors"),
+                        ], [text(if next.is_some() { "Next" } else { "Done" })]),
+                    ]),
+                ]),
+            ]),
+        ])
+    }
+
+    fn view_update_banner(&self) -> Node<Msg> {
+        if !self.update_available {
+            return span([], []);
+        }
+
+        div([class("bg-ctp-blue/20 border-b border-ctp-blue text-ctp-text")], [
+            div([class("max-w-6xl mx-auto px-6 py-2 flex items-center justify-between text-sm")], [
+                span([], [text("A new version has been deployed.")]),
+                button([
+                    on_click(|_| Msg::ReloadApp),
+                    class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-3 py-1 rounded-md transition-colors duration-200"),
+                ], [text("Reload")]),
+            ]),
+        ])
+    }
+
+    fn view_degraded_banner(&self) -> Node<Msg> {
+        if !self.backend_degraded {
+            return span([], []);
+        }
+
+        div([class("bg-ctp-red/20 border-b border-ctp-red text-ctp-text")], [
+            div([class("max-w-6xl mx-auto px-6 py-2 text-sm")], [
+                text("Having trouble reaching the server. Recent changes may not be saved yet; we'll keep trying."),
+            ]),
+        ])
+    }
+
+    fn view_toasts(&self) -> Node<Msg> {
+        div(
+            [class("fixed bottom-6 right-6 flex flex-col items-end gap-2 z-50")],
+            self.toasts.iter().map(|toast| {
+                let toast_id = toast.id;
+                div([class(&format!(
+                    "flex items-start gap-3 max-w-sm bg-ctp-surface1 border {} text-ctp-text text-sm px-4 py-2 rounded-lg shadow-lg",
+                    toast.severity.border_class()
+                ))], [
+                    span([class("break-words")], [text(&toast.message)]),
+                    if toast.undo.is_empty() {
+                        span([], [])
+                    } else {
+                        button([
+                            on_click(move |_| Msg::Task(TaskMsg::UndoDelete(toast_id))),
+                            class("font-medium text-ctp-blue hover:text-ctp-sapphire transition-colors"),
+                            r#type("button"),
+                        ], [text("Undo")])
+                    },
+                    button([
+                        on_click(move |_| Msg::DismissToast(toast_id)),
+                        class("text-ctp-subtext0 hover:text-ctp-text transition-colors"),
+                        r#type("button"),
+                    ], [text("×")]),
+                ])
+            }).collect::<Vec<_>>(),
+        )
+    }
+
+    /// Render and update counters for finding what slows the app down, e.g.
+    /// with thousands of tasks. Opened with Alt+Shift+P.
+    fn view_perf_overlay(&self) -> Node<Msg> {
+        let Some(stats) = &self.perf else {
+            return span([], []);
+        };
+        let row = |label: &str, value: String| {
+            div([class("flex justify-between gap-4")], [
+                span([class("text-ctp-subtext0")], [text(label)]),
+                span([], [text(&value)]),
+            ])
+        };
+        let slowest = if stats.slowest_msg.is_empty() {
+            "-".to_string()
+        } else {
+            format!("{:.1} ms ({})", stats.slowest_update_ms, stats.slowest_msg)
+        };
+
+        div([class("fixed bottom-6 left-6 z-50 w-64 bg-ctp-crust/90 border border-ctp-surface1 rounded-lg shadow-lg p-3 font-mono text-xs text-ctp-text space-y-1")], [
+            div([class("flex items-center justify-between mb-1")], [
+                span([class("font-semibold")], [text("Performance")]),
+                button([
+                    on_click(|_| Msg::TogglePerfOverlay),
+                    class("text-ctp-subtext0 hover:text-ctp-text transition-colors"),
+                    r#type("button"),
+                ], [text("×")]),
+            ]),
+            row("Renders", stats.renders.to_string()),
+            row("Last view", format!("{:.1} ms", stats.last_view_ms)),
+            row("Messages", stats.updates.to_string()),
+            row("Messages/s", format!("{:.1}", stats.msgs_per_sec)),
+            row("Last update", format!("{:.1} ms", stats.last_update_ms)),
+            row("Slowest update", slowest),
+            row("DOM nodes", stats.dom_nodes.to_string()),
+            row("Tasks loaded", self.tasks.len().to_string()),
+            div([class("flex items-center justify-between pt-1")], [
+                span([class("text-ctp-overlay0")], [text("Refreshes count too")]),
+                button([
+                    on_click(|_| Msg::ResetPerfSlowest),
+                    class("text-ctp-blue hover:underline"),
+                    r#type("button"),
+                ], [text("Reset slowest")]),
+            ]),
+        ])
+    }
+
+    fn nav_link(&self, label: &str, page: Page) -> Node<Msg> {
+        let is_active = self.current_page == page;
+        a([
+            href(&base::url(page.to_path())),
+            on("mouseenter", {
+                let page = page.clone();
+                move |_| Msg::Nav(NavMsg::Prefetch(page.clone()))
+            }),
+            on_click(move |event| {
+                event.prevent_default();
+                Msg::Nav(NavMsg::NavigateTo(page.clone()))
+            }),
+            class(&format!(
+                "px-3 py-2 rounded-md text-sm font-medium transition-colors duration-200 {}",
+                if is_active {
+                    "bg-ctp-blue text-ctp-base"
+                } else {
+                    "text-ctp-subtext0 hover:text-ctp-text hover:bg-ctp-surface0"
+                }
+            )),
+        ], [text(label)])
+    }
+
+    fn view_dashboard(&self) -> Node<Msg> {
+        div([class("space-y-8")], [
+            // Welcome section
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-8 border border-ctp-surface1")], [
+                h2([class("text-3xl font-bold text-ctp-text mb-4")], [text("Welcome to the Full-Stack Rust Demo")]),
+                p([class("text-lg text-ctp-subtext1 mb-6")], [text("This application demonstrates a complete full-stack Rust implementation using Axum (backend) and Sauron (frontend) with WebAssembly.")]),
+                self.tour_target(tour::Step::Analytics, div([class("grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-6 mt-8")], [
+                    self.stat_card("Total Tasks", &self.tasks.len().to_string(), "📝"),
+                    self.stat_card("Completed", &self.tasks.iter().filter(|t| t.completed).count().to_string(), "✅"),
+                    self.stat_card("Pending", &self.tasks.iter().filter(|t| !t.completed).count().to_string(), "⏳"),
+                    self.stat_card(
+                        "Avg. Time to Complete",
+                        &self.analytics.as_ref().and_then(|a| a.average_completion_secs).map(format_age).unwrap_or_else(|| "—".to_string()),
+                        "⏱️",
+                    ),
+                ])),
+            ]),
+
+            self.view_completion_charts(),
+            self.view_release_notes(),
+            self.view_tag_leaderboard(),
+            
+            // Tech stack section
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-8 border border-ctp-surface1")], [
+                h3([class("text-2xl font-semibold text-ctp-text mb-6")], [text("Technology Stack")]),
+                div([class("grid grid-cols-1 md:grid-cols-2 gap-8")], [
+                    div([], [
+                        h4([class("text-lg font-medium text-ctp-text mb-4")], [text("Backend")]),
+                        ul([class("space-y-2 text-ctp-subtext1")], [
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-peach rounded-full mr-3")], []), text("Rust + Axum")]),
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-red rounded-full mr-3")], []), text("Redis for persistence")]),
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-blue rounded-full mr-3")], []), text("REST API with CRUD operations")]),
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-green rounded-full mr-3")], []), text("Docker containerization")]),
+                        ]),
+                    ]),
+                    div([], [
+                        h4([class("text-lg font-medium text-ctp-text mb-4")], [text("Frontend")]),
+                        ul([class("space-y-2 text-ctp-subtext1")], [
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-peach rounded-full mr-3")], []), text("Rust + Sauron")]),
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-mauve rounded-full mr-3")], []), text("WebAssembly (WASM)")]),
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-teal rounded-full mr-3")], []), text("Tailwind CSS")]),
+                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-yellow rounded-full mr-3")], []), text("Reactive UI with Elm architecture")]),
+                        ]),
+                    ]),
+                ]),
+            ]),
+        ])
+    }
+
+    /// Completions over the last 30 days and by hour of the day. Hidden
+    /// without Redis, which is where the history is kept.
+    fn view_completion_charts(&self) -> Node<Msg> {
+        let Some(analytics) = &self.analytics else {
+            return span([], []);
+        };
+
+        // Oldest day on the left
+        let days: Vec<(String, u64)> = analytics.daily.iter().rev().map(|day| (day.date[5..].to_string(), day.completed)).collect();
+        // The backend buckets by UTC hour; shift into the browser's time zone
+        let offset_hours = (js_sys::Date::new_0().get_timezone_offset() / 60.0).round() as i64;
+        let mut hours: Vec<(String, u64)> = (0..24).map(|hour| (format!("{:02}:00", hour), 0)).collect();
+        for bucket in &analytics.hours {
+            hours[(i64::from(bucket.hour) - offset_hours).rem_euclid(24) as usize].1 = bucket.completed;
+        }
+        let busiest = hours.iter().filter(|(_, count)| *count > 0).max_by_key(|(_, count)| *count).map(|(label, _)| label.clone());
+
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-8 border border-ctp-surface1")], [
+            h3([class("text-2xl font-semibold text-ctp-text mb-6")], [text("Completions")]),
+            div([class("grid grid-cols-1 lg:grid-cols-2 gap-8")], [
+                div([], [
+                    h4([class("text-lg font-medium text-ctp-text mb-4")], [text("Per day, last 30 days")]),
+                    bar_chart(&days, "bg-ctp-green"),
+                ]),
+                div([], [
+                    h4([class("text-lg font-medium text-ctp-text mb-4")], [text(&match busiest {
+                        Some(hour) => format!("By hour of day (busiest {})", hour),
+                        None => "By hour of day".to_string(),
+                    })]),
+                    bar_chart(&hours, "bg-ctp-blue"),
+                ]),
+            ]),
+        ])
+    }
+
+    fn view_release_notes(&self) -> Node<Msg> {
+        let field = "px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent";
+        let params: Vec<String> = [("from", &self.release_from), ("to", &self.release_to)]
+            .into_iter()
+            .filter(|(_, date)| !date.is_empty())
+            .map(|(name, date)| format!("{}={}", name, date))
+            .collect();
+        let url = if params.is_empty() {
+            "/api/release-notes".to_string()
+        } else {
+            format!("/api/release-notes?{}", params.join("&"))
+        };
+
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-8 border border-ctp-surface1")], [
+            h3([class("text-2xl font-semibold text-ctp-text mb-2")], [text("Release Notes")]),
+            p([class("text-sm text-ctp-subtext0 mb-4")], [text("Tasks completed in a period, grouped by their first tag, as Markdown. Leave the dates empty for the last two weeks.")]),
+            div([class("flex flex-wrap items-end gap-3")], [
+                label([class("flex flex-col text-sm text-ctp-subtext1 gap-1")], [
+                    text("From"),
+                    input([r#type("date"), value(&self.release_from), on_input(|event| Msg::SetReleaseFrom(event.value())), class(field)], []),
+                ]),
+                label([class("flex flex-col text-sm text-ctp-subtext1 gap-1")], [
+                    text("To"),
+                    input([r#type("date"), value(&self.release_to), on_input(|event| Msg::SetReleaseTo(event.value())), class(field)], []),
+                ]),
+                a([
+                    href(&url),
+                    attr("download", ""),
+                    class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200"),
+                ], [text("Download")]),
+            ]),
+        ])
+    }
+
+    fn view_tag_leaderboard(&self) -> Node<Msg> {
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-8 border border-ctp-surface1")], [
+            h3([class("text-2xl font-semibold text-ctp-text mb-6")], [text("Tag Leaderboard")]),
+            if self.tag_stats.is_empty() {
+                p([class("text-ctp-subtext0 italic")], [text("Tag some tasks to see where your effort goes.")])
+            } else {
+                table([class("w-full text-left text-sm")], [
+                    thead([], [
+                        tr([class("text-ctp-subtext0 border-b border-ctp-surface2")], [
+                            th([class("py-2 font-medium")], [text("Tag")]),
+                            th([class("py-2 font-medium")], [text("Tasks")]),
+                            th([class("py-2 font-medium")], [text("Completion")]),
+                            th([class("py-2 font-medium")], [text("Average age")]),
+                        ]),
+                    ]),
+                    tbody(
+                        [],
+                        self.tag_stats.iter().map(|stats| {
+                            let percent = stats.completion_rate * 100.0;
+                            tr([class("border-b border-ctp-surface1 text-ctp-text")], [
+                                td([class("py-2 text-ctp-mauve")], [text(&format!("#{}", stats.tag))]),
+                                td([class("py-2")], [text(&format!("{} ({} done)", stats.total, stats.completed))]),
+                                td([class("py-2")], [
+                                    div([class("flex items-center gap-2")], [
+                                        div([class("w-24 h-2 bg-ctp-surface2 rounded-full overflow-hidden")], [
+                                            div([
+                                                class("h-full bg-ctp-green"),
+                                                attr("style", format!("width: {:.0}%", percent)),
+                                            ], []),
+                                        ]),
+                                        span([class("text-ctp-subtext0")], [text(&format!("{:.0}%", percent))]),
+                                    ]),
+                                ]),
+                                td([class("py-2 text-ctp-subtext1")], [
+                                    text(&stats.average_age_secs.map(format_age).unwrap_or_else(|| "—".to_string())),
+                                ]),
+                            ])
+                        }).collect::<Vec<_>>(),
+                    ),
+                ])
+            },
+        ])
+    }
+
+    fn stat_card(&self, card_title: &str, value: &str, icon: &str) -> Node<Msg> {
+        div([class("bg-ctp-surface1 rounded-lg p-6 border border-ctp-surface2")], [
+            div([class("flex items-center justify-between")], [
+                div([], [
+                    p([class("text-sm font-medium text-ctp-subtext0")], [text(card_title)]),
+                    p([class("text-2xl font-bold text-ctp-text mt-1")], [text(value)]),
+                ]),
+                span([class("text-3xl")], [text(icon)]),
+            ]),
+        ])
+    }
+
+    fn view_tasks_page(&self) -> Node<Msg> {
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("Task Management")]),
+            self.tour_target(tour::Step::CreateForm, self.view_create_form()),
+            self.view_nearby(),
+            self.view_quick_filters(),
+            if self.loading {
+                div([class("text-center py-10 text-ctp-subtext0 italic")], [text("Loading...")])
+            } else if let Some(filter) = self.quick_filter {
+                self.view_quick_filter_results(filter)
+            } else {
+                self.tour_target(tour::Step::ToggleTask, self.view_task_list())
+            },
+        ])
+    }
+
+    fn view_quick_filters(&self) -> Node<Msg> {
+        div(
+            [class("flex flex-wrap items-center gap-2 mb-6")],
+            QuickFilter::ALL.into_iter().map(|filter| {
+                let active = self.quick_filter == Some(filter);
+                button([
+                    // Clicking the active filter again clears it
+                    on_click(move |_| Msg::Task(TaskMsg::SetQuickFilter((!active).then_some(filter)))),
+                    class(if active {
+                        "bg-ctp-blue text-ctp-base px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"
+                    } else {
+                        "bg-ctp-surface1 text-ctp-subtext1 hover:bg-ctp-surface2 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"
+                    }),
+                ], [text(filter.label())])
+            }).chain(std::iter::once(
+                select(
+                    [
+                        on_change(|event| Msg::Task(TaskMsg::SetTaskSort(TaskSort::from_name(&event.value()).unwrap_or_default()))),
+                        class("ml-auto px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text"),
+                        disabled(self.quick_filter.is_some()),
+                    ],
+                    TaskSort::ALL.into_iter().map(|sort| {
+                        option([value(sort.name()), selected(sort == self.task_sort)], [text(sort.label())])
+                    }).collect::<Vec<_>>(),
+                ),
+            )).collect::<Vec<_>>(),
+        )
+    }
+
+    fn view_quick_filter_results(&self, filter: QuickFilter) -> Node<Msg> {
+        let Some(tasks) = &self.quick_filter_tasks else {
+            return div([class("text-center py-10 text-ctp-subtext0 italic")], [text("Loading...")]);
+        };
+        let header = div([class("flex items-center justify-between mb-4")], [
+            h2([class("text-xl font-semibold text-ctp-text pb-2 border-b border-ctp-surface2")], [text(filter.label())]),
+            button([
+                on_click(|_| Msg::Task(TaskMsg::SetQuickFilter(None))),
+                class("text-sm text-ctp-subtext0 hover:text-ctp-text"),
+            ], [text("Show all tasks")]),
+        ]);
+        if tasks.is_empty() {
+            return div([], [header, p([class("text-ctp-subtext0 italic")], [text("Nothing here.")])]);
+        }
+
+        let now = now_secs();
+        let rows = tasks.iter().map(|task| {
+            if filter == QuickFilter::RecentlyDeleted {
+                let id = task.id;
+                return li([key(id.to_string()), class("flex items-center gap-3 py-3")], [
+                    div([class("flex-1 min-w-0")], [
+                        p([class("text-ctp-text truncate")], [text(&task.title)]),
+                        p([class("text-xs text-ctp-subtext0")], [text(&format!(
+                            "Deleted {} ago",
+                            format_age(now.saturating_sub(task.deleted_at.unwrap_or(now)))
+                        ))]),
+                    ]),
+                    button([
+                        on_click(move |_| Msg::RestoreTask(id)),
+                        class("text-sm bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-3 py-1 rounded-md transition-colors duration-200"),
+                    ], [text("Restore")]),
+                ]);
+            }
+            // Changes made since the filter loaded show through
+            let task = self.tasks.iter().find(|t| t.id == task.id).unwrap_or(task);
+            li([key(task.id.to_string())], [self.view_task(task)])
+        });
+        div([], [
+            header,
+            ul(
+                [class(if filter == QuickFilter::RecentlyDeleted { "divide-y divide-ctp-surface1" } else { "space-y-4" })],
+                rows.collect::<Vec<_>>(),
+            ),
+        ])
+    }
+
+    fn view_nearby(&self) -> Node<Msg> {
+        div([class("mb-8 p-4 bg-ctp-surface1 rounded-lg border border-ctp-surface2")], [
+            div([class("flex items-center justify-between gap-4")], [
+                h3([class("text-lg font-semibold text-ctp-text")], [text("📍 Nearby")]),
+                button([
+                    on_click(|_| Msg::FindNearby),
+                    class("bg-ctp-blue/20 text-ctp-blue hover:bg-ctp-blue/30 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                    r#type("button"),
+                    disabled(self.locating),
+                ], [text(if self.locating { "Locating..." } else { "Show tasks near me" })]),
+            ]),
+            match &self.nearby {
+                None => span([], []),
+                Some(tasks) if tasks.is_empty() => {
+                    p([class("mt-3 text-sm text-ctp-subtext0 italic")], [text("No open tasks around here.")])
+                }
+                Some(tasks) => ul(
+                    [class("mt-3 space-y-1")],
+                    tasks.iter().map(|task| {
+                        let location = task.location.as_ref().map(|l| l.name.as_str()).unwrap_or_default();
+                        li([class("text-sm text-ctp-text")], [
+                            text(&task.title),
+                            span([class("ml-2 text-ctp-subtext0")], [text(&format!("· {}", location))]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                ),
+            },
+        ])
+    }
+
+    fn view_create_form(&self) -> Node<Msg> {
+        if self.experiments.variant(experiments::CREATE_FORM) == Some("compact") {
+            return self.view_compact_create_form();
+        }
+
+        div(
+            [class("mb-8 p-6 bg-ctp-surface1 rounded-lg border border-ctp-surface2")],
+            [
+                h2([class("text-xl font-semibold text-ctp-text mb-4 pb-2 border-b border-ctp-surface2")], [text("Add New Task")]),
+                div([class("space-y-4")], [
+                    input([
+                        r#type("text"),
+                        placeholder("Task title"),
+                        value(&self.new_task_title),
+                        on_input(|event| Msg::Form(FormMsg::SetNewTaskTitle(event.value()))),
+                        class("w-full px-3 py-2 bg-ctp-surface0 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                    ], []),
+                    view_field_error(&self.create_errors, validation::TITLE),
+                    textarea([
+                        placeholder("Task description"),
+                        value(&self.new_task_description),
+                        on_input(|event| Msg::Form(FormMsg::SetNewTaskDescription(event.value()))),
+                        class("w-full px-3 py-2 bg-ctp-surface0 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent h-20 resize-y"),
+                    ], []),
+                    view_field_error(&self.create_errors, validation::DESCRIPTION),
+                    view_priority_select(self.new_task_priority, |priority| Msg::Form(FormMsg::SetNewTaskPriority(priority)), "bg-ctp-surface0"),
+                    button([
+                        on_click(|_| Msg::Form(FormMsg::CreateTask)),
+                        disabled(self.create_paused),
+                        class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-6 py-2 rounded-md transition-colors duration-200 disabled:opacity-50 disabled:cursor-not-allowed"),
+                    ], [text("Add Task")]),
+                ]),
+            ],
+        )
+    }
+
+    /// `compact` variant of the create form experiment: a single inline row.
+    fn view_compact_create_form(&self) -> Node<Msg> {
+        div([class("mb-8")], [
+            div([class("flex gap-3")], [
+                input([
+                    r#type("text"),
+                    placeholder("What needs doing?"),
+                    value(&self.new_task_title),
+                    on_input(|event| Msg::Form(FormMsg::SetNewTaskTitle(event.value()))),
+                    class("flex-1 px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                ], []),
+                button([
+                    on_click(|_| Msg::Form(FormMsg::CreateTask)),
+                    disabled(self.create_paused),
+                    class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-6 py-2 rounded-md transition-colors duration-200 disabled:opacity-50 disabled:cursor-not-allowed"),
+                ], [text("Add")]),
+            ]),
+            view_field_error(&self.create_errors, validation::TITLE),
+        ])
+    }
+
+    fn view_my_day_page(&self) -> Node<Msg> {
+        let Some(plan) = &self.plan else {
+            return div([class("text-center py-10 text-ctp-subtext0 italic")], [text("Loading...")]);
+        };
+
+        let planned: Vec<&Task> = plan
+            .task_ids
+            .iter()
+            .filter_map(|id| self.tasks.iter().find(|t| t.id == *id))
+            .collect();
+        let done = planned.iter().filter(|t| t.completed).count();
+        let percent = if planned.is_empty() { 0.0 } else { done as f64 * 100.0 / planned.len() as f64 };
+
+        // Pinned tasks first, then whatever else is still open
+        let now = now_secs();
+        let mut suggestions: Vec<&Task> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.completed && !t.is_snoozed(now) && !plan.task_ids.contains(&t.id))
+            .collect();
+        suggestions.sort_by_key(|t| !t.pinned);
+
+        div([class("space-y-8")], [
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                div([class("flex items-center justify-between mb-4")], [
+                    h2([class("text-2xl font-bold text-ctp-text")], [text(&format!("My Day · {}", plan.date))]),
+                    button([
+                        on_click(|_| Msg::RollOverPlan),
+                        class("bg-ctp-surface1 text-ctp-subtext1 hover:bg-ctp-surface2 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                        disabled(done == planned.len()),
+                    ], [text("Roll unfinished to tomorrow")]),
+                ]),
+                div([class("flex items-center gap-3 mb-6")], [
+                    div([class("flex-1 h-2 bg-ctp-surface2 rounded-full overflow-hidden")], [
+                        div([
+                            class("h-full bg-ctp-green transition-all duration-300"),
+                            attr("style", format!("width: {:.0}%", percent)),
+                        ], []),
+                    ]),
+                    span([class("text-sm text-ctp-subtext0")], [text(&format!("{} of {} done", done, planned.len()))]),
+                ]),
+                if planned.is_empty() {
+                    p([class("text-ctp-subtext0 italic")], [text("Nothing planned yet. Pick tasks from the suggestions below.")])
+                } else {
+                    ul(
+                        [class("space-y-2")],
+                        planned.iter().map(|task| {
+                            let task_id = task.id;
+                            li([class("flex items-center gap-3 p-3 bg-ctp-surface1 rounded-lg")], [
+                                input([
+                                    r#type("checkbox"),
+                                    checked(task.completed),
+                                    on_click(move |_| Msg::Task(TaskMsg::ToggleTask(task_id))),
+                                    class("w-4 h-4 accent-ctp-green"),
+                                ], []),
+                                span([class(if task.completed { "flex-1 line-through text-ctp-overlay1" } else { "flex-1 text-ctp-text" })], [
+                                    text(&task.title),
+                                ]),
+                                button([
+                                    on_click(move |_| Msg::RemoveFromPlan(task_id)),
+                                    class("text-xs text-ctp-subtext0 hover:text-ctp-red"),
+                                    r#type("button"),
+                                ], [text("Remove")]),
+                            ])
+                        }).collect::<Vec<_>>(),
+                    )
+                },
+            ]),
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h3([class("text-xl font-semibold text-ctp-text mb-4")], [text("Suggestions")]),
+                if suggestions.is_empty() {
+                    p([class("text-ctp-subtext0 italic")], [text("No open tasks left to plan.")])
+                } else {
+                    ul(
+                        [class("space-y-2")],
+                        suggestions.iter().map(|task| {
+                            let task_id = task.id;
+                            li([class("flex items-center gap-3 p-3 bg-ctp-surface1 rounded-lg")], [
+                                span([class("flex-1 text-ctp-text")], [
+                                    text(&if task.pinned { format!("📌 {}", task.title) } else { task.title.clone() }),
+                                ]),
+                                button([
+                                    on_click(move |_| Msg::AddToPlan(task_id)),
+                                    class("bg-ctp-blue/20 text-ctp-blue hover:bg-ctp-blue/30 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                                    r#type("button"),
+                                ], [text("Add to today")]),
+                            ])
+                        }).collect::<Vec<_>>(),
+                    )
+                },
+            ]),
+        ])
+    }
+
+    fn view_activity_page(&self) -> Node<Msg> {
+        let now = now_secs();
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("Activity")]),
+            if self.activity.is_empty() && !self.activity_loading {
+                p([class("text-ctp-subtext0 italic")], [text("No activity yet.")])
+            } else {
+                ul(
+                    [class("divide-y divide-ctp-surface1")],
+                    self.activity.iter().map(|event| {
+                        let (icon, verb) = match event.kind {
+                            ActivityKind::Created => ("➕", "created"),
+                            ActivityKind::Updated => ("✏️", "updated"),
+                            ActivityKind::Completed => ("✅", "completed"),
+                            ActivityKind::Reopened => ("↩️", "reopened"),
+                            ActivityKind::Deleted => ("🗑️", "deleted"),
+                            ActivityKind::Restored => ("♻️", "restored"),
+                            ActivityKind::Archived => ("📦", "archived"),
+                        };
+                        li([key(event.id.clone()), class("flex items-center gap-3 py-3")], [
+                            span([class("w-6 text-center")], [text(icon)]),
+                            span([class("flex-1 text-ctp-text")], [
+                                text(&event.title),
+                                span([class("text-ctp-subtext0")], [text(&format!(" {}", verb))]),
+                            ]),
+                            span([class("text-xs text-ctp-subtext0")], [
+                                text(&format!("{} ago", format_age(now.saturating_sub(event.at)))),
+                            ]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                )
+            },
+            if self.activity_cursor.is_some() || self.activity_loading {
+                div([id("activity-sentinel"), class("py-4 text-center text-sm text-ctp-subtext0 italic")], [text("Loading more...")])
+            } else {
+                span([], [])
+            },
+        ])
+    }
+
+    /// Repeat rule controls of the edit form; the cron field only shows for
+    /// a custom rule.
+    fn view_repeat_fields(&self, draft: &EditDraft) -> Node<Msg> {
+        let choices = [
+            ("", "Doesn't repeat"),
+            ("daily", "Repeats daily"),
+            ("weekly", "Repeats weekly"),
+            ("monthly", "Repeats monthly"),
+            ("cron", "Custom (cron)"),
+        ];
+        div([class("flex flex-wrap gap-2")], [
+            select(
+                [
+                    on_change(|event| Msg::Form(FormMsg::SetEditRepeat(event.value()))),
+                    class("flex-1 min-w-32 px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                ],
+                choices.iter().map(|(kind, label)| {
+                    option([value(*kind), selected(draft.repeat == *kind)], [text(*label)])
+                }).collect::<Vec<_>>(),
+            ),
+            if draft.repeat == "cron" {
+                input([
+                    r#type("text"),
+                    placeholder("0 9 * * 1-5 (UTC)"),
+                    value(&draft.cron),
+                    on_input(|event| Msg::Form(FormMsg::SetEditCron(event.value()))),
+                    class("flex-1 min-w-32 px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text font-mono placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                ], [])
+            } else {
+                span([], [])
+            },
+        ])
+    }
+
+    fn view_trash_page(&self) -> Node<Msg> {
+        let now = now_secs();
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("Trash")]),
+            p([class("text-sm text-ctp-subtext0 mb-6")], [text(&format!(
+                "Deleted tasks stay here for {} days before they're removed for good.",
+                TRASH_TTL_DAYS
+            ))]),
+            if self.trash.is_empty() && !self.trash_loading {
+                p([class("text-ctp-subtext0 italic")], [text("The trash is empty.")])
+            } else {
+                ul(
+                    [class("divide-y divide-ctp-surface1")],
+                    self.trash.iter().map(|task| {
+                        let id = task.id;
+                        let deleted_at = task.deleted_at.unwrap_or(now);
+                        let days_left = TRASH_TTL_DAYS.saturating_sub(now.saturating_sub(deleted_at) / 86_400);
+                        li([key(id.to_string()), class("flex items-center gap-3 py-3")], [
+                            div([class("flex-1 min-w-0")], [
+                                p([class("text-ctp-text truncate")], [text(&task.title)]),
+                                p([class("text-xs text-ctp-subtext0")], [text(&format!(
+                                    "Deleted {} ago · removed in {} day{}",
+                                    format_age(now.saturating_sub(deleted_at)),
+                                    days_left,
+                                    if days_left == 1 { "" } else { "s" }
+                                ))]),
+                            ]),
+                            button([
+                                on_click(move |_| Msg::RestoreTask(id)),
+                                class("text-sm bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-3 py-1 rounded-md transition-colors duration-200"),
+                            ], [text("Restore")]),
+                            button([
+                                on_click(move |_| Msg::PurgeTask(id)),
+                                class("bg-ctp-red/20 text-ctp-red hover:bg-ctp-red/30 px-3 py-1 rounded-md text-sm font-medium transition-colors duration-200"),
+                            ], [text("Delete forever")]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                )
+            },
+        ])
+    }
+
+    fn view_archive_page(&self) -> Node<Msg> {
+        let now = now_secs();
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("Archive")]),
+            p([class("text-sm text-ctp-subtext0 mb-6")], [text(
+                "Completed tasks are moved here some days after completion, when the server is set up to; restoring one puts it back in the task list.",
+            )]),
+            if self.archive.is_empty() && !self.archive_loading {
+                p([class("text-ctp-subtext0 italic")], [text("The archive is empty.")])
+            } else {
+                ul(
+                    [class("divide-y divide-ctp-surface1")],
+                    self.archive.iter().map(|task| {
+                        let id = task.id;
+                        let archived_at = task.archived_at.unwrap_or(now);
+                        let completed_at = task.completed_at.unwrap_or(archived_at);
+                        li([key(id.to_string()), class("flex items-center gap-3 py-3")], [
+                            div([class("flex-1 min-w-0")], [
+                                p([class("text-ctp-text truncate")], [text(&task.title)]),
+                                p([class("text-xs text-ctp-subtext0")], [text(&format!(
+                                    "Completed {} ago · archived {} ago",
+                                    format_age(now.saturating_sub(completed_at)),
+                                    format_age(now.saturating_sub(archived_at))
+                                ))]),
+                            ]),
+                            button([
+                                on_click(move |_| Msg::UnarchiveTask(id)),
+                                class("text-sm bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-3 py-1 rounded-md transition-colors duration-200"),
+                            ], [text("Restore")]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                )
+            },
+        ])
+    }
+
+    fn view_settings_page(&self) -> Node<Msg> {
+        div([class("space-y-8")], [
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("Appearance")]),
+                p([class("text-sm text-ctp-subtext0 mb-4")], [text("Pick a theme for this browser.")]),
+                div(
+                    [class("flex flex-wrap gap-3")],
+                    Theme::ALL.into_iter().map(|theme| {
+                        let current = theme == self.theme;
+                        button([
+                            on_click(move |_| Msg::Settings(SettingsMsg::SetTheme(theme))),
+                            class(if current {
+                                "bg-ctp-blue text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200"
+                            } else {
+                                "bg-ctp-surface1 hover:bg-ctp-surface2 text-ctp-text font-medium px-4 py-2 rounded-md transition-colors duration-200"
+                            }),
+                        ], [text(theme.label())])
+                    }).collect::<Vec<_>>(),
+                ),
+            ]),
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("Confirmations")]),
+                p([class("text-sm text-ctp-subtext0 mb-4")], [text("Choose which destructive actions ask before going ahead.")]),
+                div([class("space-y-3")], [
+                    self.view_confirm_toggle(ConfirmAction::BulkDelete, "Deleting selected tasks"),
+                ]),
+            ]),
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("Getting Started")]),
+                p([class("text-sm text-ctp-subtext0 mb-4")], [text("Walk through the main parts of the app again.")]),
+                button([
+                    on_click(|_| Msg::StartTour),
+                    class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200"),
+                ], [text("Take the tour")]),
+            ]),
+            self.view_transfer(),
+            self.view_api_playground(),
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("About")]),
+                div([class("grid grid-cols-1 md:grid-cols-2 gap-6")], [
+                    self.view_build_info("Frontend", Some(&self.frontend_build)),
+                    self.view_build_info("Backend", self.backend_build.as_ref()),
+                ]),
+            ]),
+            self.view_status_badge(),
+        ])
+    }
+
+    fn view_status_badge(&self) -> Node<Msg> {
+        let (dot, summary) = match &self.service_status {
+            None => ("bg-ctp-overlay0", "Checking status...".to_string()),
+            Some(Err(_)) => ("bg-ctp-red", "Backend unreachable".to_string()),
+            Some(Ok(status)) => {
+                let (dot, health) = match status.health {
+                    Health::Ok => ("bg-ctp-green", "All systems operational".to_string()),
+                    Health::Degraded => {
+                        let jobs: Vec<String> = status
+                            .jobs
+                            .iter()
+                            .filter_map(|job| match job.health {
+                                JobHealth::Failing => Some(format!("{} failing", job.name)),
+                                JobHealth::Stalled => Some(format!("{} stalled", job.name)),
+                                _ => None,
+                            })
+                            .collect();
+                        ("bg-ctp-yellow", format!("Degraded: {}", jobs.join(", ")))
+                    }
+                    Health::Down => ("bg-ctp-red", "Storage is down".to_string()),
+                };
+                let latency = status
+                    .storage
+                    .latency_ms
+                    .map(|ms| format!(" \u{00b7} {} {:.1} ms", status.storage.backend, ms))
+                    .unwrap_or_default();
+                (dot, format!("{} \u{00b7} up {}{}", health, format_age(status.uptime_secs), latency))
+            }
+        };
+
+        footer([class("flex items-center justify-center gap-2 text-sm text-ctp-subtext0")], [
+            span([class(&format!("inline-block w-2 h-2 rounded-full {}", dot))], []),
+            a([href(&base::url("/status")), class("hover:text-ctp-text")], [text(&summary)]),
+            button([
+                on_click(|_| Msg::LoadStatus),
+                class("text-ctp-blue hover:text-ctp-sapphire"),
+            ], [text("Refresh")]),
+        ])
+    }
+
+    fn view_transfer(&self) -> Node<Msg> {
+        let button_class = "bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200";
+
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("Export & Import")]),
+            p([class("text-sm text-ctp-subtext0 mb-4")], [text("Download every task, or import an export. JSON keeps everything but attachments; CSV is for spreadsheets. Tasks that are already here are skipped.")]),
+            div([class("flex flex-wrap gap-3")], [
+                a([href(&base::url("/api/tasks/export?format=json")), attr("download", ""), class(button_class)], [text("Export JSON")]),
+                a([href(&base::url("/api/tasks/export?format=csv")), attr("download", ""), class(button_class)], [text("Export CSV")]),
+                label([class("bg-ctp-surface1 hover:bg-ctp-surface2 text-ctp-text font-medium px-4 py-2 rounded-md transition-colors duration-200 cursor-pointer")], [
+                    text(match (self.importing, &self.import_file) {
+                        (true, Some(_)) => "Checking...",
+                        (true, None) => "Importing...",
+                        (false, _) => "Import file...",
+                    }),
+                    input([
+                        r#type("file"),
+                        attr("accept", ".json,.csv,application/json,text/csv"),
+                        disabled(self.importing || self.import_preview.is_some()),
+                        class("hidden"),
+                        on("change", |event| Msg::Settings(SettingsMsg::ImportTasks(event.as_web().map(|event| uploads::files_from_input(&event)).unwrap_or_default()))),
+                    ], []),
+                ]),
+            ]),
+            self.view_import_preview(),
+            match &self.import_summary {
+                None => span([], []),
+                Some(summary) => div([class("mt-4 text-sm text-ctp-subtext1 space-y-1")], [
+                    p([], [text(&format!("{} imported, {} skipped as duplicates, {} with errors.", summary.imported, summary.duplicates, summary.errors.len()))]),
+                    ul(
+                        [class("text-ctp-red")],
+                        summary.errors.iter().map(|error| {
+                            li([], [text(&format!("Task {}: {}", error.record, error.message))])
+                        }).collect::<Vec<_>>(),
+                    ),
+                ]),
+            },
+        ])
+    }
+
+    fn view_import_preview(&self) -> Node<Msg> {
+        let (Some(preview), Some(file)) = (&self.import_preview, &self.import_file) else {
+            return span([], []);
+        };
+        let errors = preview.errors.len();
+
+        div([class("mt-4 space-y-3")], [
+            p([class("text-sm text-ctp-subtext1")], [text(&format!(
+                "{}: {} to import, {} already here, {} with errors.",
+                file.name(), preview.imported, preview.duplicates, errors
+            ))]),
+            div([class("max-h-80 overflow-y-auto border border-ctp-surface1 rounded-md")], [
+                table([class("w-full text-sm")], [
+                    thead([class("sticky top-0 bg-ctp-surface1 text-ctp-subtext0 text-left")], [
+                        tr([], [
+                            th([class("px-3 py-2 w-12")], [text("#")]),
+                            th([class("px-3 py-2")], [text("Title")]),
+                            th([class("px-3 py-2")], [text("Status")]),
+                        ]),
+                    ]),
+                    tbody(
+                        [class("divide-y divide-ctp-surface1")],
+                        preview.rows.iter().take(IMPORT_PREVIEW_ROWS).map(|row| {
+                            let (label, colors) = match row.status {
+                                ImportStatus::Ok => ("Import", "bg-ctp-green/20 text-ctp-green"),
+                                ImportStatus::Duplicate => ("Already here", "bg-ctp-overlay0/20 text-ctp-subtext0"),
+                                ImportStatus::Invalid => ("Error", "bg-ctp-red/20 text-ctp-red"),
+                            };
+                            tr([], [
+                                td([class("px-3 py-2 text-ctp-subtext0")], [text(row.record.to_string())]),
+                                td([class("px-3 py-2 text-ctp-text truncate max-w-xs")], [text(row.title.as_deref().unwrap_or("\u{2014}"))]),
+                                td([class("px-3 py-2")], [
+                                    span([class(&format!("px-2 py-0.5 rounded-full text-xs font-medium {}", colors))], [text(label)]),
+                                    match &row.message {
+                                        Some(message) => span([class("ml-2 text-ctp-red")], [text(message)]),
+                                        None => span([], []),
+                                    },
+                                ]),
+                            ])
+                        }).collect::<Vec<_>>(),
+                    ),
+                ]),
+            ]),
+            if preview.rows.len() > IMPORT_PREVIEW_ROWS {
+                p([class("text-xs text-ctp-subtext0")], [text(&format!("And {} more.", preview.rows.len() - IMPORT_PREVIEW_ROWS))])
+            } else {
+                span([], [])
+            },
+            div([class("flex gap-3")], [
+                button([
+                    on_click(|_| Msg::Settings(SettingsMsg::ConfirmImport)),
+                    disabled(preview.imported == 0),
+                    class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200 disabled:opacity-50"),
+                ], [text(&format!("Import {} task{}", preview.imported, if preview.imported == 1 { "" } else { "s" }))]),
+                button([
+                    on_click(|_| Msg::Settings(SettingsMsg::CancelImport)),
+                    class("bg-ctp-surface1 hover:bg-ctp-surface2 text-ctp-text font-medium px-4 py-2 rounded-md transition-colors duration-200"),
+                ], [text("Cancel")]),
+            ]),
+        ])
+    }
+
+    fn view_api_playground(&self) -> Node<Msg> {
+        let endpoint = self.playground_endpoint.and_then(|i| self.api_endpoints.get(i));
+        let field = "w-full px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text font-mono placeholder-ctp-subtext0";
+
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("API Playground")]),
+            p([class("text-sm text-ctp-subtext0 mb-4")], [
+                text("Try the backend's endpoints from here. Requests are real: writes change your data. "),
+                a([href(&base::url("/api/docs")), attr("target", "_blank"), class("text-ctp-blue hover:underline")], [text("Full API reference")]),
+            ]),
+            select(
+                [
+                    on_change(|event| Msg::SelectEndpoint(event.value().parse().ok())),
+                    class(field),
+                ],
+                std::iter::once(option([value(""), selected(endpoint.is_none()), disabled(true)], [text("Pick an endpoint...")]))
+                    .chain(self.api_endpoints.iter().enumerate().map(|(index, e)| {
+                        option(
+                            [value(index.to_string()), selected(self.playground_endpoint == Some(index))],
+                            [text(&format!("{} \u{2014} {}", e.label(), e.summary))],
+                        )
+                    }))
+                    .collect::<Vec<_>>(),
+            ),
+            match endpoint {
+                None => span([], []),
+                Some(endpoint) if endpoint.multipart => {
+                    p([class("mt-4 text-sm text-ctp-subtext0 italic")], [text("This endpoint takes a file upload; drop files on a task card instead.")])
+                }
+                Some(endpoint) => div([class("mt-4 space-y-3")], [
+                    div(
+                        [class("grid grid-cols-1 md:grid-cols-2 gap-3")],
+                        endpoint.path_params.iter().chain(&endpoint.query_params).map(|name| {
+                            let param = name.clone();
+                            label([class("text-sm text-ctp-subtext1")], [
+                                span([class("font-mono")], [text(name)]),
+                                input([
+                                    r#type("text"),
+                                    value(self.playground_params.get(name).map(String::as_str).unwrap_or_default()),
+                                    on_input(move |event| Msg::SetPlaygroundParam(param.clone(), event.value())),
+                                    class(&format!("mt-1 {}", field)),
+                                ], []),
+                            ])
+                        }).collect::<Vec<_>>(),
+                    ),
+                    if endpoint.takes_body() {
+                        textarea([
+                            value(&self.playground_body),
+                            on_input(|event| Msg::SetPlaygroundBody(event.value())),
+                            class(&format!("{} h-32 resize-y", field)),
+                        ], [])
+                    } else {
+                        span([], [])
+                    },
+                    div([class("flex items-center gap-3")], [
+                        button([
+                            on_click(|_| Msg::SendPlaygroundRequest),
+                            disabled(self.playground_sending),
+                            class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200 disabled:opacity-50"),
+                        ], [text(if self.playground_sending { "Sending..." } else { "Send" })]),
+                        span([class("font-mono text-sm text-ctp-subtext0 break-all")], [
+                            text(&format!("{} {}", endpoint.method, endpoint.url(&self.playground_params))),
+                        ]),
+                    ]),
+                    match &self.playground_reply {
+                        None => span([], []),
+                        Some(Err(e)) => p([class("text-sm text-ctp-red")], [text(e)]),
+                        Some(Ok(reply)) => div([], [
+                            p([class(if reply.status < 400 { "text-sm font-mono text-ctp-green mb-2" } else { "text-sm font-mono text-ctp-red mb-2" })], [
+                                text(&format!("{} {} \u{00b7} {} ms", reply.status, reply.status_text, reply.elapsed_ms)),
+                            ]),
+                            pre([class("max-h-96 overflow-auto bg-ctp-mantle border border-ctp-surface1 rounded-md p-3 text-xs font-mono text-ctp-text")], [
+                                text(&reply.body),
+                            ]),
+                        ]),
+                    },
+                ]),
+            },
+        ])
+    }
+
+    fn view_confirm_toggle(&self, action: ConfirmAction, label_text: &str) -> Node<Msg> {
+        let enabled = self.confirm_preferences.get(action);
+        label([class("flex items-center gap-3 text-ctp-text cursor-pointer")], [
+            input([
+                r#type("checkbox"),
+                checked(enabled),
+                on_click(move |_| Msg::Settings(SettingsMsg::SetConfirmPreference(action, !enabled))),
+                class("w-4 h-4 accent-ctp-blue"),
+            ], []),
+            span([], [text(label_text)]),
+        ])
+    }
+
+    fn view_build_info(&self, label: &str, info: Option<&BuildInfo>) -> Node<Msg> {
+        div([class("bg-ctp-surface1 rounded-lg p-4 border border-ctp-surface2")], [
+            h3([class("text-lg font-semibold text-ctp-text mb-2")], [text(label)]),
+            match info {
+                Some(info) => div([class("text-sm text-ctp-subtext1 space-y-1")], [
+                    p([], [
+                        text("Version "),
+                        span([class("font-mono bg-ctp-surface0 px-2 py-0.5 rounded")], [text(&format!("{} ({})", info.version, info.git_hash))]),
+                    ]),
+                    p([], [text(&format!("Built {}", format_timestamp(info.build_time)))]),
+                ]),
+                None => p([class("text-sm text-ctp-subtext0 italic")], [text("Loading...")]),
+            },
+        ])
+    }
+
+    fn view_admin_page(&self) -> Node<Msg> {
+        if !self.admin_signed_in {
+            return self.view_admin_sign_in();
+        }
+        div([class("space-y-8")], [
+            div([class("flex justify-end")], [
+                button([
+                    on_click(|_| Msg::SignOutAdmin),
+                    class("text-sm text-ctp-subtext0 hover:text-ctp-text"),
+                ], [text("Sign out")]),
+            ]),
+            self.view_overview(),
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("Experiments")]),
+                if self.experiment_results.is_empty() {
+                    p([class("text-ctp-subtext0 italic")], [text("No experiments are running.")])
                 } else {
-                    Cmd::none()
-                }
-            }
-            Msg::ToggleCompletedSection => {
-                self.show_completed = !self.show_completed;
-                Cmd::none()
-            }
-            Msg::SetTaskLoading(id, loading) => {
-                if loading {
-                    self.task_loading_states.insert(id, true);
+                    div(
+                        [class("space-y-6")],
+                        self.experiment_results.iter().map(|results| self.view_experiment_results(results)).collect::<Vec<_>>(),
+                    )
+                },
+            ]),
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("Server Configuration")]),
+                table([class("w-full text-sm text-left")], [
+                    tbody(
+                        [class("divide-y divide-ctp-surface1")],
+                        self.server_config.iter().map(|entry| {
+                            tr([], [
+                                td([class("py-2 pr-4 font-mono text-ctp-text whitespace-nowrap")], [text(&entry.name)]),
+                                td([class("py-2 pr-4 font-mono text-ctp-blue whitespace-nowrap")], [text(&entry.value)]),
+                                td([class("py-2 text-ctp-subtext0")], [text(&entry.description)]),
+                            ])
+                        }).collect::<Vec<_>>(),
+                    ),
+                ]),
+            ]),
+            self.view_content_filter(),
+            self.view_automations(),
+            self.view_announcements_admin(),
+            self.view_logs(),
+            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+                h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("Client Errors")]),
+                if self.client_errors.is_empty() {
+                    p([class("text-ctp-subtext0 italic")], [text("No errors reported.")])
                 } else {
-                    self.task_loading_states.remove(&id);
-                }
-                Cmd::none()
-            }
-            Msg::Error(error) => {
-                console::log_1(&format!("Error: {}", error).into());
-                Cmd::none()
+                    div(
+                        [class("space-y-3")],
+                        self.client_errors.iter().map(|report| self.view_client_error(report)).collect::<Vec<_>>(),
+                    )
+                },
+            ]),
+        ])
+    }
+
+    fn view_overview(&self) -> Node<Msg> {
+        let Some(overview) = &self.overview else {
+            return span([], []);
+        };
+        let stat = |label: &str, value: String| {
+            div([class("bg-ctp-surface1 rounded-lg p-4 border border-ctp-surface2")], [
+                p([class("text-sm text-ctp-subtext0")], [text(label)]),
+                p([class("text-2xl font-bold text-ctp-text")], [text(&value)]),
+            ])
+        };
+        let tasks = &overview.tasks;
+
+        let mut stats = vec![
+            stat("Tasks", tasks.total.to_string()),
+            stat("Completed", tasks.completed.to_string()),
+            stat("Pinned", tasks.pinned.to_string()),
+            stat("Snoozed", tasks.snoozed.to_string()),
+            stat("Flagged", tasks.flagged.to_string()),
+        ];
+        stats.extend(tasks.by_priority.iter().map(|(priority, count)| stat(&format!("{} priority", priority.label()), count.to_string())));
+        stats.push(stat("Live connections", overview.live_clients.to_string()));
+        match &overview.redis {
+            Some(redis) => {
+                stats.push(stat(
+                    "Redis memory",
+                    format!("{} (peak {})", uploads::format_size(redis.used_memory), uploads::format_size(redis.peak_memory)),
+                ));
+                stats.push(stat(
+                    "Redis connections",
+                    format!("{} ({} idle)", redis.connections, redis.idle_connections),
+                ));
+                stats.extend(redis.queues.iter().map(|queue| stat(&queue.name, queue.length.to_string())));
             }
+            None => stats.push(stat("Redis", "not in use".to_string())),
         }
-    }
 
-    fn view(&self) -> Node<Msg> {
-        div(
-            [class("min-h-screen bg-ctp-base text-ctp-text")],
-            [
-                self.view_header(),
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            div([class("flex items-center justify-between mb-6")], [
+                h2([class("text-2xl font-bold text-ctp-text")], [text("Overview")]),
+                div([class("flex items-center gap-3")], [
+                    span([class("text-sm text-ctp-subtext0")], [text(&format!("Storage: {}", overview.store))]),
+                    button(
+                        [
+                            on_click(|_| Msg::LoadOverview),
+                            class("px-3 py-1 bg-ctp-surface1 hover:bg-ctp-surface2 rounded-md text-sm text-ctp-text transition-colors"),
+                        ],
+                        [text("Refresh")],
+                    ),
+                ]),
+            ]),
+            div([class("grid grid-cols-2 md:grid-cols-4 gap-4")], stats),
+            h3([class("text-lg font-semibold text-ctp-text mt-6 mb-3")], [text("Recent errors")]),
+            if overview.recent_errors.is_empty() {
+                p([class("text-ctp-subtext0 italic")], [text("No errors logged since the server started.")])
+            } else {
                 div(
-                    [class("max-w-6xl mx-auto px-6 py-8")],
-                    [
-                        match self.current_page {
-                            Page::Dashboard => self.view_dashboard(),
-                            Page::Tasks => self.view_tasks_page(),
-                        }
-                    ]
+                    [class("font-mono text-xs space-y-1")],
+                    overview.recent_errors.iter().map(|entry| {
+                        div([class("flex gap-3")], [
+                            span([class("text-ctp-subtext0 whitespace-nowrap")], [text(&format_timestamp(entry.at / 1000))]),
+                            span([class("text-ctp-red break-all")], [text(&entry.message)]),
+                        ])
+                    }).collect::<Vec<_>>(),
                 )
-            ],
-        )
+            },
+        ])
     }
-}
 
-impl Model {
-    fn view_header(&self) -> Node<Msg> {
-        header([class("bg-ctp-mantle shadow-lg border-b border-ctp-surface0")], [
-            div([class("max-w-6xl mx-auto px-6 py-4")], [
-                div([class("flex items-center justify-between")], [
-                    h1([class("text-2xl font-bold text-ctp-text")], [text("Full-Stack Rust Demo")]),
-                    nav([class("flex space-x-8")], [
-                        self.nav_link("Tasks", Page::Tasks),
-                        self.nav_link("Dashboard", Page::Dashboard),
-                    ]),
-                ]),
+    fn view_content_filter(&self) -> Node<Msg> {
+        let Some(settings) = &self.content_filter else {
+            return span([], []);
+        };
+        let workspace = self.filter_workspace.trim().to_string();
+
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            div([class("flex items-center justify-between mb-6")], [
+                h2([class("text-2xl font-bold text-ctp-text")], [text("Content Filter")]),
+                span([class("text-sm text-ctp-subtext0")], [text(&format!("Filter: {}", settings.filter))]),
+            ]),
+            p([class("text-sm text-ctp-subtext0 mb-4")], [text("Clients pick their own workspace, so every request is held to the strictest mode below.")]),
+            table([class("w-full text-sm text-left")], [
+                tbody(
+                    [class("divide-y divide-ctp-surface1")],
+                    std::iter::once(view_filter_mode_row("All workspaces", None, settings.mode))
+                        .chain(settings.workspaces.iter().map(|(workspace, mode)| {
+                            view_filter_mode_row(workspace, Some(workspace.clone()), *mode)
+                        }))
+                        .collect::<Vec<_>>(),
+                ),
+            ]),
+            div([class("mt-4 flex gap-3")], [
+                input([
+                    r#type("text"),
+                    placeholder("Workspace id"),
+                    value(&self.filter_workspace),
+                    on_input(|event| Msg::SetFilterWorkspace(event.value())),
+                    class("flex-1 px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text placeholder-ctp-subtext0"),
+                ], []),
+                button(
+                    [
+                        on_click(move |_| Msg::SetFilterMode(Some(workspace.clone()), FilterMode::Flag)),
+                        disabled(self.filter_workspace.trim().is_empty()),
+                        class("px-3 py-1 bg-ctp-surface1 hover:bg-ctp-surface2 rounded-md text-sm text-ctp-text transition-colors disabled:opacity-50"),
+                    ],
+                    [text("Add override")],
+                ),
             ]),
         ])
     }
 
-    fn nav_link(&self, label: &str, page: Page) -> Node<Msg> {
-        let is_active = self.current_page == page;
-        a([
-            href(page.to_path()),
-            on_click(move |event| {
-                event.prevent_default();
-                Msg::NavigateTo(page.clone())
-            }),
-            class(&format!(
-                "px-3 py-2 rounded-md text-sm font-medium transition-colors duration-200 {}",
-                if is_active {
-                    "bg-ctp-blue text-ctp-base"
+    fn view_automations(&self) -> Node<Msg> {
+        let Some(settings) = &self.automations else {
+            return span([], []);
+        };
+        let (workspace, rules) = self.scoped_automation_rules();
+        let inherited = workspace.as_ref().is_some_and(|w| !settings.workspaces.contains_key(w));
+        let field_class = "px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text placeholder-ctp-subtext0";
+        let draft = &self.rule_draft;
+
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            div([class("flex items-center justify-between mb-6")], [
+                h2([class("text-2xl font-bold text-ctp-text")], [text("Automations")]),
+                input([
+                    r#type("text"),
+                    placeholder("All workspaces"),
+                    value(&self.automation_workspace),
+                    on_input(|event| Msg::SetAutomationWorkspace(event.value())),
+                    class(&format!("w-48 {}", field_class)),
+                ], []),
+            ]),
+            if settings.workspaces.is_empty() {
+                span([], [])
+            } else {
+                div(
+                    [class("flex flex-wrap items-center gap-2 mb-4 text-sm text-ctp-subtext0")],
+                    std::iter::once(span([], [text("Workspaces with their own rules:")]))
+                        .chain(settings.workspaces.keys().map(|w| {
+                            let w = w.clone();
+                            button(
+                                [
+                                    on_click({
+                                        let w = w.clone();
+                                        move |_| Msg::SetAutomationWorkspace(w.clone())
+                                    }),
+                                    class("px-2 py-0.5 bg-ctp-surface1 hover:bg-ctp-surface2 rounded-md font-mono text-ctp-text transition-colors"),
+                                ],
+                                [text(&w)],
+                            )
+                        }))
+                        .collect::<Vec<_>>(),
+                )
+            },
+            if inherited {
+                p([class("text-sm text-ctp-subtext0 italic mb-4")], [text("No rules of its own yet; changes here start from the global rules.")])
+            } else {
+                span([], [])
+            },
+            if rules.is_empty() {
+                p([class("text-ctp-subtext0 italic mb-4")], [text("No rules yet.")])
+            } else {
+                ul([class("divide-y divide-ctp-surface1 mb-4")], rules.iter().map(view_automation_rule).collect::<Vec<_>>())
+            },
+            div([class("flex flex-wrap items-center gap-2")], [
+                input([
+                    r#type("text"),
+                    placeholder("Rule name"),
+                    value(&draft.name),
+                    on_input(|event| Msg::SetRuleField(RuleField::Name, event.value())),
+                    class(&format!("w-40 {}", field_class)),
+                ], []),
+                view_rule_choice(&automations::TRIGGERS, &draft.trigger, RuleField::Trigger),
+                if draft.trigger == "stale" {
+                    input([
+                        r#type("number"),
+                        placeholder("Days"),
+                        value(&draft.days),
+                        on_input(|event| Msg::SetRuleField(RuleField::Days, event.value())),
+                        class(&format!("w-20 {}", field_class)),
+                    ], [])
                 } else {
-                    "text-ctp-subtext0 hover:text-ctp-text hover:bg-ctp-surface0"
-                }
-            )),
-        ], [text(label)])
+                    span([], [])
+                },
+                view_rule_choice(&automations::CONDITIONS, &draft.condition, RuleField::Condition),
+                view_rule_value(draft.condition_value(), &draft.condition_value, RuleField::ConditionValue),
+                view_rule_choice(&automations::ACTIONS, &draft.action, RuleField::Action),
+                view_rule_value(draft.action_value(), &draft.action_value, RuleField::ActionValue),
+                button(
+                    [
+                        on_click(|_| Msg::AddAutomationRule),
+                        disabled(draft.name.trim().is_empty()),
+                        class("px-3 py-1 bg-ctp-blue hover:bg-ctp-sapphire rounded-md text-sm text-ctp-base font-medium transition-colors disabled:opacity-50"),
+                    ],
+                    [text("Add rule")],
+                ),
+            ]),
+            h3([class("text-lg font-semibold text-ctp-text mt-6 mb-3")], [text("Recent runs")]),
+            if self.automation_log.is_empty() {
+                p([class("text-ctp-subtext0 italic")], [text("No rule has run yet.")])
+            } else {
+                div(
+                    [class("max-h-64 overflow-y-auto text-sm space-y-1")],
+                    self.automation_log.iter().map(|run| {
+                        let outcome = match &run.error {
+                            Some(e) => span([class("text-ctp-red")], [text(e)]),
+                            None => span([class("text-ctp-subtext0")], [text(&run.actions.join(", "))]),
+                        };
+                        div([class("flex gap-3")], [
+                            span([class("text-ctp-subtext0 whitespace-nowrap")], [text(&format_timestamp(run.at))]),
+                            span([class("text-ctp-text whitespace-nowrap")], [text(&run.rule_name)]),
+                            span([class("font-mono text-ctp-overlay1 whitespace-nowrap")], [text(&run.task_id.to_string()[..8])]),
+                            outcome,
+                        ])
+                    }).collect::<Vec<_>>(),
+                )
+            },
+        ])
     }
 
-    fn view_dashboard(&self) -> Node<Msg> {
-        div([class("space-y-8")], [
-            // Welcome section
-            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-8 border border-ctp-surface1")], [
-                h2([class("text-3xl font-bold text-ctp-text mb-4")], [text("Welcome to the Full-Stack Rust Demo")]),
-                p([class("text-lg text-ctp-subtext1 mb-6")], [text("This application demonstrates a complete full-stack Rust implementation using Axum (backend) and Sauron (frontend) with WebAssembly.")]),
-                div([class("grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-6 mt-8")], [
-                    self.stat_card("Total Tasks", &self.tasks.len().to_string(), "📝"),
-                    self.stat_card("Completed", &self.tasks.iter().filter(|t| t.completed).count().to_string(), "✅"),
-                    self.stat_card("Pending", &self.tasks.iter().filter(|t| !t.completed).count().to_string(), "⏳"),
-                    self.stat_card("Redis Storage", "Active", "🗄️"),
-                ]),
-            ]),
-            
-            // Tech stack section
-            div([class("bg-ctp-surface0 rounded-lg shadow-lg p-8 border border-ctp-surface1")], [
-                h3([class("text-2xl font-semibold text-ctp-text mb-6")], [text("Technology Stack")]),
-                div([class("grid grid-cols-1 md:grid-cols-2 gap-8")], [
-                    div([], [
-                        h4([class("text-lg font-medium text-ctp-text mb-4")], [text("Backend")]),
-                        ul([class("space-y-2 text-ctp-subtext1")], [
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-peach rounded-full mr-3")], []), text("Rust + Axum")]),
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-red rounded-full mr-3")], []), text("Redis for persistence")]),
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-blue rounded-full mr-3")], []), text("REST API with CRUD operations")]),
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-green rounded-full mr-3")], []), text("Docker containerization")]),
-                        ]),
-                    ]),
-                    div([], [
-                        h4([class("text-lg font-medium text-ctp-text mb-4")], [text("Frontend")]),
-                        ul([class("space-y-2 text-ctp-subtext1")], [
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-peach rounded-full mr-3")], []), text("Rust + Sauron")]),
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-mauve rounded-full mr-3")], []), text("WebAssembly (WASM)")]),
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-teal rounded-full mr-3")], []), text("Tailwind CSS")]),
-                            li([class("flex items-center")], [span([class("w-2 h-2 bg-ctp-yellow rounded-full mr-3")], []), text("Reactive UI with Elm architecture")]),
-                        ]),
-                    ]),
-                ]),
+    /// Asks for the server's `ADMIN_TOKEN` before showing the Admin page.
+    fn view_admin_sign_in(&self) -> Node<Msg> {
+        div([class("max-w-md mx-auto bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-2")], [text("Admin")]),
+            p([class("text-sm text-ctp-subtext0 mb-4")], [text("Enter the server's ADMIN_TOKEN to manage this deployment.")]),
+            div([class("flex gap-2")], [
+                input([
+                    r#type("password"),
+                    placeholder("Admin token"),
+                    value(&self.admin_token_draft),
+                    on_input(|event| Msg::SetAdminTokenDraft(event.value())),
+                    class("flex-1 px-3 py-2 bg-ctp-base border border-ctp-surface2 rounded-md text-ctp-text focus:outline-none focus:ring-2 focus:ring-ctp-blue"),
+                ], []),
+                button([
+                    on_click(|_| Msg::SignInAdmin),
+                    class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200"),
+                ], [text("Sign in")]),
             ]),
         ])
     }
 
-    fn stat_card(&self, card_title: &str, value: &str, icon: &str) -> Node<Msg> {
-        div([class("bg-ctp-surface1 rounded-lg p-6 border border-ctp-surface2")], [
-            div([class("flex items-center justify-between")], [
-                div([], [
-                    p([class("text-sm font-medium text-ctp-subtext0")], [text(card_title)]),
-                    p([class("text-2xl font-bold text-ctp-text mt-1")], [text(value)]),
-                ]),
-                span([class("text-3xl")], [text(icon)]),
+    fn view_announcements_admin(&self) -> Node<Msg> {
+        div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
+            h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("Announcements")]),
+            div([class("space-y-3 mb-6")], [
+                input([
+                    r#type("text"),
+                    placeholder("Title"),
+                    value(&self.new_announcement_title),
+                    on_input(|event| Msg::SetAnnouncementTitle(event.value())),
+                    class("w-full px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text placeholder-ctp-subtext0"),
+                ], []),
+                textarea([
+                    placeholder("What changed?"),
+                    value(&self.new_announcement_body),
+                    on_input(|event| Msg::SetAnnouncementBody(event.value())),
+                    class("w-full px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text placeholder-ctp-subtext0"),
+                ], []),
+                button(
+                    [
+                        on_click(|_| Msg::PublishAnnouncement),
+                        disabled(self.new_announcement_title.trim().is_empty()),
+                        class("px-3 py-1 bg-ctp-blue hover:bg-ctp-sapphire rounded-md text-sm text-ctp-base font-medium transition-colors disabled:opacity-50"),
+                    ],
+                    [text("Publish")],
+                ),
             ]),
+            if self.announcements.is_empty() {
+                p([class("text-ctp-subtext0 italic")], [text("No announcements published.")])
+            } else {
+                div(
+                    [class("divide-y divide-ctp-surface1")],
+                    self.announcements.iter().map(|announcement| {
+                        let id = announcement.id;
+                        div([class("flex items-center justify-between py-2 text-sm")], [
+                            span([class("text-ctp-text")], [text(&announcement.title)]),
+                            div([class("flex items-center gap-3")], [
+                                span([class("text-ctp-subtext0")], [text(&format_timestamp(announcement.published_at))]),
+                                button(
+                                    [
+                                        on_click(move |_| Msg::DeleteAnnouncement(id)),
+                                        class("text-ctp-red hover:text-ctp-maroon transition-colors"),
+                                    ],
+                                    [text("Delete")],
+                                ),
+                            ]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                )
+            },
         ])
     }
 
-    fn view_tasks_page(&self) -> Node<Msg> {
+    fn view_logs(&self) -> Node<Msg> {
         div([class("bg-ctp-surface0 rounded-lg shadow-lg p-6 border border-ctp-surface1")], [
-            h2([class("text-2xl font-bold text-ctp-text mb-6")], [text("Task Management")]),
-            self.view_create_form(),
-            if self.loading {
-                div([class("text-center py-10 text-ctp-subtext0 italic")], [text("Loading...")])
+            div([class("flex items-center justify-between mb-6")], [
+                h2([class("text-2xl font-bold text-ctp-text")], [text("Backend Logs")]),
+                select(
+                    [
+                        on_change(|event| Msg::SetLogLevel(LogLevel::from_name(&event.value()).unwrap_or_default())),
+                        class("px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text"),
+                    ],
+                    LogLevel::ALL.iter().map(|level| {
+                        option(
+                            [value(level.name()), selected(*level == self.log_level)],
+                            [text(&format!("{} and up", level.name()))],
+                        )
+                    }).collect::<Vec<_>>(),
+                ),
+            ]),
+            if self.logs.is_empty() {
+                p([class("text-ctp-subtext0 italic")], [text("Nothing logged at this level yet.")])
             } else {
-                self.view_task_list()
+                div(
+                    [class("max-h-96 overflow-y-auto font-mono text-xs space-y-1")],
+                    self.logs.iter().map(|entry| {
+                        let color = match entry.level {
+                            LogLevel::Error => "text-ctp-red",
+                            LogLevel::Warn => "text-ctp-peach",
+                            LogLevel::Info => "text-ctp-blue",
+                            LogLevel::Debug | LogLevel::Trace => "text-ctp-overlay1",
+                        };
+                        div([class("flex gap-3")], [
+                            span([class("text-ctp-subtext0 whitespace-nowrap")], [text(&format_timestamp(entry.at / 1000))]),
+                            span([class(&format!("w-12 uppercase {}", color))], [text(entry.level.name())]),
+                            span([class("text-ctp-overlay1 whitespace-nowrap")], [text(&entry.target)]),
+                            span([class("text-ctp-text break-all")], [text(&entry.message)]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                )
             },
         ])
     }
 
-    fn view_create_form(&self) -> Node<Msg> {
-        div(
-            [class("mb-8 p-6 bg-ctp-surface1 rounded-lg border border-ctp-surface2")],
-            [
-                h2([class("text-xl font-semibold text-ctp-text mb-4 pb-2 border-b border-ctp-surface2")], [text("Add New Task")]),
-                div([class("space-y-4")], [
-                    input([
-                        r#type("text"),
-                        placeholder("Task title"),
-                        value(&self.new_task_title),
-                        on_input(|event| Msg::SetNewTaskTitle(event.value())),
-                        class("w-full px-3 py-2 bg-ctp-surface0 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
-                    ], []),
-                    textarea([
-                        placeholder("Task description"),
-                        value(&self.new_task_description),
-                        on_input(|event| Msg::SetNewTaskDescription(event.value())),
-                        class("w-full px-3 py-2 bg-ctp-surface0 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent h-20 resize-y"),
-                    ], []),
-                    button([
-                        on_click(|_| Msg::CreateTask),
-                        class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-6 py-2 rounded-md transition-colors duration-200"),
-                    ], [text("Add Task")]),
+    fn view_client_error(&self, report: &ClientErrorReport) -> Node<Msg> {
+        let kind = match report.kind {
+            ClientErrorKind::Panic => "panic",
+            ClientErrorKind::Api => "api",
+        };
+
+        details([class("bg-ctp-surface1 rounded-lg p-4 border border-ctp-surface2")], [
+            summary([class("cursor-pointer text-sm text-ctp-text")], [
+                span([class("inline-flex px-2 py-0.5 mr-2 rounded-full text-xs font-medium bg-ctp-red/20 text-ctp-red")], [text(kind)]),
+                text(&report.message),
+            ]),
+            div([class("mt-3 text-xs text-ctp-subtext0 space-y-1")], [
+                p([], [text(&format!("Version {} · {}", report.app_version, report.url))]),
+                p([], [text(&report.user_agent)]),
+                pre([class("mt-2 p-2 bg-ctp-surface0 rounded overflow-x-auto")], [text(&report.msg_log.join("\n"))]),
+            ]),
+        ])
+    }
+
+    fn view_experiment_results(&self, results: &ExperimentResults) -> Node<Msg> {
+        div([class("bg-ctp-surface1 rounded-lg p-4 border border-ctp-surface2")], [
+            h3([class("text-lg font-semibold text-ctp-text mb-3")], [text(&results.experiment)]),
+            table([class("w-full text-sm text-left")], [
+                thead([class("text-ctp-subtext0")], [
+                    tr([], [
+                        th([class("py-1")], [text("Variant")]),
+                        th([class("py-1")], [text("Exposures")]),
+                        th([class("py-1")], [text("Unique users")]),
+                    ]),
                 ]),
-            ],
-        )
+                tbody(
+                    [class("text-ctp-text")],
+                    results.variants.iter().map(|variant| {
+                        tr([], [
+                            td([class("py-1")], [text(&variant.variant)]),
+                            td([class("py-1")], [text(&variant.exposures.to_string())]),
+                            td([class("py-1")], [text(&variant.unique_subjects.to_string())]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                ),
+            ]),
+        ])
+    }
+
+    /// Pending and completed tasks in the order they are displayed.
+    fn task_sections(&self) -> (Vec<&Task>, Vec<&Task>) {
+        let now = now_secs();
+        let mut pending_tasks: Vec<&Task> = self.tasks.iter().filter(|t| !t.completed && !t.is_snoozed(now)).collect();
+        self.task_sort.sort(&mut pending_tasks);
+        // Pinned tasks float to the top; the sort is stable so the rest keep their order
+        pending_tasks.sort_by_key(|t| !t.pinned);
+        let mut completed_tasks: Vec<&Task> = self.tasks.iter().filter(|t| t.completed).collect();
+        self.task_sort.sort(&mut completed_tasks);
+        (pending_tasks, completed_tasks)
+    }
+
+    /// Ids of the tasks currently on screen, top to bottom, for range selection.
+    fn visible_task_ids(&self) -> Vec<Uuid> {
+        let (pending_tasks, completed_tasks) = self.task_sections();
+        let completed_tasks = if self.show_completed { completed_tasks } else { Vec::new() };
+        pending_tasks.iter().chain(&completed_tasks).map(|t| t.id).collect()
+    }
+
+    fn view_selection_bar(&self) -> Node<Msg> {
+        if !self.selecting {
+            return span([], []);
+        }
+
+        let has_selection = !self.selected_tasks.is_empty();
+        let no_tag = self.bulk_tag.trim().trim_start_matches('#').is_empty();
+        div([class("mb-6 flex items-center justify-between px-4 py-3 bg-ctp-surface1 rounded-lg border border-ctp-blue")], [
+            span([class("text-sm text-ctp-subtext1")], [
+                text(&format!("{} selected · click, Shift-click for a range, Ctrl/⌘-click to toggle", self.selected_tasks.len())),
+            ]),
+            div([class("flex gap-2")], [
+                button([
+                    on_click(|_| Msg::CompleteSelected),
+                    class("bg-ctp-green/20 text-ctp-green hover:bg-ctp-green/30 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                    disabled(!has_selection),
+                ], [text("Complete")]),
+                input([
+                    r#type("text"),
+                    placeholder("Tag"),
+                    value(&self.bulk_tag),
+                    on_input(|event| Msg::SetBulkTag(event.value())),
+                    class("w-28 px-3 py-1 bg-ctp-surface0 border border-ctp-surface2 rounded-full text-sm text-ctp-text placeholder-ctp-subtext0"),
+                ], []),
+                button([
+                    on_click(|_| Msg::RetagSelected(true)),
+                    class("bg-ctp-blue/20 text-ctp-blue hover:bg-ctp-blue/30 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                    disabled(!has_selection || no_tag),
+                ], [text("Tag")]),
+                button([
+                    on_click(|_| Msg::RetagSelected(false)),
+                    class("bg-ctp-blue/20 text-ctp-blue hover:bg-ctp-blue/30 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                    disabled(!has_selection || no_tag),
+                ], [text("Untag")]),
+                button([
+                    on_click(|_| Msg::DeleteSelected),
+                    class("bg-ctp-red/20 text-ctp-red hover:bg-ctp-red/30 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                    disabled(!has_selection),
+                ], [text("Delete")]),
+                button([
+                    on_click(|_| Msg::ClearSelection),
+                    class("text-ctp-subtext0 hover:text-ctp-text px-3 py-1 text-sm"),
+                    disabled(!has_selection),
+                ], [text("Clear")]),
+            ]),
+        ])
     }
 
     fn view_task_list(&self) -> Node<Msg> {
-        let pending_tasks: Vec<&Task> = self.tasks.iter().filter(|t| !t.completed).collect();
-        let completed_tasks: Vec<&Task> = self.tasks.iter().filter(|t| t.completed).collect();
+        let (pending_tasks, completed_tasks) = self.task_sections();
+        let known: std::collections::HashSet<Uuid> = self.tasks.iter().map(|t| t.id).collect();
+        self.cards.retain(|id| known.contains(id));
+        let now = now_secs();
+        let snoozed_count = self.tasks.iter().filter(|t| !t.completed && t.is_snoozed(now)).count();
         
         console::log_1(&format!("[DEBUG] Task list - Total: {}, Pending: {}, Completed: {}", 
             self.tasks.len(), pending_tasks.len(), completed_tasks.len()).into());
@@ -545,19 +4849,38 @@ impl Model {
             completed_tasks.iter().map(|t| format!("{}:{}", t.id, t.title)).collect::<Vec<_>>()).into());
         
         div(
-            [class("space-y-8")],
+            [class(if self.selecting { "space-y-8 select-none" } else { "space-y-8" })],
             [
+                self.view_selection_bar(),
                 // Pending Tasks Section
                 div([], [
                     div([class("flex items-center justify-between mb-4")], [
                         h2([class("text-xl font-semibold text-ctp-text pb-2 border-b border-ctp-surface2")], [text("Active Tasks")]),
-                        if !pending_tasks.is_empty() {
-                            span([class("bg-ctp-blue/20 text-ctp-blue px-2 py-1 rounded-full text-sm font-medium")], [
-                                text(&format!("{} active", pending_tasks.len()))
-                            ])
-                        } else {
-                            span([], [])
-                        }
+                        div([class("flex items-center space-x-3")], [
+                            if !pending_tasks.is_empty() {
+                                span([class("bg-ctp-blue/20 text-ctp-blue px-2 py-1 rounded-full text-sm font-medium")], [
+                                    text(&format!("{} active", pending_tasks.len()))
+                                ])
+                            } else {
+                                span([], [])
+                            },
+                            if snoozed_count > 0 {
+                                span([class("bg-ctp-lavender/20 text-ctp-lavender px-2 py-1 rounded-full text-sm font-medium")], [
+                                    text(&format!("💤 {} snoozed", snoozed_count))
+                                ])
+                            } else {
+                                span([], [])
+                            },
+                            button([
+                                on_click(|_| Msg::StartTriage),
+                                attr("title", "Go through untagged or undated tasks from the keyboard"),
+                                class("bg-ctp-surface1 text-ctp-subtext1 hover:bg-ctp-surface2 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                            ], [text("Triage")]),
+                            button([
+                                on_click(|_| Msg::ToggleSelectionMode),
+                                class("bg-ctp-surface1 text-ctp-subtext1 hover:bg-ctp-surface2 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
+                            ], [text(if self.selecting { "Done" } else { "Select" })]),
+                        ]),
                     ]),
                     if pending_tasks.is_empty() {
                         div([class("text-center py-12")], [
@@ -570,7 +4893,14 @@ impl Model {
                             [class("space-y-4")],
                             pending_tasks.iter().map(|task| self.view_task(task)).collect::<Vec<_>>(),
                         )
-                    }
+                    },
+                    if self.tasks_next_page.is_some() {
+                        div([id("tasks-sentinel"), class("py-4 text-center text-sm text-ctp-subtext0 italic")], [
+                            text(if self.loading_more_tasks { "Loading more tasks..." } else { "" }),
+                        ])
+                    } else {
+                        span([], [])
+                    },
                 ]),
                 
                 // Completed Tasks Section
@@ -578,7 +4908,7 @@ impl Model {
                     div([class("border-t border-ctp-surface1 pt-8")], [
                         div([class("flex items-center justify-between mb-4")], [
                             button([
-                                on_click(|_| Msg::ToggleCompletedSection),
+                                on_click(|_| Msg::Task(TaskMsg::ToggleCompletedSection)),
                                 class("flex items-center space-x-2 text-xl font-semibold text-ctp-text hover:text-ctp-blue transition-colors duration-200"),
                             ], [
                                 span([], [text("Completed Tasks")]),
@@ -595,66 +4925,327 @@ impl Model {
                                     text(&format!("{} completed", completed_tasks.len()))
                                 ]),
                                 button([
-                                    on_click(|_| Msg::ClearCompleted),
+                                    on_click(|_| Msg::Task(TaskMsg::ClearCompleted)),
                                     class("bg-ctp-red/20 text-ctp-red hover:bg-ctp-red/30 px-3 py-1 rounded-full text-sm font-medium transition-colors duration-200"),
                                 ], [text("Clear All")])
                             ])
                         ]),
-                        if self.show_completed {
-                            div([class("bg-ctp-surface1/50 rounded-lg p-4 border border-ctp-surface2")], [
-                                div(
-                                    [class("space-y-3")],
-                                    completed_tasks.iter().map(|task| self.view_task(task)).collect::<Vec<_>>(),
-                                )
-                            ])
-                        } else {
-                            span([], [])
-                        }
+                        if self.show_completed {
+                            div([class("bg-ctp-surface1/50 rounded-lg p-4 border border-ctp-surface2")], [
+                                div(
+                                    [class("space-y-3")],
+                                    completed_tasks.iter().map(|task| self.view_task(task)).collect::<Vec<_>>(),
+                                )
+                            ])
+                        } else {
+                            span([], [])
+                        }
+                    ])
+                } else {
+                    span([], [])
+                }
+            ],
+        )
+    }
+
+    fn view_task_menu_button(&self, task_id: Uuid) -> Node<Msg> {
+        button([
+            on_click(move |event| Msg::OpenTaskMenu(task_id, event.client_x(), event.client_y())),
+            class("inline-flex items-center justify-center w-8 h-8 rounded-lg bg-ctp-surface1 text-ctp-subtext0 hover:bg-ctp-surface2 transition-colors duration-200"),
+            r#type("button"),
+        ], [text("⋮")])
+    }
+
+    fn view_task_context_menu(&self) -> Node<Msg> {
+        use context_menu::MenuItem;
+
+        let Some((task_id, x, y)) = self.open_task_menu else {
+            return span([], []);
+        };
+        let Some(task) = self.tasks.iter().find(|t| t.id == task_id) else {
+            return span([], []);
+        };
+
+        let mut items = Vec::new();
+        if !task.completed {
+            items.push(MenuItem::new("✏️", "Edit", Msg::Form(FormMsg::EditTask(task_id))));
+        }
+        items.push(MenuItem::new("📄", "Duplicate", Msg::DuplicateTask(task_id)));
+        items.push(MenuItem::new("📌", if task.pinned { "Unpin" } else { "Pin to top" }, Msg::TogglePin(task_id)));
+        if !task.completed {
+            for preset in [SnoozePreset::OneHour, SnoozePreset::Tomorrow, SnoozePreset::NextWeek] {
+                items.push(MenuItem::new("💤", preset.label(), Msg::SnoozeTask(task_id, preset)));
+            }
+        }
+        items.push(MenuItem::new("🔗", "Copy task link", Msg::CopyTaskLink(task_id)));
+        items.push(MenuItem::new("📱", "Show QR code", Msg::ShowTaskQr(task_id)));
+        items.push(MenuItem::new("📋", "Copy as Markdown", Msg::CopyTaskMarkdown(task_id)));
+        items.push(MenuItem::new("🗑️", "Delete", Msg::Task(TaskMsg::DeleteTask(task_id))).danger());
+
+        context_menu::view(x, y, items, Msg::CloseTaskMenu)
+    }
+
+    /// A task's attachments with their download and remove links, uploads in
+    /// progress, and a button to attach more. Files can also be dropped or
+    /// pasted onto the card.
+    fn view_attachments(&self, task: &Task) -> Node<Msg> {
+        let task_id = task.id;
+        let uploads: Vec<&uploads::Upload> = self.uploads.iter().filter(|u| u.task_id == task_id).collect();
+
+        div([class("mt-3 space-y-1 text-sm")], [
+            ul(
+                [class("space-y-1")],
+                task.attachments.iter().map(|attachment| {
+                    let attachment_id = attachment.id;
+                    li([class("flex items-center gap-2")], [
+                        span([], [text("📎")]),
+                        a([
+                            href(&base::url(&format!("/api/tasks/{}/attachments/{}", task_id, attachment_id))),
+                            attr("download", ""),
+                            class("text-ctp-blue hover:underline truncate"),
+                        ], [text(&attachment.filename)]),
+                        span([class("text-xs text-ctp-subtext0")], [text(&uploads::format_size(attachment.size))]),
+                        button([
+                            on_click(move |_| Msg::DeleteAttachment(task_id, attachment_id)),
+                            class("text-xs text-ctp-red hover:underline"),
+                            r#type("button"),
+                        ], [text("Remove")]),
+                    ])
+                }).collect::<Vec<_>>(),
+            ),
+            div(
+                [class("space-y-2")],
+                uploads.iter().map(|upload| {
+                    let upload_id = upload.id;
+                    let percent = upload.fraction() * 100.0;
+                    div([class("flex items-center gap-2")], [
+                        span([class("text-xs text-ctp-subtext1 truncate w-32")], [text(&upload.filename)]),
+                        div([class("flex-1 h-1.5 bg-ctp-surface2 rounded-full overflow-hidden")], [
+                            div([
+                                class("h-full bg-ctp-blue transition-all duration-200"),
+                                attr("style", format!("width: {:.0}%", percent)),
+                            ], []),
+                        ]),
+                        span([class("text-xs text-ctp-subtext0 w-10 text-right")], [text(&format!("{:.0}%", percent))]),
+                        button([
+                            on_click(move |_| Msg::CancelUpload(upload_id)),
+                            class("text-xs text-ctp-red hover:underline"),
+                            r#type("button"),
+                        ], [text("Cancel")]),
                     ])
-                } else {
-                    span([], [])
-                }
-            ],
-        )
+                }).collect::<Vec<_>>(),
+            ),
+            if self.selecting {
+                span([], [])
+            } else {
+                label([class("inline-block text-xs text-ctp-subtext0 hover:text-ctp-text cursor-pointer")], [
+                    text("📎 Attach file"),
+                    input([
+                        r#type("file"),
+                        attr("multiple", ""),
+                        class("hidden"),
+                        on("change", move |event| Msg::UploadFiles(task_id, event.as_web().map(|event| uploads::files_from_input(&event)).unwrap_or_default())),
+                    ], []),
+                ])
+            },
+        ])
+    }
+
+    /// The toggle for a task's comments and, when open, the comments and
+    /// a box to add one. Hidden while selecting, as clicks select the card.
+    fn view_comments(&self, task_id: Uuid) -> Node<Msg> {
+        if self.selecting {
+            return span([], []);
+        }
+        let comments = self.comments.get(&task_id);
+        let label = match comments {
+            Some(comments) if !comments.is_empty() => format!("💬 Comments ({})", comments.len()),
+            _ => "💬 Comments".to_string(),
+        };
+        let toggle = button([
+            on_click(move |_| Msg::ToggleComments(task_id)),
+            class("text-xs text-ctp-subtext0 hover:text-ctp-blue"),
+            r#type("button"),
+        ], [text(&label)]);
+        if !self.open_comments.contains(&task_id) {
+            return div([class("mt-3")], [toggle]);
+        }
+
+        let draft = self.comment_drafts.get(&task_id).map(String::as_str).unwrap_or_default();
+        div([class("mt-3 space-y-2 text-sm")], [
+            toggle,
+            match comments {
+                None => p([class("text-xs text-ctp-subtext0")], [text("Loading comments...")]),
+                Some(comments) if comments.is_empty() => p([class("text-xs text-ctp-subtext0")], [text("No comments yet.")]),
+                Some(comments) => ul(
+                    [class("space-y-2")],
+                    comments.iter().map(|comment| {
+                        li([class("border-l-2 border-ctp-surface2 pl-2")], [
+                            p([class("text-ctp-text whitespace-pre-wrap break-words")], [text(&comment.body)]),
+                            span([class("text-xs text-ctp-subtext0")], [text(&format_timestamp(comment.created_at))]),
+                        ])
+                    }).collect::<Vec<_>>(),
+                ),
+            },
+            div([class("flex items-end gap-2")], [
+                textarea([
+                    value(draft),
+                    on_input(move |event| Msg::SetCommentDraft(task_id, event.value())),
+                    placeholder("Add a comment"),
+                    attr("maxlength", Comment::MAX_CHARS.to_string()),
+                    class("flex-1 h-16 resize-y bg-ctp-surface1 text-ctp-text rounded-md px-2 py-1 border border-ctp-surface2 focus:outline-none focus:ring-2 focus:ring-ctp-blue"),
+                ], []),
+                button([
+                    on_click(move |_| Msg::PostComment(task_id)),
+                    disabled(draft.trim().is_empty()),
+                    class("bg-ctp-blue hover:bg-ctp-sapphire text-ctp-base font-medium px-3 py-1 rounded-md transition-colors duration-200 disabled:opacity-50"),
+                    r#type("button"),
+                ], [text("Post")]),
+            ]),
+        ])
     }
 
+    /// The task's card, reused from the last render unless something it
+    /// shows changed. Cards being edited, uploaded to or showing comments
+    /// depend on too much to be worth keeping.
     fn view_task(&self, task: &Task) -> Node<Msg> {
-    let is_editing = self.editing_task == Some(task.id);
+        let busy = self.edit.editing() == Some(task.id)
+            || self.open_comments.contains(&task.id)
+            || self.uploads.iter().any(|upload| upload.task_id == task.id);
+        if busy {
+            return self.render_task(task);
+        }
+
+        let key = memo::CardKey {
+            task: task.clone(),
+            loading: self.task_loading_states.contains_key(&task.id),
+            highlighted: self.drop_target == Some(task.id)
+                || self.focused_task == Some(task.id)
+                || self.selected_tasks.contains(&task.id),
+            selecting: self.selecting,
+            comments: self.comments.get(&task.id).map(Vec::len),
+            created: created_ago(task),
+        };
+        self.cards.get_or_render(key, || self.render_task(task))
+    }
+
+    fn render_task(&self, task: &Task) -> Node<Msg> {
+    let draft = self.edit.draft(task.id);
+    let is_editing = draft.is_some();
     let is_loading = self.task_loading_states.contains_key(&task.id);
 
     // Debug logging for task rendering
     console::log_1(&format!("[DEBUG] Rendering task - ID: {}, Title: '{}', Completed: {}, Is Editing: {}, Is Loading: {}",
         task.id, task.title, task.completed, is_editing, is_loading).into());
 
-    div(
-        [key(task.id.to_string()),
+    let is_drop_target = self.drop_target == Some(task.id);
+
+    let mut card_attributes = vec![
+        key(task.id.to_string()),
+        id(&format!("task-{}", task.id)),
         class(&format!(
-            "group border rounded-xl p-6 bg-ctp-surface0 shadow-sm transition-all duration-300 hover:shadow-lg {}",
-            if task.completed {
+            "group border rounded-xl p-6 bg-ctp-surface0 shadow-sm transition-all duration-300 hover:shadow-lg {} {}",
+            if self.selecting { "cursor-pointer" } else { "" },
+            if is_drop_target || self.focused_task == Some(task.id) || self.selected_tasks.contains(&task.id) {
+                "border-ctp-blue ring-2 ring-ctp-blue"
+            } else if task.completed {
                 "border-ctp-green bg-ctp-green/10"
             } else {
                 "border-ctp-surface1 hover:border-ctp-blue hover:-translate-y-0.5"
             }
-        ))],
-        if is_editing {
+        )),
+    ];
+    card_attributes.extend(attachment_listeners(task.id));
+    if self.selecting {
+        let task_id = task.id;
+        card_attributes.push(on_click(move |event| {
+            Msg::SelectTask(task_id, event.shift_key(), event.ctrl_key() || event.meta_key())
+        }));
+    }
+    card_attributes.push(on("contextmenu", {
+        let task_id = task.id;
+        move |event| {
+            let position = event.as_web().and_then(|event| {
+                event.prevent_default();
+                event
+                    .dyn_ref::<web_sys::MouseEvent>()
+                    .map(|mouse| (mouse.client_x(), mouse.client_y()))
+            });
+            let (x, y) = position.unwrap_or_default();
+            Msg::OpenTaskMenu(task_id, x, y)
+        }
+    }));
+
+    div(
+        card_attributes,
+        if let Some(draft) = draft {
             vec![
                 div([class("space-y-3")], [
+                    if draft.conflict {
+                        div([class("p-3 bg-ctp-yellow/10 border border-ctp-yellow rounded-md text-sm text-ctp-text")], [
+                            p([class("font-medium")], [text("This task was changed somewhere else while you were editing.")]),
+                            p([class("text-ctp-subtext1 mt-1")], [
+                                text(&format!("It's now titled \u{201c}{}\u{201d}. Save again to replace it with your version below, or cancel to keep theirs.", task.title)),
+                            ]),
+                        ])
+                    } else {
+                        span([], [])
+                    },
                     input([
                         r#type("text"),
-                        value(&self.edit_title),
-                        on_input(|event| Msg::SetEditTitle(event.value())),
+                        value(&draft.title),
+                        on_input(|event| Msg::Form(FormMsg::SetEditTitle(event.value()))),
                         class("w-full px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
                     ], []),
+                    view_field_error(&draft.errors, validation::TITLE),
                     textarea([
-                        value(&self.edit_description),
-                        on_input(|event| Msg::SetEditDescription(event.value())),
+                        value(&draft.description),
+                        on_input(|event| Msg::Form(FormMsg::SetEditDescription(event.value()))),
                         class("w-full px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent h-20 resize-y"),
                     ], []),
+                    view_field_error(&draft.errors, validation::DESCRIPTION),
+                    view_priority_select(draft.priority, |priority| Msg::Form(FormMsg::SetEditPriority(priority)), "bg-ctp-surface1"),
+                    input([
+                        r#type("text"),
+                        placeholder("Tags, comma separated"),
+                        value(&draft.tags),
+                        on_input(|event| Msg::Form(FormMsg::SetEditTags(event.value()))),
+                        class("w-full px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                    ], []),
+                    div([class("flex flex-wrap gap-2")], [
+                        input([
+                            r#type("text"),
+                            placeholder("Location name"),
+                            value(&draft.location_name),
+                            on_input(|event| Msg::Form(FormMsg::SetEditLocationName(event.value()))),
+                            class("flex-1 min-w-32 px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                        ], []),
+                        input([
+                            r#type("text"),
+                            placeholder("lat, lng"),
+                            value(&draft.location_coordinates),
+                            on_input(|event| Msg::Form(FormMsg::SetEditLocationCoordinates(event.value()))),
+                            class("flex-1 min-w-32 px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                        ], []),
+                        input([
+                            r#type("number"),
+                            placeholder("Radius (m)"),
+                            value(&draft.location_radius),
+                            on_input(|event| Msg::Form(FormMsg::SetEditLocationRadius(event.value()))),
+                            class("w-28 px-3 py-2 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-ctp-text placeholder-ctp-subtext0 focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent"),
+                        ], []),
+                        button([
+                            on_click(|_| Msg::Form(FormMsg::UseCurrentLocation)),
+                            class("bg-ctp-surface2 hover:bg-ctp-overlay0 text-ctp-text px-3 py-2 rounded-md text-sm transition-colors duration-200"),
+                            r#type("button"),
+                        ], [text("📍 Use my location")]),
+                    ]),
+                    self.view_repeat_fields(draft),
                     div([class("flex gap-2")], [
                         button([
                             on_click({
                                 let captured_id = task.id;
-                                move |_| Msg::SaveEdit(captured_id)
+                                move |_| Msg::Form(FormMsg::SaveEdit(captured_id))
                             }),
                             class("bg-ctp-green hover:bg-ctp-teal text-ctp-base font-medium px-4 py-2 rounded-md transition-colors duration-200"),
                             disabled(is_loading),
@@ -666,7 +5257,7 @@ impl Model {
                             }
                         ]),
                         button([
-                            on_click(|_| Msg::CancelEdit),
+                            on_click(|_| Msg::Form(FormMsg::CancelEdit)),
                             class("bg-ctp-overlay0 hover:bg-ctp-overlay1 text-ctp-text font-medium px-4 py-2 rounded-md transition-colors duration-200"),
                             disabled(is_loading),
                         ], [text("Cancel")]),
@@ -685,10 +5276,10 @@ impl Model {
                                 id(&format!("checkbox-{}", task.id)), // Add unique ID
                                 on_click({
                                     let task_id = task.id;
-                                    move |_| Msg::ToggleTask(task_id)
+                                    move |_| Msg::Task(TaskMsg::ToggleTask(task_id))
                                 }),
                                 class("sr-only"),
-                                disabled(is_loading),
+                                disabled(is_loading || self.selecting),
                             ], []),
                             div([class(&format!(
                                 "w-6 h-6 rounded-lg border-2 flex items-center justify-center transition-all duration-200 {}",
@@ -722,6 +5313,8 @@ impl Model {
                         ))], [
                             if is_loading {
                                 text(&format!("{} (updating...)", task.title))
+                            } else if task.pinned {
+                                text(&format!("📌 {}", task.title))
                             } else {
                                 text(&task.title)
                             }
@@ -734,6 +5327,40 @@ impl Model {
                                 "text-ctp-subtext1"
                             }
                         ))], [text(&task.description)]),
+
+                        div(
+                            [class("mt-2 flex flex-wrap gap-1")],
+                            std::iter::once(view_priority_badge(task.priority))
+                                .chain(task.flagged.then(|| {
+                                    span([class("px-2 py-0.5 rounded-full text-xs font-medium bg-ctp-yellow/20 text-ctp-yellow")], [text("Flagged")])
+                                }))
+                                .chain(task.due_at.map(|due| {
+                                    span([class("px-2 py-0.5 rounded-full text-xs bg-ctp-peach/20 text-ctp-peach")], [text(&format!("📅 Due {}", format_timestamp(due)))])
+                                }))
+                                .chain(task.recurrence.as_ref().map(|rule| {
+                                    span([class("px-2 py-0.5 rounded-full text-xs bg-ctp-teal/20 text-ctp-teal")], [text(&format!("🔁 {}", rule.label()))])
+                                }))
+                                .chain(task.tags.iter().map(|tag| {
+                                    span([class("px-2 py-0.5 rounded-full text-xs bg-ctp-mauve/20 text-ctp-mauve")], [text(&format!("#{}", tag))])
+                                }))
+                                .collect::<Vec<_>>(),
+                        ),
+                        if let Some(location) = &task.location {
+                            p([class("mt-2 text-xs text-ctp-subtext0")], [text(&format!("📍 {}", location.name))])
+                        } else {
+                            span([], [])
+                        },
+                        if let Some(created) = created_ago(task) {
+                            p(
+                                [class("mt-2 text-xs text-ctp-subtext0"), attr("title", task.created_at.map(format_timestamp).unwrap_or_default())],
+                                [text(&created)],
+                            )
+                        } else {
+                            span([], [])
+                        },
+
+                        self.view_attachments(task),
+                        self.view_comments(task.id),
                         
                         // Completion status badge
                         if task.completed {
@@ -761,14 +5388,15 @@ impl Model {
                         }
                     ]),
                     
-                    // Action buttons with improved styling
-                    div([class("flex-shrink-0")], [
+                    // Action buttons with improved styling; hidden while selecting so clicks select
+                    div([class(if self.selecting { "hidden" } else { "flex-shrink-0" })], [
                         div([class("flex flex-col gap-2")], [
+                            self.view_task_menu_button(task.id),
                             if !task.completed {
                                 button([
                                     on_click({
                                         let captured_id = task.id;
-                                        move |_| Msg::EditTask(captured_id)
+                                        move |_| Msg::Form(FormMsg::EditTask(captured_id))
                                     }),
                                     class("inline-flex items-center justify-center w-8 h-8 rounded-lg bg-ctp-blue/20 text-ctp-blue hover:bg-ctp-blue/30 transition-colors duration-200 group"),
                                     r#type("button"),
@@ -782,7 +5410,7 @@ impl Model {
                             button([
                                 on_click({
                                     let captured_id = task.id;
-                                    move |_| Msg::DeleteTask(captured_id)
+                                    move |_| Msg::Task(TaskMsg::DeleteTask(captured_id))
                                 }),
                                 class(&format!(
                                     "inline-flex items-center justify-center w-8 h-8 rounded-lg transition-colors duration-200 group {}",
@@ -813,50 +5441,54 @@ impl Model {
 }
 
 
-async fn fetch_tasks() -> Result<Vec<Task>, String> {
-    let promise = web_sys::window()
-        .unwrap()
-        .fetch_with_str("/api/tasks");
-    
-    let response: Response = JsFuture::from(promise)
+/// Tasks changed after change number `since`; without it, just the current number.
+async fn fetch_changes(since: Option<u64>) -> Result<TaskChanges, String> {
+    let url = match since {
+        Some(since) => format!("/api/tasks/changes?since={}", since),
+        None => "/api/tasks/changes".to_string(),
+    };
+    api::request("GET", &url, api::NO_BODY)
         .await
-        .map_err(|_| "Failed to fetch tasks")?
-        .into();
+        .map_err(|e| e.describe("Failed to fetch task changes"))
+}
 
-    let text_promise = response.text().map_err(|_| "Failed to read response")?;
-    let text = JsFuture::from(text_promise)
+async fn fetch_activity(after: Option<&str>) -> Result<ListResponse<ActivityEvent>, String> {
+    let url = match after {
+        Some(cursor) => format!("/api/activity?after={}", cursor),
+        None => "/api/activity".to_string(),
+    };
+    api::request("GET", &url, api::NO_BODY)
         .await
-        .map_err(|_| "Failed to get text")?
-        .as_string()
-        .ok_or("Failed to convert to string")?;
+        .map_err(|e| e.describe("Failed to fetch activity"))
+}
 
-    serde_json::from_str(&text).map_err(|e| format!("Failed to parse JSON: {}", e))
+async fn fetch_analytics() -> Result<AnalyticsSummary, String> {
+    api::request("GET", "/api/analytics?days=30", api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to fetch analytics"))
 }
 
-async fn create_task(task_title: String, description: String) -> Result<Task, String> {
-    let request = CreateTaskRequest { title: task_title, description };
-    let body = serde_json::to_string(&request).map_err(|_| "Failed to serialize request")?;
+/// Imports `file`, or with `dry_run` only reports what importing it would do.
+async fn import_tasks(file: &web_sys::File, dry_run: bool) -> Result<ImportSummary, String> {
+    let form = web_sys::FormData::new().map_err(|_| "Failed to create form data")?;
+    form.append_with_blob_and_filename("file", file, &file.name())
+        .map_err(|_| "Failed to add file to form")?;
 
     let opts = RequestInit::new();
     opts.set_method("POST");
-    opts.set_body(&wasm_bindgen::JsValue::from_str(&body));
+    opts.set_body(&form);
 
-    let request = Request::new_with_str_and_init("/api/tasks", &opts)
+    let url = if dry_run { "/api/tasks/import?dry_run=true" } else { "/api/tasks/import" };
+    let request = Request::new_with_str_and_init(&base::url(url), &opts)
         .map_err(|_| "Failed to create request")?;
 
-    request
-        .headers()
-        .set("Content-Type", "application/json")
-        .map_err(|_| "Failed to set header")?;
-
-    let promise = web_sys::window()
-        .unwrap()
-        .fetch_with_request(&request);
-
-    let response: Response = JsFuture::from(promise)
+    let response = api::send(&request)
         .await
-        .map_err(|_| "Failed to send request")?
-        .into();
+        .map_err(|_| "Failed to import tasks")?;
+
+    if !response.ok() {
+        return Err(http_error(&format!("Failed to import {}", file.name()), &response).await);
+    }
 
     let text_promise = response.text().map_err(|_| "Failed to read response")?;
     let text = JsFuture::from(text_promise)
@@ -868,79 +5500,535 @@ async fn create_task(task_title: String, description: String) -> Result<Task, St
     serde_json::from_str(&text).map_err(|e| format!("Failed to parse JSON: {}", e))
 }
 
-async fn update_task(
-    id: Uuid,
-    task_title: Option<String>,
-    description: Option<String>,
-    completed: Option<bool>,
-) -> Result<Task, String> {
-    console::log_1(&format!("[DEBUG] update_task called - ID: {}, completed: {:?}", id, completed).into());
-    
-    let request = UpdateTaskRequest {
-        title: task_title,
-        description,
-        completed,
-    };
-    let body = serde_json::to_string(&request).map_err(|_| "Failed to serialize request")?;
-    
-    console::log_1(&format!("[DEBUG] Update request - Only updating completion status to: {:?} (title/description preserved)", completed).into());
-    console::log_1(&format!("[DEBUG] Request body: {}", body).into());
+async fn fetch_tag_stats() -> Result<Vec<TagStats>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/analytics/tags", api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch tag statistics"))
+}
 
-    let opts = RequestInit::new();
-    opts.set_method("PUT");
-    opts.set_body(&wasm_bindgen::JsValue::from_str(&body));
+async fn fetch_recent(filter: QuickFilter) -> Result<Vec<Task>, String> {
+    let url = format!("/api/tasks/recent?by={}&since={}&per_page=200", filter.timestamp(), filter.since());
+    api::request::<ListResponse<_>>("GET", &url, api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch tasks"))
+}
 
-    let url = format!("/api/tasks/{}", id);
-    let request = Request::new_with_str_and_init(&url, &opts)
-        .map_err(|_| "Failed to create request")?;
+async fn fetch_nearby(lat: f64, lng: f64) -> Result<Vec<Task>, String> {
+    api::request::<ListResponse<_>>("GET", &format!("/api/tasks/nearby?lat={}&lng={}", lat, lng), api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch nearby tasks"))
+}
+
+async fn fetch_flags() -> Result<FeatureFlags, String> {
+    api::request("GET", "/api/flags", api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to fetch flags"))
+}
 
-    request
-        .headers()
-        .set("Content-Type", "application/json")
-        .map_err(|_| "Failed to set header")?;
+/// Anonymous per-browser id used as the experiment subject.
+fn client_id() -> String {
+    let storage = window().and_then(|w| w.local_storage().ok().flatten());
+    if let Some(existing) = storage.as_ref().and_then(|s| s.get_item("client_id").ok().flatten()) {
+        return existing;
+    }
 
-    let promise = web_sys::window()
-        .unwrap()
-        .fetch_with_request(&request);
+    let generated = Uuid::new_v4().to_string();
+    if let Some(storage) = storage {
+        let _ = storage.set_item("client_id", &generated);
+    }
+    generated
+}
 
-    let response: Response = JsFuture::from(promise)
+async fn fetch_experiments() -> Result<ExperimentAssignments, String> {
+    api::request("GET", "/api/experiments", api::NO_BODY)
         .await
-        .map_err(|_| "Failed to send request")?
-        .into();
+        .map_err(|e| e.describe("Failed to fetch experiments"))
+}
 
-    let text_promise = response.text().map_err(|_| "Failed to read response")?;
-    let text = JsFuture::from(text_promise)
+async fn log_exposure(exposure: ExposureRequest) -> Result<(), String> {
+    match api::request::<IgnoredAny>("POST", "/api/experiments/exposures", Some(&exposure)).await {
+        Err(api::Error::Network) => Err("Failed to send request".to_string()),
+        _ => Ok(()),
+    }
+}
+
+async fn fetch_experiment_results() -> Result<Vec<ExperimentResults>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/admin/experiments", api::NO_BODY)
         .await
-        .map_err(|_| "Failed to get text")?
-        .as_string()
-        .ok_or("Failed to convert to string")?;
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch experiment results"))
+}
 
-    console::log_1(&format!("[DEBUG] Update response text: {}", text).into());
-    
-    let parsed_task: Task = serde_json::from_str(&text).map_err(|e| format!("Failed to parse JSON: {}", e))?;
-    console::log_1(&format!("[DEBUG] Parsed updated task - ID: {}, Title: '{}', Completed: {}", 
-        parsed_task.id, parsed_task.title, parsed_task.completed).into());
-    
-    Ok(parsed_task)
+async fn fetch_logs(level: LogLevel) -> Result<Vec<LogEntry>, String> {
+    api::request::<ListResponse<_>>("GET", &format!("/api/admin/logs?level={}&per_page=200", level.name()), api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch logs"))
 }
 
-async fn delete_task(id: Uuid) -> Result<(), String> {
-    let opts = RequestInit::new();
-    opts.set_method("DELETE");
+async fn fetch_client_errors() -> Result<Vec<ClientErrorReport>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/admin/client-errors", api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch client errors"))
+}
 
-    let url = format!("/api/tasks/{}", id);
-    let request = Request::new_with_str_and_init(&url, &opts)
-        .map_err(|_| "Failed to create request")?;
+async fn fetch_version() -> Result<BuildInfo, String> {
+    api::request("GET", "/api/version", api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to fetch version"))
+}
 
-    let promise = web_sys::window()
-        .unwrap()
-        .fetch_with_request(&request);
+/// `GET /status`. Also answered with a body when the backend is down.
+async fn fetch_status() -> Result<ServiceStatus, String> {
+    match api::request("GET", "/status", api::NO_BODY).await {
+        Err(e) if e.status() == Some(503) => e.json().ok_or_else(|| e.describe("Failed to fetch status")),
+        result => result.map_err(|e| e.describe("Failed to fetch status")),
+    }
+}
+
+async fn fetch_announcements() -> Result<Vec<Announcement>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/announcements", api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch announcements"))
+}
+
+async fn publish_announcement(request: CreateAnnouncementRequest) -> Result<Announcement, String> {
+    api::request("POST", "/api/admin/announcements", Some(&request))
+        .await
+        .map_err(|e| e.describe("Failed to publish announcement"))
+}
+
+async fn delete_announcement(id: Uuid) -> Result<(), String> {
+    api::request::<IgnoredAny>("DELETE", &format!("/api/admin/announcements/{}", id), api::NO_BODY)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.describe("Failed to delete announcement"))
+}
+
+async fn fetch_overview() -> Result<AdminOverview, String> {
+    api::request("GET", "/api/admin/overview", api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to fetch admin overview"))
+}
 
-    JsFuture::from(promise)
+async fn fetch_content_filter() -> Result<ContentFilterSettings, String> {
+    api::request("GET", "/api/admin/content-filter", api::NO_BODY)
         .await
-        .map_err(|_| "Failed to send request")?;
+        .map_err(|e| e.describe("Failed to fetch content filter settings"))
+}
+
+async fn set_filter_mode(request: SetFilterModeRequest) -> Result<ContentFilterSettings, String> {
+    api::request("PUT", "/api/admin/content-filter", Some(&request))
+        .await
+        .map_err(|e| e.describe("Failed to set content filter mode"))
+}
+
+async fn fetch_automations() -> Result<AutomationSettings, String> {
+    api::request("GET", "/api/admin/automations", api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to fetch automation rules"))
+}
+
+fn save_automation_rules(workspace: Option<String>, rules: Vec<AutomationRule>) -> Cmd<Msg> {
+    cmd::run(set_automation_rules(SetAutomationRulesRequest { rules, workspace }), Msg::AutomationsLoaded)
+}
+
+async fn set_automation_rules(request: SetAutomationRulesRequest) -> Result<AutomationSettings, String> {
+    api::request("PUT", "/api/admin/automations", Some(&request))
+        .await
+        .map_err(|e| e.describe("Failed to save automation rules"))
+}
+
+async fn fetch_comments(task_id: Uuid) -> Result<Vec<Comment>, String> {
+    api::request::<ListResponse<_>>("GET", &format!("/api/tasks/{}/comments?per_page=100", task_id), api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch comments"))
+}
+
+async fn post_comment(task_id: Uuid, request: CreateCommentRequest) -> Result<Comment, String> {
+    api::request("POST", &format!("/api/tasks/{}/comments", task_id), Some(&request))
+        .await
+        .map_err(|e| e.describe("Failed to post comment"))
+}
+
+async fn fetch_automation_log() -> Result<Vec<RuleExecution>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/admin/automations/log", api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch automation runs"))
+}
+
+async fn fetch_server_config() -> Result<Vec<ConfigEntry>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/admin/config", api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch server config"))
+}
+
+async fn fetch_plan(date: &str) -> Result<DayPlan, String> {
+    api::request("GET", &format!("/api/plans/{}", date), api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to fetch plan"))
+}
+
+async fn save_plan(date: &str, task_ids: Vec<Uuid>) -> Result<DayPlan, String> {
+    api::request("PUT", &format!("/api/plans/{}", date), Some(&SavePlanRequest { task_ids }))
+        .await
+        .map_err(|e| e.describe("Failed to save plan"))
+}
+
+async fn roll_over_plan(date: &str, to: String) -> Result<DayPlan, String> {
+    api::request("POST", &format!("/api/plans/{}/rollover", date), Some(&RollOverRequest { to }))
+        .await
+        .map_err(|e| e.describe("Failed to roll over plan"))
+}
+
+/// Asks whether to drop the unsaved changes in the edit form for another
+/// edit. Not one of the confirmations that can be turned off, as the changes
+/// would be lost.
+fn discard_unsaved_edit() -> bool {
+    window()
+        .and_then(|w| w.confirm_with_message("You have unsaved changes to another task. Discard them?").ok())
+        .unwrap_or(false)
+}
+
+/// Stamps an edit made now on this device.
+fn edit_stamp() -> Stamp {
+    Stamp {
+        at: js_sys::Date::now() as u64,
+        replica: client_id(),
+    }
+}
+
+/// Sends stamped edits to be merged into the task (the `crdt_sync` experiment).
+async fn send_edits(id: Uuid, edits: Vec<TaskEdit>) -> Result<Task, String> {
+    api::request("POST", &format!("/api/tasks/{}/edits", id), Some(&edits))
+        .await
+        .map_err(|e| e.describe("Failed to save task"))
+}
+
+async fn bulk_update(request: BulkRequest) -> Result<BulkResponse, String> {
+    api::request("POST", "/api/tasks/bulk", Some(&request))
+        .await
+        .map_err(|e| e.describe("Failed to update selected tasks"))
+}
+
+async fn fetch_trash() -> Result<Vec<Task>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/trash?per_page=200", api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch the trash"))
+}
+
+async fn restore_task(id: Uuid) -> Result<Task, String> {
+    api::request("POST", &format!("/api/tasks/{}/restore", id), api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to restore task"))
+}
+
+async fn fetch_archive() -> Result<Vec<Task>, String> {
+    api::request::<ListResponse<_>>("GET", "/api/archive?per_page=200", api::NO_BODY)
+        .await
+        .map(|list| list.data)
+        .map_err(|e| e.describe("Failed to fetch the archive"))
+}
+
+async fn unarchive_task(id: Uuid) -> Result<Task, String> {
+    api::request("POST", &format!("/api/archive/{}/restore", id), api::NO_BODY)
+        .await
+        .map_err(|e| e.describe("Failed to restore task"))
+}
+
+async fn purge_task(id: Uuid) -> Result<(), String> {
+    api::request::<IgnoredAny>("DELETE", &format!("/api/trash/{}", id), api::NO_BODY)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.describe("Failed to delete task"))
+}
+
+fn view_priority_badge(priority: Priority) -> Node<Msg> {
+    let colors = match priority {
+        Priority::Low => "bg-ctp-overlay0/20 text-ctp-subtext0",
+        Priority::Medium => "bg-ctp-blue/20 text-ctp-blue",
+        Priority::High => "bg-ctp-peach/20 text-ctp-peach",
+        Priority::Urgent => "bg-ctp-red/20 text-ctp-red",
+    };
+    span(
+        [class(&format!("px-2 py-0.5 rounded-full text-xs font-medium {}", colors))],
+        [text(priority.label())],
+    )
+}
+
+fn view_filter_mode_row(label: &str, workspace: Option<String>, current: FilterMode) -> Node<Msg> {
+    tr([], [
+        td([class("py-2 pr-4 font-mono text-ctp-text")], [text(label)]),
+        td([class("py-2")], [
+            select(
+                [
+                    on_change(move |event| {
+                        Msg::SetFilterMode(workspace.clone(), FilterMode::from_name(&event.value()).unwrap_or_default())
+                    }),
+                    class("px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text"),
+                ],
+                FilterMode::ALL.iter().map(|mode| {
+                    option([value(mode.name()), selected(*mode == current)], [text(mode.name())])
+                }).collect::<Vec<_>>(),
+            ),
+        ]),
+    ])
+}
+
+fn view_automation_rule(rule: &AutomationRule) -> Node<Msg> {
+    let id = rule.id;
+    li([class("flex items-center gap-3 py-2")], [
+        input([
+            r#type("checkbox"),
+            checked(rule.enabled),
+            on_click(move |_| Msg::ToggleAutomationRule(id)),
+            class("w-4 h-4 accent-ctp-blue"),
+        ], []),
+        div([class(if rule.enabled { "flex-1" } else { "flex-1 opacity-50" })], [
+            p([class("text-ctp-text font-medium")], [text(&rule.name)]),
+            p([class("text-sm text-ctp-subtext0")], [text(&rule.summary())]),
+        ]),
+        button(
+            [
+                on_click(move |_| Msg::DeleteAutomationRule(id)),
+                class("text-sm text-ctp-red hover:text-ctp-maroon transition-colors"),
+            ],
+            [text("Delete")],
+        ),
+    ])
+}
+
+/// A select over `choices`, as `(value, label)` pairs, for a field of the
+/// rule form.
+fn view_rule_choice(choices: &[(&str, &str)], current: &str, field: RuleField) -> Node<Msg> {
+    select(
+        [
+            on_change(move |event| Msg::SetRuleField(field, event.value())),
+            class("px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text"),
+        ],
+        choices.iter().map(|(choice, label)| {
+            option([value(*choice), selected(*choice == current)], [text(*label)])
+        }).collect::<Vec<_>>(),
+    )
+}
+
+/// The value a condition or action of the rule form takes, if any.
+fn view_rule_value(kind: Option<ValueKind>, current: &str, field: RuleField) -> Node<Msg> {
+    let field_class = "px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text placeholder-ctp-subtext0";
+    match kind {
+        None => span([], []),
+        Some(ValueKind::Priority) => select(
+            [
+                on_change(move |event| Msg::SetRuleField(field, event.value())),
+                class("px-3 py-1 bg-ctp-surface1 border border-ctp-surface2 rounded-md text-sm text-ctp-text"),
+            ],
+            Priority::ALL.iter().map(|priority| {
+                option([value(priority.name()), selected(priority.name() == current)], [text(priority.label())])
+            }).collect::<Vec<_>>(),
+        ),
+        Some(kind) => input([
+            r#type("text"),
+            placeholder(if let ValueKind::Text(hint) = kind { hint } else { "Tag" }),
+            value(current),
+            on_input(move |event| Msg::SetRuleField(field, event.value())),
+            class(&format!("w-40 {}", field_class)),
+        ], []),
+    }
+}
+
+/// The edit form's repeat choice and cron field for a task's rule.
+fn repeat_fields(rule: Option<&RecurrenceRule>) -> (String, String) {
+    match rule {
+        None => (String::new(), String::new()),
+        Some(RecurrenceRule::Daily) => ("daily".to_string(), String::new()),
+        Some(RecurrenceRule::Weekly) => ("weekly".to_string(), String::new()),
+        Some(RecurrenceRule::Monthly) => ("monthly".to_string(), String::new()),
+        Some(RecurrenceRule::Cron { expression }) => ("cron".to_string(), expression.clone()),
+    }
+}
+
+/// The inverse of [`repeat_fields`], checking a cron expression before it's
+/// sent.
+fn recurrence_rule(repeat: &str, cron: &str) -> Result<Option<RecurrenceRule>, String> {
+    let rule = match repeat {
+        "daily" => RecurrenceRule::Daily,
+        "weekly" => RecurrenceRule::Weekly,
+        "monthly" => RecurrenceRule::Monthly,
+        "cron" => RecurrenceRule::Cron { expression: cron.trim().to_string() },
+        _ => return Ok(None),
+    };
+    rule.validate()?;
+    Ok(Some(rule))
+}
+
+/// The error for `field` in `errors`, to go under its input.
+fn view_field_error(errors: &[FieldError], field: &str) -> Node<Msg> {
+    match errors.iter().find(|e| e.field == field) {
+        Some(error) => p([class("text-sm text-ctp-red")], [text(&error.message)]),
+        None => span([], []),
+    }
+}
+
+fn view_priority_select(current: Priority, to_msg: fn(Priority) -> Msg, background: &str) -> Node<Msg> {
+    select(
+        [
+            on_change(move |event| to_msg(Priority::from_name(&event.value()).unwrap_or_default())),
+            class(&format!(
+                "w-full px-3 py-2 {} border border-ctp-surface2 rounded-md text-ctp-text focus:outline-none focus:ring-2 focus:ring-ctp-blue focus:border-transparent",
+                background
+            )),
+        ],
+        Priority::ALL.iter().map(|priority| {
+            option(
+                [value(priority.name()), selected(*priority == current)],
+                [text(&format!("{} priority", priority.label()))],
+            )
+        }).collect::<Vec<_>>(),
+    )
+}
+
+/// Drag-and-drop and paste handlers that upload files onto a task card. The
+/// card is focusable so a clipboard image can be pasted straight onto it.
+fn attachment_listeners(task_id: Uuid) -> Vec<Attribute<Msg>> {
+    vec![
+        tabindex(0),
+        on("dragover", move |event| {
+            // Dropping is only allowed when dragover is cancelled
+            if let Some(event) = event.as_web() {
+                event.prevent_default();
+            }
+            Msg::SetDropTarget(Some(task_id))
+        }),
+        on("dragleave", |_| Msg::SetDropTarget(None)),
+        on("drop", move |event| {
+            let files = event.as_web().map(|event| {
+                event.prevent_default();
+                uploads::files_from_event(&event)
+            });
+            Msg::UploadFiles(task_id, files.unwrap_or_default())
+        }),
+        on("paste", move |event| {
+            let files = match event.as_web() {
+                Some(event) => {
+                    let files = uploads::files_from_event(&event);
+                    // Plain text pastes (e.g. into the edit form) are left alone
+                    if !files.is_empty() {
+                        event.prevent_default();
+                    }
+                    files
+                }
+                None => Vec::new(),
+            };
+            Msg::UploadFiles(task_id, files)
+        }),
+    ]
+}
+
+/// Writes `text` to the system clipboard with the async Clipboard API.
+async fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let clipboard = window().ok_or("No window")?.navigator().clipboard();
+    JsFuture::from(clipboard.write_text(text))
+        .await
+        .map(|_| ())
+        .map_err(|_| "Failed to copy to clipboard".to_string())
+}
+
+fn scroll_to_task(id: Uuid) {
+    let element = window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(&format!("task-{}", id)));
+    if let Some(element) = element {
+        let options = web_sys::ScrollIntoViewOptions::new();
+        options.set_behavior(web_sys::ScrollBehavior::Smooth);
+        element.scroll_into_view_with_scroll_into_view_options(&options);
+    }
+}
+
+/// Error message for a non-2xx response, as [`api::Error::describe`] words it.
+async fn http_error(context: &str, response: &Response) -> String {
+    api::Error::from_response(response).await.describe(context)
+}
+
+async fn connect_live() -> Msg {
+    if live::connect().await {
+        Msg::LiveConnected
+    } else {
+        Msg::LiveDisconnected
+    }
+}
+
+fn next_live_event() -> Cmd<Msg> {
+    Cmd::new(async {
+        match live::next_event().await {
+            Some(event) => Msg::LiveEvent(event),
+            None => Msg::LiveDisconnected,
+        }
+    })
+}
+
+/// Resolves after `ms` milliseconds using `setTimeout`.
+async fn sleep(ms: i32) {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        if let Some(window) = window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+        }
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+/// Vertical bars scaled to the largest value, labelled on hover.
+fn bar_chart(bars: &[(String, u64)], color: &str) -> Node<Msg> {
+    let max = bars.iter().map(|(_, value)| *value).max().unwrap_or(0).max(1);
+    div([class("flex items-end gap-px h-32 border-b border-ctp-surface2")], bars.iter().map(|(label, value)| {
+        let height = *value as f64 / max as f64 * 100.0;
+        div([
+            class(&format!("flex-1 rounded-t {}", color)),
+            attr("style", format!("height: {:.0}%", height)),
+            attr("title", format!("{}: {}", label, value)),
+        ], [])
+    }).collect::<Vec<_>>())
+}
+
+/// Coarse human-readable duration, e.g. `3d` or `5h`.
+fn format_age(secs: u64) -> String {
+    match secs {
+        s if s >= 86_400 => format!("{}d", s / 86_400),
+        s if s >= 3_600 => format!("{}h", s / 3_600),
+        s => format!("{}m", s / 60),
+    }
+}
+
+/// "created 2h ago" for the task's card, if its creation was recorded.
+fn created_ago(task: &Task) -> Option<String> {
+    let created_at = task.created_at?;
+    Some(format!("created {} ago", format_age(now_secs().saturating_sub(created_at))))
+}
+
+/// The local calendar date `days` from today, as `YYYY-MM-DD`.
+fn local_date(days: u32) -> String {
+    let date = js_sys::Date::new_0();
+    date.set_date(date.get_date() + days);
+    format!("{:04}-{:02}-{:02}", date.get_full_year(), date.get_month() + 1, date.get_date())
+}
+
+/// Current unix time in seconds, from the browser clock.
+fn now_secs() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
 
-    Ok(())
+/// Formats a unix timestamp (seconds) in the browser's locale.
+fn format_timestamp(secs: u64) -> String {
+    let date = js_sys::Date::new(&wasm_bindgen::JsValue::from_f64(secs as f64 * 1000.0));
+    String::from(date.to_locale_string("default", &wasm_bindgen::JsValue::UNDEFINED))
 }
 
 fn setup_popstate_listener() {
@@ -968,6 +6056,6 @@ fn setup_popstate_listener() {
 
 #[wasm_bindgen::prelude::wasm_bindgen(start)]
 pub fn main() {
-    console_error_panic_hook::set_once();
+    reporting::install_panic_hook();
     Program::mount_to_body(Model::default());
 }
\ No newline at end of file
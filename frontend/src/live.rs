@@ -0,0 +1,223 @@
+//! Live task updates from `GET /api/ws`, or from `GET /api/events` where
+//! WebSockets are blocked.
+//!
+//! [`connect`] opens the socket once per app; events that arrive are queued
+//! until [`next_event`] hands them out one at a time, so the caller can turn
+//! each into a message. When the socket drops, `next_event` returns `None`
+//! and the caller reconnects after a delay, catching up on anything missed
+//! through `GET /api/tasks/changes`.
+//!
+//! When the socket won't open but the event stream does, something between
+//! here and the server refuses WebSockets, so later reconnects go straight
+//! to the event stream. Both carry the same events, and the caller can't
+//! tell which one is in use.
+
+use shared::TaskEvent;
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    rc::Rc,
+};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{EventSource, MessageEvent, WebSocket};
+
+/// How long to wait before reconnecting a dropped socket.
+pub const RECONNECT_DELAY_MS: i32 = 3000;
+
+#[derive(Default)]
+struct Inbox {
+    events: VecDeque<String>,
+    open: bool,
+    closed: bool,
+    /// Resolves whoever is waiting for the socket to change.
+    wake: Option<js_sys::Function>,
+}
+
+impl Inbox {
+    fn wake(&mut self) {
+        if let Some(wake) = self.wake.take() {
+            let _ = wake.call0(&JsValue::NULL);
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Transport {
+    WebSocket,
+    EventSource,
+}
+
+#[derive(Clone)]
+enum Channel {
+    Socket(WebSocket),
+    Events(EventSource),
+}
+
+impl Channel {
+    fn open(transport: Transport) -> Option<Self> {
+        match transport {
+            Transport::WebSocket => WebSocket::new(&socket_url()?).ok().map(Channel::Socket),
+            Transport::EventSource => EventSource::new(&crate::base::url("/api/events")).ok().map(Channel::Events),
+        }
+    }
+
+    fn close(&self) {
+        match self {
+            Channel::Socket(socket) => {
+                let _ = socket.close();
+            }
+            Channel::Events(source) => source.close(),
+        }
+    }
+
+    /// Closes the channel for good, before its handlers are dropped.
+    fn detach(&self) {
+        match self {
+            Channel::Socket(socket) => {
+                socket.set_onopen(None);
+                socket.set_onmessage(None);
+                socket.set_onclose(None);
+            }
+            Channel::Events(source) => {
+                source.set_onopen(None);
+                source.set_onmessage(None);
+                source.set_onerror(None);
+            }
+        }
+        self.close();
+    }
+}
+
+struct Connection {
+    channel: Channel,
+    inbox: Rc<RefCell<Inbox>>,
+    _on_open: Closure<dyn FnMut()>,
+    _on_message: Closure<dyn FnMut(MessageEvent)>,
+    _on_close: Closure<dyn FnMut()>,
+}
+
+thread_local! {
+    static CONNECTION: RefCell<Option<Connection>> = const { RefCell::new(None) };
+    /// Set once the event stream opened where the socket didn't.
+    static WEBSOCKETS_BLOCKED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Opens the socket, replacing any previous one. Returns whether it opened.
+pub async fn connect() -> bool {
+    if !WEBSOCKETS_BLOCKED.get() && open(Transport::WebSocket).await {
+        return true;
+    }
+    // Also fails when the server is down, so only a stream that opens
+    // shows the socket was refused
+    let opened = open(Transport::EventSource).await;
+    if opened {
+        WEBSOCKETS_BLOCKED.set(true);
+    }
+    opened
+}
+
+async fn open(transport: Transport) -> bool {
+    let Some(channel) = Channel::open(transport) else {
+        return false;
+    };
+
+    let inbox = Rc::new(RefCell::new(Inbox::default()));
+    let on_open = Closure::wrap(Box::new({
+        let inbox = inbox.clone();
+        move || {
+            let mut inbox = inbox.borrow_mut();
+            inbox.open = true;
+            inbox.wake();
+        }
+    }) as Box<dyn FnMut()>);
+    let on_message = Closure::wrap(Box::new({
+        let inbox = inbox.clone();
+        move |event: MessageEvent| {
+            if let Some(text) = event.data().as_string() {
+                let mut inbox = inbox.borrow_mut();
+                inbox.events.push_back(text);
+                inbox.wake();
+            }
+        }
+    }) as Box<dyn FnMut(MessageEvent)>);
+    // Socket errors are always followed by a close; the event stream would
+    // retry by itself, but it's closed so the caller catches up on reconnect
+    let on_close = Closure::wrap(Box::new({
+        let inbox = inbox.clone();
+        let channel = channel.clone();
+        move || {
+            channel.close();
+            let mut inbox = inbox.borrow_mut();
+            inbox.closed = true;
+            inbox.wake();
+        }
+    }) as Box<dyn FnMut()>);
+    match &channel {
+        Channel::Socket(socket) => {
+            socket.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+            socket.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            socket.set_onclose(Some(on_close.as_ref().unchecked_ref()));
+        }
+        Channel::Events(source) => {
+            source.set_onopen(Some(on_open.as_ref().unchecked_ref()));
+            source.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            source.set_onerror(Some(on_close.as_ref().unchecked_ref()));
+        }
+    }
+
+    let previous = CONNECTION.with(|connection| {
+        connection.borrow_mut().replace(Connection {
+            channel,
+            inbox: inbox.clone(),
+            _on_open: on_open,
+            _on_message: on_message,
+            _on_close: on_close,
+        })
+    });
+    if let Some(previous) = previous {
+        previous.channel.detach();
+    }
+
+    loop {
+        {
+            let inbox = inbox.borrow();
+            if inbox.open || inbox.closed {
+                return inbox.open && !inbox.closed;
+            }
+        }
+        changed(&inbox).await;
+    }
+}
+
+/// The next event from the server, or `None` once the socket has closed.
+pub async fn next_event() -> Option<TaskEvent> {
+    let inbox = CONNECTION.with(|connection| connection.borrow().as_ref().map(|c| c.inbox.clone()))?;
+    loop {
+        let next = inbox.borrow_mut().events.pop_front();
+        match next {
+            Some(text) => match serde_json::from_str(&text) {
+                Ok(event) => return Some(event),
+                // Sent by a newer server; nothing to do with it here
+                Err(_) => continue,
+            },
+            None if inbox.borrow().closed => return None,
+            None => changed(&inbox).await,
+        }
+    }
+}
+
+/// Waits for the next open, message or close.
+async fn changed(inbox: &Rc<RefCell<Inbox>>) {
+    let promise = js_sys::Promise::new(&mut |resolve_fn, _reject| {
+        inbox.borrow_mut().wake = Some(resolve_fn);
+    });
+    let _ = JsFuture::from(promise).await;
+}
+
+/// `ws(s)://<host><base path>/api/ws` for the page's own origin.
+fn socket_url() -> Option<String> {
+    let location = web_sys::window()?.location();
+    let scheme = if location.protocol().ok()? == "https:" { "wss" } else { "ws" };
+    Some(format!("{}://{}{}", scheme, location.host().ok()?, crate::base::url("/api/ws")))
+}
@@ -0,0 +1,159 @@
+//! Offline-first copy of the loaded tasks in IndexedDB.
+//!
+//! The task list and what's needed to catch it up (sync change number,
+//! paging position, filters) are written once changes settle. On startup
+//! they render straight away, even without a connection, and only what
+//! changed since is fetched. Unlike localStorage, IndexedDB isn't capped at
+//! a few megabytes and keeps each task as its own record.
+
+use js_sys::Promise;
+use serde::{Deserialize, Serialize};
+use shared::{FeatureFlags, Task};
+use std::{cell::RefCell, future::Future};
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbRequest, IdbTransaction, IdbTransactionMode};
+
+use crate::outbox::Mutation;
+
+const DB_NAME: &str = "task-manager";
+const DB_VERSION: u32 = 1;
+/// Task JSON keyed by list position, so the list comes back in order.
+const TASKS_STORE: &str = "tasks";
+/// Everything else, as one [`Meta`] record.
+const META_STORE: &str = "metadata";
+const META_KEY: &str = "session";
+
+/// How long after a change the copy is written, so bursts of messages
+/// (typing, progress ticks) cost one write.
+pub const SAVE_DELAY_MS: i32 = 500;
+
+thread_local! {
+    static DATABASE: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Meta {
+    pub tasks_next_page: Option<u32>,
+    /// Change number the tasks are current as of; without it the copy can't
+    /// be caught up and isn't used.
+    pub sync_version: Option<u64>,
+    pub show_completed: bool,
+    pub flags: FeatureFlags,
+    /// Changes made offline and not sent yet.
+    #[serde(default)]
+    pub outbox: Vec<Mutation>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub tasks: Vec<Task>,
+    pub meta: Meta,
+}
+
+/// The copy saved by the last session, if there is a usable one.
+pub async fn load() -> Option<Snapshot> {
+    let db = database().await.ok()?;
+    let tx = db.transaction_with_str_sequence(&store_names()).ok()?;
+    let meta = finished(&tx.object_store(META_STORE).ok()?.get(&JsValue::from_str(META_KEY)).ok()?);
+    let tasks = finished(&tx.object_store(TASKS_STORE).ok()?.get_all().ok()?);
+
+    // A copy from an older build may not parse; just load afresh
+    let meta: Meta = serde_json::from_str(&meta.await.ok()?.as_string()?).ok()?;
+    meta.sync_version?;
+    let tasks = js_sys::Array::from(&tasks.await.ok()?)
+        .iter()
+        .filter_map(|json| serde_json::from_str(&json.as_string()?).ok())
+        .collect();
+
+    Some(Snapshot { tasks, meta })
+}
+
+/// Replaces the saved copy in the background.
+pub fn save(tasks: &[Task], meta: &Meta) {
+    let Ok(meta) = serde_json::to_string(meta) else {
+        return;
+    };
+    let tasks: Vec<String> = tasks.iter().filter_map(|task| serde_json::to_string(task).ok()).collect();
+
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(e) = write(tasks, meta).await {
+            web_sys::console::warn_2(&"Failed to save local task copy:".into(), &e);
+        }
+    });
+}
+
+async fn write(tasks: Vec<String>, meta: String) -> Result<(), JsValue> {
+    let db = database().await?;
+    let tx = db.transaction_with_str_sequence_and_mode(&store_names(), IdbTransactionMode::Readwrite)?;
+    let done = committed(&tx);
+
+    let task_store = tx.object_store(TASKS_STORE)?;
+    task_store.clear()?;
+    for (position, json) in tasks.iter().enumerate() {
+        task_store.put_with_key(&JsValue::from_str(json), &JsValue::from_f64(position as f64))?;
+    }
+    tx.object_store(META_STORE)?
+        .put_with_key(&JsValue::from_str(&meta), &JsValue::from_str(META_KEY))?;
+
+    done.await
+}
+
+/// The open database, opening (and if need be creating) it the first time.
+async fn database() -> Result<IdbDatabase, JsValue> {
+    if let Some(db) = DATABASE.with(|db| db.borrow().clone()) {
+        return Ok(db);
+    }
+
+    let factory = web_sys::window()
+        .and_then(|w| w.indexed_db().ok().flatten())
+        .ok_or_else(|| JsValue::from_str("IndexedDB is not available"))?;
+    let request = factory.open_with_u32(DB_NAME, DB_VERSION)?;
+
+    let upgrade = Closure::<dyn FnMut(JsValue)>::new({
+        let request = request.clone();
+        move |_event| {
+            let Ok(db) = request.result().map(JsCast::unchecked_into::<IdbDatabase>) else {
+                return;
+            };
+            for name in [TASKS_STORE, META_STORE] {
+                if !db.object_store_names().contains(name) {
+                    let _ = db.create_object_store(name);
+                }
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(upgrade.as_ref().unchecked_ref()));
+
+    // `upgrade` stays alive until the open has finished
+    let db: IdbDatabase = finished(&request).await?.unchecked_into();
+    DATABASE.with(|cached| *cached.borrow_mut() = Some(db.clone()));
+    Ok(db)
+}
+
+fn store_names() -> JsValue {
+    js_sys::Array::of2(&TASKS_STORE.into(), &META_STORE.into()).into()
+}
+
+/// Resolves with the request's result. The handlers are attached right
+/// away, so requests can be issued together and awaited one by one.
+fn finished(request: &IdbRequest) -> impl Future<Output = Result<JsValue, JsValue>> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    let request = request.clone();
+    async move {
+        JsFuture::from(promise).await?;
+        request.result()
+    }
+}
+
+fn committed(tx: &IdbTransaction) -> impl Future<Output = Result<(), JsValue>> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        tx.set_oncomplete(Some(&resolve));
+        tx.set_onerror(Some(&reject));
+        tx.set_onabort(Some(&reject));
+    });
+    async move { JsFuture::from(promise).await.map(|_| ()) }
+}
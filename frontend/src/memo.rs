@@ -0,0 +1,80 @@
+//! Task cards kept from one render to the next.
+//!
+//! Building the card of every task on each update gets slow with hundreds
+//! of tasks, though most of them haven't changed. [`Cards`] keeps each
+//! card's node with the [`CardKey`] it was built for and hands it back while
+//! the key stays the same.
+
+use sauron::Node;
+use shared::Task;
+use std::{cell::RefCell, collections::HashMap};
+use uuid::Uuid;
+
+use crate::Msg;
+
+/// Everything a card's look depends on. The task is compared whole rather
+/// than by `version` alone, as offline and optimistic edits change it
+/// without a new version.
+#[derive(Debug, Clone)]
+pub struct CardKey {
+    pub task: Task,
+    pub loading: bool,
+    /// Outlined as the drop target, the focused task or a selected one.
+    pub highlighted: bool,
+    pub selecting: bool,
+    /// Comments loaded for the task, counted on its comments toggle.
+    pub comments: Option<usize>,
+    /// How long ago the task was created, which changes with the clock.
+    pub created: Option<String>,
+}
+
+impl CardKey {
+    fn matches(&self, other: &CardKey) -> bool {
+        self.task.version == other.task.version
+            && self.loading == other.loading
+            && self.highlighted == other.highlighted
+            && self.selecting == other.selecting
+            && self.comments == other.comments
+            && self.created == other.created
+            && self.task == other.task
+    }
+}
+
+#[derive(Default)]
+pub struct Cards {
+    cards: RefCell<HashMap<Uuid, (CardKey, Node<Msg>)>>,
+}
+
+impl Cards {
+    /// The card cached for `key`, or the one `render` builds, which is kept
+    /// in its place.
+    pub fn get_or_render(&self, key: CardKey, render: impl FnOnce() -> Node<Msg>) -> Node<Msg> {
+        let id = key.task.id;
+        if let Some((cached, node)) = self.cards.borrow().get(&id) {
+            if cached.matches(&key) {
+                return node.clone();
+            }
+        }
+        let node = render();
+        self.cards.borrow_mut().insert(id, (key, node.clone()));
+        node
+    }
+
+    /// Drops the cards of tasks `keep` says are gone.
+    pub fn retain(&self, keep: impl Fn(&Uuid) -> bool) {
+        self.cards.borrow_mut().retain(|id, _| keep(id));
+    }
+}
+
+/// A copy starts empty; the cards are rebuilt on its first render.
+impl Clone for Cards {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl std::fmt::Debug for Cards {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cards({} cached)", self.cards.borrow().len())
+    }
+}
@@ -0,0 +1,47 @@
+//! Toasts stacked in the corner of the screen: confirmations, warnings, and
+//! the errors that used to only reach the console.
+
+use uuid::Uuid;
+
+/// Toasts on screen at once; the oldest goes when another arrives.
+pub const MAX_VISIBLE: usize = 4;
+
+/// How long a deletion can be undone from its toast, in milliseconds.
+pub const UNDO_MS: i32 = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// How long a toast stays on screen, in milliseconds. Problems stay up
+    /// longer so there's time to read them.
+    pub fn duration_ms(self) -> i32 {
+        match self {
+            Severity::Info => 2500,
+            Severity::Warning => 5000,
+            Severity::Error => 8000,
+        }
+    }
+
+    pub fn border_class(self) -> &'static str {
+        match self {
+            Severity::Info => "border-ctp-green",
+            Severity::Warning => "border-ctp-yellow",
+            Severity::Error => "border-ctp-red",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub id: u32,
+    pub severity: Severity,
+    pub message: String,
+    /// Tasks moved to the trash that the toast's "Undo" brings back; empty
+    /// for toasts without one.
+    pub undo: Vec<Uuid>,
+}
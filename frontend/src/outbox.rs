@@ -0,0 +1,133 @@
+//! Changes made while offline, sent once the connection is back.
+//!
+//! Creating, completing, editing and deleting tasks keep working without a
+//! network: the change shows in the list straight away and a [`Mutation`]
+//! is queued. The queue is saved with the offline copy in
+//! [`local_store`](crate::local_store), so it survives a reload, and is
+//! replayed in order when the browser comes back online. Replayed edits
+//! carry no version, so they win over changes made elsewhere meanwhile.
+
+use serde::{Deserialize, Serialize};
+use shared::{CreateTaskRequest, Task, UpdateTaskRequest};
+use uuid::Uuid;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit, Response};
+
+use crate::api;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mutation {
+    /// `local_id` is the task's id in the list until the server gives it
+    /// its own.
+    Create { local_id: Uuid, request: CreateTaskRequest },
+    Update { id: Uuid, request: UpdateTaskRequest },
+    Delete { id: Uuid },
+}
+
+/// How replaying a mutation went.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// Saved; carries the task as the server has it, except for deletes.
+    Sent(Option<Task>),
+    /// The server refused it, with its reason; it's dropped from the queue.
+    Refused(String),
+    /// The server still can't be reached; it stays queued.
+    Offline,
+}
+
+/// Whether the browser reports having no network.
+pub fn offline() -> bool {
+    web_sys::window().is_some_and(|w| !w.navigator().on_line())
+}
+
+/// Waits for the browser to go offline or come back online.
+pub async fn connectivity_changed() {
+    let Some(window) = web_sys::window() else {
+        return std::future::pending().await;
+    };
+
+    let mut resolve = None;
+    let promise = js_sys::Promise::new(&mut |resolve_fn, _reject| resolve = Some(resolve_fn));
+    let Some(resolve) = resolve else {
+        return std::future::pending().await;
+    };
+
+    let _ = window.add_event_listener_with_callback("online", &resolve);
+    let _ = window.add_event_listener_with_callback("offline", &resolve);
+    let _ = JsFuture::from(promise).await;
+    let _ = window.remove_event_listener_with_callback("online", &resolve);
+    let _ = window.remove_event_listener_with_callback("offline", &resolve);
+}
+
+impl Mutation {
+    pub fn task_id(&self) -> Uuid {
+        match self {
+            Mutation::Create { local_id, .. } => *local_id,
+            Mutation::Update { id, .. } | Mutation::Delete { id } => *id,
+        }
+    }
+
+    /// Points the mutation at the id the server gave a task created offline.
+    pub fn rename(&mut self, from: Uuid, to: Uuid) {
+        match self {
+            Mutation::Update { id, .. } | Mutation::Delete { id } if *id == from => *id = to,
+            _ => {}
+        }
+    }
+
+    pub async fn send(&self) -> Outcome {
+        let request = match self.request() {
+            Ok(request) => request,
+            Err(e) => return Outcome::Refused(e),
+        };
+        let response = match api::send(&request).await {
+            Ok(response) => response,
+            Err(_) => return Outcome::Offline,
+        };
+
+        match response.status() {
+            // Deleted on another device too
+            404 if matches!(self, Mutation::Delete { .. }) => Outcome::Sent(None),
+            status if status >= 500 || status == 429 => Outcome::Offline,
+            _ if !response.ok() => Outcome::Refused(crate::http_error("A change made offline couldn't be saved", &response).await),
+            _ if matches!(self, Mutation::Delete { .. }) => Outcome::Sent(None),
+            _ => match task_from(&response).await {
+                Ok(task) => Outcome::Sent(Some(task)),
+                Err(e) => Outcome::Refused(e),
+            },
+        }
+    }
+
+    fn request(&self) -> Result<Request, String> {
+        let (method, url, body) = match self {
+            Mutation::Create { request, .. } => ("POST", "/api/tasks".to_string(), Some(serde_json::to_string(request))),
+            Mutation::Update { id, request } => ("PUT", format!("/api/tasks/{}", id), Some(serde_json::to_string(request))),
+            Mutation::Delete { id } => ("DELETE", format!("/api/tasks/{}", id), None),
+        };
+
+        let opts = RequestInit::new();
+        opts.set_method(method);
+        if let Some(body) = body {
+            let body = body.map_err(|e| format!("Failed to serialize request: {}", e))?;
+            opts.set_body(&wasm_bindgen::JsValue::from_str(&body));
+        }
+        let request = Request::new_with_str_and_init(&crate::base::url(&url), &opts).map_err(|_| "Failed to create request")?;
+        let headers = request.headers();
+        let _ = headers.set("Content-Type", "application/json");
+        // Creation limits are per client
+        let _ = headers.set("X-Client-Id", &crate::client_id());
+        Ok(request)
+    }
+}
+
+async fn task_from(response: &Response) -> Result<Task, String> {
+    let text_promise = response.text().map_err(|_| "Failed to read response")?;
+    let text = JsFuture::from(text_promise)
+        .await
+        .map_err(|_| "Failed to get text")?
+        .as_string()
+        .ok_or("Failed to convert to string")?;
+
+    serde_json::from_str(&text).map_err(|e| format!("Failed to parse JSON: {}", e))
+}
@@ -0,0 +1,152 @@
+//! Counters for the performance overlay, toggled with Alt+Shift+P.
+//!
+//! `update` and `view` report how long they took; the overlay samples the
+//! totals once a second, along with the number of elements in the page, to
+//! show where time goes when the task list gets long. Timings cover building
+//! the view, not Sauron's diffing and patching of the DOM after it.
+
+use js_sys::Promise;
+use std::cell::RefCell;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{window, KeyboardEvent};
+
+/// How often the overlay refreshes, in milliseconds.
+pub const SAMPLE_INTERVAL_MS: i32 = 1000;
+
+#[derive(Debug, Default)]
+struct Counters {
+    renders: u64,
+    last_view_ms: f64,
+    updates: u64,
+    last_update_ms: f64,
+    slowest_update_ms: f64,
+    slowest_msg: String,
+    /// `updates` and the time at the last sample, for the throughput.
+    sampled: Option<(u64, f64)>,
+}
+
+thread_local! {
+    static COUNTERS: RefCell<Counters> = RefCell::new(Counters::default());
+}
+
+/// What the overlay shows.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    pub renders: u64,
+    pub last_view_ms: f64,
+    pub updates: u64,
+    pub last_update_ms: f64,
+    pub slowest_update_ms: f64,
+    /// Which message took `slowest_update_ms`.
+    pub slowest_msg: String,
+    /// Messages per second since the previous sample.
+    pub msgs_per_sec: f64,
+    pub dom_nodes: u32,
+}
+
+/// Milliseconds on the page's high-resolution clock.
+pub fn now() -> f64 {
+    window()
+        .and_then(|w| w.performance())
+        .map(|p| p.now())
+        .unwrap_or_else(js_sys::Date::now)
+}
+
+/// The variant name of a message, e.g. `TasksLoaded`.
+pub fn msg_kind(msg: &impl std::fmt::Debug) -> String {
+    let name = format!("{:?}", msg);
+    let end = name.find(|c: char| !c.is_alphanumeric()).unwrap_or(name.len());
+    name[..end].to_string()
+}
+
+/// Records an update that began at `started`. `kind` is only worked out
+/// while the overlay is open, as formatting every message isn't free.
+pub fn updated(started: f64, kind: Option<String>) {
+    let elapsed = now() - started;
+    COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        counters.updates += 1;
+        counters.last_update_ms = elapsed;
+        if let Some(kind) = kind.filter(|_| elapsed > counters.slowest_update_ms) {
+            counters.slowest_update_ms = elapsed;
+            counters.slowest_msg = kind;
+        }
+    });
+}
+
+/// Records a view built since `started`.
+pub fn rendered(started: f64) {
+    let elapsed = now() - started;
+    COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        counters.renders += 1;
+        counters.last_view_ms = elapsed;
+    });
+}
+
+/// Forgets the slowest update, so the next one seen replaces it.
+pub fn reset_slowest() {
+    COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        counters.slowest_update_ms = 0.0;
+        counters.slowest_msg.clear();
+    });
+}
+
+pub fn sample() -> Stats {
+    let at = now();
+    COUNTERS.with(|counters| {
+        let mut counters = counters.borrow_mut();
+        let msgs_per_sec = match counters.sampled {
+            Some((updates, then)) if at > then => (counters.updates - updates) as f64 * 1000.0 / (at - then),
+            _ => 0.0,
+        };
+        counters.sampled = Some((counters.updates, at));
+        Stats {
+            renders: counters.renders,
+            last_view_ms: counters.last_view_ms,
+            updates: counters.updates,
+            last_update_ms: counters.last_update_ms,
+            slowest_update_ms: counters.slowest_update_ms,
+            slowest_msg: counters.slowest_msg.clone(),
+            msgs_per_sec,
+            dom_nodes: dom_nodes(),
+        }
+    })
+}
+
+fn dom_nodes() -> u32 {
+    window()
+        .and_then(|w| w.document())
+        .map(|d| d.get_elements_by_tag_name("*").length())
+        .unwrap_or(0)
+}
+
+/// Waits for Alt+Shift+P. Matches on the key's position, as Alt changes
+/// the character it types on macOS.
+pub async fn next_toggle() {
+    let Some(window) = window() else {
+        return std::future::pending().await;
+    };
+
+    loop {
+        let mut resolve = None;
+        let promise = Promise::new(&mut |resolve_fn, _reject| resolve = Some(resolve_fn));
+        let Some(resolve) = resolve else {
+            return std::future::pending().await;
+        };
+
+        let _ = window.add_event_listener_with_callback("keydown", &resolve);
+        let event = JsFuture::from(promise).await;
+        let _ = window.remove_event_listener_with_callback("keydown", &resolve);
+
+        let Some(event) = event.ok().and_then(|event| event.dyn_into::<KeyboardEvent>().ok()) else {
+            continue;
+        };
+        if event.alt_key() && event.shift_key() && !event.ctrl_key() && !event.meta_key() && event.code() == "KeyP" {
+            event.prevent_default();
+            return;
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! API playground on the Settings page: endpoints come from the backend's
+//! OpenAPI document, and requests go out exactly as typed.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Request, RequestInit};
+
+use crate::api;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Endpoint {
+    /// Upper case, e.g. `GET`.
+    pub method: String,
+    /// OpenAPI path template, e.g. `/api/tasks/{id}`.
+    pub path: String,
+    pub summary: String,
+    pub path_params: Vec<String>,
+    pub query_params: Vec<String>,
+    /// Pretty-printed example JSON body, for endpoints that take one.
+    pub example_body: Option<String>,
+    /// Takes a multipart upload, which the playground can't compose.
+    pub multipart: bool,
+}
+
+impl Endpoint {
+    pub fn label(&self) -> String {
+        format!("{} {}", self.method, self.path)
+    }
+
+    pub fn takes_body(&self) -> bool {
+        self.example_body.is_some()
+    }
+
+    /// The path with parameters filled in and the non-empty query ones
+    /// appended.
+    pub fn url(&self, params: &BTreeMap<String, String>) -> String {
+        let value = |name: &str| params.get(name).map(|v| v.trim()).unwrap_or_default();
+
+        let mut url = self.path.clone();
+        for name in &self.path_params {
+            url = url.replace(&format!("{{{}}}", name), &String::from(js_sys::encode_uri_component(value(name))));
+        }
+        let query: Vec<String> = self
+            .query_params
+            .iter()
+            .filter(|name| !value(name).is_empty())
+            .map(|name| format!("{}={}", name, js_sys::encode_uri_component(value(name))))
+            .collect();
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query.join("&"));
+        }
+        url
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reply {
+    pub status: u16,
+    pub status_text: String,
+    pub elapsed_ms: u64,
+    /// Pretty-printed when it's JSON.
+    pub body: String,
+}
+
+/// Every operation in an OpenAPI document, by path.
+pub fn endpoints(document: &Value) -> Vec<Endpoint> {
+    let Some(paths) = document["paths"].as_object() else {
+        return Vec::new();
+    };
+
+    let mut endpoints = Vec::new();
+    for (path, item) in paths {
+        let Some(operations) = item.as_object() else {
+            continue;
+        };
+        for (method, operation) in operations {
+            let params = |location: &str| -> Vec<String> {
+                operation["parameters"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter(|param| param["in"] == location)
+                    .filter_map(|param| param["name"].as_str().map(str::to_string))
+                    .collect()
+            };
+            let content = &operation["requestBody"]["content"];
+            endpoints.push(Endpoint {
+                method: method.to_uppercase(),
+                path: path.clone(),
+                summary: operation["summary"].as_str().unwrap_or_default().to_string(),
+                path_params: params("path"),
+                query_params: params("query"),
+                example_body: content
+                    .get("application/json")
+                    .map(|json| serde_json::to_string_pretty(&json["example"]).unwrap_or_default()),
+                multipart: content.get("multipart/form-data").is_some(),
+            });
+        }
+    }
+    endpoints
+}
+
+pub async fn fetch_endpoints() -> Result<Vec<Endpoint>, String> {
+    let response = api::get("/api/openapi.json")
+        .await
+        .map_err(|_| "Failed to fetch the API description")?;
+
+    if !response.ok() {
+        return Err(crate::http_error("Failed to fetch the API description", &response).await);
+    }
+
+    let text_promise = response.text().map_err(|_| "Failed to read response")?;
+    let text = JsFuture::from(text_promise)
+        .await
+        .map_err(|_| "Failed to get text")?
+        .as_string()
+        .ok_or("Failed to convert to string")?;
+
+    let document: Value = serde_json::from_str(&text).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    Ok(endpoints(&document))
+}
+
+/// Sends one request for `endpoint`. Any status is a reply; only a request
+/// that couldn't be sent at all is an error.
+pub async fn send(endpoint: &Endpoint, params: &BTreeMap<String, String>, body: &str) -> Result<Reply, String> {
+    let opts = RequestInit::new();
+    opts.set_method(&endpoint.method);
+    if endpoint.takes_body() {
+        opts.set_body(&wasm_bindgen::JsValue::from_str(body));
+    }
+
+    let request = Request::new_with_str_and_init(&crate::base::url(&endpoint.url(params)), &opts)
+        .map_err(|_| "Failed to create request")?;
+    if endpoint.takes_body() {
+        request
+            .headers()
+            .set("Content-Type", "application/json")
+            .map_err(|_| "Failed to set header")?;
+    }
+
+    let started = js_sys::Date::now();
+    let response = api::send_once(&request)
+        .await
+        .map_err(|_| "Failed to send request")?;
+
+    let text_promise = response.text().map_err(|_| "Failed to read response")?;
+    let text = JsFuture::from(text_promise)
+        .await
+        .map_err(|_| "Failed to get text")?
+        .as_string()
+        .unwrap_or_default();
+
+    Ok(Reply {
+        status: response.status(),
+        status_text: response.status_text(),
+        elapsed_ms: (js_sys::Date::now() - started) as u64,
+        body: match serde_json::from_str::<Value>(&text) {
+            Ok(json) => serde_json::to_string_pretty(&json).unwrap_or(text),
+            Err(_) => text,
+        },
+    })
+}
@@ -0,0 +1,181 @@
+//! Per-browser preferences kept in localStorage.
+
+use serde::{Deserialize, Serialize};
+use web_sys::window;
+
+const CONFIRM_KEY: &str = "confirm_preferences";
+const ANNOUNCEMENTS_SEEN_KEY: &str = "announcements_seen";
+const TOUR_DISMISSED_KEY: &str = "tour_dismissed";
+const ADMIN_TOKEN_KEY: &str = "admin_token";
+/// Also read by the script in `index.html`, which applies the theme before
+/// the app loads so the page doesn't flash the other palette.
+const THEME_KEY: &str = "theme";
+
+/// Destructive actions that can ask for confirmation first. Deleting a task
+/// or clearing completed ones offers an undo instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfirmAction {
+    BulkDelete,
+}
+
+/// Which destructive actions ask before going ahead. Everything is confirmed
+/// until the user opts out on the Settings page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfirmPreferences {
+    pub bulk_delete: bool,
+}
+
+impl Default for ConfirmPreferences {
+    fn default() -> Self {
+        Self { bulk_delete: true }
+    }
+}
+
+impl ConfirmPreferences {
+    pub fn load() -> Self {
+        window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(CONFIRM_KEY).ok().flatten())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        let storage = window().and_then(|w| w.local_storage().ok().flatten());
+        if let (Some(storage), Ok(json)) = (storage, serde_json::to_string(self)) {
+            let _ = storage.set_item(CONFIRM_KEY, &json);
+        }
+    }
+
+    pub fn get(&self, action: ConfirmAction) -> bool {
+        match action {
+            ConfirmAction::BulkDelete => self.bulk_delete,
+        }
+    }
+
+    pub fn set(&mut self, action: ConfirmAction, enabled: bool) {
+        match action {
+            ConfirmAction::BulkDelete => self.bulk_delete = enabled,
+        }
+    }
+}
+
+/// When the newest announcement the user has opened was published; anything
+/// later counts as unread.
+pub fn load_announcements_seen() -> u64 {
+    window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(ANNOUNCEMENTS_SEEN_KEY).ok().flatten())
+        .and_then(|seen| seen.parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn save_announcements_seen(published_at: u64) {
+    if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = storage.set_item(ANNOUNCEMENTS_SEEN_KEY, &published_at.to_string());
+    }
+}
+
+/// Whether the user finished or skipped the first-run tour.
+pub fn load_tour_dismissed() -> bool {
+    window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(TOUR_DISMISSED_KEY).ok().flatten())
+        .is_some()
+}
+
+pub fn save_tour_dismissed(dismissed: bool) {
+    if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = if dismissed {
+            storage.set_item(TOUR_DISMISSED_KEY, "1")
+        } else {
+            storage.remove_item(TOUR_DISMISSED_KEY)
+        };
+    }
+}
+
+/// The `ADMIN_TOKEN` the user signed in to the Admin page with, sent with
+/// admin requests.
+pub fn load_admin_token() -> Option<String> {
+    window()
+        .and_then(|w| w.local_storage().ok().flatten())
+        .and_then(|storage| storage.get_item(ADMIN_TOKEN_KEY).ok().flatten())
+        .filter(|token| !token.is_empty())
+}
+
+pub fn save_admin_token(token: Option<&str>) {
+    if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
+        let _ = match token {
+            Some(token) => storage.set_item(ADMIN_TOKEN_KEY, token),
+            None => storage.remove_item(ADMIN_TOKEN_KEY),
+        };
+    }
+}
+
+/// Catppuccin flavour the app is drawn in. The palettes are CSS variables
+/// in `input.css`, switched by a class on the root element.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Theme {
+    /// Dark.
+    #[default]
+    Mocha,
+    /// Light.
+    Latte,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 2] = [Theme::Mocha, Theme::Latte];
+
+    /// The stored value and root element class.
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Mocha => "mocha",
+            Theme::Latte => "latte",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Mocha => "Dark (Mocha)",
+            Theme::Latte => "Light (Latte)",
+        }
+    }
+
+    /// Background colour for the browser's UI, as in the `theme-color` meta tag.
+    fn browser_color(self) -> &'static str {
+        match self {
+            Theme::Mocha => "#1e1e2e",
+            Theme::Latte => "#eff1f5",
+        }
+    }
+
+    pub fn load() -> Self {
+        let stored = window()
+            .and_then(|w| w.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(THEME_KEY).ok().flatten());
+        Theme::ALL
+            .into_iter()
+            .find(|theme| stored.as_deref() == Some(theme.name()))
+            .unwrap_or_default()
+    }
+
+    pub fn save(self) {
+        if let Some(storage) = window().and_then(|w| w.local_storage().ok().flatten()) {
+            let _ = storage.set_item(THEME_KEY, self.name());
+        }
+    }
+
+    /// Switches the page over to this theme.
+    pub fn apply(self) {
+        let Some(document) = window().and_then(|w| w.document()) else {
+            return;
+        };
+        if let Some(root) = document.document_element() {
+            root.set_class_name(self.name());
+        }
+        if let Ok(Some(meta)) = document.query_selector("meta[name=theme-color]") {
+            let _ = meta.set_attribute("content", self.browser_color());
+        }
+    }
+}
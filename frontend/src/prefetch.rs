@@ -0,0 +1,58 @@
+//! Data fetched ahead of navigation.
+//!
+//! Hovering a nav link starts loading that page's data into a small cache;
+//! navigating there shortly after takes the entry instead of waiting on the
+//! network. Entries expire after [`TTL_MS`], and the cache keeps at most
+//! [`CAPACITY`] of them, dropping the oldest first.
+
+use shared::{ActivityEvent, DayPlan, ListResponse, TagStats};
+
+/// How long prefetched data counts as fresh.
+const TTL_MS: f64 = 30_000.0;
+const CAPACITY: usize = 8;
+
+#[derive(Debug, Clone)]
+pub enum Prefetched {
+    TagStats(Vec<TagStats>),
+    Plan(DayPlan),
+    Activity(ListResponse<ActivityEvent>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Cache<K> {
+    /// Oldest first, with the time (ms) each entry was fetched.
+    entries: Vec<(K, f64, Prefetched)>,
+}
+
+impl<K: PartialEq> Default for Cache<K> {
+    fn default() -> Self {
+        Self { entries: Vec::new() }
+    }
+}
+
+impl<K: PartialEq> Cache<K> {
+    fn evict_stale(&mut self) {
+        let now = js_sys::Date::now();
+        self.entries.retain(|(_, fetched_at, _)| now - fetched_at < TTL_MS);
+    }
+
+    pub fn has_fresh(&mut self, key: &K) -> bool {
+        self.evict_stale();
+        self.entries.iter().any(|(k, _, _)| k == key)
+    }
+
+    pub fn insert(&mut self, key: K, value: Prefetched) {
+        self.entries.retain(|(k, _, _)| *k != key);
+        if self.entries.len() >= CAPACITY {
+            self.entries.remove(0);
+        }
+        self.entries.push((key, js_sys::Date::now(), value));
+    }
+
+    /// Removes and returns the entry for `key` if it is still fresh.
+    pub fn take(&mut self, key: &K) -> Option<Prefetched> {
+        self.evict_stale();
+        let index = self.entries.iter().position(|(k, _, _)| k == key)?;
+        Some(self.entries.remove(index).2)
+    }
+}
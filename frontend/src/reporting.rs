@@ -0,0 +1,81 @@
+//! Reports panics and failed API operations to `/api/client-errors`, along
+//! with the most recent messages the app processed.
+
+use shared::{ClientErrorKind, ClientErrorReport};
+use std::{cell::RefCell, collections::VecDeque};
+use wasm_bindgen::JsValue;
+use web_sys::{window, Blob, BlobPropertyBag};
+
+pub fn app_version() -> String {
+    format!("{}+{}", env!("CARGO_PKG_VERSION"), shared::GIT_HASH)
+}
+
+const MSG_LOG_LEN: usize = 20;
+const MSG_ENTRY_LEN: usize = 120;
+
+thread_local! {
+    static MSG_LOG: RefCell<VecDeque<String>> = RefCell::new(VecDeque::with_capacity(MSG_LOG_LEN));
+}
+
+/// Remembers a processed message so it can be attached to later reports.
+pub fn record_msg(msg: &impl std::fmt::Debug) {
+    let mut entry = format!("{:?}", msg);
+    if entry.len() > MSG_ENTRY_LEN {
+        let mut end = MSG_ENTRY_LEN;
+        while !entry.is_char_boundary(end) {
+            end -= 1;
+        }
+        entry.truncate(end);
+        entry.push('…');
+    }
+
+    MSG_LOG.with(|log| {
+        let mut log = log.borrow_mut();
+        if log.len() == MSG_LOG_LEN {
+            log.pop_front();
+        }
+        log.push_back(entry);
+    });
+}
+
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        console_error_panic_hook::hook(info);
+        report(ClientErrorKind::Panic, &info.to_string());
+    }));
+}
+
+pub fn report(kind: ClientErrorKind, message: &str) {
+    let Some(window) = window() else {
+        return;
+    };
+
+    // The panic may have happened while the log was being written to.
+    let msg_log = MSG_LOG.with(|log| {
+        log.try_borrow()
+            .map(|log| log.iter().cloned().collect())
+            .unwrap_or_default()
+    });
+
+    let report = ClientErrorReport {
+        kind,
+        message: message.to_string(),
+        msg_log,
+        app_version: app_version(),
+        url: window.location().href().unwrap_or_default(),
+        user_agent: window.navigator().user_agent().unwrap_or_default(),
+        received_at: None,
+    };
+    let Ok(body) = serde_json::to_string(&report) else {
+        return;
+    };
+
+    // sendBeacon queues the request synchronously, so it still goes out when
+    // called from a panic hook right before the wasm instance traps.
+    let options = BlobPropertyBag::new();
+    options.set_type("application/json");
+    let parts = js_sys::Array::of1(&JsValue::from_str(&body));
+    if let Ok(blob) = Blob::new_with_str_sequence_and_options(&parts, &options) {
+        let _ = window.navigator().send_beacon_with_opt_blob(&crate::base::url("/api/client-errors"), Some(&blob));
+    }
+}
@@ -0,0 +1,62 @@
+//! Web Share Target intake. The manifest registers `/share` as a GET share
+//! target, so sharing from another app opens `/share?title=…&text=…&url=…`;
+//! the shared content is turned into a prefilled create form.
+
+use web_sys::window;
+
+/// Route the manifest's `share_target.action` points at.
+pub const PATH: &str = "/share";
+
+/// What ends up in the create form.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shared {
+    pub title: String,
+    pub description: String,
+}
+
+/// Reads the shared fields from the current URL. `None` when nothing usable
+/// was shared.
+pub fn intake() -> Option<Shared> {
+    let search = window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    let field = |name: &str| params.get(name).unwrap_or_default();
+    from_fields(&field("title"), &field("text"), &field("url"))
+}
+
+/// Apps fill the fields inconsistently: many put the link in `text` and leave
+/// `url` empty, and some send no title. The first non-empty of title, the
+/// first line of text and the link becomes the task title; whatever's left
+/// goes in the description.
+fn from_fields(title: &str, text: &str, url: &str) -> Option<Shared> {
+    let title = title.trim();
+    let text = text.trim();
+    let url = url.trim();
+
+    let mut rest: Vec<&str> = Vec::new();
+    let title = if !title.is_empty() {
+        rest.extend([text, url]);
+        title.to_string()
+    } else if !text.is_empty() {
+        let (first, remainder) = text.split_once('\n').unwrap_or((text, ""));
+        rest.extend([remainder.trim(), url]);
+        first.trim().to_string()
+    } else if !url.is_empty() {
+        url.to_string()
+    } else {
+        return None;
+    };
+
+    let description = rest
+        .into_iter()
+        .filter(|part| !part.is_empty() && *part != title)
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(Shared { title, description })
+}
+
+/// Swaps `/share?…` for the Tasks URL so a reload doesn't prefill again.
+pub fn leave_intake_route() {
+    if let Some(history) = window().and_then(|w| w.history().ok()) {
+        let _ = history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&crate::base::url("/")));
+    }
+}
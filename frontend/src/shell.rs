@@ -0,0 +1,17 @@
+//! The page the server streams before the app starts: a skeleton, then on
+//! `/` the first page of tasks, as cards and as JSON (see the backend's
+//! `shell` module). The app takes the JSON over and clears the rest away.
+
+use shared::InitialTasks;
+use web_sys::window;
+
+/// Removes the server's markup, returning the tasks it was sent with.
+pub fn take() -> Option<InitialTasks> {
+    let document = window()?.document()?;
+    if let Some(shell) = document.get_element_by_id("shell") {
+        shell.remove();
+    }
+    let data = document.get_element_by_id("initial-tasks")?;
+    data.remove();
+    serde_json::from_str(&data.text_content()?).ok()
+}
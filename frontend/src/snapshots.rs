@@ -0,0 +1,150 @@
+//! Rendered views compared against the HTML they rendered to before, to
+//! catch markup and class changes made by accident while refactoring.
+//!
+//! The views call into the browser (clock, time zone, console), so these run
+//! under Node with `TZ=UTC wasm-pack test --node frontend`. A view without a
+//! file in `frontend/snapshots` yet has one written for it, and so does every
+//! view when `UPDATE_SNAPSHOTS` is set; review and commit the files like code.
+//! Tasks are built with fixed ids and no creation time, so nothing in the
+//! markup depends on when the tests run.
+
+use shared::{AnalyticsSummary, DailyCounts, HourCount, Location, Priority, TagStats, Task};
+use uuid::Uuid;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use crate::{
+    editing::{EditDraft, EditState},
+    Model,
+};
+
+#[wasm_bindgen(module = "fs")]
+extern "C" {
+    #[wasm_bindgen(js_name = readFileSync, catch)]
+    fn read_file(path: &str, encoding: &str) -> Result<String, JsValue>;
+    #[wasm_bindgen(js_name = writeFileSync)]
+    fn write_file(path: &str, contents: &str);
+}
+
+/// Whether `UPDATE_SNAPSHOTS` is set in Node's environment.
+fn updating() -> bool {
+    let get = |target: &JsValue, key: &str| js_sys::Reflect::get(target, &JsValue::from_str(key));
+    get(&js_sys::global(), "process")
+        .and_then(|process| get(&process, "env"))
+        .and_then(|env| get(&env, "UPDATE_SNAPSHOTS"))
+        .is_ok_and(|value| !value.is_undefined())
+}
+
+/// Checks `node` against the snapshot called `name`, writing it when there's
+/// none yet or `UPDATE_SNAPSHOTS` is set.
+fn assert_snapshot(name: &str, node: sauron::Node<crate::Msg>) {
+    let rendered = format!("{}\n", node.render_to_string());
+    let path = format!("{}/snapshots/{}.html", env!("CARGO_MANIFEST_DIR"), name);
+    match read_file(&path, "utf8") {
+        Ok(expected) if !updating() => {
+            assert!(
+                expected == rendered,
+                "{} no longer renders as snapshots/{}.html. If the change is intended, rerun with UPDATE_SNAPSHOTS=1.\nrendered:\n{}",
+                name,
+                name,
+                rendered
+            );
+        }
+        _ => write_file(&path, &rendered),
+    }
+}
+
+fn task(n: u128, title: &str) -> Task {
+    let mut task = Task::new(title.to_string(), "Pick up on the way home".to_string());
+    task.id = Uuid::from_u128(n);
+    task.version = 1;
+    task
+}
+
+fn model(tasks: Vec<Task>) -> Model {
+    Model { tasks, ..Model::default() }
+}
+
+#[wasm_bindgen_test]
+fn open_task_card() {
+    let task = task(1, "Buy milk");
+    assert_snapshot("task_card_open", model(vec![task.clone()]).render_task(&task));
+}
+
+#[wasm_bindgen_test]
+fn completed_task_card() {
+    let task = task(1, "Buy milk").completed_at(1_700_000_000);
+    assert_snapshot("task_card_completed", model(vec![task.clone()]).render_task(&task));
+}
+
+#[wasm_bindgen_test]
+fn task_card_with_everything_set() {
+    let mut task = task(1, "Renew passport")
+        .pinned()
+        .with_priority(Priority::Urgent)
+        .with_tags(["travel", "admin"])
+        .with_location(Location {
+            name: "Post office".to_string(),
+            lat: 51.5,
+            lng: -0.12,
+            radius_m: Location::DEFAULT_RADIUS_M,
+        })
+        .with_recurrence(shared::recurrence::RecurrenceRule::Monthly);
+    task.flagged = true;
+    assert_snapshot("task_card_full", model(vec![task.clone()]).render_task(&task));
+}
+
+#[wasm_bindgen_test]
+fn task_card_while_saving() {
+    let task = task(1, "Buy milk");
+    let mut model = model(vec![task.clone()]);
+    model.task_loading_states.insert(task.id, true);
+    assert_snapshot("task_card_loading", model.render_task(&task));
+}
+
+#[wasm_bindgen_test]
+fn task_card_being_edited() {
+    let task = task(1, "Buy milk");
+    let mut model = model(vec![task.clone()]);
+    model.edit = EditState::Editing {
+        id: task.id,
+        draft: EditDraft::from_task(&task),
+    };
+    assert_snapshot("task_card_editing", model.render_task(&task));
+}
+
+#[wasm_bindgen_test]
+fn task_card_in_selection_mode() {
+    let task = task(1, "Buy milk");
+    let mut model = model(vec![task.clone()]);
+    model.selecting = true;
+    model.selected_tasks.insert(task.id);
+    assert_snapshot("task_card_selected", model.render_task(&task));
+}
+
+#[wasm_bindgen_test]
+fn dashboard() {
+    let mut model = model(vec![task(1, "Buy milk"), task(2, "Call the bank").completed_at(1_700_000_000)]);
+    model.tag_stats = vec![TagStats {
+        tag: "errands".to_string(),
+        total: 4,
+        completed: 3,
+        completion_rate: 0.75,
+        average_age_secs: Some(7_200),
+    }];
+    assert_snapshot("dashboard", model.view_dashboard());
+}
+
+#[wasm_bindgen_test]
+fn analytics_charts() {
+    let mut model = model(Vec::new());
+    model.analytics = Some(AnalyticsSummary {
+        daily: vec![
+            DailyCounts { date: "2024-03-02".to_string(), created: 4, completed: 2 },
+            DailyCounts { date: "2024-03-01".to_string(), created: 1, completed: 5 },
+        ],
+        hours: (0..24).map(|hour| HourCount { hour, completed: u64::from(hour % 5) }).collect(),
+        average_completion_secs: Some(5_400),
+    });
+    assert_snapshot("analytics", model.view_completion_charts());
+}
@@ -0,0 +1,33 @@
+//! Catching up with changes made elsewhere while the app wasn't looking.
+//!
+//! [`resumed`] resolves when the tab is shown again or the browser comes
+//! back online; the caller then asks `GET /api/tasks/changes` for what
+//! happened in the meantime instead of reloading every task.
+
+use wasm_bindgen_futures::JsFuture;
+
+/// Waits until the tab becomes visible again or the network reconnects.
+pub async fn resumed() {
+    let Some((window, document)) = web_sys::window().and_then(|w| w.document().map(|d| (w, d))) else {
+        return std::future::pending().await;
+    };
+
+    loop {
+        let mut resolve = None;
+        let promise = js_sys::Promise::new(&mut |resolve_fn, _reject| resolve = Some(resolve_fn));
+        let Some(resolve) = resolve else {
+            return std::future::pending().await;
+        };
+
+        let _ = document.add_event_listener_with_callback("visibilitychange", &resolve);
+        let _ = window.add_event_listener_with_callback("online", &resolve);
+        let _ = JsFuture::from(promise).await;
+        let _ = document.remove_event_listener_with_callback("visibilitychange", &resolve);
+        let _ = window.remove_event_listener_with_callback("online", &resolve);
+
+        // Hiding the tab fires the same event
+        if !document.hidden() {
+            return;
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! First-run guided tour. Each step highlights one part of the app and
+//! explains it in a card pinned to the bottom of the screen.
+
+use web_sys::window;
+
+use crate::Page;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    CreateForm,
+    ToggleTask,
+    Analytics,
+}
+
+impl Step {
+    pub const ALL: [Step; 3] = [Step::CreateForm, Step::ToggleTask, Step::Analytics];
+
+    pub fn first() -> Self {
+        Self::ALL[0]
+    }
+
+    pub fn next(self) -> Option<Self> {
+        Self::ALL.get(self.index() + 1).copied()
+    }
+
+    pub fn previous(self) -> Option<Self> {
+        self.index().checked_sub(1).map(|i| Self::ALL[i])
+    }
+
+    /// 0-based position in the tour.
+    pub fn index(self) -> usize {
+        Self::ALL.iter().position(|step| *step == self).unwrap_or_default()
+    }
+
+    /// Page the highlighted element lives on.
+    pub fn page(self) -> Page {
+        match self {
+            Step::CreateForm | Step::ToggleTask => Page::Tasks,
+            Step::Analytics => Page::Dashboard,
+        }
+    }
+
+    /// DOM id of the highlighted element.
+    pub fn target(self) -> &'static str {
+        match self {
+            Step::CreateForm => "tour-create-form",
+            Step::ToggleTask => "tour-task-list",
+            Step::Analytics => "tour-analytics",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Step::CreateForm => "Add your first task",
+            Step::ToggleTask => "Check things off",
+            Step::Analytics => "See how you're doing",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Step::CreateForm => "Give the task a title, optionally a description and priority, then press Add.",
+            Step::ToggleTask => "Click the checkbox on a task to mark it done. Completed tasks move to their own section and can be reopened the same way.",
+            Step::Analytics => "The Dashboard totals your tasks and ranks your tags by completion rate.",
+        }
+    }
+}
+
+/// Scrolls the step's highlighted element into view, if it's rendered.
+pub fn scroll_to(step: Step) {
+    let element = window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(step.target()));
+    if let Some(element) = element {
+        let options = web_sys::ScrollIntoViewOptions::new();
+        options.set_behavior(web_sys::ScrollBehavior::Smooth);
+        options.set_block(web_sys::ScrollLogicalPosition::Center);
+        element.scroll_into_view_with_scroll_into_view_options(&options);
+    }
+}
@@ -0,0 +1,174 @@
+//! Triage mode: going through untagged or undated open tasks one at a time
+//! from the keyboard.
+//!
+//! `d` sets when the task is due, `p` its priority and `t` adds a tag; each
+//! asks for the value next. `c` completes the task, `Enter` moves on to the
+//! next one and `Escape` backs out of a prompt, then out of triage.
+
+use js_sys::Promise;
+use shared::{Priority, Task};
+use uuid::Uuid;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::KeyboardEvent;
+
+use crate::SnoozePreset;
+
+/// Due dates offered by `d`, picked with `1` to `3`.
+pub const DUE_PRESETS: [SnoozePreset; 3] = [SnoozePreset::OneHour, SnoozePreset::Tomorrow, SnoozePreset::NextWeek];
+
+/// Id of the tag input, focused when `t` asks for a tag.
+pub const TAG_INPUT: &str = "triage-tag";
+
+/// Whether a task still needs sorting out: open, and either without tags or
+/// without a due date.
+pub fn needs_triage(task: &Task) -> bool {
+    !task.completed && (task.tags.is_empty() || task.due_at.is_none())
+}
+
+/// What the current key asks for next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prompt {
+    Due,
+    Priority,
+    Tag,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    SetDue(SnoozePreset),
+    SetPriority(Priority),
+    AddTag(String),
+    Complete,
+    Next,
+    Exit,
+}
+
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// Tells this session's key presses apart from those of one that ended.
+    pub id: u32,
+    queue: Vec<Uuid>,
+    position: usize,
+    pub prompt: Option<Prompt>,
+    /// Tag typed after `t`.
+    pub tag: String,
+}
+
+impl Session {
+    /// A session over the tasks needing triage, in list order.
+    pub fn new(id: u32, tasks: &[Task]) -> Self {
+        Self {
+            id,
+            queue: tasks.iter().filter(|task| needs_triage(task)).map(|task| task.id).collect(),
+            position: 0,
+            prompt: None,
+            tag: String::new(),
+        }
+    }
+
+    pub fn current(&self) -> Option<Uuid> {
+        self.queue.get(self.position).copied()
+    }
+
+    /// 1-based position of the current task and the number of tasks.
+    pub fn progress(&self) -> (usize, usize) {
+        ((self.position + 1).min(self.queue.len()), self.queue.len())
+    }
+
+    /// Moves on to the next task; returns whether there is one.
+    pub fn advance(&mut self) -> bool {
+        self.position += 1;
+        self.prompt = None;
+        self.tag.clear();
+        self.current().is_some()
+    }
+
+    /// Handles a key press, returning what it asks for, if anything.
+    pub fn key(&mut self, key: &str) -> Option<Action> {
+        let Some(prompt) = self.prompt else {
+            return match key {
+                "d" => self.ask(Prompt::Due),
+                "p" => self.ask(Prompt::Priority),
+                "t" => self.ask(Prompt::Tag),
+                "c" => Some(Action::Complete),
+                "Enter" => Some(Action::Next),
+                "Escape" => Some(Action::Exit),
+                _ => None,
+            };
+        };
+
+        if key == "Escape" {
+            self.prompt = None;
+            self.tag.clear();
+            return None;
+        }
+        let action = match prompt {
+            Prompt::Due => choice(key, &DUE_PRESETS).map(Action::SetDue),
+            Prompt::Priority => choice(key, &Priority::ALL).map(Action::SetPriority),
+            Prompt::Tag if key == "Enter" => Some(shared::normalize_tags([self.tag.as_str()]))
+                .filter(|tags| !tags.is_empty())
+                .map(|mut tags| Action::AddTag(tags.remove(0))),
+            // Typed into the tag input
+            Prompt::Tag => return None,
+        };
+        if action.is_some() {
+            self.prompt = None;
+            self.tag.clear();
+        }
+        action
+    }
+
+    fn ask(&mut self, prompt: Prompt) -> Option<Action> {
+        self.prompt = Some(prompt);
+        None
+    }
+}
+
+/// The option numbered `key`, counting from 1.
+fn choice<T: Copy>(key: &str, options: &[T]) -> Option<T> {
+    let n: usize = key.parse().ok()?;
+    options.get(n.checked_sub(1)?).copied()
+}
+
+/// Waits for the next key pressed without Ctrl, Alt or Cmd, and returns its
+/// `key` value.
+pub async fn next_key() -> String {
+    let Some(window) = web_sys::window() else {
+        return std::future::pending().await;
+    };
+
+    loop {
+        let mut resolve = None;
+        let promise = Promise::new(&mut |resolve_fn, _reject| resolve = Some(resolve_fn));
+        let Some(resolve) = resolve else {
+            return std::future::pending().await;
+        };
+
+        let _ = window.add_event_listener_with_callback("keydown", &resolve);
+        let event = JsFuture::from(promise).await;
+        let _ = window.remove_event_listener_with_callback("keydown", &resolve);
+
+        let Some(event) = event.ok().and_then(|event| event.dyn_into::<KeyboardEvent>().ok()) else {
+            continue;
+        };
+        if event.ctrl_key() || event.alt_key() || event.meta_key() {
+            continue;
+        }
+        // Keeps Enter from also pressing a focused button
+        if event.key() == "Enter" {
+            event.prevent_default();
+        }
+        return event.key();
+    }
+}
+
+pub fn focus_tag_input() {
+    let input = web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.get_element_by_id(TAG_INPUT))
+        .and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok());
+    if let Some(input) = input {
+        let _ = input.focus();
+    }
+}
@@ -0,0 +1,34 @@
+//! Asks before the tab is closed or reloaded while a task edit hasn't been
+//! saved.
+//!
+//! The browser's `beforeunload` prompt can't wait on the app, so the update
+//! loop keeps [`set`] current and the listener only reads it.
+
+use std::cell::Cell;
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::{window, BeforeUnloadEvent};
+
+thread_local! {
+    static UNSAVED: Cell<bool> = const { Cell::new(false) };
+}
+
+pub fn set(unsaved: bool) {
+    UNSAVED.with(|flag| flag.set(unsaved));
+}
+
+pub fn install() {
+    let Some(window) = window() else {
+        return;
+    };
+
+    let callback = Closure::wrap(Box::new(|event: BeforeUnloadEvent| {
+        if UNSAVED.with(Cell::get) {
+            event.prevent_default();
+            // Older browsers only prompt when a return value is set
+            event.set_return_value("unsaved");
+        }
+    }) as Box<dyn FnMut(_)>);
+
+    let _ = window.add_event_listener_with_callback("beforeunload", callback.as_ref().unchecked_ref());
+    callback.forget();
+}
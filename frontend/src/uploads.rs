@@ -0,0 +1,163 @@
+//! Attachment uploads.
+//!
+//! Uploads go through `XMLHttpRequest` rather than `fetch` because only XHR
+//! reports upload progress. Progress is written into a shared cell that the
+//! view reads while a periodic tick re-renders the bars.
+
+use shared::Attachment;
+use std::{cell::Cell, rc::Rc};
+use uuid::Uuid;
+use wasm_bindgen::{prelude::*, JsCast};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{File, FileList, FormData, HtmlInputElement, ProgressEvent, XmlHttpRequest};
+
+/// Returned by [`Upload::finish`] when the user cancelled the upload.
+pub const CANCELLED: &str = "Upload cancelled";
+
+#[derive(Debug, Clone)]
+pub struct Upload {
+    pub id: u32,
+    pub task_id: Uuid,
+    pub filename: String,
+    xhr: XmlHttpRequest,
+    /// Bytes sent so far and the total, as last reported by the browser.
+    progress: Rc<Cell<(f64, f64)>>,
+    cancelled: Rc<Cell<bool>>,
+    // Kept alive for as long as the request can fire progress events
+    _on_progress: Rc<Closure<dyn FnMut(ProgressEvent)>>,
+}
+
+impl Upload {
+    pub fn start(id: u32, task_id: Uuid, file: &File) -> Result<Self, String> {
+        let form = FormData::new().map_err(|_| "Failed to create form data")?;
+        form.append_with_blob_and_filename("file", file, &file.name())
+            .map_err(|_| "Failed to add file to form")?;
+
+        let xhr = XmlHttpRequest::new().map_err(|_| "Failed to create request")?;
+        xhr.open("POST", &crate::base::url(&format!("/api/tasks/{}/attachments", task_id)))
+            .map_err(|_| "Failed to open request")?;
+
+        let progress = Rc::new(Cell::new((0.0, file.size())));
+        let on_progress = Closure::wrap(Box::new({
+            let progress = progress.clone();
+            move |event: ProgressEvent| {
+                if event.length_computable() {
+                    progress.set((event.loaded(), event.total()));
+                }
+            }
+        }) as Box<dyn FnMut(ProgressEvent)>);
+        xhr.upload()
+            .map_err(|_| "Failed to track upload progress")?
+            .set_onprogress(Some(on_progress.as_ref().unchecked_ref()));
+
+        xhr.send_with_opt_form_data(Some(&form)).map_err(|_| "Failed to send request")?;
+
+        Ok(Self {
+            id,
+            task_id,
+            filename: file.name(),
+            xhr,
+            progress,
+            cancelled: Rc::new(Cell::new(false)),
+            _on_progress: Rc::new(on_progress),
+        })
+    }
+
+    /// Fraction of the file sent so far, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        let (loaded, total) = self.progress.get();
+        if total > 0.0 {
+            (loaded / total).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+        let _ = self.xhr.abort();
+    }
+
+    /// Resolves once the backend has answered, or with [`CANCELLED`] after
+    /// [`Upload::cancel`].
+    pub async fn finish(&self) -> Result<Attachment, String> {
+        let xhr = self.xhr.clone();
+        let done = js_sys::Promise::new(&mut |resolve, reject| {
+            if xhr.ready_state() == XmlHttpRequest::DONE {
+                let _ = resolve.call0(&JsValue::NULL);
+                return;
+            }
+            xhr.set_onload(Some(&resolve));
+            xhr.set_onerror(Some(&reject));
+            xhr.set_onabort(Some(&reject));
+        });
+
+        let result = JsFuture::from(done).await;
+        if self.cancelled.get() {
+            return Err(CANCELLED.to_string());
+        }
+        let status = self.xhr.status().unwrap_or(0);
+        if result.is_err() || status == 0 {
+            return Err(format!("Failed to upload {}", self.filename));
+        }
+        if status == 413 {
+            return Err(format!("{} is too large to upload", self.filename));
+        }
+        if !(200..300).contains(&status) {
+            return Err(format!("Failed to upload {} (HTTP {})", self.filename, status));
+        }
+
+        let text = self
+            .xhr
+            .response_text()
+            .ok()
+            .flatten()
+            .ok_or("Failed to read response")?;
+        serde_json::from_str(&text).map_err(|e| format!("Failed to parse JSON: {}", e))
+    }
+}
+
+/// Files from a drop or paste, if the event carried any.
+pub fn files_from_event(event: &web_sys::Event) -> Vec<File> {
+    let files: Option<FileList> = if let Some(drag) = event.dyn_ref::<web_sys::DragEvent>() {
+        drag.data_transfer().and_then(|transfer| transfer.files())
+    } else if let Some(clipboard) = event.dyn_ref::<web_sys::ClipboardEvent>() {
+        clipboard.clipboard_data().and_then(|transfer| transfer.files())
+    } else {
+        None
+    };
+
+    files
+        .map(|list| (0..list.length()).filter_map(|i| list.get(i)).collect())
+        .unwrap_or_default()
+}
+
+/// Files picked in the `<input type="file">` that fired `event`. The input is
+/// cleared, so picking the same file again fires another change.
+pub fn files_from_input(event: &web_sys::Event) -> Vec<File> {
+    let Some(input) = event.target().and_then(|target| target.dyn_into::<HtmlInputElement>().ok()) else {
+        return Vec::new();
+    };
+    let files = input
+        .files()
+        .map(|list| (0..list.length()).filter_map(|i| list.get(i)).collect())
+        .unwrap_or_default();
+    input.set_value("");
+    files
+}
+
+/// Human-readable file size, e.g. `1.4 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
@@ -2,39 +2,40 @@
 module.exports = {
   content: [
     "./src/**/*.rs",
-    "./dist/index.html"
+    "./dist/index.html",
+    "../backend/src/shell.rs"
   ],
   theme: {
     extend: {
       colors: {
-        // Catppuccin Mocha color palette
+        // Catppuccin, from the palette variables in input.css (Mocha or Latte)
         'ctp': {
-          'base': '#1e1e2e',
-          'mantle': '#181825',
-          'crust': '#11111b',
-          'surface0': '#313244',
-          'surface1': '#45475a',
-          'surface2': '#585b70',
-          'overlay0': '#6c7086',
-          'overlay1': '#7f849c',
-          'overlay2': '#9399b2',
-          'subtext0': '#a6adc8',
-          'subtext1': '#bac2de',
-          'text': '#cdd6f4',
-          'lavender': '#b4befe',
-          'blue': '#89b4fa',
-          'sapphire': '#74c7ec',
-          'sky': '#89dceb',
-          'teal': '#94e2d5',
-          'green': '#a6e3a1',
-          'yellow': '#f9e2af',
-          'peach': '#fab387',
-          'maroon': '#eba0ac',
-          'red': '#f38ba8',
-          'mauve': '#cba6f7',
-          'pink': '#f5c2e7',
-          'flamingo': '#f2cdcd',
-          'rosewater': '#f5e0dc',
+          'base': 'rgb(var(--ctp-base) / <alpha-value>)',
+          'mantle': 'rgb(var(--ctp-mantle) / <alpha-value>)',
+          'crust': 'rgb(var(--ctp-crust) / <alpha-value>)',
+          'surface0': 'rgb(var(--ctp-surface0) / <alpha-value>)',
+          'surface1': 'rgb(var(--ctp-surface1) / <alpha-value>)',
+          'surface2': 'rgb(var(--ctp-surface2) / <alpha-value>)',
+          'overlay0': 'rgb(var(--ctp-overlay0) / <alpha-value>)',
+          'overlay1': 'rgb(var(--ctp-overlay1) / <alpha-value>)',
+          'overlay2': 'rgb(var(--ctp-overlay2) / <alpha-value>)',
+          'subtext0': 'rgb(var(--ctp-subtext0) / <alpha-value>)',
+          'subtext1': 'rgb(var(--ctp-subtext1) / <alpha-value>)',
+          'text': 'rgb(var(--ctp-text) / <alpha-value>)',
+          'lavender': 'rgb(var(--ctp-lavender) / <alpha-value>)',
+          'blue': 'rgb(var(--ctp-blue) / <alpha-value>)',
+          'sapphire': 'rgb(var(--ctp-sapphire) / <alpha-value>)',
+          'sky': 'rgb(var(--ctp-sky) / <alpha-value>)',
+          'teal': 'rgb(var(--ctp-teal) / <alpha-value>)',
+          'green': 'rgb(var(--ctp-green) / <alpha-value>)',
+          'yellow': 'rgb(var(--ctp-yellow) / <alpha-value>)',
+          'peach': 'rgb(var(--ctp-peach) / <alpha-value>)',
+          'maroon': 'rgb(var(--ctp-maroon) / <alpha-value>)',
+          'red': 'rgb(var(--ctp-red) / <alpha-value>)',
+          'mauve': 'rgb(var(--ctp-mauve) / <alpha-value>)',
+          'pink': 'rgb(var(--ctp-pink) / <alpha-value>)',
+          'flamingo': 'rgb(var(--ctp-flamingo) / <alpha-value>)',
+          'rosewater': 'rgb(var(--ctp-rosewater) / <alpha-value>)',
         }
       },
       animation: {
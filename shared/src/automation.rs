@@ -0,0 +1,225 @@
+//! Automation rules: when something happens to a task (the [`RuleTrigger`])
+//! and it matches every [`RuleCondition`], the backend runs the rule's
+//! [`RuleAction`]s. Rules are set from the Admin page, globally or for a
+//! single workspace, and each run is logged as a [`RuleExecution`]. Triggers
+//! noticed by background jobs (snoozes ending, stale tasks, due dates
+//! passing) have no workspace, so only the global rules run for them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::{normalize_tags, Priority, Task};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleTrigger {
+    Created,
+    Completed,
+    Reopened,
+    /// The task's snooze ran out, so it's due.
+    SnoozeEnded,
+    /// The task has been open for `days` days since it was created.
+    Stale { days: u32 },
+    /// The task's [`Task::due_at`] passed while it was still open.
+    DuePassed,
+}
+
+impl RuleTrigger {
+    pub fn label(&self) -> String {
+        match self {
+            RuleTrigger::Created => "When created".to_string(),
+            RuleTrigger::Completed => "When completed".to_string(),
+            RuleTrigger::Reopened => "When reopened".to_string(),
+            RuleTrigger::SnoozeEnded => "When its snooze ends".to_string(),
+            RuleTrigger::Stale { days } => format!("When open for {} days", days),
+            RuleTrigger::DuePassed => "When its due date passes".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleCondition {
+    HasTag { tag: String },
+    LacksTag { tag: String },
+    PriorityAtLeast { priority: Priority },
+}
+
+impl RuleCondition {
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            RuleCondition::HasTag { tag } => task.tags.contains(tag),
+            RuleCondition::LacksTag { tag } => !task.tags.contains(tag),
+            RuleCondition::PriorityAtLeast { priority } => task.priority >= *priority,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            RuleCondition::HasTag { tag } => format!("tagged #{}", tag),
+            RuleCondition::LacksTag { tag } => format!("not tagged #{}", tag),
+            RuleCondition::PriorityAtLeast { priority } => format!("priority {} or higher", priority.label()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RuleAction {
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+    SetPriority { priority: Priority },
+    ClearSnooze,
+    /// Shows `message` to everyone with the app open.
+    Notify { message: String },
+    /// POSTs the rule's name, the trigger and the task to `url` as JSON.
+    Webhook { url: String },
+}
+
+impl RuleAction {
+    /// Makes the change to `task`, for the actions that change it. Returns
+    /// whether anything changed.
+    pub fn apply(&self, task: &mut Task) -> bool {
+        match self {
+            RuleAction::AddTag { tag } if !task.tags.contains(tag) => {
+                task.tags.push(tag.clone());
+                true
+            }
+            RuleAction::RemoveTag { tag } if task.tags.contains(tag) => {
+                task.tags.retain(|t| t != tag);
+                true
+            }
+            RuleAction::SetPriority { priority } if task.priority != *priority => {
+                task.priority = *priority;
+                true
+            }
+            RuleAction::ClearSnooze if task.snoozed_until.is_some() => {
+                task.snoozed_until = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            RuleAction::AddTag { tag } => format!("add #{}", tag),
+            RuleAction::RemoveTag { tag } => format!("remove #{}", tag),
+            RuleAction::SetPriority { priority } => format!("set priority {}", priority.label()),
+            RuleAction::ClearSnooze => "clear the snooze".to_string(),
+            RuleAction::Notify { message } => format!("notify \"{}\"", message),
+            RuleAction::Webhook { url } => format!("call {}", url),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutomationRule {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    pub trigger: RuleTrigger,
+    /// All of them must match.
+    #[serde(default)]
+    pub conditions: Vec<RuleCondition>,
+    pub actions: Vec<RuleAction>,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl AutomationRule {
+    /// Whether the rule runs for `task` on `trigger`.
+    pub fn matches(&self, trigger: RuleTrigger, task: &Task) -> bool {
+        self.enabled && self.trigger == trigger && self.conditions.iter().all(|condition| condition.matches(task))
+    }
+
+    /// Normalizes the tags the rule mentions and checks that it can run.
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err("Give the rule a name.".to_string());
+        }
+        if self.actions.is_empty() {
+            return Err(format!("Rule \"{}\" has nothing to do.", self.name));
+        }
+        if self.trigger == (RuleTrigger::Stale { days: 0 }) {
+            return Err(format!("Rule \"{}\": tasks can only go stale after at least a day.", self.name));
+        }
+
+        let conditions = self.conditions.iter_mut().filter_map(|condition| match condition {
+            RuleCondition::HasTag { tag } | RuleCondition::LacksTag { tag } => Some(tag),
+            RuleCondition::PriorityAtLeast { .. } => None,
+        });
+        let actions = self.actions.iter_mut().filter_map(|action| match action {
+            RuleAction::AddTag { tag } | RuleAction::RemoveTag { tag } => Some(tag),
+            _ => None,
+        });
+        for tag in conditions.chain(actions) {
+            *tag = normalize_tags([tag.as_str()])
+                .pop()
+                .ok_or_else(|| format!("Rule \"{}\" has an empty tag.", self.name))?;
+        }
+
+        for action in &self.actions {
+            match action {
+                RuleAction::Notify { message } if message.trim().is_empty() => {
+                    return Err(format!("Rule \"{}\" has an empty notification.", self.name));
+                }
+                RuleAction::Webhook { url } if !(url.starts_with("http://") || url.starts_with("https://")) => {
+                    return Err(format!("Rule \"{}\": webhook URLs start with http:// or https://.", self.name));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// One line describing the rule, e.g. `When completed, if tagged #work:
+    /// clear the snooze`.
+    pub fn summary(&self) -> String {
+        let mut summary = self.trigger.label();
+        if !self.conditions.is_empty() {
+            let conditions: Vec<String> = self.conditions.iter().map(RuleCondition::label).collect();
+            summary.push_str(&format!(", if {}", conditions.join(" and ")));
+        }
+        let actions: Vec<String> = self.actions.iter().map(RuleAction::label).collect();
+        summary.push_str(&format!(": {}", actions.join(", ")));
+        summary
+    }
+}
+
+/// Returned by `GET /api/admin/automations`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AutomationSettings {
+    /// Apply to workspaces without rules of their own.
+    pub rules: Vec<AutomationRule>,
+    pub workspaces: BTreeMap<String, Vec<AutomationRule>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetAutomationRulesRequest {
+    pub rules: Vec<AutomationRule>,
+    /// When set, the rules only apply to this workspace.
+    pub workspace: Option<String>,
+}
+
+/// One run of a rule, as listed by `GET /api/admin/automations/log`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleExecution {
+    /// Unix timestamp (seconds).
+    pub at: u64,
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub trigger: RuleTrigger,
+    pub task_id: Uuid,
+    pub workspace: Option<String>,
+    /// Labels of the actions that ran.
+    pub actions: Vec<String>,
+    /// Why the run failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
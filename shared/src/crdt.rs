@@ -0,0 +1,147 @@
+//! Conflict-free merging of task edits made on several devices. Experimental,
+//! behind the `crdt_sync` flag.
+//!
+//! Each plain field is a last-writer-wins register: an edit only lands if
+//! its [`Stamp`] is newer than the one the field was last written with. Tags
+//! form a last-writer-wins element set, where each tag remembers when it was
+//! last added and last removed. Applying the same edits in any order, any
+//! number of times, gives the same task, so edits made offline on two
+//! devices merge without asking the user which one to keep.
+//!
+//! Only edits sent as a [`TaskEdit`] are stamped; plain `PUT`/`PATCH`
+//! updates leave the clock alone.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{normalize_tags, Priority, Task};
+
+/// When and where an edit was made. Ordered by time, then by replica so two
+/// devices writing in the same millisecond still agree on a winner.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Stamp {
+    /// Unix milliseconds on the editing device.
+    pub at: u64,
+    /// Stable id of the editing device.
+    pub replica: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct TagStamps {
+    pub added: Option<Stamp>,
+    pub removed: Option<Stamp>,
+}
+
+impl TagStamps {
+    /// An add and a remove with the same stamp keep the tag.
+    fn present(&self) -> bool {
+        self.added >= self.removed
+    }
+}
+
+/// Stamps of the last accepted write to each field of a task.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(default)]
+pub struct TaskClock {
+    pub title: Option<Stamp>,
+    pub description: Option<Stamp>,
+    pub completed: Option<Stamp>,
+    pub pinned: Option<Stamp>,
+    pub priority: Option<Stamp>,
+    pub tags: BTreeMap<String, TagStamps>,
+}
+
+impl TaskClock {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Changes made together on one device, as sent to
+/// `POST /api/tasks/:id/edits`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskEdit {
+    pub stamp: Stamp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub add_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove_tags: Vec<String>,
+}
+
+impl TaskEdit {
+    /// The tag changes that turn `before` into `after`.
+    pub fn diff_tags(&mut self, before: &[String], after: &[String]) {
+        self.add_tags = after.iter().filter(|tag| !before.contains(tag)).cloned().collect();
+        self.remove_tags = before.iter().filter(|tag| !after.contains(tag)).cloned().collect();
+    }
+}
+
+/// Writes `new` if `stamp` beats the field's clock. Equal stamps fall back to
+/// comparing values, so the outcome never depends on arrival order.
+fn assign<T: Ord>(value: &mut T, clock: &mut Option<Stamp>, new: Option<T>, stamp: &Stamp) {
+    let Some(new) = new else {
+        return;
+    };
+    let wins = match clock.as_ref() {
+        None => true,
+        Some(current) => stamp > current || (stamp == current && new > *value),
+    };
+    if wins {
+        *value = new;
+        *clock = Some(stamp.clone());
+    }
+}
+
+fn advance(slot: &mut Option<Stamp>, stamp: &Stamp) {
+    if slot.as_ref().is_none_or(|current| stamp > current) {
+        *slot = Some(stamp.clone());
+    }
+}
+
+impl Task {
+    /// Merges an edit into the task.
+    pub fn apply_edit(&mut self, edit: TaskEdit) {
+        let stamp = &edit.stamp;
+        assign(&mut self.title, &mut self.clock.title, edit.title, stamp);
+        assign(&mut self.description, &mut self.clock.description, edit.description, stamp);
+        assign(&mut self.completed, &mut self.clock.completed, edit.completed, stamp);
+        assign(&mut self.pinned, &mut self.clock.pinned, edit.pinned, stamp);
+        assign(&mut self.priority, &mut self.clock.priority, edit.priority, stamp);
+
+        for tag in normalize_tags(edit.add_tags) {
+            advance(&mut self.clock.tags.entry(tag).or_default().added, stamp);
+        }
+        for tag in normalize_tags(edit.remove_tags) {
+            advance(&mut self.clock.tags.entry(tag).or_default().removed, stamp);
+        }
+
+        // Tags from before stamping keep their place; stamped ones follow in
+        // the order they were added
+        let mut stamped: Vec<(&Stamp, &String)> = self
+            .clock
+            .tags
+            .iter()
+            .filter(|(_, stamps)| stamps.present())
+            .map(|(tag, stamps)| (stamps.added.as_ref().unwrap_or(stamp), tag))
+            .collect();
+        stamped.sort();
+        let mut tags: Vec<String> = self.tags.iter().filter(|tag| !self.clock.tags.contains_key(*tag)).cloned().collect();
+        tags.extend(stamped.into_iter().map(|(_, tag)| tag.clone()));
+        self.tags = tags;
+    }
+}
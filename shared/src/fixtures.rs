@@ -0,0 +1,137 @@
+//! Made-up tasks for filling an empty store (`SEED_TASKS`) and for tests.
+//!
+//! [`Fixtures`] draws titles, tags, priorities and dates from small word
+//! lists with a seeded generator, so the same seed always gives the same
+//! tasks, ids included. Dates are spread over the weeks before `now`: about
+//! a third of the tasks are completed, some are pinned or due later, and a
+//! few repeat or belong to a place.
+
+use uuid::Uuid;
+
+use crate::{recurrence::RecurrenceRule, Location, Priority, Task};
+
+const DAY: u64 = 86_400;
+
+const VERBS: &[&str] = &[
+    "Write", "Review", "Fix", "Plan", "Call", "Book", "Renew", "Clean", "Update", "Order", "Send", "Prepare",
+];
+
+const OBJECTS: &[&str] = &[
+    "the quarterly report",
+    "dentist appointment",
+    "car insurance",
+    "the garage",
+    "release notes",
+    "flight to Lisbon",
+    "groceries",
+    "the onboarding doc",
+    "birthday present for Sam",
+    "login page bug",
+    "team offsite agenda",
+    "passport",
+];
+
+const DESCRIPTIONS: &[&str] = &[
+    "",
+    "",
+    "Check with the others first.",
+    "Should take about an hour.",
+    "Blocked until the invoice arrives.",
+    "See the notes from last week's meeting.",
+    "Low effort, just needs doing.",
+];
+
+const TAGS: &[&str] = &["work", "home", "errands", "health", "finance", "travel", "urgent", "someday"];
+
+const PLACES: &[(&str, f64, f64)] = &[
+    ("Office", 51.5226, -0.0857),
+    ("Supermarket", 51.5155, -0.0922),
+    ("Gym", 51.5308, -0.1238),
+];
+
+/// Deterministic task generator; see the module docs.
+pub struct Fixtures {
+    state: u64,
+}
+
+impl Fixtures {
+    pub fn new(seed: u64) -> Self {
+        // xorshift never leaves zero, so any seed has to be mixed away from it
+        Self {
+            state: (seed ^ 0x9e37_79b9_7f4a_7c15) | 1,
+        }
+    }
+
+    /// `count` tasks, created over the weeks before `now` (unix seconds).
+    pub fn tasks(&mut self, count: usize, now: u64) -> Vec<Task> {
+        (0..count).map(|_| self.task(now)).collect()
+    }
+
+    pub fn task(&mut self, now: u64) -> Task {
+        let title = format!("{} {}", self.pick(VERBS), self.pick(OBJECTS));
+        let description = self.pick(DESCRIPTIONS).to_string();
+        let created_at = now.saturating_sub(self.below(30 * DAY));
+
+        let tag_count = self.below(3) as usize;
+        let tags: Vec<&str> = (0..tag_count).map(|_| *self.pick(TAGS)).collect();
+        let priority = *self.pick(&Priority::ALL);
+
+        let mut task = Task::new(title, description)
+            .created_at(created_at)
+            .with_tags(tags)
+            .with_priority(priority);
+        task.id = self.uuid();
+
+        if self.chance(3) {
+            let completed_at = created_at + self.below(now.saturating_sub(created_at) + 1);
+            task = task.completed_at(completed_at);
+        } else if self.chance(4) {
+            task = task.with_due_date(now + DAY + self.below(14 * DAY));
+        }
+        if self.chance(8) {
+            task = task.pinned();
+        }
+        if self.chance(10) {
+            let rule = self.pick(&[RecurrenceRule::Daily, RecurrenceRule::Weekly, RecurrenceRule::Monthly]).clone();
+            task = task.with_recurrence(rule);
+        }
+        if self.chance(10) {
+            let (name, lat, lng) = *self.pick(PLACES);
+            task = task.with_location(Location {
+                name: name.to_string(),
+                lat,
+                lng,
+                radius_m: Location::DEFAULT_RADIUS_M,
+            });
+        }
+        task
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in `0..bound`; `bound` must not be zero.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    /// True about one time in `n`.
+    fn chance(&mut self, n: u64) -> bool {
+        self.below(n) == 0
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    fn uuid(&mut self) -> Uuid {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&self.next().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.next().to_le_bytes());
+        uuid::Builder::from_random_bytes(bytes).into_uuid()
+    }
+}
@@ -1,25 +1,870 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub mod automation;
+pub mod crdt;
+pub mod fixtures;
+pub mod recurrence;
+pub mod validation;
+
+/// Names of the feature flags known to both the backend and the frontend.
+pub mod flags {
+    pub const BOARDS: &str = "boards";
+    pub const ANALYTICS_V2: &str = "analytics_v2";
+    /// Accept stamped, conflict-free task edits; see [`crate::crdt`].
+    pub const CRDT_SYNC: &str = "crdt_sync";
+}
+
+/// Names of the A/B experiments the frontend knows how to render.
+pub mod experiments {
+    pub const CREATE_FORM: &str = "create_form";
+}
+
+/// Days a deleted task stays in the trash before it's purged.
+pub const TRASH_TTL_DAYS: u64 = 30;
+
+/// Tasks per page of the Tasks list.
+pub const TASK_PAGE_SIZE: u32 = 30;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct Task {
     pub id: Uuid,
     pub title: String,
     pub description: String,
     pub completed: bool,
+    /// Pinned tasks are listed before the others.
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub priority: Priority,
+    /// Set when the content filter objected to the text but let it through.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub flagged: bool,
+    /// Unix timestamp (seconds) until which the task is hidden from default lists.
+    #[serde(default)]
+    pub snoozed_until: Option<u64>,
+    /// Unix timestamp (seconds) the task is due. Unlike `snoozed_until` it
+    /// doesn't hide the task.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<u64>,
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub location: Option<Location>,
+    /// Lowercase labels without the leading `#`; see [`normalize_tags`].
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Unix timestamp (seconds), set by the backend. Missing on tasks created
+    /// before it was recorded.
+    #[serde(default)]
+    pub created_at: Option<u64>,
+    /// Unix timestamp (seconds) the task was last completed, set by the
+    /// backend. Missing on open tasks and on tasks completed before it was
+    /// recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<u64>,
+    /// Unix timestamp (seconds) of the last save, set by the store along
+    /// with `version`. Missing on tasks not saved since it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<u64>,
+    /// Bumped by the store on every save. `PUT` answers 409 when the client's
+    /// copy is at an older version.
+    #[serde(default)]
+    pub version: u64,
+    /// Brings the task back, snoozed until the next occurrence, once it's
+    /// completed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<recurrence::RecurrenceRule>,
+    /// Unix timestamp (seconds) the task was moved to the trash; only set on
+    /// tasks listed by `GET /api/trash`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<u64>,
+    /// Unix timestamp (seconds) the task was moved to the archive; only set
+    /// on tasks listed by `GET /api/archive`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archived_at: Option<u64>,
+    /// Per-field stamps for conflict-free merging; empty unless edits went
+    /// through [`crdt::TaskEdit`].
+    #[serde(default, skip_serializing_if = "crdt::TaskClock::is_empty")]
+    pub clock: crdt::TaskClock,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 4] = [Priority::Low, Priority::Medium, Priority::High, Priority::Urgent];
+
+    /// Name used in JSON, e.g. `urgent`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+            Priority::Urgent => "urgent",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|priority| priority.name() == name)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Low => "Low",
+            Priority::Medium => "Medium",
+            Priority::High => "High",
+            Priority::Urgent => "Urgent",
+        }
+    }
+}
+
+/// Trims, lowercases and strips a leading `#` from each tag, dropping empty
+/// and duplicate ones while keeping the first-seen order.
+pub fn normalize_tags<I, S>(tags: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.as_ref().trim().trim_start_matches('#').trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// One entry in the activity log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityEvent {
+    /// Position in the log, usable as the `after` cursor. Filled in on read.
+    #[serde(default)]
+    pub id: String,
+    pub task_id: Uuid,
+    /// Task title at the time of the change.
+    pub title: String,
+    pub kind: ActivityKind,
+    /// Unix timestamp (seconds).
+    pub at: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Created,
+    Updated,
+    Completed,
+    Reopened,
+    Deleted,
+    Restored,
+    Archived,
+}
+
+/// Tasks created and completed on one UTC day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyCounts {
+    /// `YYYY-MM-DD`.
+    pub date: String,
+    pub created: u64,
+    pub completed: u64,
+}
+
+/// Completions that happened in one UTC hour of the day, over all time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HourCount {
+    /// 0 to 23.
+    pub hour: u8,
+    pub completed: u64,
+}
+
+/// `GET /api/analytics`: completion trends recorded as tasks change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsSummary {
+    /// Newest day first, as from `GET /api/analytics/daily`.
+    pub daily: Vec<DailyCounts>,
+    /// All 24 hours in order.
+    pub hours: Vec<HourCount>,
+    /// Mean time from creation to completion, over completions of tasks with
+    /// a `created_at`.
+    pub average_completion_secs: Option<u64>,
+}
+
+/// Pushed to connected clients over `GET /api/ws` as tasks change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TaskEvent {
+    Created { task: Task },
+    Updated { task: Task },
+    Deleted { id: Uuid },
+    /// A message from an automation rule about the task.
+    Notice { task_id: Uuid, message: String },
+}
+
+/// One step of a `POST /api/tasks/bulk` request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+    Complete { id: Uuid },
+    Delete { id: Uuid },
+    /// Adds and removes tags; removals win when a tag is in both.
+    Retag {
+        id: Uuid,
+        #[serde(default)]
+        add: Vec<String>,
+        #[serde(default)]
+        remove: Vec<String>,
+    },
+}
+
+impl BulkOperation {
+    pub fn id(&self) -> Uuid {
+        match self {
+            BulkOperation::Complete { id } | BulkOperation::Delete { id } | BulkOperation::Retag { id, .. } => *id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BulkRequest {
+    pub operations: Vec<BulkOperation>,
+}
+
+/// What a bulk request changed, as returned by `POST /api/tasks/bulk`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct BulkResponse {
+    pub updated: Vec<Task>,
+    pub deleted: Vec<Uuid>,
+}
+
+/// Tasks touched since a client's last sync, as returned by
+/// `GET /api/tasks/changes`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct TaskChanges {
+    /// Current change number; pass it as `since` next time.
+    pub version: u64,
+    /// Tasks created or updated since then.
+    pub changed: Vec<Uuid>,
+    pub deleted: Vec<Uuid>,
+}
+
+/// Envelope returned by every list endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ListResponse<T> {
+    pub data: Vec<T>,
+    /// 1-based page number of `data`.
+    pub page: u32,
+    pub per_page: u32,
+    /// Number of items across all pages.
+    pub total: u64,
+    /// Opaque position to resume from, for feeds paged by cursor rather than
+    /// page number. `None` on the last page and on page-numbered lists.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+impl<T> ListResponse<T> {
+    /// Whether pages after this one hold more items.
+    pub fn has_more(&self) -> bool {
+        self.next_cursor.is_some() || (self.page as u64) * (self.per_page as u64) < self.total
+    }
+}
+
+/// The first page of the Tasks list, sent along with the page itself so the
+/// app can start from it instead of asking again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InitialTasks {
+    pub list: ListResponse<Task>,
+    /// The change number the list is as of; see [`TaskChanges::version`].
+    pub version: Option<u64>,
+}
+
+/// Aggregates for one tag, as returned by `GET /api/analytics/tags`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagStats {
+    pub tag: String,
+    pub total: u64,
+    pub completed: u64,
+    /// `completed / total`, between 0 and 1.
+    pub completion_rate: f64,
+    /// Mean age in seconds of the tagged tasks that record a creation time.
+    pub average_age_secs: Option<u64>,
+}
+
+/// A place a task belongs to, so it can be surfaced when the user is nearby.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Location {
+    pub name: String,
+    pub lat: f64,
+    pub lng: f64,
+    /// How close (in metres) counts as "at" the location.
+    pub radius_m: f64,
+}
+
+impl Location {
+    pub const DEFAULT_RADIUS_M: f64 = 200.0;
+
+    /// Great-circle distance in metres from this location to `(lat, lng)`.
+    pub fn distance_m(&self, lat: f64, lng: f64) -> f64 {
+        const EARTH_RADIUS_M: f64 = 6_371_000.0;
+        let (lat1, lat2) = (self.lat.to_radians(), lat.to_radians());
+        let d_lat = lat2 - lat1;
+        let d_lng = (lng - self.lng).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lng / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_M * a.sqrt().asin()
+    }
+}
+
+/// The tasks picked for one day in the "My day" view, in the user's order.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DayPlan {
+    /// Local calendar date, `YYYY-MM-DD`.
+    pub date: String,
+    pub task_ids: Vec<Uuid>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+pub struct SavePlanRequest {
+    pub task_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RollOverRequest {
+    /// Date (`YYYY-MM-DD`) whose plan receives the unfinished tasks.
+    pub to: String,
+}
+
+/// A file uploaded to a task; the bytes live in the attachment storage backend.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Attachment {
+    pub id: Uuid,
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct CreateTaskRequest {
     pub title: String,
     pub description: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub priority: Priority,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// A page sent by the browser extension to `POST /api/capture`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRequest {
+    /// Page title; the URL is used when it's empty.
+    #[serde(default)]
+    pub title: String,
+    pub url: String,
+    /// Text the user had selected on the page, if any.
+    #[serde(default)]
+    pub selection: String,
+}
+
+/// A comment on a task, as listed by `GET /api/tasks/:id/comments`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct Comment {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub body: String,
+    /// Unix timestamp (seconds).
+    pub created_at: u64,
+}
+
+impl Comment {
+    /// Longest body accepted, in characters.
+    pub const MAX_CHARS: usize = 2000;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct CreateCommentRequest {
+    pub body: String,
+}
+
+/// JSON body of every API error response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct ErrorBody {
+    /// Machine-readable kind: `unavailable`, `internal`, `validation`,
+    /// `not_found`, or the status's reason in snake case, e.g. `conflict`.
+    pub error: String,
+    /// Fit to show to the user as is.
+    pub message: String,
+    /// Set on internal errors. The server logged the details under it, so
+    /// it's worth quoting when reporting the problem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_id: Option<String>,
+    /// On `validation` errors about particular fields, one entry per field.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<validation::FieldError>,
+}
+
+/// Result of `POST /api/tasks/import`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Tasks skipped because their id was already taken, by an existing or
+    /// trashed task or one earlier in the file.
+    pub duplicates: usize,
+    /// Tasks that didn't validate; they were skipped too.
+    pub errors: Vec<ImportError>,
+    /// Nothing was saved; `imported` counts the tasks that would have been.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+    /// What importing each task in the file does, on a dry run.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rows: Vec<ImportRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportRow {
+    /// 1-based position of the task in the file, as in [`ImportError`].
+    pub record: usize,
+    /// Missing when the record couldn't be read as a task.
+    pub title: Option<String>,
+    pub status: ImportStatus,
+    /// Why an invalid task would be skipped.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Ok,
+    Duplicate,
+    Invalid,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportError {
+    /// 1-based position of the task in the file, not counting a CSV header.
+    pub record: usize,
+    pub message: String,
+}
+
+/// Partial task update. Serializes without its unset fields, so it doubles as
+/// a JSON Merge Patch for `PATCH /api/tasks/:id`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
 pub struct UpdateTaskRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub completed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Absent leaves the snooze alone; `null` clears it.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub snoozed_until: Option<Option<u64>>,
+    /// Absent leaves the due date alone; `null` clears it.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<Option<u64>>,
+    /// Absent leaves the location alone; `null` clears it.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub location: Option<Option<Location>>,
+    /// Absent leaves the rule alone; `null` stops the task repeating.
+    #[serde(default, deserialize_with = "double_option", skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Option<recurrence::RecurrenceRule>>,
+    /// Version of the task the edit was made against; the update is refused
+    /// with 409 if the task has changed since.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+}
+
+impl UpdateTaskRequest {
+    /// Sets the fields the request has on `task`, normalizing the tags. The
+    /// location and recurrence rule are taken as they are; check them first.
+    pub fn apply(self, task: &mut Task) {
+        if let Some(title) = self.title {
+            task.title = title;
+        }
+        if let Some(description) = self.description {
+            task.description = description;
+        }
+        if let Some(completed) = self.completed {
+            task.completed = completed;
+        }
+        if let Some(pinned) = self.pinned {
+            task.pinned = pinned;
+        }
+        if let Some(priority) = self.priority {
+            task.priority = priority;
+        }
+        if let Some(tags) = self.tags {
+            task.tags = normalize_tags(tags);
+        }
+        if let Some(snoozed_until) = self.snoozed_until {
+            task.snoozed_until = snoozed_until;
+        }
+        if let Some(due_at) = self.due_at {
+            task.due_at = due_at;
+        }
+        if let Some(location) = self.location {
+            task.location = location;
+        }
+        if let Some(recurrence) = self.recurrence {
+            task.recurrence = recurrence;
+        }
+    }
+}
+
+/// Lets an `Option<Option<T>>` field tell a missing key (`None`) apart from an
+/// explicit `null` (`Some(None)`).
+fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+/// Feature flags as evaluated for a single request, keyed by flag name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FeatureFlags(pub BTreeMap<String, bool>);
+
+impl FeatureFlags {
+    /// Unknown flags are treated as disabled so features ship dark by default.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.0.get(name).copied().unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetFlagRequest {
+    pub enabled: bool,
+    /// When set, the override only applies to this workspace.
+    pub workspace: Option<String>,
+}
+
+/// Variant assigned to the requesting user for each running experiment.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ExperimentAssignments(pub BTreeMap<String, String>);
+
+impl ExperimentAssignments {
+    pub fn variant(&self, experiment: &str) -> Option<&str> {
+        self.0.get(experiment).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExposureRequest {
+    pub experiment: String,
+    pub variant: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantResults {
+    pub variant: String,
+    pub exposures: u64,
+    pub unique_subjects: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExperimentResults {
+    pub experiment: String,
+    pub variants: Vec<VariantResults>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientErrorKind {
+    Panic,
+    Api,
+}
+
+/// Severity of a backend log entry, least severe first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 5] = [LogLevel::Trace, LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error];
+
+    /// Name used in JSON and query strings, e.g. `warn`.
+    pub fn name(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.name() == name)
+    }
+}
+
+/// What happens to a task the content filter objects to, from the most
+/// lenient to the strictest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterMode {
+    /// The filter isn't consulted.
+    #[default]
+    Off,
+    /// Saved, but marked [`Task::flagged`] for review.
+    Flag,
+    /// Refused with 422.
+    Reject,
+}
+
+impl FilterMode {
+    pub const ALL: [FilterMode; 3] = [FilterMode::Off, FilterMode::Flag, FilterMode::Reject];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FilterMode::Off => "off",
+            FilterMode::Flag => "flag",
+            FilterMode::Reject => "reject",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
+
+/// Returned by `GET /api/admin/content-filter`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentFilterSettings {
+    /// Filter in use, e.g. `word list (12 words)`.
+    pub filter: String,
+    /// Applies to workspaces without an override.
+    pub mode: FilterMode,
+    pub workspaces: BTreeMap<String, FilterMode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetFilterModeRequest {
+    pub mode: FilterMode,
+    /// When set, the mode only applies to this workspace.
+    pub workspace: Option<String>,
+}
+
+/// A backend log line, as returned by `GET /api/admin/logs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Increases with every captured entry.
+    pub id: u64,
+    /// Unix timestamp in milliseconds.
+    pub at: u64,
+    pub level: LogLevel,
+    /// Module that logged it, e.g. `backend::activity`.
+    pub target: String,
+    /// The message followed by any other fields as `key=value`.
+    pub message: String,
+}
+
+/// Failure reported by the frontend so maintainers can see real user errors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientErrorReport {
+    pub kind: ClientErrorKind,
+    pub message: String,
+    /// Most recent messages processed by the app, oldest first.
+    pub msg_log: Vec<String>,
+    pub app_version: String,
+    pub url: String,
+    pub user_agent: String,
+    /// Filled in by the backend when the report is stored.
+    #[serde(default)]
+    pub received_at: Option<u64>,
+}
+
+/// Version and build metadata embedded at compile time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_hash: String,
+    /// Unix timestamp (seconds).
+    pub build_time: u64,
+}
+
+/// Short git hash of the commit this workspace was built from, or `unknown`.
+pub const GIT_HASH: &str = env!("GIT_HASH");
+
+/// When this workspace was built, as a Unix timestamp in seconds.
+pub const BUILD_TIME: &str = env!("BUILD_TIME");
+
+/// Builds a [`BuildInfo`] with the calling crate's version and the
+/// workspace's [`GIT_HASH`] and [`BUILD_TIME`].
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::BuildInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: $crate::GIT_HASH.to_string(),
+            build_time: $crate::BUILD_TIME.parse().unwrap_or(0),
+        }
+    };
+}
+
+/// Returned by `GET /status`, for uptime monitors and the status badge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub health: Health,
+    pub build: BuildInfo,
+    pub uptime_secs: u64,
+    pub storage: StorageStatus,
+    pub jobs: Vec<JobStatus>,
+}
+
+/// Overall health. The endpoint answers 503 when it's `down`, so monitors
+/// that only look at the status code notice too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Health {
+    Ok,
+    /// Serving requests, but a background job is failing or late.
+    Degraded,
+    /// Storage isn't answering.
+    Down,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageStatus {
+    /// e.g. `SQLite`.
+    pub backend: String,
+    /// How long a ping took; absent when it failed or timed out.
+    pub latency_ms: Option<f64>,
+}
+
+/// A background job's latest run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobStatus {
+    pub name: String,
+    pub health: JobHealth,
+    /// When it last finished a run (unix seconds).
+    pub last_run: Option<u64>,
+    /// When it last finished a run without errors.
+    pub last_success: Option<u64>,
+    /// How long the last run took.
+    pub duration_ms: Option<f64>,
+    /// Runs in a row that ran into errors, up to the last one.
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobHealth {
+    /// Hasn't finished a run since the backend started.
+    Pending,
+    Ok,
+    /// The last run ran into errors.
+    Failing,
+    /// Overdue: no run finished in twice its interval.
+    Stalled,
+}
+
+/// One runtime setting as reported to admins, with what it controls.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigEntry {
+    pub name: String,
+    pub value: String,
+    pub description: String,
+}
+
+/// A "What's new" entry, as returned by `GET /api/announcements`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Announcement {
+    pub id: Uuid,
+    pub title: String,
+    pub body: String,
+    /// Unix timestamp (seconds).
+    pub published_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAnnouncementRequest {
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// Returned by `GET /api/admin/overview`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdminOverview {
+    /// Storage backend in use, e.g. `SQLite`.
+    pub store: String,
+    pub tasks: TaskTotals,
+    /// Absent when Redis isn't configured or couldn't be reached.
+    pub redis: Option<RedisOverview>,
+    /// Browsers connected to `GET /api/ws`.
+    pub live_clients: u64,
+    /// Newest error-level backend log entries, newest first.
+    pub recent_errors: Vec<LogEntry>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskTotals {
+    pub total: u64,
+    pub completed: u64,
+    pub pinned: u64,
+    pub snoozed: u64,
+    /// Let through by the content filter but marked for review.
+    pub flagged: u64,
+    /// Every priority in [`Priority::ALL`] order, including empty ones.
+    pub by_priority: Vec<(Priority, u64)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RedisOverview {
+    /// Bytes, from `INFO memory`.
+    pub used_memory: u64,
+    pub peak_memory: u64,
+    /// Pooled connections open, and how many of them are idle.
+    pub connections: u64,
+    pub idle_connections: u64,
+    /// Lengths of the capped lists and streams the backend appends to.
+    pub queues: Vec<QueueDepth>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueDepth {
+    pub name: String,
+    pub length: u64,
 }
 
 impl Task {
@@ -29,6 +874,107 @@ impl Task {
             title,
             description,
             completed: false,
+            pinned: false,
+            flagged: false,
+            priority: Priority::default(),
+            snoozed_until: None,
+            due_at: None,
+            attachments: Vec::new(),
+            location: None,
+            tags: Vec::new(),
+            created_at: None,
+            completed_at: None,
+            updated_at: None,
+            version: 0,
+            recurrence: None,
+            deleted_at: None,
+            archived_at: None,
+            clock: crdt::TaskClock::default(),
+        }
+    }
+
+    /// Due at `due` (unix seconds).
+    pub fn with_due_date(mut self, due: u64) -> Self {
+        self.due_at = Some(due);
+        self
+    }
+
+    /// Replaces the tags, normalized with [`normalize_tags`].
+    pub fn with_tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.tags = normalize_tags(tags);
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_location(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    pub fn with_recurrence(mut self, rule: recurrence::RecurrenceRule) -> Self {
+        self.recurrence = Some(rule);
+        self
+    }
+
+    pub fn pinned(mut self) -> Self {
+        self.pinned = true;
+        self
+    }
+
+    pub fn created_at(mut self, at: u64) -> Self {
+        self.created_at = Some(at);
+        self
+    }
+
+    /// Completed at `at`, with `completed_at` stamped to match.
+    pub fn completed_at(mut self, at: u64) -> Self {
+        self.completed = true;
+        self.completed_at = Some(at);
+        self
+    }
+
+    /// Keeps `completed_at` in step with `completed` after an edit to a task
+    /// that was or wasn't completed before, as `was_completed` says.
+    pub fn stamp_completion(&mut self, was_completed: bool, now: u64) {
+        match (was_completed, self.completed) {
+            (false, true) => self.completed_at = Some(now),
+            (_, false) => self.completed_at = None,
+            (true, true) => {}
+        }
+    }
+
+    /// Whether the task is still hidden at `now` (unix seconds).
+    pub fn is_snoozed(&self, now: u64) -> bool {
+        self.snoozed_until.is_some_and(|until| until > now)
+    }
+
+    /// Markdown rendering used when copying or exporting a task.
+    pub fn to_markdown(&self) -> String {
+        let (checkbox, status) = if self.completed {
+            ("x", "Completed")
+        } else {
+            (" ", "Pending")
+        };
+
+        let mut markdown = format!("- [{}] **{}**\n  - Status: {}\n", checkbox, self.title, status);
+        if let Some(due) = self.due_at {
+            let (year, month, day) = recurrence::civil_from_days((due / 86_400) as i64);
+            let minutes = due % 86_400 / 60;
+            markdown.push_str(&format!("  - Due: {}-{:02}-{:02} {:02}:{:02} UTC\n", year, month, day, minutes / 60, minutes % 60));
+        }
+        if !self.description.trim().is_empty() {
+            for line in self.description.trim().lines() {
+                markdown.push_str(&format!("  > {}\n", line));
+            }
         }
+        markdown
     }
 }
\ No newline at end of file
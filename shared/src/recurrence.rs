@@ -0,0 +1,219 @@
+//! Repeating tasks.
+//!
+//! A task with a [`RecurrenceRule`] comes back after it's completed: the
+//! backend's recurrence job saves a fresh copy, snoozed until the rule's next
+//! occurrence, and moves the rule onto it. All times are UTC.
+
+use serde::{Deserialize, Serialize};
+
+const DAY: u64 = 86_400;
+const WEEK: u64 = 7 * DAY;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecurrenceRule {
+    Daily,
+    Weekly,
+    Monthly,
+    /// Five-field cron expression: minute, hour, day of month, month and day
+    /// of week (0 or 7 is Sunday). Fields take `*`, numbers, `a-b` ranges,
+    /// `/step`s and comma-separated lists.
+    Cron { expression: String },
+}
+
+impl RecurrenceRule {
+    /// Checks that a cron expression parses; the other rules are always valid.
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            RecurrenceRule::Cron { expression } => Cron::parse(expression).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+
+    /// The first occurrence strictly after `after` (unix seconds). Daily,
+    /// weekly and monthly rules repeat from `anchor`, keeping its time of day
+    /// and weekday or day of month. `None` for an invalid cron expression or
+    /// one that never matches, like February 30th.
+    pub fn next_after(&self, anchor: u64, after: u64) -> Option<u64> {
+        match self {
+            RecurrenceRule::Daily => Some(next_multiple(anchor, DAY, after)),
+            RecurrenceRule::Weekly => Some(next_multiple(anchor, WEEK, after)),
+            RecurrenceRule::Monthly => Some(next_month(anchor, after)),
+            RecurrenceRule::Cron { expression } => Cron::parse(expression).ok()?.next_after(after),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            RecurrenceRule::Daily => "Daily".to_string(),
+            RecurrenceRule::Weekly => "Weekly".to_string(),
+            RecurrenceRule::Monthly => "Monthly".to_string(),
+            RecurrenceRule::Cron { expression } => format!("Cron {}", expression),
+        }
+    }
+}
+
+fn next_multiple(anchor: u64, period: u64, after: u64) -> u64 {
+    if anchor > after {
+        return anchor;
+    }
+    anchor + ((after - anchor) / period + 1) * period
+}
+
+/// Same day of the month as `anchor`, or the month's last day when it's
+/// shorter, at the same time of day.
+fn next_month(anchor: u64, after: u64) -> u64 {
+    let (year, month, day) = civil_from_days((anchor / DAY) as i64);
+    let time_of_day = anchor % DAY;
+    let (mut year, mut month) = (year, month);
+    loop {
+        let day = day.min(days_in_month(year, month));
+        let candidate = days_from_civil(year, month, day) as u64 * DAY + time_of_day;
+        if candidate > after {
+            return candidate;
+        }
+        (year, month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    }
+}
+
+/// Parsed cron fields as bitmasks, bit `n` set when value `n` matches.
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// A `*` day field doesn't restrict; when both are restricted, a day
+    /// matching either one matches, as in standard cron.
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err("A cron expression has five fields: minute hour day month weekday".to_string());
+        };
+
+        let mut weekdays = parse_field(weekday, 0, 7, "weekday")?;
+        // 7 is Sunday too
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    fn matches_day(&self, days: i64) -> bool {
+        let (_, month, day) = civil_from_days(days);
+        // 1970-01-01 was a Thursday
+        let weekday = (days + 4).rem_euclid(7);
+        let day_matches = self.days & (1 << day) != 0;
+        let weekday_matches = self.weekdays & (1 << weekday) != 0;
+        let day_ok = match (self.any_day, self.any_weekday) {
+            (false, false) => day_matches || weekday_matches,
+            _ => day_matches && weekday_matches,
+        };
+        self.months & (1 << month) != 0 && day_ok
+    }
+
+    /// Walks forward a day at a time, so only days need checking one by
+    /// one; gives up after eight years, which covers every leap-day rule.
+    fn next_after(&self, after: u64) -> Option<u64> {
+        let start = (after / 60 + 1) * 60;
+        let first_day = (start / DAY) as i64;
+        for days in first_day..first_day + 8 * 366 {
+            if !self.matches_day(days) {
+                continue;
+            }
+            let midnight = days as u64 * DAY;
+            for hour in (0..24).filter(|hour| self.hours & (1 << hour) != 0) {
+                for minute in (0..60).filter(|minute| self.minutes & (1 << minute) != 0) {
+                    let at = midnight + hour * 3_600 + minute * 60;
+                    if at >= start {
+                        return Some(at);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// One cron field as a bitmask of the values in `min..=max` it matches.
+fn parse_field(field: &str, min: u64, max: u64, name: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid {} field \"{}\"", name, field);
+    let number = |value: &str| -> Result<u64, String> {
+        value.parse::<u64>().ok().filter(|n| (min..=max).contains(n)).ok_or_else(invalid)
+    };
+
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u64>().ok().filter(|step| *step > 0).ok_or_else(invalid)?),
+            None => (item, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (number(from)?, number(to)?),
+                // `5/15` runs from 5 to the end of the field
+                None if step > 1 => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if from > to {
+            return Err(invalid());
+        }
+        for value in (from..=to).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Howard Hinnant's civil_from_days: days since 1970-01-01 to (year, month, day).
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// The inverse of [`civil_from_days`].
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
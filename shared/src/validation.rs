@@ -0,0 +1,84 @@
+//! Limits on a task's title and description.
+//!
+//! The backend checks them on every create and update and answers 422 with
+//! a [`FieldError`] per failing field in the [`ErrorBody`](crate::ErrorBody).
+//! The frontend runs the same checks before sending, so it can show the
+//! errors under the inputs without a round trip.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Task;
+
+/// Longest title, in characters.
+pub const MAX_TITLE_CHARS: usize = 200;
+/// Longest description, in characters.
+pub const MAX_DESCRIPTION_CHARS: usize = 10_000;
+
+/// Names used in [`FieldError::field`].
+pub const TITLE: &str = "title";
+pub const DESCRIPTION: &str = "description";
+
+/// Why one field of a request was refused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "openapi", derive(utoipa::ToSchema))]
+pub struct FieldError {
+    /// The request field, e.g. [`TITLE`].
+    pub field: String,
+    /// Fit to show under the input as is.
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// What's wrong with an already trimmed title, if anything.
+pub fn title_error(title: &str) -> Option<String> {
+    if title.is_empty() {
+        Some("Give the task a title.".to_string())
+    } else if title.chars().count() > MAX_TITLE_CHARS {
+        Some(format!("Titles can be at most {} characters.", MAX_TITLE_CHARS))
+    } else {
+        None
+    }
+}
+
+/// What's wrong with an already trimmed description, if anything.
+pub fn description_error(description: &str) -> Option<String> {
+    (description.chars().count() > MAX_DESCRIPTION_CHARS)
+        .then(|| format!("Descriptions can be at most {} characters.", MAX_DESCRIPTION_CHARS))
+}
+
+/// Errors for the given fields; `None` skips a field.
+pub fn check(title: Option<&str>, description: Option<&str>) -> Vec<FieldError> {
+    let title = title.and_then(title_error).map(|message| FieldError::new(TITLE, message));
+    let description = description
+        .and_then(description_error)
+        .map(|message| FieldError::new(DESCRIPTION, message));
+    title.into_iter().chain(description).collect()
+}
+
+/// Trims the task's title and description, then checks them. Given the
+/// task as it was, fields left as they were aren't checked, so tasks saved
+/// before a limit existed can still be edited.
+pub fn check_task(before: Option<&Task>, task: &mut Task) -> Result<(), Vec<FieldError>> {
+    task.title = task.title.trim().to_string();
+    task.description = task.description.trim().to_string();
+
+    let title_changed = before.is_none_or(|before| before.title != task.title);
+    let description_changed = before.is_none_or(|before| before.description != task.description);
+    let errors = check(
+        title_changed.then_some(task.title.as_str()),
+        description_changed.then_some(task.description.as_str()),
+    );
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
@@ -0,0 +1,219 @@
+//! Properties of the task rules shared by the backend and the frontend:
+//! applying updates, completing and reopening, repeating, and the fixtures.
+
+use proptest::prelude::*;
+use shared::{fixtures::Fixtures, normalize_tags, recurrence::RecurrenceRule, Location, Priority, Task, UpdateTaskRequest};
+
+const DAY: u64 = 86_400;
+/// 1970 to 2100, in seconds.
+const TIMES: std::ops::Range<u64> = 0..4_102_444_800;
+
+fn priority() -> impl Strategy<Value = Priority> {
+    prop::sample::select(Priority::ALL.to_vec())
+}
+
+/// Coordinates to four decimal places, about 10 m, like the ones typed into
+/// the form; serde_json doesn't promise to read back every last digit.
+fn location() -> impl Strategy<Value = Location> {
+    ("[a-zA-Z ]{1,20}", -900_000..900_000, -1_800_000..1_800_000, 1..5_000u32).prop_map(|(name, lat, lng, radius_m)| Location {
+        name,
+        lat: f64::from(lat) / 1e4,
+        lng: f64::from(lng) / 1e4,
+        radius_m: f64::from(radius_m),
+    })
+}
+
+fn rule() -> impl Strategy<Value = RecurrenceRule> {
+    prop_oneof![
+        Just(RecurrenceRule::Daily),
+        Just(RecurrenceRule::Weekly),
+        Just(RecurrenceRule::Monthly),
+        (0..60u32, 0..24u32).prop_map(|(minute, hour)| RecurrenceRule::Cron {
+            expression: format!("{} {} * * *", minute, hour),
+        }),
+    ]
+}
+
+fn tags() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec("#?[ a-zA-Z]{0,8}", 0..5)
+}
+
+fn task() -> impl Strategy<Value = Task> {
+    (
+        ".{0,40}",
+        ".{0,80}",
+        any::<bool>(),
+        any::<bool>(),
+        priority(),
+        tags(),
+        prop::option::of(TIMES),
+        prop::option::of(TIMES),
+        prop::option::of(location()),
+        prop::option::of(rule()),
+        TIMES,
+    )
+        .prop_map(|(title, description, completed, pinned, priority, tags, snoozed, due, location, rule, now)| {
+            let mut task = Task::new(title, description).with_priority(priority).with_tags(tags).created_at(now);
+            task.pinned = pinned;
+            task.snoozed_until = snoozed;
+            task.due_at = due;
+            task.location = location;
+            task.recurrence = rule;
+            if completed {
+                task = task.completed_at(now);
+            }
+            task
+        })
+}
+
+fn request() -> impl Strategy<Value = UpdateTaskRequest> {
+    (
+        prop::option::of(".{0,40}"),
+        prop::option::of(".{0,80}"),
+        prop::option::of(any::<bool>()),
+        prop::option::of(any::<bool>()),
+        prop::option::of(priority()),
+        prop::option::of(tags()),
+        prop::option::of(prop::option::of(TIMES)),
+        prop::option::of(prop::option::of(TIMES)),
+        prop::option::of(prop::option::of(location())),
+        prop::option::of(prop::option::of(rule())),
+    )
+        .prop_map(
+            |(title, description, completed, pinned, priority, tags, snoozed_until, due_at, location, recurrence)| UpdateTaskRequest {
+                title,
+                description,
+                completed,
+                pinned,
+                priority,
+                tags,
+                snoozed_until,
+                due_at,
+                location,
+                recurrence,
+                version: None,
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn an_empty_update_changes_nothing(task in task()) {
+        let mut updated = task.clone();
+        UpdateTaskRequest::default().apply(&mut updated);
+        prop_assert_eq!(updated, task);
+    }
+
+    #[test]
+    fn an_update_sets_what_it_has_and_leaves_the_rest(task in task(), request in request()) {
+        let mut updated = task.clone();
+        request.clone().apply(&mut updated);
+
+        prop_assert_eq!(&updated.title, request.title.as_ref().unwrap_or(&task.title));
+        prop_assert_eq!(&updated.description, request.description.as_ref().unwrap_or(&task.description));
+        prop_assert_eq!(updated.completed, request.completed.unwrap_or(task.completed));
+        prop_assert_eq!(updated.pinned, request.pinned.unwrap_or(task.pinned));
+        prop_assert_eq!(updated.priority, request.priority.unwrap_or(task.priority));
+        prop_assert_eq!(&updated.tags, &request.tags.clone().map(normalize_tags).unwrap_or(task.tags.clone()));
+        prop_assert_eq!(updated.snoozed_until, request.snoozed_until.unwrap_or(task.snoozed_until));
+        prop_assert_eq!(updated.due_at, request.due_at.unwrap_or(task.due_at));
+        prop_assert_eq!(&updated.location, request.location.as_ref().unwrap_or(&task.location));
+        prop_assert_eq!(&updated.recurrence, request.recurrence.as_ref().unwrap_or(&task.recurrence));
+        prop_assert_eq!(updated.id, task.id);
+        prop_assert_eq!(updated.created_at, task.created_at);
+        prop_assert_eq!(updated.version, task.version);
+    }
+
+    #[test]
+    fn applying_an_update_twice_is_applying_it_once(task in task(), request in request()) {
+        let mut once = task.clone();
+        request.clone().apply(&mut once);
+        let mut twice = once.clone();
+        request.apply(&mut twice);
+        prop_assert_eq!(twice, once);
+    }
+
+    #[test]
+    fn an_update_survives_the_trip_through_json(request in request()) {
+        let json = serde_json::to_string(&request).unwrap();
+        let parsed: UpdateTaskRequest = serde_json::from_str(&json).unwrap();
+        prop_assert_eq!(serde_json::to_string(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn normalized_tags_stay_normalized(tags in tags()) {
+        let normalized = normalize_tags(&tags);
+        prop_assert_eq!(normalize_tags(&normalized), normalized.clone());
+        prop_assert!(normalized.iter().all(|tag| !tag.is_empty() && !tag.starts_with('#') && *tag == tag.to_lowercase()));
+    }
+
+    #[test]
+    fn completed_at_is_set_exactly_while_completed(task in task(), completed in prop::collection::vec(any::<bool>(), 1..10), now in TIMES) {
+        let mut task = task;
+        for completed in completed {
+            let was_completed = task.completed;
+            let before = task.completed_at;
+            task.completed = completed;
+            task.stamp_completion(was_completed, now);
+
+            prop_assert_eq!(task.completed_at.is_some(), task.completed);
+            match (was_completed, completed) {
+                (false, true) => prop_assert_eq!(task.completed_at, Some(now)),
+                (true, true) => prop_assert_eq!(task.completed_at, before),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn the_next_occurrence_is_after_the_time_asked(rule in rule(), anchor in TIMES, after in TIMES) {
+        let next = rule.next_after(anchor, after).unwrap();
+        prop_assert!(next > after);
+        // Nothing here repeats less often than monthly
+        prop_assert!(next <= after.max(anchor) + 31 * DAY);
+    }
+
+    #[test]
+    fn daily_and_weekly_occurrences_keep_the_anchor_period(anchor in TIMES, after in TIMES) {
+        for (rule, period) in [(RecurrenceRule::Daily, DAY), (RecurrenceRule::Weekly, 7 * DAY)] {
+            let next = rule.next_after(anchor, after).unwrap();
+            prop_assert_eq!(next.abs_diff(anchor) % period, 0);
+            prop_assert!(next <= after.max(anchor) + period);
+        }
+    }
+
+    #[test]
+    fn monthly_occurrences_keep_the_time_of_day(anchor in TIMES, after in TIMES) {
+        let next = RecurrenceRule::Monthly.next_after(anchor, after).unwrap();
+        prop_assert_eq!(next % DAY, anchor % DAY);
+    }
+
+    #[test]
+    fn occurrences_move_forward(rule in rule(), anchor in TIMES, after in TIMES) {
+        let first = rule.next_after(anchor, after).unwrap();
+        let second = rule.next_after(anchor, first).unwrap();
+        prop_assert!(second > first);
+    }
+
+    #[test]
+    fn a_daily_cron_runs_at_its_minute(minute in 0..60u64, hour in 0..24u64, after in TIMES) {
+        let rule = RecurrenceRule::Cron { expression: format!("{} {} * * *", minute, hour) };
+        let next = rule.next_after(0, after).unwrap();
+        prop_assert_eq!(next % DAY, hour * 3_600 + minute * 60);
+        prop_assert!(next > after && next - after <= DAY);
+    }
+
+    #[test]
+    fn fixtures_are_the_same_for_a_seed_and_due_without_being_hidden(seed in any::<u64>(), now in TIMES) {
+        let tasks = Fixtures::new(seed).tasks(20, now);
+        prop_assert_eq!(&tasks, &Fixtures::new(seed).tasks(20, now));
+        prop_assert!(tasks.iter().all(|task| !task.is_snoozed(now)));
+        prop_assert!(tasks.iter().filter_map(|task| task.due_at).all(|due| due > now));
+    }
+
+    #[test]
+    fn markdown_shows_the_due_date(task in task()) {
+        let markdown = task.with_due_date(1_791_363_600).to_markdown();
+        prop_assert!(markdown.contains("\n  - Due: 2026-10-07 09:00 UTC\n"), "{}", markdown);
+    }
+}